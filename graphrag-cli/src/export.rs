@@ -0,0 +1,66 @@
+//! CLI implementation of the export subcommand.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use log::info;
+
+use graphrag::api;
+use graphrag::config::load_config::load_config;
+use graphrag::utils::api::create_storage_from_config;
+use graphrag::utils::storage::{load_table_from_storage, storage_has_table};
+
+/**
+Export the knowledge graph index as Neo4j CSV files and a Cypher import script.
+
+Args:
+    - config_filepath: The configuration to use (optional).
+    - data_dir: The indexing pipeline output directory, overrides output.base_dir in the configuration file (optional).
+    - root_dir: The project root directory.
+    - output_dir: The directory to write the exported files to.
+
+Returns the error of the first file that cannot be written.
+*/
+pub async fn export_cli(
+    config_filepath: Option<&Path>,
+    data_dir: Option<&Path>,
+    root_dir: &Path,
+    output_dir: &Path,
+) -> io::Result<()> {
+    let mut cli_overrides = HashMap::new();
+    if let Some(data_dir) = data_dir {
+        cli_overrides.insert("output.base_dir".to_string(), data_dir.display().to_string());
+    }
+    let config = load_config(root_dir, config_filepath, cli_overrides);
+    let storage = create_storage_from_config(config.output);
+
+    let entities = load_table_from_storage("entities", storage.clone()).await;
+    let relationships = load_table_from_storage("relationships", storage.clone()).await;
+    let communities = load_table_from_storage("communities", storage.clone()).await;
+    let community_reports = load_table_from_storage("community_reports", storage.clone()).await;
+    let text_units = load_table_from_storage("text_units", storage.clone()).await;
+    // covariates are only created when claim extraction is enabled
+    let covariates = if storage_has_table("covariates", storage.clone()).await {
+        Some(load_table_from_storage("covariates", storage).await)
+    } else {
+        None
+    };
+
+    let files = api::export_neo4j(
+        entities,
+        relationships,
+        communities,
+        community_reports,
+        covariates,
+        text_units,
+    );
+
+    fs::create_dir_all(output_dir)?;
+    for (name, content) in &files {
+        fs::write(output_dir.join(name), content)?;
+    }
+    info!("Exported {} files for Neo4j to {}", files.len(), output_dir.display());
+    Ok(())
+}
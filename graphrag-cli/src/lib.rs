@@ -1,5 +1,6 @@
 //! CLI for GraphRAG.
 
+pub mod export;
 pub mod index;
 pub mod initialize;
 pub mod main;
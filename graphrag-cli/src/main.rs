@@ -491,6 +491,7 @@ use graphrag::logger::types::LoggerType;
 use graphrag::prompt_tune::defaults::{K, LIMIT, MAX_TOKEN_COUNT, N_SUBSET_MAX};
 use graphrag::prompt_tune::types::DocSelectionType;
 
+use crate::export::export_cli;
 use crate::index::index_cli;
 use crate::index::update_cli;
 use crate::initialize::initialize_project_at;
@@ -515,6 +516,8 @@ pub enum Kind {
     PromptTune(PromptTune),
     /// Query a knowledge graph index.
     Query(Query),
+    /// Export a knowledge graph index as Neo4j CSV files and a Cypher import script.
+    Export(Export),
 }
 
 #[derive(Debug, Parser)]
@@ -669,6 +672,22 @@ pub struct Query {
     streaming: bool,
 }
 
+#[derive(Debug, Parser)]
+pub struct Export {
+    /// The configuration to use.
+    #[arg(short, long, default_value_t = None)]
+    config: Option<PathBuf>,
+    /// Indexing pipeline output directory (i.e. contains the parquet files).
+    #[arg(short, long)]
+    data: Option<PathBuf>,
+    /// The project root directory.
+    #[arg(short, long)]
+    root: PathBuf,
+    /// The directory to write the CSV files and the Cypher script to.
+    #[arg(short, long, default_value = "neo4j")]
+    output: PathBuf,
+}
+
 #[tokio::main]
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
@@ -752,6 +771,13 @@ fn main() -> Result<(), Box<dyn Error>> {
                 query = query.query,
             ),
         },
+        Kind::Export(export) => export_cli(
+            export.config.as_deref(),
+            export.data.as_deref(),
+            &export.root,
+            &export.output,
+        )
+        .await?,
     };
     Ok(())
 }
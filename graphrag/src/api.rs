@@ -3,10 +3,12 @@
 //! WARNING: This API is under development and may undergo changes in future releases.
 //! Backwards compatibility is not guaranteed at this time.
 
+pub mod export;
 pub mod index;
 pub mod prompt_tune;
 pub mod query;
 
+pub use crate::api::export::export_neo4j;
 pub use crate::api::index::build_index;
 pub use crate::api::prompt_tune::generate_indexing_prompts;
 pub use crate::api::query::{
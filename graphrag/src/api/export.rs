@@ -0,0 +1,665 @@
+//! Export API for GraphRAG.
+//!
+//! WARNING: This API is under development and may undergo changes in future releases.
+//! Backwards compatibility is not guaranteed at this time.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use polars::prelude::{DataFrame, DataType, LazyFrame};
+
+use crate::data_model::schemas;
+
+/// The name of the generated Cypher import script.
+pub const NEO4J_CYPHER_SCRIPT: &str = "import.cypher";
+
+/**
+Export the final index tables as a Neo4j graph.
+
+Every table becomes a node file whose IDs are the stable `id` of the `Identified` rows, and every
+reference between the tables becomes a relationship file:
+    - (Entity)-[:RELATED_TO]->(Entity), from the relationships.
+    - (Entity)-[:IN_COMMUNITY]->(Community), from the community entity ids.
+    - (Community)-[:CHILD_OF]->(Community), from the community parents.
+    - (CommunityReport)-[:REPORTS_ON]->(Community), from the report communities.
+    - (TextUnit)-[:MENTIONS]->(Entity), from the text unit entity ids.
+    - (Covariate)-[:HAS_SUBJECT]->(Entity), from the covariate subjects.
+    - (Covariate)-[:EXTRACTED_FROM]->(TextUnit), from the covariate text units.
+
+References to entities or text units missing from the export are dropped, as `neo4j-admin` rejects
+relationships to unknown nodes.
+
+The CSV headers follow the `neo4j-admin database import` format, and a Cypher script loading the
+same files with `LOAD CSV` is included for databases that are already running.
+
+Args:
+    - entities: The final entities.
+    - relationships: The final relationships.
+    - communities: The final communities.
+    - community_reports: The final community reports.
+    - covariates: The final covariates (optional).
+    - text_units: The final text units.
+
+Returns the (file name, content) of every CSV file and of the Cypher script.
+*/
+pub fn export_neo4j(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    communities: LazyFrame,
+    community_reports: LazyFrame,
+    covariates: Option<LazyFrame>,
+    text_units: LazyFrame,
+) -> Vec<(String, String)> {
+    let entities = entities.collect().unwrap();
+    let relationships = relationships.collect().unwrap();
+    let communities = communities.collect().unwrap();
+    let community_reports = community_reports.collect().unwrap();
+    let covariates = covariates.map(|covariates| covariates.collect().unwrap());
+    let text_units = text_units.collect().unwrap();
+
+    let mut export = Neo4jExport::default();
+
+    // relationships and covariates refer to entities by title
+    let entity_ids: HashMap<String, String> = strings(&entities, schemas::TITLE)
+        .into_iter()
+        .zip(strings(&entities, schemas::ID))
+        .filter_map(|(title, id)| Some((title?, id?)))
+        .collect();
+    let entity_rows: HashSet<String> = strings(&entities, schemas::ID).into_iter().flatten().collect();
+    let text_unit_rows: HashSet<String> = strings(&text_units, schemas::ID).into_iter().flatten().collect();
+    // parents and reports refer to communities by community number
+    let community_ids: HashMap<i64, String> = integers(&communities, schemas::COMMUNITY_ID)
+        .into_iter()
+        .zip(strings(&communities, schemas::ID))
+        .filter_map(|(community, id)| Some((community?, id?)))
+        .collect();
+
+    export.add_nodes(
+        "Entity",
+        "entities.csv",
+        &entities,
+        &[
+            (schemas::SHORT_ID, Neo4jType::Long),
+            (schemas::TITLE, Neo4jType::String),
+            (schemas::TYPE, Neo4jType::String),
+            (schemas::DESCRIPTION, Neo4jType::String),
+            (schemas::NODE_FREQUENCY, Neo4jType::Long),
+            (schemas::NODE_DEGREE, Neo4jType::Long),
+        ],
+    );
+    export.add_nodes(
+        "Community",
+        "communities.csv",
+        &communities,
+        &[
+            (schemas::SHORT_ID, Neo4jType::Long),
+            (schemas::COMMUNITY_ID, Neo4jType::Long),
+            (schemas::COMMUNITY_LEVEL, Neo4jType::Long),
+            (schemas::TITLE, Neo4jType::String),
+            (schemas::PERIOD, Neo4jType::String),
+            (schemas::SIZE, Neo4jType::Long),
+        ],
+    );
+    export.add_nodes(
+        "CommunityReport",
+        "community_reports.csv",
+        &community_reports,
+        &[
+            (schemas::SHORT_ID, Neo4jType::Long),
+            (schemas::COMMUNITY_ID, Neo4jType::Long),
+            (schemas::COMMUNITY_LEVEL, Neo4jType::Long),
+            (schemas::TITLE, Neo4jType::String),
+            (schemas::SUMMARY, Neo4jType::String),
+            (schemas::FULL_CONTENT, Neo4jType::String),
+            (schemas::RATING, Neo4jType::Double),
+            (schemas::EXPLANATION, Neo4jType::String),
+        ],
+    );
+    export.add_nodes(
+        "TextUnit",
+        "text_units.csv",
+        &text_units,
+        &[
+            (schemas::SHORT_ID, Neo4jType::Long),
+            (schemas::TEXT, Neo4jType::String),
+            (schemas::N_TOKENS, Neo4jType::Long),
+        ],
+    );
+    if let Some(covariates) = &covariates {
+        export.add_nodes(
+            "Covariate",
+            "covariates.csv",
+            covariates,
+            &[
+                (schemas::SHORT_ID, Neo4jType::Long),
+                ("covariate_type", Neo4jType::String),
+                (schemas::TYPE, Neo4jType::String),
+                (schemas::DESCRIPTION, Neo4jType::String),
+                (schemas::CLAIM_STATUS, Neo4jType::String),
+                ("start_date", Neo4jType::String),
+                ("end_date", Neo4jType::String),
+                ("source_text", Neo4jType::String),
+            ],
+        );
+    }
+
+    let relationship_properties = [
+        (schemas::ID, Neo4jType::String),
+        (schemas::SHORT_ID, Neo4jType::Long),
+        (schemas::DESCRIPTION, Neo4jType::String),
+        (schemas::EDGE_WEIGHT, Neo4jType::Double),
+        (schemas::EDGE_DEGREE, Neo4jType::Long),
+    ];
+    let endpoints = strings(&relationships, schemas::EDGE_SOURCE)
+        .into_iter()
+        .zip(strings(&relationships, schemas::EDGE_TARGET))
+        .enumerate()
+        .filter_map(|(row, (source, target))| {
+            Some((row, entity_ids.get(&source?)?.clone(), entity_ids.get(&target?)?.clone()))
+        })
+        .collect();
+    export.add_relationships(
+        "RELATED_TO",
+        ("Entity", "Entity"),
+        "related_to.csv",
+        endpoints,
+        Some((&relationships, &relationship_properties[..])),
+    );
+
+    let community_rows = strings(&communities, schemas::ID);
+    let memberships = lists(&communities, schemas::ENTITY_IDS)
+        .into_iter()
+        .zip(&community_rows)
+        .filter_map(|(entity_ids, community)| Some((entity_ids, community.clone()?)))
+        .flat_map(|(entity_ids, community)| {
+            entity_ids.into_iter().map(move |entity| (0, entity, community.clone()))
+        })
+        .filter(|(_, entity, _)| entity_rows.contains(entity))
+        .collect();
+    export.add_relationships(
+        "IN_COMMUNITY",
+        ("Entity", "Community"),
+        "in_community.csv",
+        memberships,
+        None,
+    );
+
+    let parents = integers(&communities, schemas::COMMUNITY_PARENT)
+        .into_iter()
+        .zip(&community_rows)
+        .filter_map(|(parent, community)| {
+            Some((0, community.clone()?, community_ids.get(&parent?)?.clone()))
+        })
+        .collect();
+    export.add_relationships(
+        "CHILD_OF",
+        ("Community", "Community"),
+        "child_of.csv",
+        parents,
+        None,
+    );
+
+    let reports = integers(&community_reports, schemas::COMMUNITY_ID)
+        .into_iter()
+        .zip(strings(&community_reports, schemas::ID))
+        .filter_map(|(community, report)| Some((0, report?, community_ids.get(&community?)?.clone())))
+        .collect();
+    export.add_relationships(
+        "REPORTS_ON",
+        ("CommunityReport", "Community"),
+        "reports_on.csv",
+        reports,
+        None,
+    );
+
+    let mentions = lists(&text_units, schemas::ENTITY_IDS)
+        .into_iter()
+        .zip(strings(&text_units, schemas::ID))
+        .filter_map(|(entity_ids, text_unit)| Some((entity_ids, text_unit?)))
+        .flat_map(|(entity_ids, text_unit)| {
+            entity_ids.into_iter().map(move |entity| (0, text_unit.clone(), entity))
+        })
+        .filter(|(_, _, entity)| entity_rows.contains(entity))
+        .collect();
+    export.add_relationships(
+        "MENTIONS",
+        ("TextUnit", "Entity"),
+        "mentions.csv",
+        mentions,
+        None,
+    );
+
+    if let Some(covariates) = &covariates {
+        let covariate_rows = strings(covariates, schemas::ID);
+        let subjects = strings(covariates, schemas::CLAIM_SUBJECT)
+            .into_iter()
+            .zip(&covariate_rows)
+            .filter_map(|(subject, covariate)| {
+                Some((0, covariate.clone()?, entity_ids.get(&subject?)?.clone()))
+            })
+            .collect();
+        export.add_relationships(
+            "HAS_SUBJECT",
+            ("Covariate", "Entity"),
+            "has_subject.csv",
+            subjects,
+            None,
+        );
+
+        let sources = strings(covariates, "text_unit_id")
+            .into_iter()
+            .zip(&covariate_rows)
+            .filter_map(|(text_unit, covariate)| Some((0, covariate.clone()?, text_unit?)))
+            .filter(|(_, _, text_unit)| text_unit_rows.contains(text_unit))
+            .collect();
+        export.add_relationships(
+            "EXTRACTED_FROM",
+            ("Covariate", "TextUnit"),
+            "extracted_from.csv",
+            sources,
+            None,
+        );
+    }
+
+    export.finish()
+}
+
+/// The Neo4j type of an exported property.
+#[derive(Clone, Copy)]
+enum Neo4jType {
+    String,
+    Long,
+    Double,
+}
+
+impl Neo4jType {
+    /// The type name used in `neo4j-admin` CSV headers.
+    fn header(&self) -> &str {
+        match self {
+            Neo4jType::String => "string",
+            Neo4jType::Long => "long",
+            Neo4jType::Double => "double",
+        }
+    }
+
+    /// The Cypher expression converting a `LOAD CSV` value to this type.
+    fn cypher(&self, value: &str) -> String {
+        match self {
+            Neo4jType::String => value.to_string(),
+            Neo4jType::Long => format!("toInteger({value})"),
+            Neo4jType::Double => format!("toFloat({value})"),
+        }
+    }
+}
+
+/// The files of a Neo4j export, built up node file by relationship file.
+#[derive(Default)]
+struct Neo4jExport {
+    files: Vec<(String, String)>,
+    node_files: Vec<String>,
+    relationship_files: Vec<String>,
+    cypher: String,
+}
+
+impl Neo4jExport {
+    /// Add a node file with one node per row, identified by the `id` column.
+    fn add_nodes(&mut self, label: &str, file: &str, table: &DataFrame, properties: &[(&str, Neo4jType)]) {
+        let mut header = vec![format!("{}:ID({label})", schemas::ID)];
+        header.extend(
+            properties
+                .iter()
+                .map(|(property, r#type)| format!("{property}:{}", r#type.header())),
+        );
+        header.push(":LABEL".to_string());
+
+        let ids = strings(table, schemas::ID);
+        let values = property_values(table, properties);
+        let mut csv = csv_line(&header);
+        for (row, id) in ids.iter().enumerate() {
+            let Some(id) = id else {
+                continue;
+            };
+            let mut line = vec![quote(id)];
+            line.extend(values.iter().map(|column| column[row].clone()));
+            line.push(label.to_string());
+            csv.push_str(&csv_line(&line));
+        }
+
+        let id = format!("row.`{}`", header[0]);
+        writeln!(
+            self.cypher,
+            "CREATE CONSTRAINT {}_id IF NOT EXISTS FOR (n:{label}) REQUIRE n.id IS UNIQUE;",
+            snake_case(label)
+        )
+        .unwrap();
+        writeln!(self.cypher, "LOAD CSV WITH HEADERS FROM 'file:///{file}' AS row").unwrap();
+        write!(self.cypher, "MERGE (n:{label} {{id: {id}}})").unwrap();
+        write!(self.cypher, "{}", set_clause("n", &header[1..header.len() - 1], properties)).unwrap();
+        self.cypher.push_str(";\n\n");
+
+        self.node_files.push(format!("--nodes={label}={file}"));
+        self.files.push((file.to_string(), csv));
+    }
+
+    /**
+    Add a relationship file.
+
+    Args:
+        - r#type: The relationship type.
+        - labels: The labels of the start and end nodes.
+        - file: The name of the relationship file.
+        - endpoints: The (row, start id, end id) of every relationship.
+        - properties: The table rows hold the relationship properties of (optional).
+    */
+    fn add_relationships(
+        &mut self,
+        r#type: &str,
+        labels: (&str, &str),
+        file: &str,
+        endpoints: Vec<(usize, String, String)>,
+        properties: Option<(&DataFrame, &[(&str, Neo4jType)])>,
+    ) {
+        let (start_label, end_label) = labels;
+        let mut header = vec![format!(":START_ID({start_label})"), format!(":END_ID({end_label})")];
+        let (values, properties) = match properties {
+            Some((table, properties)) => {
+                header.extend(
+                    properties
+                        .iter()
+                        .map(|(property, r#type)| format!("{property}:{}", r#type.header())),
+                );
+                (property_values(table, properties), properties)
+            }
+            None => (Vec::new(), &[][..]),
+        };
+        header.push(":TYPE".to_string());
+
+        let mut csv = csv_line(&header);
+        for (row, start, end) in &endpoints {
+            let mut line = vec![quote(start), quote(end)];
+            line.extend(values.iter().map(|column| column[*row].clone()));
+            line.push(r#type.to_string());
+            csv.push_str(&csv_line(&line));
+        }
+
+        writeln!(self.cypher, "LOAD CSV WITH HEADERS FROM 'file:///{file}' AS row").unwrap();
+        writeln!(self.cypher, "MATCH (a:{start_label} {{id: row.`{}`}})", header[0]).unwrap();
+        writeln!(self.cypher, "MATCH (b:{end_label} {{id: row.`{}`}})", header[1]).unwrap();
+        write!(self.cypher, "MERGE (a)-[r:{}]->(b)", r#type).unwrap();
+        write!(self.cypher, "{}", set_clause("r", &header[2..header.len() - 1], properties)).unwrap();
+        self.cypher.push_str(";\n\n");
+
+        self.relationship_files.push(format!("--relationships={}={file}", r#type));
+        self.files.push((file.to_string(), csv));
+    }
+
+    /// Add the Cypher script to the exported files.
+    fn finish(mut self) -> Vec<(String, String)> {
+        let mut script = String::new();
+        script.push_str("// Load the GraphRAG index into a running database.\n");
+        script.push_str("// Copy the CSV files to the Neo4j import directory before running this script.\n");
+        script.push_str("//\n");
+        script.push_str("// To create a new database from the same files instead, run:\n");
+        writeln!(
+            script,
+            "//   neo4j-admin database import full --multiline-fields=true {} {} neo4j",
+            self.node_files.join(" "),
+            self.relationship_files.join(" ")
+        )
+        .unwrap();
+        script.push('\n');
+        script.push_str(&self.cypher);
+
+        self.files.push((NEO4J_CYPHER_SCRIPT.to_string(), script));
+        self.files
+    }
+}
+
+/// Build the SET clause loading the given CSV columns into the properties of a node or relationship.
+fn set_clause(variable: &str, header: &[String], properties: &[(&str, Neo4jType)]) -> String {
+    let assignments: Vec<String> = header
+        .iter()
+        .zip(properties)
+        .map(|(column, (property, r#type))| {
+            format!("{variable}.{property} = {}", r#type.cypher(&format!("row.`{column}`")))
+        })
+        .collect();
+    if assignments.is_empty() {
+        String::new()
+    } else {
+        format!("\nSET {}", assignments.join(",\n    "))
+    }
+}
+
+/// Format the values of the given properties as CSV fields, one column per property.
+fn property_values(table: &DataFrame, properties: &[(&str, Neo4jType)]) -> Vec<Vec<String>> {
+    properties
+        .iter()
+        .map(|(property, r#type)| match r#type {
+            Neo4jType::String => strings(table, property)
+                .into_iter()
+                .map(|value| value.as_deref().map(quote).unwrap_or_default())
+                .collect(),
+            Neo4jType::Long => integers(table, property)
+                .into_iter()
+                .map(|value| value.map(|value| value.to_string()).unwrap_or_default())
+                .collect(),
+            Neo4jType::Double => floats(table, property)
+                .into_iter()
+                .map(|value| value.map(|value| value.to_string()).unwrap_or_default())
+                .collect(),
+        })
+        .collect()
+}
+
+/// Read a column as strings, all missing if the table has no such column.
+fn strings(table: &DataFrame, column: &str) -> Vec<Option<String>> {
+    table
+        .column(column)
+        .ok()
+        .and_then(|values| values.cast(&DataType::String).ok())
+        .map(|values| {
+            values
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|value| value.map(|value| value.to_string()))
+                .collect()
+        })
+        .unwrap_or_else(|| vec![None; table.height()])
+}
+
+/// Read a column as integers, all missing if the table has no such column.
+fn integers(table: &DataFrame, column: &str) -> Vec<Option<i64>> {
+    table
+        .column(column)
+        .ok()
+        .and_then(|values| values.cast(&DataType::Int64).ok())
+        .map(|values| values.i64().unwrap().into_iter().collect())
+        .unwrap_or_else(|| vec![None; table.height()])
+}
+
+/// Read a column as floats, all missing if the table has no such column.
+fn floats(table: &DataFrame, column: &str) -> Vec<Option<f64>> {
+    table
+        .column(column)
+        .ok()
+        .and_then(|values| values.cast(&DataType::Float64).ok())
+        .map(|values| values.f64().unwrap().into_iter().collect())
+        .unwrap_or_else(|| vec![None; table.height()])
+}
+
+/// Read a list column as lists of strings, all empty if the table has no such column.
+fn lists(table: &DataFrame, column: &str) -> Vec<Vec<String>> {
+    let Some(values) = table.column(column).ok().and_then(|values| values.list().ok()) else {
+        return vec![Vec::new(); table.height()];
+    };
+    (0..table.height())
+        .map(|row| {
+            values
+                .get_as_series(row)
+                .and_then(|list| list.cast(&DataType::String).ok())
+                .map(|list| {
+                    list.str()
+                        .unwrap()
+                        .into_iter()
+                        .flatten()
+                        .map(|value| value.to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Quote a CSV field, doubling any quote in it.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Join the fields of a CSV line.
+fn csv_line(fields: &[String]) -> String {
+    format!("{}\n", fields.join(","))
+}
+
+/// Convert a node label to snake case, for constraint names.
+fn snake_case(label: &str) -> String {
+    let mut name = String::new();
+    for (index, character) in label.chars().enumerate() {
+        if character.is_uppercase() && index > 0 {
+            name.push('_');
+        }
+        name.push(character.to_ascii_lowercase());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{IntoLazy, NamedFrom, Series};
+
+    use super::*;
+
+    fn frame(columns: Vec<Series>) -> LazyFrame {
+        DataFrame::new(columns.into_iter().map(Into::into).collect()).unwrap().lazy()
+    }
+
+    fn list(name: &str, rows: &[&[&str]]) -> Series {
+        let rows: Vec<Series> = rows.iter().map(|row| Series::new("".into(), *row)).collect();
+        Series::new(name.into(), rows)
+    }
+
+    /// Two entities, a community of both with a child community, and references to the missing entity `gone`,
+    /// the missing title `Z` and the missing text unit `t2`.
+    fn export() -> HashMap<String, String> {
+        let entities = frame(vec![
+            Series::new(schemas::ID.into(), ["e1", "e2"]),
+            Series::new(schemas::SHORT_ID.into(), [0i64, 1]),
+            Series::new(schemas::TITLE.into(), ["A", "B \"the second\""]),
+            Series::new(schemas::TYPE.into(), ["PERSON", "GEO"]),
+        ]);
+        let relationships = frame(vec![
+            Series::new(schemas::ID.into(), ["r1", "r2"]),
+            Series::new(schemas::EDGE_SOURCE.into(), ["A", "A"]),
+            Series::new(schemas::EDGE_TARGET.into(), ["B \"the second\"", "Z"]),
+            Series::new(schemas::EDGE_WEIGHT.into(), [2.5, 1.0]),
+        ]);
+        let communities = frame(vec![
+            Series::new(schemas::ID.into(), ["c1", "c2"]),
+            Series::new(schemas::COMMUNITY_ID.into(), [0i64, 1]),
+            Series::new(schemas::COMMUNITY_PARENT.into(), [-1i64, 0]),
+            list(schemas::ENTITY_IDS, &[&["e1", "e2", "gone"], &["e1"]]),
+        ]);
+        let community_reports = frame(vec![
+            Series::new(schemas::ID.into(), ["cr1"]),
+            Series::new(schemas::COMMUNITY_ID.into(), [0i64]),
+        ]);
+        let covariates = frame(vec![
+            Series::new(schemas::ID.into(), ["cv1", "cv2"]),
+            Series::new(schemas::CLAIM_SUBJECT.into(), ["A", "Z"]),
+            Series::new("text_unit_id".into(), ["t1", "t2"]),
+        ]);
+        let text_units = frame(vec![
+            Series::new(schemas::ID.into(), ["t1"]),
+            list(schemas::ENTITY_IDS, &[&["e1", "gone"]]),
+        ]);
+        export_neo4j(entities, relationships, communities, community_reports, Some(covariates), text_units)
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn csv_files_have_neo4j_admin_headers() {
+        let files = export();
+        let header = |file: &str| files[file].lines().next().unwrap().to_string();
+
+        assert_eq!(
+            header("entities.csv"),
+            "id:ID(Entity),human_readable_id:long,title:string,type:string,description:string,frequency:long,degree:long,:LABEL"
+        );
+        assert_eq!(
+            header("related_to.csv"),
+            ":START_ID(Entity),:END_ID(Entity),id:string,human_readable_id:long,description:string,weight:double,combined_degree:long,:TYPE"
+        );
+        assert_eq!(header("in_community.csv"), ":START_ID(Entity),:END_ID(Community),:TYPE");
+        assert_eq!(header("extracted_from.csv"), ":START_ID(Covariate),:END_ID(TextUnit),:TYPE");
+        assert_eq!(
+            files["entities.csv"].lines().skip(1).collect::<Vec<_>>(),
+            ["\"e1\",0,\"A\",\"PERSON\",,,,Entity", "\"e2\",1,\"B \"\"the second\"\"\",\"GEO\",,,,Entity"]
+        );
+        assert_eq!(
+            files["related_to.csv"].lines().skip(1).collect::<Vec<_>>(),
+            ["\"e1\",\"e2\",\"r1\",,,2.5,,RELATED_TO"]
+        );
+    }
+
+    #[test]
+    fn references_to_unknown_nodes_are_dropped() {
+        let files = export();
+        let rows = |file: &str| files[file].lines().skip(1).map(str::to_string).collect::<Vec<_>>();
+
+        assert_eq!(
+            rows("in_community.csv"),
+            [
+                "\"e1\",\"c1\",IN_COMMUNITY",
+                "\"e2\",\"c1\",IN_COMMUNITY",
+                "\"e1\",\"c2\",IN_COMMUNITY",
+            ]
+        );
+        assert_eq!(rows("child_of.csv"), ["\"c2\",\"c1\",CHILD_OF"]);
+        assert_eq!(rows("reports_on.csv"), ["\"cr1\",\"c1\",REPORTS_ON"]);
+        assert_eq!(rows("mentions.csv"), ["\"t1\",\"e1\",MENTIONS"]);
+        assert_eq!(rows("has_subject.csv"), ["\"cv1\",\"e1\",HAS_SUBJECT"]);
+        assert_eq!(rows("extracted_from.csv"), ["\"cv1\",\"t1\",EXTRACTED_FROM"]);
+    }
+
+    #[test]
+    fn cypher_script_loads_every_file() {
+        let script = &export()[NEO4J_CYPHER_SCRIPT];
+
+        assert!(script.contains(
+            "//   neo4j-admin database import full --multiline-fields=true \
+             --nodes=Entity=entities.csv --nodes=Community=communities.csv \
+             --nodes=CommunityReport=community_reports.csv --nodes=TextUnit=text_units.csv \
+             --nodes=Covariate=covariates.csv --relationships=RELATED_TO=related_to.csv \
+             --relationships=IN_COMMUNITY=in_community.csv --relationships=CHILD_OF=child_of.csv \
+             --relationships=REPORTS_ON=reports_on.csv --relationships=MENTIONS=mentions.csv \
+             --relationships=HAS_SUBJECT=has_subject.csv --relationships=EXTRACTED_FROM=extracted_from.csv neo4j\n"
+        ));
+        assert!(script.contains(
+            "CREATE CONSTRAINT text_unit_id IF NOT EXISTS FOR (n:TextUnit) REQUIRE n.id IS UNIQUE;\n\
+             LOAD CSV WITH HEADERS FROM 'file:///text_units.csv' AS row\n\
+             MERGE (n:TextUnit {id: row.`id:ID(TextUnit)`})\n\
+             SET n.human_readable_id = toInteger(row.`human_readable_id:long`),\n    \
+             n.text = row.`text:string`,\n    \
+             n.n_tokens = toInteger(row.`n_tokens:long`);\n"
+        ));
+        assert!(script.contains(
+            "LOAD CSV WITH HEADERS FROM 'file:///mentions.csv' AS row\n\
+             MATCH (a:TextUnit {id: row.`:START_ID(TextUnit)`})\n\
+             MATCH (b:Entity {id: row.`:END_ID(Entity)`})\n\
+             MERGE (a)-[r:MENTIONS]->(b);\n"
+        ));
+        assert_eq!(script.matches("LOAD CSV").count(), 12);
+    }
+}
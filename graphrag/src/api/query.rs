@@ -14,6 +14,7 @@ WARNING: This API is under development and may undergo changes in future release
 Backwards compatibility is not guaranteed at this time.
 */
 
+use chrono::NaiveDate;
 use polars::prelude::LazyFrame;
 
 use crate::callbacks::noop_query_callbacks::NoopQueryCallbacks;
@@ -25,6 +26,12 @@ use crate::config::embeddings::{
 };
 use crate::config::models::graph_rag_config::GraphRagConfig;
 use crate::logger::print_progress::PrintProgressLogger;
+use crate::query::context_builder::temporal::{
+    filter_entities_as_of,
+    filter_relationships_as_of,
+    filter_reports_as_of,
+    filter_text_units_as_of,
+};
 use crate::query::factory::{
     get_basic_search_engine,
     get_drift_search_engine,
@@ -41,6 +48,7 @@ use crate::query::indexer_adapters::{
     read_indexer_text_units,
 };
 use crate::utils::api::{
+    create_storage_from_config,
     get_embedding_store,
     load_search_prompt,
     update_context_data,
@@ -61,6 +69,7 @@ async def global_search(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -77,6 +86,7 @@ async def global_search(
     - dynamic_community_selection (bool): Enable dynamic community selection instead of using all community reports at a fixed level. Note that you can still provide community_level cap the maximum level to search.
     - response_type (str): The type of response to return.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -108,6 +118,7 @@ async def global_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     ):
         full_response += chunk
     return full_response, context_data
@@ -124,6 +135,7 @@ def global_search_streaming(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> AsyncGenerator:
     """Perform a global search and return the context data and response via a generator.
 
@@ -139,6 +151,7 @@ def global_search_streaming(
     - dynamic_community_selection (bool): Enable dynamic community selection instead of using all community reports at a fixed level. Note that you can still provide community_level cap the maximum level to search.
     - response_type (str): The type of response to return.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -158,6 +171,9 @@ def global_search_streaming(
     entities_ = read_indexer_entities(
         entities, communities, community_level=community_level
     )
+    # restrict the reports and entities to the graph as it stood at the requested date
+    reports = filter_reports_as_of(reports, &entities_, as_of)
+    entities_ = filter_entities_as_of(entities_, as_of)
     map_prompt = load_search_prompt(config.root_dir, config.global_search.map_prompt)
     reduce_prompt = load_search_prompt(
         config.root_dir, config.global_search.reduce_prompt
@@ -194,6 +210,7 @@ async def multi_index_global_search(
     streaming: bool,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -212,6 +229,7 @@ async def multi_index_global_search(
     - response_type (str): The type of response to return.
     - streaming (bool): Whether to stream the results or not.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -314,6 +332,7 @@ async def multi_index_global_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     )
 
     # Update the context data by linking index names and community ids
@@ -335,6 +354,7 @@ async def local_search(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -351,6 +371,7 @@ async def local_search(
     - community_level (int): The community level to search at.
     - response_type (str): The response type to return.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -384,6 +405,7 @@ async def local_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     ):
         full_response += chunk
     return full_response, context_data
@@ -402,6 +424,7 @@ def local_search_streaming(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> AsyncGenerator:
     """Perform a local search and return the context data and response via a generator.
 
@@ -416,6 +439,7 @@ def local_search_streaming(
     - community_level (int): The community level to search at.
     - response_type (str): The response type to return.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -434,7 +458,21 @@ def local_search_streaming(
     description_embedding_store = get_embedding_store(
         config_args=vector_store_args,
         embedding_name=entity_description_embedding,
-    )
+        storage=create_storage_from_config(config.output),
+        hybrid_search=Some(&config.hybrid_search),
+        multi_vector=Some(&config.multi_vector),
+    ).await?
+
+    // text units embedded with a vector per segment rank the text unit context by late interaction
+    text_unit_embedding_store = None
+    if config.multi_vector.enabled and text_unit_text_embedding in config.multi_vector.embeddings:
+        text_unit_embedding_store = get_embedding_store(
+            config_args=vector_store_args,
+            embedding_name=text_unit_text_embedding,
+            storage=create_storage_from_config(config.output),
+            hybrid_search=Some(&config.hybrid_search),
+            multi_vector=Some(&config.multi_vector),
+        ).await?
 
     entities_ = read_indexer_entities(entities, communities, community_level)
     covariates_ = read_indexer_covariates(covariates) if covariates is not None else []
@@ -448,11 +486,12 @@ def local_search_streaming(
         relationships=read_indexer_relationships(relationships),
         covariates={"claims": covariates_},
         description_embedding_store=description_embedding_store,
+        text_unit_embedding_store=text_unit_embedding_store,
         response_type=response_type,
         system_prompt=prompt,
         callbacks=callbacks,
-    )
-    return search_engine.stream_search(query=query)
+    )?
+    return search_engine.stream_search(query=query, as_of=as_of)
 
 
 @validate_call(config={"arbitrary_types_allowed": True})
@@ -470,6 +509,7 @@ async def multi_index_local_search(
     streaming: bool,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -489,6 +529,7 @@ async def multi_index_local_search(
     - response_type (str): The response type to return.
     - streaming (bool): Whether to stream the results or not.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -675,6 +716,7 @@ async def multi_index_local_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     )
 
     # Update the context data by linking index names and community ids
@@ -695,6 +737,7 @@ async def drift_search(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -710,6 +753,7 @@ async def drift_search(
     - relationships (LazyFrame): A LazyFrame containing the final relationships (from relationships.parquet)
     - community_level (int): The community level to search at.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -742,6 +786,7 @@ async def drift_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     ):
         full_response += chunk
     return full_response, context_data
@@ -759,6 +804,7 @@ def drift_search_streaming(
     response_type: str,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> AsyncGenerator:
     """Perform a DRIFT search and return the context data and response.
 
@@ -771,6 +817,7 @@ def drift_search_streaming(
     - relationships (LazyFrame): A LazyFrame containing the final relationships (from relationships.parquet)
     - community_level (int): The community level to search at.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -789,15 +836,27 @@ def drift_search_streaming(
     description_embedding_store = get_embedding_store(
         config_args=vector_store_args,
         embedding_name=entity_description_embedding,
-    )
+        storage=create_storage_from_config(config.output),
+        hybrid_search=None,
+        multi_vector=None,
+    ).await?
 
     full_content_embedding_store = get_embedding_store(
         config_args=vector_store_args,
         embedding_name=community_full_content_embedding,
-    )
+        storage=create_storage_from_config(config.output),
+        hybrid_search=None,
+        multi_vector=None,
+    ).await?
 
     entities_ = read_indexer_entities(entities, communities, community_level)
     reports = read_indexer_reports(community_reports, communities, community_level)
+    relationships_ = read_indexer_relationships(relationships)
+    # restrict the graph to the entities and relationships that hold at the requested date
+    reports = filter_reports_as_of(reports, &entities_, as_of)
+    text_units_ = filter_text_units_as_of(read_indexer_text_units(text_units), &relationships_, as_of)
+    entities_ = filter_entities_as_of(entities_, as_of)
+    relationships_ = filter_relationships_as_of(relationships_, as_of)
     read_indexer_report_embeddings(reports, full_content_embedding_store)
     prompt = load_search_prompt(config.root_dir, config.drift_search.prompt)
     reduce_prompt = load_search_prompt(
@@ -807,9 +866,9 @@ def drift_search_streaming(
     search_engine = get_drift_search_engine(
         config=config,
         reports=reports,
-        text_units=read_indexer_text_units(text_units),
+        text_units=text_units_,
         entities=entities_,
-        relationships=read_indexer_relationships(relationships),
+        relationships=relationships_,
         description_embedding_store=description_embedding_store,
         local_system_prompt=prompt,
         reduce_system_prompt=reduce_prompt,
@@ -833,6 +892,7 @@ async def multi_index_drift_search(
     streaming: bool,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -851,6 +911,7 @@ async def multi_index_drift_search(
     - response_type (str): The response type to return.
     - streaming (bool): Whether to stream the results or not.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -1013,6 +1074,7 @@ async def multi_index_drift_search(
         response_type=response_type,
         query=query,
         callbacks=callbacks,
+        as_of=as_of,
     )
 
     # Update the context data by linking index names and community ids
@@ -1031,6 +1093,8 @@ async def basic_search(
     text_units: LazyFrame,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    relationships: LazyFrame | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -1041,7 +1105,9 @@ async def basic_search(
     ----------
     - config (GraphRagConfig): A graphrag configuration (from settings.yaml)
     - text_units (LazyFrame): A LazyFrame containing the final text units (from text_units.parquet)
+    - relationships (LazyFrame): [Optional] A LazyFrame containing the final relationships (from relationships.parquet), required by as_of
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -1068,6 +1134,8 @@ async def basic_search(
         text_units=text_units,
         query=query,
         callbacks=callbacks,
+        relationships=relationships,
+        as_of=as_of,
     ):
         full_response += chunk
     return full_response, context_data
@@ -1079,6 +1147,8 @@ def basic_search_streaming(
     text_units: LazyFrame,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    relationships: LazyFrame | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> AsyncGenerator:
     """Perform a local search and return the context data and response via a generator.
 
@@ -1086,7 +1156,9 @@ def basic_search_streaming(
     ----------
     - config (GraphRagConfig): A graphrag configuration (from settings.yaml)
     - text_units (LazyFrame): A LazyFrame containing the final text units (from text_units.parquet)
+    - relationships (LazyFrame): [Optional] A LazyFrame containing the final relationships (from relationships.parquet), required by as_of
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -1105,13 +1177,23 @@ def basic_search_streaming(
     description_embedding_store = get_embedding_store(
         config_args=vector_store_args,
         embedding_name=text_unit_text_embedding,
-    )
-
+        storage=create_storage_from_config(config.output),
+        hybrid_search=Some(&config.hybrid_search),
+        multi_vector=Some(&config.multi_vector),
+    ).await?
+
+    text_units_ = read_indexer_text_units(text_units)
+    if as_of is not None:
+        if relationships is None:
+            message = "Searching as of a date needs the relationships of the text units"
+            raise ValueError(message)
+        # keep the text units stating something that holds at the requested date
+        text_units_ = filter_text_units_as_of(text_units_, &read_indexer_relationships(relationships), as_of)
     prompt = load_search_prompt(config.root_dir, config.basic_search.prompt)
 
     search_engine = get_basic_search_engine(
         config=config,
-        text_units=read_indexer_text_units(text_units),
+        text_units=text_units_,
         text_unit_embeddings=description_embedding_store,
         system_prompt=prompt,
         callbacks=callbacks,
@@ -1127,6 +1209,8 @@ async def multi_index_basic_search(
     streaming: bool,
     query: str,
     callbacks: Vec<QueryCallbacks> | None = None,
+    relationships_list: Vec<LazyFrame> | None = None,
+    as_of: Option<NaiveDate> = None,
 ) -> tuple[
     str | HashMap<String, Box<dyn Any>> | list[HashMap<String, Box<dyn Any>>],
     str | Vec<LazyFrame> | dict[str, LazyFrame],
@@ -1137,9 +1221,11 @@ async def multi_index_basic_search(
     ----------
     - config (GraphRagConfig): A graphrag configuration (from settings.yaml)
     - text_units_list (Vec<LazyFrame>): A list of DataFrames containing the final text units (from text_units.parquet)
+    - relationships_list (Vec<LazyFrame>): [Optional] A list of DataFrames containing the final relationships (from relationships.parquet), required by as_of
     - index_names (Vec<str>): A list of index names.
     - streaming (bool): Whether to stream the results or not.
     - query (str): The user query to search for.
+    - as_of (NaiveDate): [Optional] Restrict the search to the entities, relationships and text units that hold at this date.
 
     Returns
     -------
@@ -1185,9 +1271,17 @@ async def multi_index_basic_search(
         text_units_dfs, axis=0, ignore_index=True, sort=False
     )
 
+    relationships_combined = (
+        pd.concat(relationships_list, axis=0, ignore_index=True, sort=False)
+        if relationships_list is not None
+        else None
+    )
+
     return await basic_search(
         config,
         text_units=text_units_combined,
         query=query,
         callbacks=callbacks,
+        relationships=relationships_combined,
+        as_of=as_of,
     )
@@ -65,6 +65,8 @@ pub fn get_embedding_settings(
         "vector_store": {
             **(vector_store_params or {}),
             **(vector_store_settings),
+            "hybrid_search": settings.hybrid_search,
+            "multi_vector": settings.multi_vector,
         }
     });  // update the default strategy with the vector store settings
     // This ensures the vector store config is part of the strategy and not the global config
@@ -172,6 +172,8 @@ pub enum ModelType {
     // Embeddings
     OpenAIEmbedding,
     AzureOpenAIEmbedding,
+    /// A sentence-transformer exported to ONNX, run locally on the CPU.
+    OnnxEmbedding,
 
     // Chat Completion
     OpenAIChat,
@@ -187,6 +189,7 @@ impl ModelType {
         match self {
             ModelType::OpenAIEmbedding => "openai_embedding",
             ModelType::AzureOpenAIEmbedding => "azure_openai_embedding",
+            ModelType::OnnxEmbedding => "onnx_embedding",
             ModelType::OpenAIChat => "openai_chat",
             ModelType::AzureOpenAIChat => "azure_openai_chat",
             ModelType::MockChat => "mock_chat",
@@ -255,6 +258,70 @@ impl std::fmt::Debug for ChunkStrategyType {
     }
 }
 
+/// The community detection algorithm used to cluster the graph.
+pub enum ClusteringAlgorithmType {
+    /// Hierarchical Leiden, maximizing modularity with well connected communities.
+    Leiden,
+    /// Louvain, maximizing modularity without refining communities.
+    Louvain,
+    /// Label propagation, near linear time for very large graphs.
+    LabelPropagation,
+    /// Infomap, minimizing the description length of a random walk.
+    Infomap,
+}
+
+impl ClusteringAlgorithmType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ClusteringAlgorithmType::Leiden => "leiden",
+            ClusteringAlgorithmType::Louvain => "louvain",
+            ClusteringAlgorithmType::LabelPropagation => "label_propagation",
+            ClusteringAlgorithmType::Infomap => "infomap",
+        }
+    }
+}
+
+impl std::fmt::Debug for ClusteringAlgorithmType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The entity attribute used to rank entities.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EntityRankType {
+    /// The number of relationships of the entity.
+    Degree,
+    /// PageRank over the relationship graph.
+    PageRank,
+    /// Sampled betweenness centrality.
+    Betweenness,
+    /// Eigenvector centrality.
+    Eigenvector,
+    /// The k-core number.
+    CoreNumber,
+}
+
+impl EntityRankType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EntityRankType::Degree => "degree",
+            EntityRankType::PageRank => "pagerank",
+            EntityRankType::Betweenness => "betweenness",
+            EntityRankType::Eigenvector => "eigenvector",
+            EntityRankType::CoreNumber => "core_number",
+        }
+    }
+}
+
+impl std::fmt::Debug for EntityRankType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// The type of search to run.
 pub enum SearchMethod {
     Local,
@@ -304,7 +371,8 @@ pub enum NounPhraseExtractorType {
     RegexEnglish,
     /// Noun phrase extractor based on dependency parsing and NER using SpaCy.
     Syntactic,
-    /// Noun phrase extractor combining CFG-based noun-chunk extraction and NER.
+    /// Noun phrase extractor combining CFG-based noun-chunk extraction and NER,
+    /// using a built-in rule-based POS tagger (no downloaded models required).
     Cfg,
 }
 
@@ -317,3 +385,112 @@ impl NounPhraseExtractorType {
         }
     }
 }
+
+/// The distance used to compare vectors in the embedded vector store.
+#[derive(Clone, Copy, PartialEq)]
+pub enum VectorDistanceType {
+    /// Cosine distance, ignoring the length of the vectors.
+    Cosine,
+    /// Negative dot product, for vectors whose length is meaningful.
+    Dot,
+    /// Euclidean distance.
+    L2,
+}
+
+impl VectorDistanceType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VectorDistanceType::Cosine => "cosine",
+            VectorDistanceType::Dot => "dot",
+            VectorDistanceType::L2 => "l2",
+        }
+    }
+}
+
+impl std::fmt::Debug for VectorDistanceType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The quantization of the vectors held by the embedded vector store.
+#[derive(Clone, Copy, PartialEq)]
+pub enum VectorQuantizationType {
+    /// Full 32-bit vectors.
+    None,
+    /// One byte per dimension, scaled between the minimum and maximum of every dimension.
+    Int8,
+    /// One byte per group of dimensions, the nearest of 256 centroids learned for the group.
+    Product,
+}
+
+impl VectorQuantizationType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VectorQuantizationType::None => "none",
+            VectorQuantizationType::Int8 => "int8",
+            VectorQuantizationType::Product => "pq",
+        }
+    }
+}
+
+impl std::fmt::Debug for VectorQuantizationType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The file format of the embedding snapshots.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EmbeddingSnapshotFormat {
+    /// A parquet table of ids and vectors.
+    Parquet,
+    /// A NumPy float32 matrix, with the ids of its rows in a text file next to it.
+    Npy,
+    /// The vectors and metadata TSV files loaded by the TensorBoard embedding projector.
+    Tsv,
+}
+
+impl EmbeddingSnapshotFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EmbeddingSnapshotFormat::Parquet => "parquet",
+            EmbeddingSnapshotFormat::Npy => "npy",
+            EmbeddingSnapshotFormat::Tsv => "tsv",
+        }
+    }
+}
+
+impl std::fmt::Debug for EmbeddingSnapshotFormat {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// How texts are split when they are embedded with several vectors.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MultiVectorSplitType {
+    /// One vector per sentence, long sentences being split into windows.
+    Sentence,
+    /// One vector per overlapping window of words.
+    Window,
+}
+
+impl MultiVectorSplitType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MultiVectorSplitType::Sentence => "sentence",
+            MultiVectorSplitType::Window => "window",
+        }
+    }
+}
+
+impl std::fmt::Debug for MultiVectorSplitType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
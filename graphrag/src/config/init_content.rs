@@ -34,7 +34,7 @@ models:
     tokens_per_minute: 0              # set to 0 to disable rate limiting
     requests_per_minute: 0            # set to 0 to disable rate limiting
   {defs.DEFAULT_EMBEDDING_MODEL_ID}:
-    type: {defs.DEFAULT_EMBEDDING_MODEL_TYPE.value} # or azure_openai_embedding
+    type: {defs.DEFAULT_EMBEDDING_MODEL_TYPE.value} # or azure_openai_embedding, or onnx_embedding with model set to a local model directory
     # api_base: https://<instance>.openai.azure.com
     # api_version: 2024-05-01-preview
     auth_type: {defs.DEFAULT_EMBEDDING_MODEL_AUTH_TYPE.value} # or azure_managed_identity
@@ -82,10 +82,14 @@ reporting:
 
 vector_store:
   {defs.DEFAULT_VECTOR_STORE_ID}:
-    type: {VECTOR_STORE.type}
-    db_uri: {VECTOR_STORE.db_uri}
+    type: {VECTOR_STORE.type} # [hnsw, lancedb, azure_ai_search, cosmosdb, qdrant, pgvector]
+    # db_uri: output/lancedb # only used when type == lancedb
+    # url: http://localhost:6333 # only used when type == azure_ai_search, cosmosdb, qdrant or pgvector
     container_name: {VECTOR_STORE.container_name}
     overwrite: {VECTOR_STORE.overwrite}
+    rebuild: {VECTOR_STORE.rebuild} # if true, embeds every row again instead of only new and changed rows
+    distance: {VECTOR_STORE.distance.value} # [cosine, dot, l2]
+    quantization: {VECTOR_STORE.quantization.value} # [none, int8, pq]
 
 ### Workflow settings ###
 
@@ -93,6 +97,15 @@ embed_text:
   model_id: {GRAPHRAG_CONFIG.embed_text.model_id}
   vector_store_id: {GRAPHRAG_CONFIG.embed_text.vector_store_id}
 
+hybrid_search:
+  enabled: false # if true, will build BM25 indexes and fuse them with vector search in local and basic search
+  embeddings: [{",".join(GRAPHRAG_CONFIG.hybrid_search.embeddings)}]
+
+multi_vector:
+  enabled: false # if true, will store a vector per segment and rank texts by their best matching segments in local and basic search
+  embeddings: [{",".join(GRAPHRAG_CONFIG.multi_vector.embeddings)}]
+  split: sentence # [sentence, window]
+
 extract_graph:
   model_id: {GRAPHRAG_CONFIG.extract_graph.model_id}
   prompt: "prompts/extract_graph.txt"
@@ -103,13 +116,18 @@ summarize_descriptions:
   model_id: {GRAPHRAG_CONFIG.summarize_descriptions.model_id}
   prompt: "prompts/summarize_descriptions.txt"
   max_length: {GRAPHRAG_CONFIG.summarize_descriptions.max_length}
+  max_input_tokens: {GRAPHRAG_CONFIG.summarize_descriptions.max_input_tokens}
 
 extract_graph_nlp:
   text_analyzer:
     extractor_type: {GRAPHRAG_CONFIG.extract_graph_nlp.text_analyzer.extractor_type.value} # [regex_english, syntactic_parser, cfg]
 
 cluster_graph:
+  algorithm: {GRAPHRAG_CONFIG.cluster_graph.algorithm.value} # [leiden, louvain, label_propagation, infomap]
   max_cluster_size: {GRAPHRAG_CONFIG.cluster_graph.max_cluster_size}
+  resolutions: [{",".join(map(str, GRAPHRAG_CONFIG.cluster_graph.resolutions))}] # one per level, the last one is reused for deeper levels
+  # max_depth: 3
+  min_community_size: {GRAPHRAG_CONFIG.cluster_graph.min_community_size}
 
 extract_claims:
   enabled: false
@@ -128,12 +146,23 @@ community_reports:
 embed_graph:
   enabled: false # if true, will generate node2vec embeddings for nodes
 
+graph_centrality: # additional entity columns, each can be used to rank entities in local search
+  pagerank: false
+  betweenness: false
+  eigenvector: false
+  core_number: false
+
+graph_stats:
+  enabled: false # if true, will write graph_stats.json with graph quality statistics
+
 umap:
   enabled: false # if true, will generate UMAP embeddings for nodes (embed_graph must also be enabled)
 
 snapshots:
   graphml: false
+  gexf: false
   embeddings: false
+  embeddings_format: parquet # [parquet, npy, tsv]
 
 ### Query settings ###
 ## The prompt locations are required here, but each search method has a number of optional knobs that can be tuned.
@@ -143,6 +172,7 @@ local_search:
   chat_model_id: {GRAPHRAG_CONFIG.local_search.chat_model_id}
   embedding_model_id: {GRAPHRAG_CONFIG.local_search.embedding_model_id}
   prompt: "prompts/local_search_system_prompt.txt"
+  entity_rank_attribute: degree # [degree, pagerank, betweenness, eigenvector, core_number]
 
 global_search:
   chat_model_id: {GRAPHRAG_CONFIG.global_search.chat_model_id}
@@ -11,10 +11,14 @@ pub mod extract_claims_config;
 pub mod extract_graph_config;
 pub mod extract_graph_nlp_config;
 pub mod global_search_config;
+pub mod graph_centrality_config;
 pub mod graph_rag_config;
+pub mod graph_stats_config;
+pub mod hybrid_search_config;
 pub mod input_config;
 pub mod language_model_config;
 pub mod local_search_config;
+pub mod multi_vector_config;
 pub mod output_config;
 pub mod prune_graph_config;
 pub mod reporting_config;
@@ -1,7 +1,12 @@
 //! Parameterization settings for the default configuration.
 
+use crate::config::enums::ClusteringAlgorithmType;
+
 /// Configuration section for clustering graphs.
 pub struct ClusterGraphConfig {
+    /// The community detection algorithm to use.
+    pub algorithm: ClusteringAlgorithmType,
+
     /// The maximum cluster size to use.
     pub max_cluster_size: usize,
 
@@ -10,15 +15,29 @@ pub struct ClusterGraphConfig {
 
     /// The seed to use for the clustering.
     pub seed: usize,
+
+    /// The resolution to use for each level of the hierarchy, the last one is reused for deeper levels.
+    /// Only used by the modularity based algorithms (Leiden and Louvain).
+    pub resolutions: Vec<f64>,
+
+    /// The maximum number of levels in the hierarchy, unlimited if not set.
+    pub max_depth: Option<usize>,
+
+    /// The minimum number of entities in a community, smaller communities are merged into their parent.
+    pub min_community_size: usize,
 }
 
 impl Default for ClusterGraphConfig {
     /// Default values for cluster graph.
     fn default() -> Self {
         ClusterGraphConfig {
+            algorithm: ClusteringAlgorithmType::Leiden,
             max_cluster_size: 10,
             use_lcc: true,
             seed: 0xDEADBEEF,
+            resolutions: vec![1.0],
+            max_depth: None,
+            min_community_size: 1,
         }
     }
 }
@@ -3,9 +3,13 @@
 use std::collections::HashMap;
 use std::path::Path;
 
+use log::warn;
+
 use crate::config::defaults::DEFAULT_CHAT_MODEL_ID;
-use crate::config::models::language_model_config::LanguageModelConfig;
-use crate::index::operations::extract_graph::typing::ExtractEntityStrategyType;
+use crate::index::operations::extract_graph::typing::{
+    ExtractEntityStrategyType,
+    ExtractGraphStrategy,
+};
 
 /// Configuration section for entity extraction.
 pub struct ExtractGraphConfig {
@@ -21,7 +25,7 @@ pub struct ExtractGraphConfig {
     /// The maximum number of entity gleanings to use.
     pub max_gleanings: usize,
 
-    /// Override the default entity extraction strategy
+    /// The override strategy to use, with `type`, `extraction_prompt`, `tuple_delimiter`, `record_delimiter`, `completion_delimiter` and `max_gleanings` keys.
     pub strategy: Option<HashMap<String, String>>,
 
     /// The encoding model to use.
@@ -48,21 +52,43 @@ impl Default for ExtractGraphConfig {
 }
 
 impl ExtractGraphConfig {
-    /// Get the resolved entity extraction strategy.
-    pub fn resolved_strategy(self, root_dir: &str, model_config: LanguageModelConfig) -> HashMap {
-        self.strategy.unwrap_or(HashMap::from([
-            ("type", ExtractEntityStrategyType.graph_intelligence),
-            ("llm", model_config.model_dump()),
-            ("num_threads", model_config.concurrent_requests),
-            (
-                "extraction_prompt",
-                match self.prompt {
-                    Some(prompt) => (Path(root_dir) / self.prompt).read_text(encoding = "utf-8"),
-                    None => None,
-                },
-            ),
-            ("max_gleanings", self.max_gleanings),
-            ("encoding_name", model_config.encoding_model),
-        ]))
+    /**
+    Get the resolved entity extraction strategy, with the values of `strategy` overriding the settings.
+
+    Fails if the configured prompt file cannot be read.
+    */
+    pub fn resolved_strategy(&self, root_dir: &str) -> Result<ExtractGraphStrategy, String> {
+        let strategy = self.strategy.clone().unwrap_or_default();
+
+        let r#type = match strategy.get("type").map(String::as_str) {
+            None | Some("graph_intelligence") => ExtractEntityStrategyType::GraphIntelligence,
+            Some(other) => {
+                warn!("Unknown entity extraction strategy {other}, using graph_intelligence");
+                ExtractEntityStrategyType::GraphIntelligence
+            }
+        };
+        let extraction_prompt = match (strategy.get("extraction_prompt"), &self.prompt) {
+            (Some(prompt), _) => Some(prompt.clone()),
+            (None, Some(prompt)) => {
+                let path = Path::new(root_dir).join(prompt);
+                let prompt = std::fs::read_to_string(&path).map_err(|error| {
+                    format!("Failed to read the extraction prompt {}: {error}", path.display())
+                })?;
+                Some(prompt)
+            }
+            (None, None) => None,
+        };
+
+        Ok(ExtractGraphStrategy {
+            r#type,
+            extraction_prompt,
+            tuple_delimiter: strategy.get("tuple_delimiter").cloned(),
+            record_delimiter: strategy.get("record_delimiter").cloned(),
+            completion_delimiter: strategy.get("completion_delimiter").cloned(),
+            max_gleanings: strategy
+                .get("max_gleanings")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(self.max_gleanings),
+        })
     }
 }
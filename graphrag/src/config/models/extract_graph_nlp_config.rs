@@ -41,7 +41,7 @@ impl Default for TextAnalyzerConfig {
     /// Default values for text analyzer.
     fn default() -> Self {
         TextAnalyzerConfig {
-            extractor_type: NounPhraseExtractorType::RegexEnglish,
+            extractor_type: NounPhraseExtractorType::Cfg,
             model_name: "en_core_web_md".into(),
             max_word_length: 15,
             word_delimiter: " ".into(),
@@ -0,0 +1,40 @@
+//! Parameterization settings for the default configuration.
+
+/// Configuration section for graph centrality metrics.
+pub struct GraphCentralityConfig {
+    /// A flag indicating whether to compute PageRank.
+    pub pagerank: bool,
+
+    /// The PageRank damping factor.
+    pub pagerank_damping: f64,
+
+    /// A flag indicating whether to compute betweenness centrality.
+    pub betweenness: bool,
+
+    /// The number of source nodes to sample for betweenness centrality, all nodes are used for smaller graphs.
+    pub betweenness_samples: usize,
+
+    /// A flag indicating whether to compute eigenvector centrality.
+    pub eigenvector: bool,
+
+    /// A flag indicating whether to compute the k-core number.
+    pub core_number: bool,
+
+    /// The seed to use for sampling.
+    pub seed: usize,
+}
+
+impl Default for GraphCentralityConfig {
+    /// Default values for graph centrality.
+    fn default() -> Self {
+        GraphCentralityConfig {
+            pagerank: false,
+            pagerank_damping: 0.85,
+            betweenness: false,
+            betweenness_samples: 256,
+            eigenvector: false,
+            core_number: false,
+            seed: 0xDEADBEEF,
+        }
+    }
+}
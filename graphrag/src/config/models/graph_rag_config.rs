@@ -18,9 +18,13 @@ use crate::config::models::extract_claims_config::ClaimExtractionConfig;
 use crate::config::models::extract_graph_config::ExtractGraphConfig;
 use crate::config::models::extract_graph_nlp_config::ExtractGraphNLPConfig;
 use crate::config::models::global_search_config::GlobalSearchConfig;
+use crate::config::models::graph_centrality_config::GraphCentralityConfig;
+use crate::config::models::graph_stats_config::GraphStatsConfig;
+use crate::config::models::hybrid_search_config::HybridSearchConfig;
 use crate::config::models::input_config::InputConfig;
 use crate::config::models::language_model_config::LanguageModelConfig;
 use crate::config::models::local_search_config::LocalSearchConfig;
+use crate::config::models::multi_vector_config::MultiVectorConfig;
 use crate::config::models::output_config::OutputConfig;
 use crate::config::models::prune_graph_config::PruneGraphConfig;
 use crate::config::models::reporting_config::ReportingConfig;
@@ -69,6 +73,12 @@ pub struct GraphRagConfig {
     /// Text embedding configuration.
     pub embed_text: TextEmbeddingConfig,
 
+    /// The hybrid BM25 and vector retrieval configuration to use.
+    pub hybrid_search: HybridSearchConfig,
+
+    /// The multi-vector, late-interaction embedding configuration to use.
+    pub multi_vector: MultiVectorConfig,
+
     /// The entity extraction configuration to use.
     pub extract_graph: ExtractGraphConfig,
 
@@ -93,6 +103,12 @@ pub struct GraphRagConfig {
     /// Graph embedding configuration.
     pub embed_graph: EmbedGraphConfig,
 
+    /// The graph centrality configuration to use.
+    pub graph_centrality: GraphCentralityConfig,
+
+    /// The graph quality report configuration to use.
+    pub graph_stats: GraphStatsConfig,
+
     /// The UMAP configuration to use.
     pub umap: UmapConfig,
 
@@ -135,6 +151,8 @@ impl Default for GraphRagConfig {
             )]),
             workflows: None,
             embed_text: TextEmbeddingConfig::default(),
+            hybrid_search: HybridSearchConfig::default(),
+            multi_vector: MultiVectorConfig::default(),
             extract_graph: ExtractGraphConfig::default(),
             summarize_descriptions: SummarizeDescriptionsConfig::default(),
             extract_graph_nlp: ExtractGraphNLPConfig::default(),
@@ -143,6 +161,8 @@ impl Default for GraphRagConfig {
             extract_claims: ClaimExtractionConfig::default(),
             community_reports: CommunityReportsConfig::default(),
             embed_graph: EmbedGraphConfig::default(),
+            graph_centrality: GraphCentralityConfig::default(),
+            graph_stats: GraphStatsConfig::default(),
             umap: UmapConfig::default(),
             snapshots: SnapshotsConfig::default(),
             local_search: LocalSearchConfig::default(),
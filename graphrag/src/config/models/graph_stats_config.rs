@@ -0,0 +1,20 @@
+//! Parameterization settings for the default configuration.
+
+/// Configuration section for the graph quality report.
+pub struct GraphStatsConfig {
+    /// A flag indicating whether to write the graph quality report.
+    pub enabled: bool,
+
+    /// The number of most connected entities to report.
+    pub top_hubs: usize,
+}
+
+impl Default for GraphStatsConfig {
+    /// Default values for graph stats.
+    fn default() -> Self {
+        GraphStatsConfig {
+            enabled: false,
+            top_hubs: 10,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Parameterization settings for the default configuration.
+
+use crate::config::embeddings::{ENTITY_DESCRIPTION_EMBEDDING, TEXT_UNIT_TEXT_EMBEDDING};
+
+/// Configuration section for hybrid BM25 and vector retrieval.
+pub struct HybridSearchConfig {
+    /// A flag indicating whether to build BM25 indexes and fuse them with vector search.
+    pub enabled: bool,
+
+    /// The embeddings to build a BM25 index for.
+    pub embeddings: Vec<String>,
+
+    /// The BM25 term frequency saturation.
+    pub k1: f64,
+
+    /// The BM25 document length normalization.
+    pub b: f64,
+
+    /// The reciprocal rank fusion constant, higher values flatten the contribution of top ranks.
+    pub rrf_k: f64,
+
+    /// How many times `k` results are retrieved from each index before fusing them.
+    pub oversample: usize,
+}
+
+impl Default for HybridSearchConfig {
+    /// Default values for hybrid search.
+    fn default() -> Self {
+        HybridSearchConfig {
+            enabled: false,
+            embeddings: vec![
+                TEXT_UNIT_TEXT_EMBEDDING.into(),
+                ENTITY_DESCRIPTION_EMBEDDING.into(),
+            ],
+            k1: 1.2,
+            b: 0.75,
+            rrf_k: 60.0,
+            oversample: 2,
+        }
+    }
+}
@@ -23,7 +23,7 @@ pub struct LanguageModelConfig {
     /// The LLM model to use.
     pub model: String,
 
-    /// The encoding model to use
+    /// The encoding model to use, left empty for ONNX models, which tokenize with their own tokenizer.
     pub encoding_model: String,
 
     /// The base URL for the LLM API.
@@ -201,7 +201,8 @@ impl LanguageModelConfig {
         If the model name is not recognized.
      */
     fn _validate_encoding_model(&mut self) {
-        if self.encoding_model.trim() == "" {
+        // local models are paths rather than model names, and split long texts with their own tokenizer
+        if self.encoding_model.trim() == "" && !matches!(self.r#type, ModelType::OnnxEmbedding) {
             self.encoding_model = tiktoken_rs::get_bpe_from_model(&self.model);
         }
     }
@@ -1,6 +1,7 @@
 //! Parameterization settings for the default configuration.
 
 use crate::config::defaults::{DEFAULT_CHAT_MODEL_ID, DEFAULT_EMBEDDING_MODEL_ID};
+use crate::config::enums::EntityRankType;
 
 /// The default configuration section for Cache.
 pub struct LocalSearchConfig {
@@ -28,6 +29,9 @@ pub struct LocalSearchConfig {
     /// The top k mapped relations.
     pub top_k_relationships: usize,
 
+    /// The entity attribute used to rank neighboring entities.
+    pub entity_rank_attribute: EntityRankType,
+
     /// The temperature to use for token generation.
     pub temperature: f64,
 
@@ -56,6 +60,7 @@ impl Default for LocalSearchConfig {
             conversation_history_max_turns: 5,
             top_k_entities: 10,
             top_k_relationships: 10,
+            entity_rank_attribute: EntityRankType::Degree,
             temperature: 0.0,
             top_p: 1.0,
             n: 1,
@@ -0,0 +1,43 @@
+//! Parameterization settings for the default configuration.
+
+use crate::config::embeddings::TEXT_UNIT_TEXT_EMBEDDING;
+use crate::config::enums::MultiVectorSplitType;
+
+/// Configuration section for multi-vector, late-interaction embeddings.
+pub struct MultiVectorConfig {
+    /// A flag indicating whether to embed texts with a vector per segment and search them with max-sim.
+    pub enabled: bool,
+
+    /// The embeddings to store several vectors for.
+    pub embeddings: Vec<String>,
+
+    /// How texts are split into segments.
+    pub split: MultiVectorSplitType,
+
+    /// The maximum number of words of a segment.
+    pub window_size: usize,
+
+    /// The number of words shared by consecutive windows.
+    pub window_overlap: usize,
+
+    /// The maximum number of vectors of a text, consecutive segments are merged beyond it.
+    pub max_vectors: usize,
+
+    /// How many times `k` segments are retrieved for every query segment before aggregating them.
+    pub oversample: usize,
+}
+
+impl Default for MultiVectorConfig {
+    /// Default values for multi-vector embeddings.
+    fn default() -> Self {
+        MultiVectorConfig {
+            enabled: false,
+            embeddings: vec![TEXT_UNIT_TEXT_EMBEDDING.into()],
+            split: MultiVectorSplitType::Sentence,
+            window_size: 64,
+            window_overlap: 16,
+            max_vectors: 32,
+            oversample: 4,
+        }
+    }
+}
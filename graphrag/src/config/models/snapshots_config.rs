@@ -1,12 +1,20 @@
 //! Parameterization settings for the default configuration.
 
+use crate::config::enums::EmbeddingSnapshotFormat;
+
 /// Configuration section for snapshots.
 pub struct SnapshotsConfig {
     /// A flag indicating whether to take snapshots of embeddings.
     pub embeddings: bool,
 
+    /// The file format of the embedding snapshots.
+    pub embeddings_format: EmbeddingSnapshotFormat,
+
     /// A flag indicating whether to take snapshots of GraphML.
     pub graphml: bool,
+
+    /// A flag indicating whether to take snapshots of GEXF.
+    pub gexf: bool,
 }
 
 impl Default for SnapshotsConfig {
@@ -14,7 +22,9 @@ impl Default for SnapshotsConfig {
     fn default() -> Self {
         SnapshotsConfig {
             embeddings: false,
+            embeddings_format: EmbeddingSnapshotFormat::Parquet,
             graphml: false,
+            gexf: false,
         }
     }
 }
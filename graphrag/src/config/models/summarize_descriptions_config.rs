@@ -1,9 +1,15 @@
 //! Parameterization settings for the default configuration.
 
 use std::collections::HashMap;
+use std::path::Path;
+
+use log::warn;
 
 use crate::config::defaults::DEFAULT_CHAT_MODEL_ID;
-// use crate::config::models::language_model_config::LanguageModelConfig;
+use crate::index::operations::summarize_descriptions::typing::{
+    SummarizeStrategy,
+    SummarizeStrategyType,
+};
 
 /// Configuration section for description summarization.
 pub struct SummarizeDescriptionsConfig {
@@ -16,7 +22,10 @@ pub struct SummarizeDescriptionsConfig {
     /// The description summarization maximum length.
     pub max_length: usize,
 
-    /// The override strategy to use.
+    /// The maximum number of input tokens per summarization batch. Longer description lists are summarized hierarchically.
+    pub max_input_tokens: usize,
+
+    /// The override strategy to use, with `type`, `summarize_prompt`, `max_summary_length` and `max_input_tokens` keys.
     pub strategy: Option<HashMap<String, String>>,
 }
 
@@ -27,29 +36,48 @@ impl Default for SummarizeDescriptionsConfig {
             model_id: DEFAULT_CHAT_MODEL_ID.into(),
             prompt: None,
             max_length: 500,
+            max_input_tokens: 4000,
             strategy: None,
         }
     }
 }
 
-// impl SummarizeDescriptionsConfig {
-//     pub fn resolved_strategy(
-//         self, root_dir: str, model_config: LanguageModelConfig
-//     ) -> dict:
-//         /// Get the resolved description summarization strategy.
-//         use crate::index.operations.summarize_descriptions::(
-//             SummarizeStrategyType,
-//         )
-
-//         return self.strategy or {
-//             "type": SummarizeStrategyType.graph_intelligence,
-//             "llm": model_config.model_dump(),
-//             "num_threads": model_config.concurrent_requests,
-//             "summarize_prompt": (Path(root_dir) / self.prompt).read_text(
-//                 encoding="utf-8"
-//             )
-//             if self.prompt
-//             else None,
-//             "max_summary_length": self.max_length,
-//         }
-//     }
+impl SummarizeDescriptionsConfig {
+    /**
+    Get the resolved description summarization strategy, with the values of `strategy` overriding the settings.
+
+    Fails if the configured prompt file cannot be read.
+    */
+    pub fn resolved_strategy(&self, root_dir: &str) -> Result<SummarizeStrategy, String> {
+        let strategy = self.strategy.clone().unwrap_or_default();
+        let number = |key: &str, default: usize| {
+            strategy.get(key).and_then(|value| value.parse().ok()).unwrap_or(default)
+        };
+
+        let r#type = match strategy.get("type").map(String::as_str) {
+            None | Some("graph_intelligence") => SummarizeStrategyType::GraphIntelligence,
+            Some(other) => {
+                warn!("Unknown summarization strategy {other}, using graph_intelligence");
+                SummarizeStrategyType::GraphIntelligence
+            }
+        };
+        let summarize_prompt = match (strategy.get("summarize_prompt"), &self.prompt) {
+            (Some(prompt), _) => Some(prompt.clone()),
+            (None, Some(prompt)) => {
+                let path = Path::new(root_dir).join(prompt);
+                let prompt = std::fs::read_to_string(&path).map_err(|error| {
+                    format!("Failed to read the summarization prompt {}: {error}", path.display())
+                })?;
+                Some(prompt)
+            }
+            (None, None) => None,
+        };
+
+        Ok(SummarizeStrategy {
+            r#type,
+            summarize_prompt,
+            max_summary_length: number("max_summary_length", self.max_length),
+            max_input_tokens: number("max_input_tokens", self.max_input_tokens),
+        })
+    }
+}
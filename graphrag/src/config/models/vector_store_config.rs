@@ -1,19 +1,20 @@
 //! Parameterization settings for the default configuration.
 
+use crate::config::enums::{VectorDistanceType, VectorQuantizationType};
 use crate::vector_stores::factory::VectorStoreType;
 
 /// The default configuration section for Vector Store.
 pub struct VectorStoreConfig {
-    /// The vector store type to use.
+    /// The vector store type to use, a built-in type or one registered with `VectorStoreFactory::register`.
     pub r#type: String,
 
     /// The database URI to use.
     pub db_uri: Option<String>,
 
-    /// The database URL when type == azure_ai_search.
+    /// The database URL when type == azure_ai_search, qdrant or pgvector.
     pub url: Option<String>,
 
-    /// The database API key when type == azure_ai_search.
+    /// The database API key when type == azure_ai_search or qdrant, or the password when type == pgvector.
     pub api_key: Option<String>,
 
     /// The database audience when type == azure_ai_search.
@@ -22,25 +23,60 @@ pub struct VectorStoreConfig {
     /// The container name to use.
     pub container_name: String,
 
-    /// The database name to use when type == cosmos_db.
+    /// The database name to use when type == cosmos_db or pgvector.
     pub database_name: Option<String>,
 
-    /// Overwrite the existing data.
+    /// Replace a collection holding no content hashes, such as one written before they were stored, instead of
+    /// adding to it. Collections with content hashes are always updated, embedding new and changed rows and
+    /// deleting removed ones.
     pub overwrite: bool,
+
+    /// Embed every row again and replace the collection, instead of only embedding new and changed rows.
+    pub rebuild: bool,
+
+    /// The distance used to compare vectors when type == hnsw, qdrant or pgvector.
+    pub distance: VectorDistanceType,
+
+    /// The maximum number of neighbors of every node of the index when type == hnsw.
+    pub hnsw_m: usize,
+
+    /// The number of candidates considered while building the index when type == hnsw.
+    pub hnsw_ef_construction: usize,
+
+    /// The number of candidates considered while searching the index when type == hnsw.
+    pub hnsw_ef_search: usize,
+
+    /// The quantization of the vectors searched in memory when type == hnsw, or on the server when type == qdrant.
+    pub quantization: VectorQuantizationType,
+
+    /// The number of dimensions encoded by every byte when quantization == pq.
+    pub pq_subvector_size: usize,
+
+    /// How many quantized candidates are re-ranked against the full vectors for every result,
+    /// 0 keeps only the quantized vectors in memory.
+    pub rerank_factor: usize,
 }
 
 impl Default for VectorStoreConfig {
     /// Default values for vector stores.
     fn default() -> Self {
         VectorStoreConfig {
-            r#type: VectorStoreType::LanceDB.as_str().into(),
-            db_uri: Some("output/lancedb".into()),
+            r#type: VectorStoreType::Hnsw.as_str().into(),
+            db_uri: None,
             url: None,
             api_key: None,
             audience: None,
             container_name: "default".into(),
             database_name: None,
             overwrite: true,
+            rebuild: false,
+            distance: VectorDistanceType::Cosine,
+            hnsw_m: 16,
+            hnsw_ef_construction: 200,
+            hnsw_ef_search: 64,
+            quantization: VectorQuantizationType::None,
+            pq_subvector_size: 8,
+            rerank_factor: 4,
         }
     }
 }
@@ -51,7 +87,7 @@ impl Default for VectorStoreConfig {
 //         if self.r#type == VectorStoreType.LanceDB.value && (
 //             self.db_uri.is_none() or self.db_uri.strip() == ""
 //         ):
-//             self.db_uri = vector_store_defaults.db_uri
+//             self.db_uri = "output/lancedb"
 
 //         if self.type != VectorStoreType.LanceDB.value and (
 //             self.db_uri is not None and self.db_uri.strip() != ""
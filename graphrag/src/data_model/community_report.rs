@@ -32,7 +32,7 @@ pub struct CommunityReport {
     pub rank: Option<f64>, // = 1.0
 
     /// The semantic (i.e. text) embedding of the full report content (optional).
-    pub full_content_embedding: Option<Vec<f64>>, // = None
+    pub full_content_embedding: Option<Vec<f32>>, // = None
 
     /// A dictionary of additional attributes associated with the report (optional).
     pub attributes: Option<HashMap<String, Value>>, // = None
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// A protocol for an entity in the system.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Entity {
     /// The ID of the item.
     pub id: String,
@@ -25,10 +25,10 @@ pub struct Entity {
     pub description: Option<String>,
 
     /// The semantic (i.e. text) embedding of the entity (optional).
-    pub description_embedding: Option<Vec<f64>>,
+    pub description_embedding: Option<Vec<f32>>,
 
     /// The semantic (i.e. text) embedding of the entity (optional).
-    pub name_embedding: Option<Vec<f64>>,
+    pub name_embedding: Option<Vec<f32>>,
 
     /// The community IDs of the entity (optional).
     pub community_ids: Option<Vec<String>>,
@@ -39,6 +39,24 @@ pub struct Entity {
     /// Rank of the entity, used for sorting (optional). Higher rank indicates more important entity. This can be based on centrality or other metrics.
     pub rank: Option<usize>, // TODO(rinarakaki) = 1,
 
+    /// PageRank of the entity in the relationship graph (optional).
+    pub pagerank: Option<f64>,
+
+    /// Sampled betweenness centrality of the entity in the relationship graph (optional).
+    pub betweenness: Option<f64>,
+
+    /// Eigenvector centrality of the entity in the relationship graph (optional).
+    pub eigenvector: Option<f64>,
+
+    /// The k-core number of the entity in the relationship graph (optional).
+    pub core_number: Option<usize>,
+
+    /// The earliest ISO-8601 date at which any relationship of the entity holds (optional).
+    pub valid_from: Option<String>,
+
+    /// The latest ISO-8601 date at which any relationship of the entity holds (optional). None means open-ended.
+    pub valid_to: Option<String>,
+
     /// Additional attributes associated with the entity (optional), e.g. start time, end time, etc. To be included in the search prompt.
     pub attributes: Option<HashMap<String, Value>>,
 }
@@ -28,7 +28,7 @@ pub struct Relationship {
     pub description: Option<String>, // = None,
 
     /// The semantic embedding for the relationship description (optional).
-    pub description_embedding: Option<Vec<f64>>, // = None,
+    pub description_embedding: Option<Vec<f32>>, // = None,
 
     /// List of text unit IDs in which the relationship appears (optional).
     pub text_unit_ids: Option<Vec<String>>, // = None,
@@ -36,6 +36,12 @@ pub struct Relationship {
     /// Rank of the relationship, used for sorting (optional). Higher rank indicates more important relationship. This can be based on centrality or other metrics.
     pub rank: Option<usize>, // = 1,
 
+    /// The ISO-8601 date from which the relationship holds (optional). Falls back to the source document creation date.
+    pub valid_from: Option<String>, // = None,
+
+    /// The ISO-8601 date until which the relationship holds (optional). None means the relationship still holds.
+    pub valid_to: Option<String>, // = None,
+
     /// Additional attributes associated with the relationship (optional). To be included in the search prompt
     pub attributes: Option<HashMap<String, Value>>,
 }
@@ -13,6 +13,10 @@ pub const NODE_FREQUENCY: &str = "frequency";
 pub const NODE_DETAILS: &str = "node_details";
 pub const NODE_X: &str = "x";
 pub const NODE_Y: &str = "y";
+pub const NODE_PAGERANK: &str = "pagerank";
+pub const NODE_BETWEENNESS: &str = "betweenness";
+pub const NODE_EIGENVECTOR: &str = "eigenvector";
+pub const NODE_CORE_NUMBER: &str = "core_number";
 
 // POST-PREP EDGE TABLE SCHEMA
 pub const EDGE_SOURCE: &str = "source";
@@ -20,6 +24,8 @@ pub const EDGE_TARGET: &str = "target";
 pub const EDGE_DEGREE: &str = "combined_degree";
 pub const EDGE_DETAILS: &str = "edge_details";
 pub const EDGE_WEIGHT: &str = "weight";
+pub const VALID_FROM: &str = "valid_from";
+pub const VALID_TO: &str = "valid_to";
 
 // POST-PREP CLAIM TABLE SCHEMA
 pub const CLAIM_SUBJECT: &str = "subject_id";
@@ -67,7 +73,7 @@ pub const CREATION_DATE: &str = "creation_date";
 pub const METADATA: &str = "metadata";
 
 // the following lists define the final content and ordering of columns in the data model parquet outputs
-pub const ENTITIES_FINAL_COLUMNS: [&str; 10] = [
+pub const ENTITIES_FINAL_COLUMNS: [&str; 12] = [
     ID,
     SHORT_ID,
     TITLE,
@@ -78,9 +84,11 @@ pub const ENTITIES_FINAL_COLUMNS: [&str; 10] = [
     NODE_DEGREE,
     NODE_X,
     NODE_Y,
+    VALID_FROM,
+    VALID_TO,
 ];
 
-pub const RELATIONSHIPS_FINAL_COLUMNS: [&str; 8] = [
+pub const RELATIONSHIPS_FINAL_COLUMNS: [&str; 10] = [
     ID,
     SHORT_ID,
     EDGE_SOURCE,
@@ -89,6 +97,8 @@ pub const RELATIONSHIPS_FINAL_COLUMNS: [&str; 8] = [
     EDGE_WEIGHT,
     EDGE_DEGREE,
     TEXT_UNIT_IDS,
+    VALID_FROM,
+    VALID_TO,
 ];
 
 pub const COMMUNITIES_FINAL_COLUMNS: [&str; 12] = [
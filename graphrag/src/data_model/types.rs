@@ -1,3 +1,3 @@
 //! Common types for the GraphRAG knowledge model.
 
-pub type TextEmbedder = fn(&str) -> Vec<f64>;
+pub type TextEmbedder = fn(&str) -> Vec<f32>;
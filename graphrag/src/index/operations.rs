@@ -1,7 +1,10 @@
 //! Reusable data frame operations.
 
+pub mod build_noun_graph;
+pub mod compute_centrality;
 pub mod compute_degree;
 pub mod compute_edge_combined_degree;
+pub mod compute_temporal_bounds;
 pub mod create_graph;
 pub mod chunk_text;
 pub mod cluster_graph;
@@ -9,9 +12,14 @@ pub mod embed_graph;
 pub mod embed_text;
 pub mod extract_covariates;
 pub mod extract_graph;
+pub mod finalize_community_reports;
 pub mod finalize_entities;
 pub mod finalize_graph;
 pub mod finalize_relationships;
+pub mod graph_stats;
 pub mod layout_graph;
+pub mod prune_graph;
+pub mod snapshot_embeddings;
+pub mod snapshot_graph;
 pub mod summarize_communities;
 pub mod summarize_descriptions;
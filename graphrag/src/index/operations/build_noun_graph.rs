@@ -0,0 +1,4 @@
+//! The Indexing Engine noun graph package root.
+
+pub mod build_noun_graph;
+pub mod np_extractors;
@@ -0,0 +1,271 @@
+//! Graph extraction using NLP.
+
+use std::collections::{BTreeMap, HashMap};
+
+use futures::stream::{self, StreamExt};
+use polars::prelude::{DataFrame, IntoLazy, IntoSeries, LazyFrame, ListChunked, NamedFrom, Series, col};
+use tokio::sync::Mutex;
+
+use crate::cache::pipeline_cache::PipelineCache;
+use crate::data_model::schemas;
+use crate::index::operations::build_noun_graph::np_extractors::base::BaseNounPhraseExtractor;
+use crate::index::utils::hashing::gen_sha512_hash;
+use crate::utils::dataframe::get_string;
+
+/// A noun phrase node, with the text units it was extracted from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NounNode {
+    pub title: String,
+    pub text_unit_ids: Vec<String>,
+}
+
+/// A co-occurrence edge between two noun phrases, with the text units they co-occur in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NounEdge {
+    pub source: String,
+    pub target: String,
+    pub weight: f64,
+    pub text_unit_ids: Vec<String>,
+}
+
+/**
+Build a noun graph from text units.
+
+Nodes are the noun phrases of the text units, with their frequency as the number of text units they
+occur in. Edges join the noun phrases that co-occur in a text unit, weighted by the number of text
+units they co-occur in, or by their pointwise mutual information when `normalize_edge_weights` is set.
+
+Args:
+    - text_unit_df: The text units, with id and text columns.
+    - text_analyzer: The noun phrase extractor.
+    - normalize_edge_weights: Whether to weight edges by pointwise mutual information.
+    - num_threads: The number of text units extracted at once.
+    - cache: The cache of extracted noun phrases, keyed by text and extractor.
+*/
+pub async fn build_noun_graph<C: PipelineCache<String>>(
+    text_unit_df: LazyFrame,
+    text_analyzer: &dyn BaseNounPhraseExtractor,
+    normalize_edge_weights: bool,
+    num_threads: usize, // = 4,
+    cache: &mut C,
+) -> (LazyFrame, LazyFrame) {
+    let text_units = text_unit_df
+        .select([col(schemas::ID), col(schemas::TEXT)])
+        .collect()
+        .unwrap();
+    let nodes = _extract_nodes(&text_units, text_analyzer, num_threads, cache).await;
+    let edges = _extract_edges(&nodes, normalize_edge_weights);
+    (nodes_frame(&nodes), edges_frame(&edges))
+}
+
+/// Extract the noun phrase nodes of the text units, ordered by title.
+async fn _extract_nodes<C: PipelineCache<String>>(
+    text_units: &DataFrame,
+    text_analyzer: &dyn BaseNounPhraseExtractor,
+    num_threads: usize,
+    cache: &mut C,
+) -> Vec<NounNode> {
+    let cache = Mutex::new(cache);
+    let analyzer = text_analyzer.description();
+    let extract = |text: String| {
+        let cache = &cache;
+        let analyzer = analyzer.clone();
+        async move {
+            let attrs = HashMap::from([("text".to_string(), text.clone()), ("analyzer".to_string(), analyzer)]);
+            let key = format!(
+                "extract_noun_phrases-{}",
+                gen_sha512_hash(attrs, ["text".to_string(), "analyzer".to_string()].into_iter())
+            );
+            {
+                let cache = cache.lock().await;
+                if cache.has(&key).await
+                    && let Ok(noun_phrases) = serde_json::from_str(&cache.get(&key).await)
+                {
+                    return noun_phrases;
+                }
+            }
+            let noun_phrases = text_analyzer.extract(&text);
+            cache
+                .lock()
+                .await
+                .set(&key, serde_json::to_string(&noun_phrases).unwrap(), None)
+                .await;
+            noun_phrases
+        }
+    };
+
+    let noun_phrases: Vec<Vec<String>> = stream::iter((0..text_units.height()).map(|row| get_string(text_units, schemas::TEXT, row)))
+        .map(extract)
+        .buffered(num_threads.max(1))
+        .collect()
+        .await;
+    let text_unit_ids: Vec<String> = (0..text_units.height())
+        .map(|row| get_string(text_units, schemas::ID, row))
+        .collect();
+    group_nodes(text_unit_ids.into_iter().zip(noun_phrases))
+}
+
+/// Group the noun phrases of every text unit into nodes, ordered by title.
+fn group_nodes(noun_phrases: impl Iterator<Item = (String, Vec<String>)>) -> Vec<NounNode> {
+    let mut nodes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (text_unit_id, phrases) in noun_phrases {
+        for phrase in phrases {
+            let text_unit_ids = nodes.entry(phrase).or_default();
+            if !text_unit_ids.contains(&text_unit_id) {
+                text_unit_ids.push(text_unit_id.clone());
+            }
+        }
+    }
+    nodes
+        .into_iter()
+        .map(|(title, text_unit_ids)| NounNode { title, text_unit_ids })
+        .collect()
+}
+
+/**
+Extract the co-occurrence edges of the noun phrase nodes.
+
+Every pair of noun phrases sharing a text unit is an edge, with the lesser title as the source.
+Edges are ordered by source and target.
+*/
+fn _extract_edges(nodes: &[NounNode], normalize_edge_weights: bool) -> Vec<NounEdge> {
+    let mut text_unit_titles: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for node in nodes {
+        for text_unit_id in &node.text_unit_ids {
+            text_unit_titles.entry(text_unit_id).or_default().push(&node.title);
+        }
+    }
+
+    let mut edges: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for (text_unit_id, titles) in &text_unit_titles {
+        for (index, first) in titles.iter().enumerate() {
+            for second in &titles[index + 1..] {
+                let key = if first <= second { (*first, *second) } else { (*second, *first) };
+                edges.entry(key).or_default().push(text_unit_id.to_string());
+            }
+        }
+    }
+
+    let mut edges: Vec<NounEdge> = edges
+        .into_iter()
+        .map(|((source, target), text_unit_ids)| NounEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight: text_unit_ids.len() as f64,
+            text_unit_ids,
+        })
+        .collect();
+    if normalize_edge_weights {
+        calculate_pmi_edge_weights(nodes, &mut edges);
+    }
+    edges
+}
+
+/**
+Replace the edge weights with their pointwise mutual information.
+
+The weight of an edge becomes `p(s, t) * log2(p(s, t) / (p(s) * p(t)))`, where `p(s, t)` is the share
+of the edge in the total edge weight and `p(s)` the share of the node in the total node frequency.
+*/
+fn calculate_pmi_edge_weights(nodes: &[NounNode], edges: &mut [NounEdge]) {
+    let total_edge_weights: f64 = edges.iter().map(|edge| edge.weight).sum();
+    let total_freq_occurrences: usize = nodes.iter().map(|node| node.text_unit_ids.len()).sum();
+    let prop_occurrence: HashMap<&str, f64> = nodes
+        .iter()
+        .map(|node| (node.title.as_str(), node.text_unit_ids.len() as f64 / total_freq_occurrences as f64))
+        .collect();
+
+    for edge in edges {
+        let prop_weight = edge.weight / total_edge_weights;
+        let source_prop = prop_occurrence[edge.source.as_str()];
+        let target_prop = prop_occurrence[edge.target.as_str()];
+        edge.weight = prop_weight * (prop_weight / (source_prop * target_prop)).log2();
+    }
+}
+
+fn text_unit_ids_series(text_unit_ids: impl Iterator<Item = Vec<String>>) -> Series {
+    let list: ListChunked = text_unit_ids
+        .map(|ids| Some(Series::new("".into(), ids)))
+        .collect();
+    list.into_series().with_name(schemas::TEXT_UNIT_IDS.into())
+}
+
+fn nodes_frame(nodes: &[NounNode]) -> LazyFrame {
+    DataFrame::new(vec![
+        Series::new(schemas::TITLE.into(), nodes.iter().map(|node| node.title.clone()).collect::<Vec<_>>()).into(),
+        Series::new(
+            schemas::NODE_FREQUENCY.into(),
+            nodes.iter().map(|node| node.text_unit_ids.len() as i64).collect::<Vec<_>>(),
+        )
+        .into(),
+        text_unit_ids_series(nodes.iter().map(|node| node.text_unit_ids.clone())).into(),
+    ])
+    .unwrap()
+    .lazy()
+}
+
+fn edges_frame(edges: &[NounEdge]) -> LazyFrame {
+    DataFrame::new(vec![
+        Series::new(schemas::EDGE_SOURCE.into(), edges.iter().map(|edge| edge.source.clone()).collect::<Vec<_>>()).into(),
+        Series::new(schemas::EDGE_TARGET.into(), edges.iter().map(|edge| edge.target.clone()).collect::<Vec<_>>()).into(),
+        Series::new(schemas::EDGE_WEIGHT.into(), edges.iter().map(|edge| edge.weight).collect::<Vec<_>>()).into(),
+        text_unit_ids_series(edges.iter().map(|edge| edge.text_unit_ids.clone())).into(),
+    ])
+    .unwrap()
+    .lazy()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrases(text_units: &[(&str, &[&str])]) -> Vec<NounNode> {
+        group_nodes(text_units.iter().map(|(id, phrases)| {
+            (id.to_string(), phrases.iter().map(|phrase| phrase.to_string()).collect())
+        }))
+    }
+
+    #[test]
+    fn nodes_count_the_text_units_of_every_phrase() {
+        let nodes = phrases(&[("t1", &["RIVER", "BRIDGE"]), ("t2", &["BRIDGE"])]);
+        assert_eq!(
+            nodes,
+            [
+                NounNode { title: "BRIDGE".into(), text_unit_ids: vec!["t1".into(), "t2".into()] },
+                NounNode { title: "RIVER".into(), text_unit_ids: vec!["t1".into()] },
+            ]
+        );
+    }
+
+    #[test]
+    fn edges_join_co_occurring_phrases_once_per_pair() {
+        let nodes = phrases(&[("t1", &["RIVER", "BRIDGE", "TOWN"]), ("t2", &["TOWN", "BRIDGE"])]);
+        let edges = _extract_edges(&nodes, false);
+        let pairs: Vec<(&str, &str, f64)> = edges
+            .iter()
+            .map(|edge| (edge.source.as_str(), edge.target.as_str(), edge.weight))
+            .collect();
+        assert_eq!(
+            pairs,
+            [("BRIDGE", "RIVER", 1.0), ("BRIDGE", "TOWN", 2.0), ("RIVER", "TOWN", 1.0)]
+        );
+        assert_eq!(edges[1].text_unit_ids, ["t1", "t2"]);
+    }
+
+    #[test]
+    fn pmi_weights_follow_the_formula() {
+        let nodes = phrases(&[("t1", &["A", "B"]), ("t2", &["A", "B"]), ("t3", &["A", "C"])]);
+        let edges = _extract_edges(&nodes, true);
+        // 6 node occurrences in total, A 3, B 2 and C 1; 3 edge weights in total, A-B 2 and A-C 1
+        let expected_ab = (2.0 / 3.0) * ((2.0f64 / 3.0) / ((3.0 / 6.0) * (2.0 / 6.0))).log2();
+        let expected_ac = (1.0 / 3.0) * ((1.0f64 / 3.0) / ((3.0 / 6.0) * (1.0 / 6.0))).log2();
+        assert!((edges[0].weight - expected_ab).abs() < 1e-12);
+        assert!((edges[1].weight - expected_ac).abs() < 1e-12);
+    }
+
+    #[test]
+    fn text_units_without_pairs_have_no_edges() {
+        let nodes = phrases(&[("t1", &["A"]), ("t2", &["B"]), ("t3", &[])]);
+        assert!(_extract_edges(&nodes, true).is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+//! NLP-based noun phrase extractors.
+
+pub mod base;
+pub mod cfg_extractor;
+pub mod factory;
+pub mod np_validator;
+pub mod rule_based_tagger;
+pub mod stop_words;
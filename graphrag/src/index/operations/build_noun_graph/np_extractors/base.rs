@@ -0,0 +1,15 @@
+//! Base class for noun phrase extractors.
+
+/// Abstract base class for noun phrase extractors.
+pub trait BaseNounPhraseExtractor: Send + Sync {
+    /// Extract noun phrases from text.
+    ///
+    /// Args:
+    ///     text: Text.
+    ///
+    /// Returns: List of noun phrases.
+    fn extract(&self, text: &str) -> Vec<String>;
+
+    /// Return string representation of the extractor, used for cache key generation.
+    fn description(&self) -> String;
+}
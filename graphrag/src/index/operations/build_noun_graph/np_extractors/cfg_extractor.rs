@@ -0,0 +1,261 @@
+//! CFG-based noun phrase extractor.
+
+use std::collections::HashMap;
+
+use crate::config::models::extract_graph_nlp_config::TextAnalyzerConfig;
+use crate::index::operations::build_noun_graph::np_extractors::{
+    base::BaseNounPhraseExtractor,
+    np_validator::{has_valid_token_length, is_compound, is_valid_entity},
+    rule_based_tagger::{self, EntitySpan, Token},
+    stop_words::EN_STOP_WORDS,
+};
+
+/// A noun phrase after tagging, with the flags used for filtering.
+struct TaggedNounPhrase {
+    cleaned_tokens: Vec<String>,
+    cleaned_text: String,
+    is_valid_entity: bool,
+    has_compound_words: bool,
+    has_valid_tokens: bool,
+}
+
+/**
+Noun phrase extractor combining CFG-based noun-chunk extraction and NER.
+
+CFG-based extraction was based on TextBlob's fast NP extractor implementation:
+This extractor tends to be faster than the dependency-parser-based extractors but grammars may need to be changed for different languages.
+
+POS tagging and NER are done by a built-in rule-based tagger, so no language model needs to be downloaded.
+*/
+pub struct CFGNounPhraseExtractor {
+    max_word_length: usize,
+    include_named_entities: bool,
+    exclude_entity_tags: Vec<String>,
+    exclude_pos_tags: Vec<String>,
+    exclude_nouns: Vec<String>,
+    word_delimiter: String,
+    noun_phrase_grammars: HashMap<(String, String), String>,
+    noun_phrase_tags: Vec<String>,
+}
+
+impl CFGNounPhraseExtractor {
+    /**
+    Noun phrase extractor combining CFG-based noun-chunk extraction and NER.
+
+    Args:
+        - analyzer_config: The text analyzer settings: the max word length, whether to include named
+          entities, the excluded entity tags, POS tags and nouns (the default stop words if not set),
+          the word delimiter, the noun phrase grammars keyed by comma-separated POS tag pairs and the
+          noun phrase tags.
+    */
+    pub fn new(analyzer_config: &TextAnalyzerConfig) -> Self {
+        let noun_phrase_grammars = analyzer_config
+            .noun_phrase_grammars
+            .iter()
+            .filter_map(|(key, value)| {
+                let (first, second) = key.split_once(',')?;
+                Some(((first.trim().to_string(), second.trim().to_string()), value.clone()))
+            })
+            .collect();
+        let exclude_nouns = match &analyzer_config.exclude_nouns {
+            Some(exclude_nouns) => exclude_nouns.iter().map(|noun| noun.to_uppercase()).collect(),
+            None => EN_STOP_WORDS.iter().map(|noun| noun.to_uppercase()).collect(),
+        };
+
+        CFGNounPhraseExtractor {
+            max_word_length: analyzer_config.max_word_length,
+            include_named_entities: analyzer_config.include_named_entities,
+            exclude_entity_tags: analyzer_config.exclude_entity_tags.clone(),
+            exclude_pos_tags: analyzer_config.exclude_pos_tags.clone(),
+            exclude_nouns,
+            word_delimiter: analyzer_config.word_delimiter.clone(),
+            noun_phrase_grammars,
+            noun_phrase_tags: analyzer_config.noun_phrase_tags.clone(),
+        }
+    }
+
+    /**
+    Return noun phrases that match a given context-free grammar.
+
+    Adjacent (word, tag) pairs are merged repeatedly while their tags match a grammar rule.
+    */
+    fn extract_cfg_matches(&self, tokens: &[Token]) -> Vec<(String, String)> {
+        let mut tagged_tokens: Vec<(String, String)> = tokens
+            .iter()
+            .filter(|token| {
+                !self.exclude_pos_tags.iter().any(|tag| tag == token.pos)
+                    && token.text != "-"
+                    && token.pos != "PUNCT"
+            })
+            .map(|token| (token.text.clone(), token.pos.to_string()))
+            .collect();
+
+        let mut merge = true;
+        while merge {
+            merge = false;
+            for index in 0..tagged_tokens.len().saturating_sub(1) {
+                let key = (tagged_tokens[index].1.clone(), tagged_tokens[index + 1].1.clone());
+                if let Some(value) = self.noun_phrase_grammars.get(&key) {
+                    let (second, _) = tagged_tokens.remove(index + 1);
+                    let (first, _) = tagged_tokens.remove(index);
+                    let match_str = format!("{}{}{}", first, self.word_delimiter, second);
+                    tagged_tokens.insert(index, (match_str, value.clone()));
+                    merge = true;
+                    break;
+                }
+            }
+        }
+
+        tagged_tokens
+            .into_iter()
+            .filter(|(_, tag)| self.noun_phrase_tags.contains(tag))
+            .collect()
+    }
+
+    /// Extract attributes of a noun chunk, to be used for filtering.
+    fn tag_noun_phrases(
+        &self,
+        noun_chunk: &(String, String),
+        entities: &[EntitySpan],
+    ) -> TaggedNounPhrase {
+        let (text, tag) = noun_chunk;
+        let tokens: Vec<&str> = text.split(self.word_delimiter.as_str()).collect();
+        let cleaned_tokens: Vec<String> = tokens
+            .iter()
+            .filter(|token| !self.exclude_nouns.contains(&token.to_uppercase()))
+            .map(|token| token.to_string())
+            .collect();
+
+        let cleaned_tokens_lower: Vec<String> =
+            cleaned_tokens.iter().map(|token| token.to_lowercase()).collect();
+        let entity = entities
+            .iter()
+            .find(|(entity_text, _)| entity_text == text)
+            .cloned()
+            .unwrap_or_else(|| (text.clone(), tag.clone()));
+        let is_entity_match = entities.iter().any(|(entity_text, _)| entity_text == text);
+
+        let cleaned_text = cleaned_tokens
+            .join(&self.word_delimiter)
+            .replace('\n', "")
+            .to_uppercase();
+
+        TaggedNounPhrase {
+            is_valid_entity: is_entity_match && is_valid_entity(&entity, &cleaned_tokens),
+            has_compound_words: is_compound(&cleaned_tokens_lower),
+            has_valid_tokens: has_valid_token_length(&cleaned_tokens, self.max_word_length),
+            cleaned_tokens,
+            cleaned_text,
+        }
+    }
+}
+
+impl BaseNounPhraseExtractor for CFGNounPhraseExtractor {
+    /**
+    Extract noun phrases from text. Noun phrases may include named entities and noun chunks, which are filtered based on some heuristics.
+
+    Args:
+        text: Text.
+
+    Returns: List of noun phrases.
+    */
+    fn extract(&self, text: &str) -> Vec<String> {
+        let tokens = rule_based_tagger::tag(text);
+        let entities: Vec<EntitySpan> = rule_based_tagger::entities(&tokens)
+            .into_iter()
+            .filter(|(_, label)| !self.exclude_entity_tags.contains(label))
+            .collect();
+
+        let mut filtered_noun_phrases: Vec<String> = Vec::new();
+        let mut push_unique = |phrase: String| {
+            if !phrase.is_empty() && !filtered_noun_phrases.contains(&phrase) {
+                filtered_noun_phrases.push(phrase);
+            }
+        };
+
+        let mut noun_chunks = self.extract_cfg_matches(&tokens);
+        if self.include_named_entities {
+            noun_chunks = entities.iter().cloned().chain(noun_chunks).collect();
+        }
+
+        for noun_chunk in &noun_chunks {
+            let tagged = self.tag_noun_phrases(noun_chunk, &entities);
+            if tagged.is_valid_entity
+                || ((tagged.cleaned_tokens.len() > 1 || tagged.has_compound_words)
+                    && tagged.has_valid_tokens)
+            {
+                push_unique(tagged.cleaned_text);
+            }
+        }
+
+        filtered_noun_phrases
+    }
+
+    /// Return string representation of the extractor, used for cache key generation.
+    fn description(&self) -> String {
+        let mut grammars: Vec<String> = self
+            .noun_phrase_grammars
+            .iter()
+            .map(|((first, second), value)| format!("{first},{second}:{value}"))
+            .collect();
+        grammars.sort();
+        format!(
+            "cfg_{}_{}_{}_{}_{}_{}_{}_{}",
+            self.include_named_entities,
+            self.max_word_length,
+            self.exclude_entity_tags.join(","),
+            self.exclude_pos_tags.join(","),
+            self.exclude_nouns.join(","),
+            self.word_delimiter,
+            grammars.join(";"),
+            self.noun_phrase_tags.join(","),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_named_entities_and_noun_compounds() {
+        let extractor = CFGNounPhraseExtractor::new(&TextAnalyzerConfig::default());
+        let phrases = extractor.extract("Alice Smith studied the water quality of the river near Paris.");
+        assert_eq!(phrases, ["ALICE SMITH", "PARIS", "WATER QUALITY"]);
+    }
+
+    #[test]
+    fn single_common_nouns_and_stop_words_are_dropped() {
+        let extractor = CFGNounPhraseExtractor::new(&TextAnalyzerConfig::default());
+        assert!(extractor.extract("the river and the stuff").is_empty());
+    }
+
+    #[test]
+    fn excluded_entity_tags_are_skipped() {
+        let extractor = CFGNounPhraseExtractor::new(&TextAnalyzerConfig::default());
+        let phrases = extractor.extract("They met Alice on Monday.");
+        assert_eq!(phrases, ["ALICE"]);
+    }
+
+    #[test]
+    fn phrases_with_long_words_are_rejected() {
+        let config = TextAnalyzerConfig {
+            max_word_length: 6,
+            include_named_entities: false,
+            ..Default::default()
+        };
+        let extractor = CFGNounPhraseExtractor::new(&config);
+        assert_eq!(extractor.extract("the data center and the water infrastructure"), ["DATA CENTER"]);
+    }
+
+    #[test]
+    fn custom_stop_words_replace_the_defaults() {
+        let config = TextAnalyzerConfig {
+            exclude_nouns: Some(vec!["quality".into()]),
+            ..Default::default()
+        };
+        let extractor = CFGNounPhraseExtractor::new(&config);
+        assert!(extractor.extract("the water quality").is_empty());
+        assert!(extractor.description().contains("QUALITY"));
+    }
+}
@@ -0,0 +1,30 @@
+//! Create instances of noun phrase extractors based on configuration.
+
+use log::warn;
+
+use crate::{
+    config::{enums::NounPhraseExtractorType, models::extract_graph_nlp_config::TextAnalyzerConfig},
+    index::operations::build_noun_graph::np_extractors::{
+        base::BaseNounPhraseExtractor, cfg_extractor::CFGNounPhraseExtractor,
+    },
+};
+
+/// Create a noun phrase extractor from a configuration, failing for extractor types that are not available.
+pub fn create_noun_phrase_extractor(
+    analyzer_config: &TextAnalyzerConfig,
+) -> Result<Box<dyn BaseNounPhraseExtractor>, String> {
+    match analyzer_config.extractor_type {
+        NounPhraseExtractorType::Cfg | NounPhraseExtractorType::Syntactic => {
+            if let NounPhraseExtractorType::Syntactic = analyzer_config.extractor_type {
+                warn!(
+                    "The syntactic parser extractor requires SpaCy, which is unavailable; falling back to the rule-based CFG extractor."
+                );
+            }
+            Ok(Box::new(CFGNounPhraseExtractor::new(analyzer_config)))
+        }
+        NounPhraseExtractorType::RegexEnglish => Err(
+            "The regex_english noun phrase extractor has not been ported yet; set extractor_type to cfg instead."
+                .to_string(),
+        ),
+    }
+}
@@ -0,0 +1,20 @@
+//! Util functions needed for nltk-based noun-phrase extractors (i.e. TextBlob).
+
+/// Return True if the word is a compound word.
+pub fn is_compound(tokens: &[String]) -> bool {
+    tokens
+        .iter()
+        .any(|token| token.contains('-') && token.trim().len() > 1 && token.trim().split('-').count() > 1)
+}
+
+/// Return true if all tokens have valid length.
+pub fn has_valid_token_length(tokens: &[String], max_length: usize) -> bool {
+    tokens.iter().all(|token| token.chars().count() <= max_length)
+}
+
+/// Return true if the entity is valid.
+pub fn is_valid_entity(entity: &(String, String), tokens: &[String]) -> bool {
+    let (_, label) = entity;
+    (label != "CARDINAL" && label != "ORDINAL" && label != "QUANTITY" && !tokens.is_empty())
+        && (tokens.len() > 1 || tokens[0].chars().count() > 1)
+}
@@ -0,0 +1,308 @@
+//! A lightweight rule and lexicon based part-of-speech tagger and entity recognizer.
+//!
+//! Stands in for the SpaCy pipeline used by the Python CFG extractor, so noun phrase
+//! extraction can run without any downloaded models. Tags follow the Universal POS tag set.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// A tagged token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The token text.
+    pub text: String,
+    /// The universal POS tag.
+    pub pos: &'static str,
+    /// Whether the token starts a sentence.
+    pub is_sent_start: bool,
+}
+
+/// A named entity span, as (text, label).
+pub type EntitySpan = (String, String);
+
+const DETERMINERS: [&str; 16] = [
+    "a", "an", "the", "this", "that", "these", "those", "each", "every", "either", "neither",
+    "some", "any", "no", "all", "both",
+];
+const PRONOUNS: [&str; 30] = [
+    "i", "me", "my", "mine", "you", "your", "yours", "he", "him", "his", "she", "her", "hers",
+    "it", "its", "we", "us", "our", "ours", "they", "them", "their", "theirs", "who", "whom",
+    "whose", "which", "what", "itself", "themselves",
+];
+const ADPOSITIONS: [&str; 35] = [
+    "of", "in", "on", "at", "by", "for", "with", "about", "against", "between", "into",
+    "through", "during", "before", "after", "above", "below", "to", "from", "up", "down", "out",
+    "off", "over", "under", "within", "without", "across", "along", "among", "around", "behind",
+    "beyond", "near", "via",
+];
+const CONJUNCTIONS: [&str; 7] = ["and", "or", "but", "nor", "yet", "so", "plus"];
+const SUBORDINATORS: [&str; 12] = [
+    "if", "because", "although", "though", "while", "whereas", "unless", "since", "when",
+    "whether", "than", "until",
+];
+const AUXILIARIES: [&str; 24] = [
+    "is", "am", "are", "was", "were", "be", "been", "being", "has", "have", "had", "having",
+    "do", "does", "did", "will", "would", "shall", "should", "can", "could", "may", "might",
+    "must",
+];
+const PARTICLES: [&str; 3] = ["not", "n't", "'s"];
+const INTERJECTIONS: [&str; 8] = ["oh", "hey", "hi", "hello", "okay", "ok", "wow", "yes"];
+const ADVERBS: [&str; 16] = [
+    "very", "also", "too", "just", "only", "never", "always", "often", "here", "there", "then",
+    "now", "again", "already", "still", "however",
+];
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june", "july", "august", "september",
+    "october", "november", "december",
+];
+const WEEKDAYS: [&str; 7] = [
+    "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+];
+
+const NOUN_SUFFIXES: [&str; 12] = [
+    "tion", "sion", "ment", "ness", "ity", "ship", "ance", "ence", "ism", "ist", "age", "hood",
+];
+const ADJ_SUFFIXES: [&str; 10] = [
+    "ous", "ful", "ive", "able", "ible", "al", "ic", "less", "ish", "ary",
+];
+
+fn closed_class() -> &'static Vec<(&'static [&'static str], &'static str)> {
+    static CLOSED_CLASS: OnceLock<Vec<(&'static [&'static str], &'static str)>> = OnceLock::new();
+    CLOSED_CLASS.get_or_init(|| {
+        vec![
+            (&DETERMINERS[..], "DET"),
+            (&PRONOUNS[..], "PRON"),
+            (&AUXILIARIES[..], "AUX"),
+            (&ADPOSITIONS[..], "ADP"),
+            (&CONJUNCTIONS[..], "CCONJ"),
+            (&SUBORDINATORS[..], "SCONJ"),
+            (&PARTICLES[..], "PART"),
+            (&INTERJECTIONS[..], "INTJ"),
+            (&ADVERBS[..], "ADV"),
+        ]
+    })
+}
+
+fn modals() -> &'static HashSet<&'static str> {
+    static MODALS: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    MODALS.get_or_init(|| {
+        HashSet::from([
+            "will", "would", "shall", "should", "can", "could", "may", "might", "must", "to",
+        ])
+    })
+}
+
+/// Split text into tokens, keeping hyphenated words and apostrophes together
+/// and emitting punctuation as separate tokens.
+pub fn tokenize(text: &str) -> Vec<(String, bool)> {
+    let mut tokens: Vec<(String, bool)> = Vec::new();
+    let mut current = String::new();
+    let mut sent_start = true;
+
+    let flush = |current: &mut String, tokens: &mut Vec<(String, bool)>, sent_start: &mut bool| {
+        if !current.is_empty() {
+            tokens.push((std::mem::take(current), *sent_start));
+            *sent_start = false;
+        }
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        let next_is_word = chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+        if c.is_alphanumeric() || ((c == '-' || c == '\'' || c == '.') && !current.is_empty() && next_is_word)
+        {
+            current.push(c);
+        } else {
+            flush(&mut current, &mut tokens, &mut sent_start);
+            if !c.is_whitespace() {
+                tokens.push((c.to_string(), false));
+                if matches!(c, '.' | '!' | '?') {
+                    sent_start = true;
+                }
+            } else if c == '\n' {
+                sent_start = true;
+            }
+        }
+    }
+    flush(&mut current, &mut tokens, &mut sent_start);
+    tokens
+}
+
+fn lexical_tag(word: &str, is_sent_start: bool) -> &'static str {
+    let lower = word.to_lowercase();
+
+    if word.chars().all(|c| !c.is_alphanumeric()) {
+        return "PUNCT";
+    }
+    if word.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',') {
+        return "NUM";
+    }
+    let is_capitalized = word.chars().next().is_some_and(|c| c.is_uppercase());
+    let is_acronym = word.chars().count() > 1
+        && word.chars().all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '-');
+
+    if let Some((_, tag)) = closed_class().iter().find(|(words, _)| words.contains(&lower.as_str())) {
+        // "May" mid-sentence is more likely the month than the modal
+        if !(is_capitalized && !is_sent_start && MONTHS.contains(&lower.as_str())) {
+            return tag;
+        }
+    }
+    if is_acronym || (is_capitalized && !is_sent_start) {
+        return "PROPN";
+    }
+    if MONTHS.contains(&lower.as_str()) || WEEKDAYS.contains(&lower.as_str()) {
+        return "PROPN";
+    }
+    if lower.ends_with("ly") && lower.len() > 4 {
+        return "ADV";
+    }
+    if NOUN_SUFFIXES.iter().any(|s| lower.ends_with(s) && lower.len() > s.len() + 2) {
+        return "NOUN";
+    }
+    if ADJ_SUFFIXES.iter().any(|s| lower.ends_with(s) && lower.len() > s.len() + 2) {
+        return "ADJ";
+    }
+    if (lower.ends_with("ing") || lower.ends_with("ed")) && lower.len() > 4 {
+        return "VERB";
+    }
+    "NOUN"
+}
+
+/// Tag the tokens of a text with universal POS tags.
+pub fn tag(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = tokenize(text)
+        .into_iter()
+        .map(|(word, is_sent_start)| Token {
+            pos: lexical_tag(&word, is_sent_start),
+            text: word,
+            is_sent_start,
+        })
+        .collect();
+
+    // contextual corrections
+    for i in 1..tokens.len() {
+        let prev_pos = tokens[i - 1].pos;
+        let prev_lower = tokens[i - 1].text.to_lowercase();
+        let token = &mut tokens[i];
+        if token.pos == "VERB" && (prev_pos == "DET" || prev_pos == "ADJ") {
+            // participles used as modifiers, e.g. "the proposed method"
+            token.pos = "ADJ";
+        } else if token.pos == "NOUN" && modals().contains(prev_lower.as_str()) {
+            token.pos = "VERB";
+        }
+    }
+
+    // a sentence-initial capitalized word followed by a proper noun is part of the name
+    for i in 0..tokens.len().saturating_sub(1) {
+        if tokens[i].is_sent_start
+            && tokens[i].pos == "NOUN"
+            && tokens[i].text.chars().next().is_some_and(|c| c.is_uppercase())
+            && tokens[i + 1].pos == "PROPN"
+        {
+            tokens[i].pos = "PROPN";
+        }
+    }
+    tokens
+}
+
+/// Recognize named entities as runs of proper nouns, labelling dates as DATE.
+pub fn entities(tokens: &[Token]) -> Vec<EntitySpan> {
+    let mut spans: Vec<EntitySpan> = Vec::new();
+    let mut current: Vec<&Token> = Vec::new();
+
+    let flush = |current: &mut Vec<&Token>, spans: &mut Vec<EntitySpan>| {
+        if current.is_empty() {
+            return;
+        }
+        let text = current.iter().map(|t| t.text.as_str()).collect::<Vec<_>>().join(" ");
+        let is_date = current.iter().all(|t| {
+            let lower = t.text.to_lowercase();
+            MONTHS.contains(&lower.as_str()) || WEEKDAYS.contains(&lower.as_str()) || t.pos == "NUM"
+        });
+        let label = if is_date { "DATE" } else { "ENTITY" };
+        spans.push((text, label.to_string()));
+        current.clear();
+    };
+
+    for token in tokens {
+        let lower = token.text.to_lowercase();
+        let is_date_part = MONTHS.contains(&lower.as_str()) || WEEKDAYS.contains(&lower.as_str());
+        let is_year = token.pos == "NUM" && token.text.len() == 4 && !current.is_empty();
+        if token.pos == "PROPN" || is_date_part || is_year {
+            current.push(token);
+        } else {
+            flush(&mut current, &mut spans);
+        }
+    }
+    flush(&mut current, &mut spans);
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(text: &str) -> Vec<(String, &'static str)> {
+        tag(text).into_iter().map(|token| (token.text, token.pos)).collect()
+    }
+
+    #[test]
+    fn tokenize_keeps_hyphenated_words_and_splits_punctuation() {
+        let tokens: Vec<String> = tokenize("State-of-the-art models, don't they?")
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect();
+        assert_eq!(tokens, ["State-of-the-art", "models", ",", "don't", "they", "?"]);
+    }
+
+    #[test]
+    fn sentence_starts_follow_terminal_punctuation() {
+        let starts: Vec<bool> = tokenize("Rain fell. Rivers rose").into_iter().map(|(_, start)| start).collect();
+        assert_eq!(starts, [true, false, false, true, false]);
+    }
+
+    #[test]
+    fn closed_class_words_and_proper_nouns_are_tagged() {
+        assert_eq!(
+            tags("The engineers met Alice Smith in Paris."),
+            [
+                ("The".into(), "DET"),
+                ("engineers".into(), "NOUN"),
+                ("met".into(), "NOUN"),
+                ("Alice".into(), "PROPN"),
+                ("Smith".into(), "PROPN"),
+                ("in".into(), "ADP"),
+                ("Paris".into(), "PROPN"),
+                (".".into(), "PUNCT"),
+            ]
+        );
+    }
+
+    #[test]
+    fn participles_after_determiners_are_adjectives() {
+        let tagged = tags("the proposed method");
+        assert_eq!(tagged[1], ("proposed".into(), "ADJ"));
+        assert_eq!(tagged[2], ("method".into(), "NOUN"));
+    }
+
+    #[test]
+    fn capitalized_months_mid_sentence_are_not_modals() {
+        let tagged = tags("It rained in May");
+        assert_eq!(tagged[3], ("May".into(), "PROPN"));
+        let tagged = tags("You may leave");
+        assert_eq!(tagged[1], ("may".into(), "AUX"));
+    }
+
+    #[test]
+    fn entities_are_runs_of_proper_nouns_with_dates_labelled() {
+        let tokens = tag("We visited New York City on Monday and met NASA.");
+        assert_eq!(
+            entities(&tokens),
+            [
+                ("New York City".to_string(), "ENTITY".to_string()),
+                ("Monday".to_string(), "DATE".to_string()),
+                ("NASA".to_string(), "ENTITY".to_string()),
+            ]
+        );
+    }
+}
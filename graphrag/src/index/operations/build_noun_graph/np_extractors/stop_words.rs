@@ -0,0 +1,8 @@
+//! Custom list of stopwords to exclude in noun phrase extraction.
+
+/// Default list of English stop words excluded from noun phrases.
+pub const EN_STOP_WORDS: [&str; 34] = [
+    "stuff", "thing", "things", "bunch", "bit", "bits", "people", "person", "okay", "hey", "hi",
+    "hello", "laughter", "oh", "a", "an", "and", "are", "as", "at", "be", "but", "by", "for",
+    "if", "in", "into", "is", "it", "no", "not", "of", "on", "or",
+];
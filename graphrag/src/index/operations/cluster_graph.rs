@@ -1,92 +1,310 @@
-//! A module containing cluster_graph, apply_clustering and run_layout methods definition.
+//! A module containing cluster_graph method definition.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use log::warn;
 
-use rustworkx_core::petgraph::graph::{Graph, UnGraph};
+use rustworkx_core::petgraph::graph::UnGraph;
+use rustworkx_core::petgraph::visit::EdgeRef;
 
+use crate::config::enums::ClusteringAlgorithmType;
+use crate::index::utils::infomap::infomap;
+use crate::index::utils::label_propagation::label_propagation;
+use crate::index::utils::leiden::leiden;
+use crate::index::utils::louvain::louvain;
 use crate::index::utils::stable_lcc::stable_largest_connected_component;
+use crate::index::utils::weighted_graph::WeightedGraph;
 
-pub type Communities = Vec<(int, int, int, Vec<String>)>;
+/// The clustered communities as (level, community, parent, node titles), root communities have parent -1.
+pub type Communities = Vec<(i64, i64, i64, Vec<String>)>;
 
-/// Apply a hierarchical clustering algorithm to a graph.
+/**
+Apply a hierarchical clustering algorithm to a graph.
+
+Level 0 partitions the whole graph with the selected algorithm. Every community larger than
+`max_cluster_size` is partitioned again on the next level, until the hierarchy is `max_depth` levels
+deep. On every level, communities smaller than `min_community_size` are merged into the sibling they
+are best connected to; the ones without any connected sibling are kept as they are.
+
+Args:
+    - graph: The graph to cluster, with entity titles as nodes and relationship weights as edges.
+    - algorithm: The community detection algorithm to partition every level with.
+    - max_cluster_size: Communities larger than this are split on the next level.
+    - use_lcc: Whether to only cluster the largest connected component.
+    - seed: The seed of the clustering.
+    - resolutions: The resolution of each level, the last one is reused for deeper levels.
+    - max_depth: The maximum number of levels, unlimited if not set.
+    - min_community_size: The minimum number of nodes in a community.
+*/
+#[allow(clippy::too_many_arguments)]
 pub fn cluster_graph(
-    graph: UnGraph,
+    graph: UnGraph<String, f64>,
+    algorithm: &ClusteringAlgorithmType,
     max_cluster_size: usize,
     use_lcc: bool,
     seed: Option<usize>,
+    resolutions: &[f64],
+    max_depth: Option<usize>,
+    min_community_size: usize,
 ) -> Communities {
-    if graph.nodes.len() == 0 {
-        warn("Graph has no nodes");
+    if graph.node_count() == 0 {
+        warn!("Graph has no nodes");
         return Vec::new();
     }
 
-    let (node_id_to_community_map, parent_mapping) = _compute_leiden_communities(
-        graph,
-        max_cluster_size,
-        use_lcc,
-        seed,
+    let graph = if use_lcc {
+        stable_largest_connected_component(graph)
+    } else {
+        graph
+    };
+    let titles: Vec<String> = graph.node_weights().cloned().collect();
+    let weighted = WeightedGraph::from_edges(
+        graph.node_count(),
+        graph
+            .edge_references()
+            .map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())),
     );
+    let seed = seed.unwrap_or_default() as u64;
+    let resolution = |level: usize| {
+        resolutions
+            .get(level)
+            .or(resolutions.last())
+            .copied()
+            .unwrap_or(1.0)
+    };
 
-    let levels = sorted(node_id_to_community_map.keys());
+    let mut results: Communities = Vec::new();
+    let mut next_id = 0;
+    // the (level, parent, nodes) of the communities left to partition, level by level
+    let mut pending = VecDeque::from([(0, -1, (0..titles.len()).collect::<Vec<usize>>())]);
+    while let Some((level, parent, nodes)) = pending.pop_front() {
+        let subgraph = weighted.subgraph(&nodes);
+        let mut membership = match algorithm {
+            ClusteringAlgorithmType::Leiden => leiden(&subgraph, resolution(level), seed),
+            ClusteringAlgorithmType::Louvain => louvain(&subgraph, resolution(level), seed),
+            ClusteringAlgorithmType::LabelPropagation => label_propagation(&subgraph, seed),
+            ClusteringAlgorithmType::Infomap => infomap(&subgraph, seed),
+        };
+        merge_small_communities(&subgraph, &mut membership, min_community_size);
+
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (node, community) in nodes.iter().zip(&membership) {
+            members.entry(*community).or_default().push(*node);
+        }
+        if level > 0 && members.len() < 2 {
+            // the parent can not be split any further
+            continue;
+        }
 
-    let mut clusters = HashMap::<usize, HashMap<usize, Vec<String>>>::new();
-    for level in levels {
-        let mut result = HashMap::new();
-        clusters[level] = result;
-        for (node_id, raw_community_id) in node_id_to_community_map[level].items() {
-            let community_id = raw_community_id;
-            if !result.contains_key(community_id) {
-                result[community_id] = Vec::new();
+        for community_nodes in members.into_values() {
+            let community = next_id;
+            next_id += 1;
+            if community_nodes.len() > max_cluster_size && max_depth.is_none_or(|depth| level + 1 < depth) {
+                pending.push_back((level + 1, community, community_nodes.clone()));
             }
-            result[community_id].push(node_id);
+            results.push((
+                level as i64,
+                community,
+                parent,
+                community_nodes.iter().map(|node| titles[*node].clone()).collect(),
+            ));
         }
     }
 
-    let mut results: Communities = Vec::new();
-    for level in clusters {
-        for (cluster_id, nodes) in clusters[level].items() {
-            results.push((level, cluster_id, parent_mapping[cluster_id], nodes));
+    results
+}
+
+/// Merge communities smaller than `min_community_size` into the neighboring community they share the most weight with.
+fn merge_small_communities(graph: &WeightedGraph, membership: &mut [usize], min_community_size: usize) {
+    loop {
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        for community in membership.iter() {
+            *sizes.entry(*community).or_default() += 1;
+        }
+        let mut small: Vec<(usize, usize)> = sizes
+            .into_iter()
+            .filter(|(_, size)| *size < min_community_size)
+            .map(|(community, size)| (size, community))
+            .collect();
+        small.sort();
+
+        // merge the smallest community that has a neighbor, then recount
+        let merge = small.into_iter().find_map(|(_, community)| {
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for node in (0..graph.node_count()).filter(|node| membership[*node] == community) {
+                for (neighbor, weight) in graph.neighbors(node) {
+                    let other = membership[*neighbor];
+                    if other != community {
+                        *weights.entry(other).or_default() += weight;
+                    }
+                }
+            }
+            let (target, _) = weights.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+            Some((community, target))
+        });
+        let Some((community, target)) = merge else {
+            break;
+        };
+        for node_community in membership.iter_mut() {
+            if *node_community == community {
+                *node_community = target;
+            }
         }
     }
-    results
 }
 
-// Taken from graph_intelligence & adapted
-/// Return Leiden root communities and their hierarchy mapping.
-fn _compute_leiden_communities(
-    mut graph: Graph,
-    max_cluster_size: u32,
-    use_lcc: bool,
-    seed: Option<u64>,
-) -> (HashMap<int, HashMap<str, int>>, HashMap<int, int>) {
-    use network_partitions::leiden::hierarchical_leiden;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if use_lcc {
-        graph = stable_largest_connected_component(graph);
+    /// Unit weight cliques of the given sizes, every clique joined to the next one by a single bridge edge.
+    fn bridged_cliques(sizes: &[usize]) -> UnGraph<String, f64> {
+        let mut graph = UnGraph::new_undirected();
+        let mut previous = None;
+        for (clique, size) in sizes.iter().enumerate() {
+            let nodes: Vec<_> = (0..*size)
+                .map(|node| graph.add_node(format!("C{clique}N{node}")))
+                .collect();
+            for (index, source) in nodes.iter().enumerate() {
+                for target in &nodes[index + 1..] {
+                    graph.add_edge(*source, *target, 1.0);
+                }
+            }
+            if let Some(previous) = previous {
+                graph.add_edge(previous, nodes[0], 1.0);
+            }
+            previous = nodes.last().copied();
+        }
+        graph
     }
 
-    let community_mapping = hierarchical_leiden(
-        graph,
-        None,
-        Some(0),
-        Some(1.0),
-        Some(0.001),
-        true,
-        max_cluster_size,
-        seed,
-    )?;
-    let results = HashMap::<int, HashMap<str, int>>::new();
-    let hierarchy = HashMap::<int, int>::new();
-    for partition in community_mapping.iter() {
-        results[partition.level] = results.get(partition.level, {});
-        results[partition.level][partition.node] = partition.cluster;
-
-        lhierarchy[partition.cluster] = (
-            if let Some(parent_cluster) = partition.parent_cluster { parent_cluster } else { -1 }
+    fn titles(clique: usize, size: usize) -> Vec<String> {
+        (0..size).map(|node| format!("C{clique}N{node}")).collect()
+    }
+
+    #[test]
+    fn every_algorithm_separates_bridged_cliques() {
+        for algorithm in [
+            ClusteringAlgorithmType::Leiden,
+            ClusteringAlgorithmType::Louvain,
+            ClusteringAlgorithmType::Infomap,
+            ClusteringAlgorithmType::LabelPropagation,
+        ] {
+            let communities = cluster_graph(bridged_cliques(&[6, 6]), &algorithm, 10, false, Some(42), &[1.0], None, 1);
+            assert_eq!(
+                communities,
+                vec![(0, 0, -1, titles(0, 6)), (0, 1, -1, titles(1, 6))],
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn large_communities_are_split_on_the_next_level() {
+        let communities = cluster_graph(
+            bridged_cliques(&[4, 4, 4]),
+            &ClusteringAlgorithmType::Leiden,
+            8,
+            false,
+            Some(42),
+            &[0.0, 1.0],
+            None,
+            1,
+        );
+        let mut everything = titles(0, 4);
+        everything.extend(titles(1, 4));
+        everything.extend(titles(2, 4));
+        assert_eq!(
+            communities,
+            vec![
+                (0, 0, -1, everything),
+                (1, 1, 0, titles(0, 4)),
+                (1, 2, 0, titles(1, 4)),
+                (1, 3, 0, titles(2, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn max_depth_limits_the_levels() {
+        let communities = cluster_graph(
+            bridged_cliques(&[4, 4, 4]),
+            &ClusteringAlgorithmType::Leiden,
+            8,
+            false,
+            Some(42),
+            &[0.0, 1.0],
+            Some(1),
+            1,
         );
+        assert_eq!(communities.len(), 1);
+        assert_eq!(communities[0].3.len(), 12);
     }
 
-    (results, hierarchy)
+    #[test]
+    fn small_communities_are_merged_into_their_neighbor() {
+        let mut graph = bridged_cliques(&[5]);
+        let first = graph.add_node("P0".to_string());
+        let second = graph.add_node("P1".to_string());
+        graph.add_edge(first, second, 10.0);
+        graph.add_edge(rustworkx_core::petgraph::graph::NodeIndex::new(0), first, 1.0);
+
+        let separate = cluster_graph(graph.clone(), &ClusteringAlgorithmType::Leiden, 10, false, Some(42), &[1.0], None, 1);
+        assert_eq!(separate.len(), 2);
+
+        let merged = cluster_graph(graph, &ClusteringAlgorithmType::Leiden, 10, false, Some(42), &[1.0], None, 3);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].3.len(), 7);
+    }
+
+    #[test]
+    fn small_communities_are_merged_on_every_level() {
+        // the pair is its own community when splitting the single level 0 community
+        let mut graph = bridged_cliques(&[4, 4, 4]);
+        let first = graph.add_node("P0".to_string());
+        let second = graph.add_node("P1".to_string());
+        graph.add_edge(first, second, 10.0);
+        graph.add_edge(rustworkx_core::petgraph::graph::NodeIndex::new(0), first, 1.0);
+        let cluster = |min_community_size| {
+            cluster_graph(
+                graph.clone(),
+                &ClusteringAlgorithmType::Leiden,
+                8,
+                false,
+                Some(42),
+                &[0.0, 1.0],
+                None,
+                min_community_size,
+            )
+        };
+        let level_sizes = |communities: &Communities, level: i64| {
+            let mut sizes: Vec<usize> = communities
+                .iter()
+                .filter(|community| community.0 == level)
+                .map(|community| community.3.len())
+                .collect();
+            sizes.sort();
+            sizes
+        };
+
+        let separate = cluster(1);
+        assert_eq!(level_sizes(&separate, 0), [14]);
+        assert_eq!(level_sizes(&separate, 1), [2, 4, 4, 4]);
+
+        let merged = cluster(3);
+        assert_eq!(level_sizes(&merged, 0), [14]);
+        assert_eq!(level_sizes(&merged, 1), [4, 4, 6]);
+        let with_pair = merged
+            .iter()
+            .find(|community| community.0 == 1 && community.3.contains(&"P0".to_string()))
+            .unwrap();
+        assert_eq!(with_pair.2, 0);
+        assert!(with_pair.3.contains(&"C0N0".to_string()));
+    }
+
+    #[test]
+    fn empty_graph_has_no_communities() {
+        let graph = UnGraph::<String, f64>::new_undirected();
+        assert!(cluster_graph(graph, &ClusteringAlgorithmType::Leiden, 10, true, None, &[1.0], None, 1).is_empty());
+    }
 }
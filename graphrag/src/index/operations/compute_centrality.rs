@@ -0,0 +1,53 @@
+//! A module containing compute_centrality method definition.
+
+use polars::prelude::{DataFrame, DataType, IntoLazy, LazyFrame, NamedFrom, Series, col};
+
+use crate::config::models::graph_centrality_config::GraphCentralityConfig;
+use crate::data_model::schemas;
+use crate::index::utils::centrality::{betweenness, core_number, eigenvector, pagerank};
+use crate::index::utils::weighted_graph::read_relationship_graph;
+
+/// Create a new LazyFrame with the enabled centrality metrics of each node in the graph.
+pub fn compute_centrality(relationships: LazyFrame, config: &GraphCentralityConfig) -> LazyFrame {
+    let relationships = relationships
+        .select([
+            col(schemas::EDGE_SOURCE),
+            col(schemas::EDGE_TARGET),
+            col(schemas::EDGE_WEIGHT).cast(DataType::Float64),
+        ])
+        .collect()
+        .unwrap();
+    let (titles, graph) = read_relationship_graph(&relationships);
+
+    let mut columns = vec![Series::new(schemas::TITLE.into(), titles).into()];
+    if config.pagerank {
+        columns.push(Series::new(schemas::NODE_PAGERANK.into(), pagerank(&graph, config.pagerank_damping)).into());
+    }
+    if config.betweenness {
+        let values = betweenness(&graph, config.betweenness_samples, config.seed as u64);
+        columns.push(Series::new(schemas::NODE_BETWEENNESS.into(), values).into());
+    }
+    if config.eigenvector {
+        columns.push(Series::new(schemas::NODE_EIGENVECTOR.into(), eigenvector(&graph)).into());
+    }
+    if config.core_number {
+        let values: Vec<i64> = core_number(&graph).into_iter().map(|core| core as i64).collect();
+        columns.push(Series::new(schemas::NODE_CORE_NUMBER.into(), values).into());
+    }
+
+    DataFrame::new(columns).unwrap().lazy()
+}
+
+/// The entity columns added by the enabled centrality metrics.
+pub fn centrality_columns(config: &GraphCentralityConfig) -> Vec<&'static str> {
+    [
+        (config.pagerank, schemas::NODE_PAGERANK),
+        (config.betweenness, schemas::NODE_BETWEENNESS),
+        (config.eigenvector, schemas::NODE_EIGENVECTOR),
+        (config.core_number, schemas::NODE_CORE_NUMBER),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, column)| column)
+    .collect()
+}
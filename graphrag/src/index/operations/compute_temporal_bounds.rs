@@ -0,0 +1,155 @@
+//! A module containing the temporal bound operations for entities and relationships.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use polars::prelude::{Expr, JoinArgs, JoinType, LazyFrame, NULL, UnionArgs, col, concat, lit, when};
+
+use crate::data_model::schemas::{
+    CREATION_DATE, DOCUMENT_IDS, EDGE_SOURCE, EDGE_TARGET, ID, TEXT_UNIT_IDS, TITLE, VALID_FROM,
+    VALID_TO,
+};
+
+/// Normalize a date emitted by the model to ISO-8601, returning None for unknown or unparsable values.
+pub fn parse_temporal_bound(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('*').trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("NONE") {
+        return None;
+    }
+    parse_date(value).map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Parse an ISO-8601 date or date-time into a calendar date.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|datetime| datetime.date_naive())
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").map(|d| d.date()).ok())
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z").map(|d| d.date()).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+        .or_else(|| NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d").ok())
+        .or_else(|| NaiveDate::parse_from_str(&format!("{value}-01-01"), "%Y-%m-%d").ok())
+}
+
+/**
+Fill missing relationship valid_from values with the creation date of the source documents.
+
+Each relationship falls back to the earliest creation date among the documents of its text units.
+*/
+pub fn fill_valid_from_with_creation_date(
+    relationships: LazyFrame,
+    text_units: LazyFrame,
+    documents: LazyFrame,
+) -> LazyFrame {
+    let text_unit_dates = text_units
+        .select([col(ID), col(DOCUMENT_IDS)])
+        .explode([col(DOCUMENT_IDS)])
+        .join(
+            documents.select([col(ID), col(CREATION_DATE)]),
+            [col(DOCUMENT_IDS)],
+            [col(ID)],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .select([col(ID), col(CREATION_DATE)]);
+
+    let relationship_dates = relationships
+        .clone()
+        .select([col(EDGE_SOURCE), col(EDGE_TARGET), col(TEXT_UNIT_IDS)])
+        .explode([col(TEXT_UNIT_IDS)])
+        .join(
+            text_unit_dates,
+            [col(TEXT_UNIT_IDS)],
+            [col(ID)],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .group_by([col(EDGE_SOURCE), col(EDGE_TARGET)])
+        .agg([col(CREATION_DATE).min()]);
+
+    relationships
+        .join(
+            relationship_dates,
+            [col(EDGE_SOURCE), col(EDGE_TARGET)],
+            [col(EDGE_SOURCE), col(EDGE_TARGET)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column(
+            when(col(VALID_FROM).is_null())
+                .then(col(CREATION_DATE).str().slice(lit(0), lit(10)))
+                .otherwise(col(VALID_FROM))
+                .alias(VALID_FROM),
+        )
+        .drop([CREATION_DATE])
+}
+
+/// The earliest valid_from of a group of rows.
+pub fn merged_valid_from() -> Expr {
+    col(VALID_FROM).min().alias(VALID_FROM)
+}
+
+/// The latest valid_to of a group of rows, staying open-ended (null) if any row has no valid_to.
+pub fn merged_valid_to() -> Expr {
+    when(col(VALID_TO).null_count().gt(lit(0)))
+        .then(lit(NULL))
+        .otherwise(col(VALID_TO).max())
+        .alias(VALID_TO)
+}
+
+/**
+Aggregate the valid_from and valid_to bounds of duplicate relationships.
+
+The merged relationship holds from the earliest valid_from; it stays open-ended if any duplicate has no valid_to.
+*/
+pub fn merge_temporal_bounds(relationships: LazyFrame) -> LazyFrame {
+    relationships
+        .group_by_stable([col(EDGE_SOURCE), col(EDGE_TARGET)])
+        .agg([merged_valid_from(), merged_valid_to()])
+}
+
+/**
+Derive entity valid_from and valid_to from the relationships each entity takes part in.
+
+Entities without any dated relationship keep null bounds, which means they are always valid.
+*/
+pub fn compute_entity_temporal_bounds(entities: LazyFrame, relationships: LazyFrame) -> LazyFrame {
+    let endpoints = concat(
+        [
+            relationships
+                .clone()
+                .select([col(EDGE_SOURCE).alias(TITLE), col(VALID_FROM), col(VALID_TO)]),
+            relationships.select([col(EDGE_TARGET).alias(TITLE), col(VALID_FROM), col(VALID_TO)]),
+        ],
+        UnionArgs::default(),
+    )
+    .unwrap();
+
+    let bounds = endpoints
+        .group_by([col(TITLE)])
+        .agg([merged_valid_from(), merged_valid_to()]);
+
+    entities.join(bounds, [col(TITLE)], [col(TITLE)], JoinArgs::new(JoinType::Left))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_parse_at_every_precision() {
+        let date = |value: &str| parse_date(value).map(|date| date.to_string());
+        assert_eq!(date("2024-03-05").as_deref(), Some("2024-03-05"));
+        assert_eq!(date("2024-03-05T10:20:30Z").as_deref(), Some("2024-03-05"));
+        assert_eq!(date("2024-03-05T10:20:30").as_deref(), Some("2024-03-05"));
+        assert_eq!(date("2024-03-05 10:20:30 +0200").as_deref(), Some("2024-03-05"));
+        assert_eq!(date("2024-03").as_deref(), Some("2024-03-01"));
+        assert_eq!(date("2024").as_deref(), Some("2024-01-01"));
+        assert_eq!(date("March 2024"), None);
+    }
+
+    #[test]
+    fn temporal_bounds_are_normalized_or_left_open() {
+        assert_eq!(parse_temporal_bound(" 2024-03-05T10:20:30Z ").as_deref(), Some("2024-03-05"));
+        assert_eq!(parse_temporal_bound("**2024**").as_deref(), Some("2024-01-01"));
+        assert_eq!(parse_temporal_bound("NONE"), None);
+        assert_eq!(parse_temporal_bound("none"), None);
+        assert_eq!(parse_temporal_bound(""), None);
+        assert_eq!(parse_temporal_bound("unknown"), None);
+    }
+}
@@ -1,6 +1,6 @@
 //! A module containing embed_text, load_strategy and create_row_from_embedding_data methods definition.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::zip;
 
 use log::info;
@@ -11,9 +11,17 @@ use polars::prelude::LazyFrame;
 use crate::cache::pipeline_cache::PipelineCache;
 use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
 use crate::config::embeddings::create_collection_name;
+use crate::config::models::hybrid_search_config::HybridSearchConfig;
+use crate::config::models::multi_vector_config::MultiVectorConfig;
+use crate::config::models::vector_store_config::VectorStoreConfig;
 use crate::index::operations::embed_text::strategies::typing::TextEmbeddingStrategy;
-use crate::vector_stores::base::{BaseVectorStore, VectorStoreDocument};
+use crate::index::text_splitting::segments::split_segments;
+use crate::index::update::documents::content_hash;
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::vector_stores::base::{BaseVectorStore, PARENT_ID_ATTRIBUTE, VectorStoreDocument};
 use crate::vector_stores::factory::VectorStoreFactory;
+use crate::vector_stores::hybrid::HybridVectorStore;
+use crate::vector_stores::late_interaction::segment_id;
 
 // Per Azure OpenAI Limits
 // https://learn.microsoft.com/en-us/azure/ai-services/openai/reference
@@ -44,6 +52,9 @@ impl std::fmt::Debug for TextEmbedStrategyType {
 /**
 Embed a piece of text into a vector space. The operation outputs a new column containing a mapping between doc_id and vector.
 
+Fails if the configured vector store type is not registered with the `VectorStoreFactory`, or if the
+embedding model could not be created or failed to embed the texts.
+
 ## Usage
 ```yaml
 args:
@@ -62,13 +73,13 @@ This strategy uses openai to embed a piece of text. In particular it uses a LLM
 strategy:
     type: openai
     llm: # The configuration for the LLM
-        type: openai_embedding # the type of llm to use, available options are: openai_embedding, azure_openai_embedding
+        type: openai_embedding # the type of llm to use, available options are: openai_embedding, azure_openai_embedding, onnx_embedding
         api_key: !ENV ${GRAPHRAG_OPENAI_API_KEY} # The api key to use for openai
         model: !ENV ${GRAPHRAG_OPENAI_MODEL:gpt-4-turbo-preview} # The model to use for openai
         max_tokens: !ENV ${GRAPHRAG_MAX_TOKENS:6000} # The max tokens to use for openai
         organization: !ENV ${GRAPHRAG_OPENAI_ORGANIZATION} # The organization to use for openai
     vector_store: # The optional configuration for the vector store
-        type: lancedb # The type of vector store to use, available options are: azure_ai_search, lancedb
+        type: hnsw # The type of vector store to use, available options are: hnsw, azure_ai_search, lancedb
         <...>
 ```
 */
@@ -76,21 +87,31 @@ pub async fn embed_text<T>(
     input: LazyFrame,
     callbacks: impl WorkflowCallbacks,
     cache: impl PipelineCache<T>,
+    storage: &mut impl PipelineStorage<Vec<u8>>,
     embed_column: &str,
     strategy: HashMap<String, String>,
     embedding_name: &str,
     // id_column: str = "id",
     // title_column: Option<str>,
-) {
+) -> Result<Vec<Option<Vec<f32>>>, String> {
     let id_column = "id";
     let title_column = None;
     let vector_store_config = strategy.get("vector_store");
 
     if vector_store_config {
         let collection_name = _get_collection_name(vector_store_config, embedding_name);
-        let vector_store = _create_vector_store(
+        let mut vector_store = _create_vector_store(
             vector_store_config, collection_name
-        );
+        )?;
+        // embeddings that are also searched by exact terms get a BM25 index next to the vectors
+        let hybrid_search: &HybridSearchConfig = vector_store_config["hybrid_search"];
+        if hybrid_search.enabled && hybrid_search.embeddings.iter().any(|name| name == embedding_name) {
+            vector_store = HybridVectorStore::new(vector_store, &collection_name, hybrid_search);
+        }
+        // long texts can be stored with a vector per segment, searched with late interaction
+        let multi_vector: &MultiVectorConfig = vector_store_config["multi_vector"];
+        let multi_vector = Some(multi_vector)
+            .filter(|multi_vector| multi_vector.enabled && multi_vector.embeddings.iter().any(|name| name == embedding_name));
         let vector_store_workflow_config = vector_store_config.get(
             embedding_name, vector_store_config
         );
@@ -98,13 +119,15 @@ pub async fn embed_text<T>(
             input,
             callbacks,
             cache,
+            storage,
             embed_column,
             strategy,
             vector_store,
             vector_store_config,
             id_column,
             title_column,
-        ).await
+            multi_vector,
+        ).await;
     }
 
     _text_embed_in_memory(
@@ -122,28 +145,30 @@ async fn _text_embed_in_memory<T>(
     cache: impl PipelineCache<T>,
     embed_column: &str,
     strategy: HashMap<String, String>,
-) -> TextEmbeddingStrategy {
+) -> Result<Vec<Option<Vec<f32>>>, String> {
     let strategy_type = strategy["type"];
     let strategy_exec = load_strategy(strategy_type);
     let strategy_config = {**strategy};
 
     let texts: Vec<String> = input[embed_column].to_numpy().tolist();
-    let result = strategy_exec(texts, callbacks, cache, strategy_config).await;
+    let result = strategy_exec(texts, callbacks, cache, strategy_config).await?;
 
-    result.embeddings
+    Ok(result.embeddings.unwrap_or_default())
 }
 
 async fn _text_embed_with_vector_store<T>(
     input: LazyFrame,
     callbacks: impl WorkflowCallbacks,
     cache: impl PipelineCache<T>,
+    storage: &mut impl PipelineStorage<Vec<u8>>,
     embed_column: &str,
     strategy: HashMap<String, String>,
-    vector_store: impl BaseVectorStore,
+    mut vector_store: impl BaseVectorStore,
     vector_store_config: HashMap<String, String>,
     id_column: &str, // = "id",
     title_column: Option<str>,
-) -> Vec<Vec<embedding>> {
+    multi_vector: Option<&MultiVectorConfig>,
+) -> Result<Vec<Option<Vec<f32>>>, String> {
     let strategy_type = strategy["type"];
     let strategy_exec = load_strategy(strategy_type);
     let strategy_config = {**strategy};
@@ -157,7 +182,9 @@ async fn _text_embed_with_vector_store<T>(
     let insert_batch_size: usize =
         vector_store_config.get("batch_size") || DEFAULT_EMBEDDING_BATCH_SIZE;
 
+    // only decides what happens to a collection without content hashes, see `_plan_embedding`
     let overwrite: bool = vector_store_config.get("overwrite", true);
+    let rebuild: bool = vector_store_config.get("rebuild", false);
 
     assert!(
         input.columns.contains(embed_column),
@@ -182,61 +209,204 @@ async fn _text_embed_with_vector_store<T>(
         }
     }
 
-    let mut i = 0;
-    let mut starting_index = 0;
-
-    let mut all_results = Vec::new();
+    // stores held in memory are read back first, so the hashes of the stored documents are known
+    vector_store.load(storage).await;
+    // a rebuild embeds every row as if nothing was stored
+    let stored_hashes = if rebuild { HashMap::new() } else { vector_store.content_hashes()? };
+    let model: &str = strategy_config.get("llm").and_then(|llm| llm.get("model")).unwrap_or_default();
+
+    let row_texts: Vec<String> = input[embed_column].to_numpy().tolist();
+    let titles: Vec<String> = input[title].to_numpy().tolist();
+    let row_ids: Vec<String> = input[id_column].to_numpy().tolist();
+    // any other column is kept as a filterable attribute
+    let attribute_columns: Vec<&str> = input.columns.iter().filter(|column| ![id_column, embed_column, title].contains(column)).collect();
+    let mut row_attributes: Vec<HashMap<String, serde_json::Value>> = input[attribute_columns].to_dict(orient="records");
+    // a title column of the input is kept over the embedded text
+    for (doc_attributes, doc_title) in zip(&mut row_attributes, &titles) {
+        doc_attributes.entry("title".into()).or_insert_with(|| doc_title.clone().into());
+    }
 
-    while insert_batch_size * i < input.shape[0] {
-        let batch = input.iloc[insert_batch_size * i..insert_batch_size * (i + 1)];
-        let texts: Vec<String> = batch[embed_column].to_numpy().tolist();
-        let titles: Vec<String> = batch[title].to_numpy().tolist();
-        let ids: Vec<String> = batch[id_column].to_numpy().tolist();
-        let result = strategy_exec(texts, callbacks, cache, strategy_config).await;
-        if result.embeddings {
-            let embeddings = result.embeddings.iter().filter(|embedding| embedding.is_some()).collect();
-            all_results.extend(embeddings);
+    // texts embedded with several vectors are stored as a document per segment, pointing back to their row
+    let (rows, ids, texts, attributes) = match multi_vector {
+        Some(multi_vector) => {
+            let (mut rows, mut ids, mut texts, mut attributes) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+            for (row, text) in row_texts.iter().enumerate() {
+                for (segment, segment_text) in split_segments(text, multi_vector).into_iter().enumerate() {
+                    let mut segment_attributes = row_attributes[row].clone();
+                    segment_attributes.insert(PARENT_ID_ATTRIBUTE.into(), row_ids[row].clone().into());
+                    rows.push(row);
+                    ids.push(segment_id(&row_ids[row], segment));
+                    texts.push(segment_text);
+                    attributes.push(segment_attributes);
+                }
+            }
+            (rows, ids, texts, attributes)
         }
-
-        let vectors = result.embeddings.unwrap_or_default();
-        let documents = Vec::<VectorStoreDocument>::new();
-        for (doc_id, doc_text, doc_title, doc_vector) in zip(
-            ids, texts, titles, vectors, strict=true
-        ) {
+        None => ((0..row_ids.len()).collect(), row_ids.clone(), row_texts, row_attributes),
+    };
+    // attributes are not part of the hash, changing them updates the stored document without embedding it again
+    let hashes: Vec<String> = texts.iter().map(|text| _document_content_hash(model, text)).collect();
+    let plan = _plan_embedding(&ids, &hashes, &stored_hashes, rebuild, overwrite);
+    info!(
+        "Embedding {} of {} documents, {} are unchanged",
+        plan.changed.len(),
+        ids.len(),
+        plan.unchanged.len()
+    );
+    let document = |position: usize, vector: Option<Vec<f32>>| VectorStoreDocument {
+        id: ids[position].clone(),
+        text: Some(texts[position].clone()),
+        vector,
+        attributes: attributes[position].clone(),
+        content_hash: Some(hashes[position].clone()),
+    };
+
+    let mut vectors: Vec<Option<Vec<f32>>> = vec![None; ids.len()];
+    for (i, batch) in plan.changed.chunks(insert_batch_size).enumerate() {
+        let batch_texts: Vec<String> = batch.iter().map(|position| texts[*position].clone()).collect();
+        let result = strategy_exec(batch_texts, callbacks, cache, strategy_config).await?;
+
+        let mut documents = Vec::<VectorStoreDocument>::new();
+        for (position, doc_vector) in zip(batch, result.embeddings.unwrap_or_default()) {
             if type(doc_vector) is np.ndarray {
                 doc_vector = doc_vector.tolist()
             }
-            let document = VectorStoreDocument {
-                id: doc_id,
-                text: doc_text,
-                vector: doc_vector,
-                attributes: {"title": doc_title},
+            vectors[*position] = doc_vector.clone();
+            documents.push(document(*position, doc_vector));
+        }
+
+        vector_store.load_documents(documents, plan.replace && i == 0)?;
+    }
+    if plan.replace && plan.changed.is_empty() {
+        // nothing was loaded to replace the collection with, so it is emptied
+        vector_store.load_documents(Vec::new(), true)?;
+    }
+
+    if !plan.removed.is_empty() {
+        info!("Deleting {} removed documents from the vector store", plan.removed.len());
+        vector_store.delete_documents(plan.removed)?;
+    }
+
+    // unchanged documents keep the vectors they were stored with, read back a batch at a time, and
+    // documents whose attributes changed are written again with them
+    for batch in plan.unchanged.chunks(insert_batch_size) {
+        let batch_ids: Vec<String> = batch.iter().map(|position| ids[*position].clone()).collect();
+        let mut stored: HashMap<String, VectorStoreDocument> = vector_store
+            .get_by_ids(&batch_ids)?
+            .into_iter()
+            .map(|stored| (stored.id.clone(), stored))
+            .collect();
+        let mut updated = Vec::new();
+        for position in batch {
+            let Some(stored) = stored.remove(&ids[*position]) else {
+                continue;
             };
-            documents.push(document);
+            vectors[*position] = stored.vector.clone();
+            if stored.attributes != attributes[*position] && stored.vector.is_some() {
+                updated.push(document(*position, stored.vector));
+            }
         }
+        if !updated.is_empty() {
+            vector_store.load_documents(updated, false)?;
+        }
+    }
 
-        vector_store.load_documents(documents, overwrite && i == 0);
-        starting_index += documents.len();
-        i += 1;
+    // stores held in memory are persisted next to the index tables
+    vector_store.save(storage).await;
+
+    if multi_vector.is_none() {
+        return Ok(vectors);
+    }
+    // the embedding column keeps a vector per row, the mean of its segment vectors
+    let mut row_vectors: Vec<Option<Vec<f32>>> = vec![None; row_ids.len()];
+    let mut segment_counts = vec![0.0f32; row_ids.len()];
+    for (row, vector) in zip(rows, vectors) {
+        let Some(vector) = vector else {
+            continue;
+        };
+        let sum = row_vectors[row].get_or_insert_with(|| vec![0.0; vector.len()]);
+        sum.iter_mut().zip(vector).for_each(|(sum, value)| *sum += value);
+        segment_counts[row] += 1.0;
     }
+    for (vector, count) in zip(&mut row_vectors, segment_counts) {
+        if let Some(vector) = vector {
+            vector.iter_mut().for_each(|value| *value /= count);
+        }
+    }
+    Ok(row_vectors)
+}
 
-    all_results
+/// The hash of what the vector of a stored document is embedded from, the model and the text.
+fn _document_content_hash(model: &str, text: &str) -> String {
+    content_hash(&format!("{model}\n{text}"))
 }
 
-fn _create_vector_store(
-    vector_store_config: dict, collection_name: str
-) -> impl BaseVectorStore {
-    let vector_store_type: str = str(vector_store_config.get("type"));
-    if collection_name {
-        vector_store_config.update({"collection_name": collection_name});
+/// What a run does with the vector store, and with every document of the input.
+#[derive(Debug, PartialEq)]
+struct EmbeddingPlan {
+    /// Whether the collection is replaced by the embedded documents rather than updated.
+    replace: bool,
+    /// The positions of the new documents and of those whose text or model changed, to embed.
+    changed: Vec<usize>,
+    /// The positions of the stored documents with the same content hash, keeping their vectors.
+    unchanged: Vec<usize>,
+    /// The ids of the stored documents missing from the input, to delete.
+    removed: Vec<String>,
+}
+
+/**
+Compare the documents of the input with the content hashes of the stored ones.
+
+A collection holding content hashes is updated incrementally. Without any, such as a new collection
+or one written before hashes were stored, every document is embedded and `overwrite` decides
+whether the collection is replaced or added to. A rebuild embeds every document and replaces the
+collection whatever it holds.
+
+Args:
+    - ids: The id of every document.
+    - hashes: The content hash of every document.
+    - stored_hashes: The content hashes of the stored documents, keyed by id.
+    - rebuild: Whether every document is embedded again.
+    - overwrite: Whether a collection without content hashes is replaced.
+*/
+fn _plan_embedding(
+    ids: &[String],
+    hashes: &[String],
+    stored_hashes: &HashMap<String, String>,
+    rebuild: bool,
+    overwrite: bool,
+) -> EmbeddingPlan {
+    if rebuild || stored_hashes.is_empty() {
+        return EmbeddingPlan {
+            replace: rebuild || overwrite,
+            changed: (0..ids.len()).collect(),
+            unchanged: Vec::new(),
+            removed: Vec::new(),
+        };
     }
+    let (unchanged, changed) =
+        (0..ids.len()).partition(|position| stored_hashes.get(&ids[*position]) == Some(&hashes[*position]));
+    // the input holds every document, so stored documents missing from it were removed
+    let current: HashSet<&String> = ids.iter().collect();
+    let mut removed: Vec<String> = stored_hashes.keys().filter(|id| !current.contains(id)).cloned().collect();
+    removed.sort();
+    EmbeddingPlan {
+        replace: false,
+        changed,
+        unchanged,
+        removed,
+    }
+}
 
-    let vector_store = VectorStoreFactory().create_vector_store(
-        vector_store_type, kwargs=vector_store_config
-    );
+fn _create_vector_store(
+    vector_store_config: dict, collection_name: str
+) -> Result<Box<dyn BaseVectorStore + Send + Sync>, String> {
+    let mut vector_store = VectorStoreFactory::create_vector_store(
+        &VectorStoreConfig(**vector_store_config), &collection_name
+    )?;
 
-    vector_store.connect(**vector_store_config);
-    vector_store
+    vector_store.connect()?;
+    Ok(vector_store)
 }
 
 fn _get_collection_name(vector_store_config: dict, embedding_name: &str) -> str {
@@ -262,3 +432,85 @@ fn load_strategy(strategy: TextEmbedStrategyType) -> TextEmbeddingStrategy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn stored(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(id, hash)| (id.to_string(), hash.to_string())).collect()
+    }
+
+    #[test]
+    fn content_hashes_depend_on_the_model_and_the_text() {
+        let hash = _document_content_hash("model", "text");
+        assert_eq!(hash, _document_content_hash("model", "text"));
+        assert_ne!(hash, _document_content_hash("other-model", "text"));
+        assert_ne!(hash, _document_content_hash("model", "other text"));
+    }
+
+    #[test]
+    fn only_new_and_changed_documents_are_embedded() {
+        let ids = strings(&["a", "b", "c"]);
+        let hashes = strings(&["hash-a", "hash-b2", "hash-c"]);
+        let stored_hashes = stored(&[("a", "hash-a"), ("b", "hash-b"), ("d", "hash-d"), ("e", "hash-e")]);
+        let plan = _plan_embedding(&ids, &hashes, &stored_hashes, false, true);
+        assert_eq!(
+            plan,
+            EmbeddingPlan {
+                replace: false,
+                changed: vec![1, 2],
+                unchanged: vec![0],
+                removed: strings(&["d", "e"]),
+            }
+        );
+    }
+
+    #[test]
+    fn unchanged_inputs_embed_nothing() {
+        let ids = strings(&["a", "b"]);
+        let hashes = strings(&["hash-a", "hash-b"]);
+        let stored_hashes = stored(&[("a", "hash-a"), ("b", "hash-b")]);
+        let plan = _plan_embedding(&ids, &hashes, &stored_hashes, false, true);
+        assert!(plan.changed.is_empty() && plan.removed.is_empty());
+        assert_eq!(plan.unchanged, vec![0, 1]);
+    }
+
+    #[test]
+    fn collections_without_hashes_are_replaced_when_overwriting() {
+        let ids = strings(&["a", "b"]);
+        let hashes = strings(&["hash-a", "hash-b"]);
+        for overwrite in [true, false] {
+            let plan = _plan_embedding(&ids, &hashes, &HashMap::new(), false, overwrite);
+            assert_eq!(plan.replace, overwrite);
+            assert_eq!(plan.changed, vec![0, 1]);
+            assert!(plan.unchanged.is_empty() && plan.removed.is_empty());
+        }
+    }
+
+    #[test]
+    fn rebuilds_embed_everything_and_replace_the_collection() {
+        let ids = strings(&["a", "b"]);
+        let hashes = strings(&["hash-a", "hash-b"]);
+        let stored_hashes = stored(&[("a", "hash-a"), ("c", "hash-c")]);
+        for overwrite in [true, false] {
+            let plan = _plan_embedding(&ids, &hashes, &stored_hashes, true, overwrite);
+            // the replaced collection drops removed documents, so none are deleted
+            assert_eq!(
+                plan,
+                EmbeddingPlan {
+                    replace: true,
+                    changed: vec![0, 1],
+                    unchanged: Vec::new(),
+                    removed: Vec::new(),
+                }
+            );
+        }
+        // an empty input still replaces the collection
+        assert!(_plan_embedding(&[], &[], &stored_hashes, true, false).replace);
+    }
+}
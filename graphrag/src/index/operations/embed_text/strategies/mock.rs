@@ -13,17 +13,17 @@ pub async fn run<T>(
     callbacks: impl WorkflowCallbacks,
     cache: impl PipelineCache<T>,
     _args: HashMap<String, String>,
-) -> TextEmbeddingResult {
+) -> Result<TextEmbeddingResult, String> {
     let ticker = progress_ticker(Some(callbacks.progress), input.len());
-    TextEmbeddingResult {
+    Ok(TextEmbeddingResult {
         embeddings: Some(input.iter().map(|text| {
             Some(_embed_text(cache, text, ticker))
         }).collect()),
-    }
+    })
 }
 
 /// Embed a single piece of text.
-fn _embed_text<T>(_cache: impl PipelineCache<T>, _text: &str, tick: ProgressTicker) -> Vec<f64> {
+fn _embed_text<T>(_cache: impl PipelineCache<T>, _text: &str, tick: ProgressTicker) -> Vec<f32> {
     tick(1);
     vec![random.random(), random.random(), random.random()]
 }
@@ -22,11 +22,11 @@ async fn run<T>(
     callbacks: impl WorkflowCallbacks,
     cache: impl PipelineCache<T>,
     args: HashMap<String, Box<dyn Any>>,
-) -> TextEmbeddingResult {
+) -> Result<TextEmbeddingResult, String> {
     if is_null(input) {
-        return TextEmbeddingResult {
+        return Ok(TextEmbeddingResult {
             embeddings: None,
-        };
+        });
     }
 
     let batch_size = args.get("batch_size", 16);
@@ -40,7 +40,7 @@ async fn run<T>(
         config=llm_config,
         callbacks=callbacks,
         cache=cache,
-    );
+    )?;
     let semaphore = asyncio.Semaphore(args.get("num_threads", 4));
 
     // Break up the input texts. The sizes here indicate how many snippets are in each input text
@@ -62,19 +62,23 @@ async fn run<T>(
     let ticker = progress_ticker(callbacks.progress, len(text_batches));
 
     // Embed each chunk of snippets
-    let embeddings = _execute(model, text_batches, ticker, semaphore).await;
+    let embeddings = _execute(model, text_batches, ticker, semaphore).await?;
     let embeddings = _reconstitute_embeddings(embeddings, input_sizes);
 
-    TextEmbeddingResult { embeddings }
+    Ok(TextEmbeddingResult { embeddings })
 }
 
+/// The splitter of texts longer than a batch, None for models without an encoding that split texts themselves.
 fn _get_splitter(
     config: LanguageModelConfig, batch_max_tokens: int
-) -> TokenTextSplitter {
-    TokenTextSplitter::new(
+) -> Option<TokenTextSplitter> {
+    if config.encoding_model.is_empty() {
+        return None;
+    }
+    Some(TokenTextSplitter::new(
         encoding_name=config.encoding_model,
         chunk_size=batch_max_tokens,
-    )
+    ))
 }
 
 async fn _execute(
@@ -82,20 +86,24 @@ async fn _execute(
     chunks: Vec<Vec<String>>,
     tick: ProgressTicker,
     semaphore: asyncio.Semaphore,
-) -> Vec<Vec<f64>> {
-    async fn embed(chunk: Vec<String>) -> Array {
+) -> Result<Vec<Vec<f32>>, String> {
+    async fn embed(chunk: Vec<String>) -> Result<Array, String> {
         async with semaphore {
-            let chunk_embeddings = model.aembed_batch(chunk).await;
+            let chunk_embeddings = model.aembed_batch(chunk).await?;
             let result = np.array(chunk_embeddings);
             tick(1);
         }
-        result
+        Ok(result)
     }
 
     let futures = chunks.iter().map(|chunk| embed(chunk)).collect();
     let results = asyncio.gather(*futures).await;
     // merge results in a single list of lists (reduce the collect dimension)
-    [item for sublist in results for item in sublist]
+    let mut embeddings = Vec::new();
+    for result in results {
+        embeddings.extend(result?);
+    }
+    Ok(embeddings)
 }
 
 /// Create batches of texts to embed.
@@ -103,7 +111,7 @@ fn _create_text_batches(
     texts: Vec<String>,
     max_batch_size: int,
     max_batch_tokens: int,
-    splitter: TokenTextSplitter,
+    splitter: Option<TokenTextSplitter>,
 ) -> Vec<Vec<String>> {
     // https://learn.microsoft.com/en-us/azure/ai-services/openai/reference
     // According to this embeddings reference, Azure limits us to 16 concurrent embeddings and 8191 tokens per request
@@ -112,7 +120,7 @@ fn _create_text_batches(
     let mut current_batch_tokens = 0;
 
     for text in texts.iter() {
-        let token_count = splitter.num_tokens(text);
+        let token_count = splitter.as_ref().map_or(0, |splitter| splitter.num_tokens(text));
         if (
             current_batch.len() >= max_batch_size
             || current_batch_tokens + token_count > max_batch_tokens
@@ -134,14 +142,17 @@ fn _create_text_batches(
 }
 
 fn _prepare_embed_texts(
-    input: Vec<String>, splitter: TokenTextSplitter,
+    input: Vec<String>, splitter: Option<TokenTextSplitter>,
 ) -> (Vec<String>, Vec<usize>) {
     let mut sizes = Vec::<usize>::new();
     let mut snippets = Vec::<String>::new();
 
     for text in input.iter() {
         // Split the input text and filter out any empty content
-        let split_texts = splitter.split_text(text);
+        let split_texts = match &splitter {
+            Some(splitter) => splitter.split_text(text),
+            None => Some(vec![text.clone()]),
+        };
         if let None = split_texts {
             continue;
         }
@@ -156,9 +167,9 @@ fn _prepare_embed_texts(
 
 /// Reconstitute the embeddings into the original input texts.
 fn _reconstitute_embeddings(
-    raw_embeddings: Vec<Vec<f64>>, sizes: Vec<int>,
-) -> Vec<Option<Vec<f64>>> {
-    let mut embeddings = Vec::<Option<Vec<f64>>>::new();
+    raw_embeddings: Vec<Vec<f32>>, sizes: Vec<int>,
+) -> Vec<Option<Vec<f32>>> {
+    let mut embeddings = Vec::<Option<Vec<f32>>>::new();
     let cursor = 0;
     for size in sizes {
         if size == 0 {
@@ -7,12 +7,13 @@ use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
 
 /// Text embedding result class definition.
 pub struct TextEmbeddingResult {
-    embeddings: Option<Vec<Option<Vec<f64>>>>,
+    embeddings: Option<Vec<Option<Vec<f32>>>>,
 }
 
+/// A text embedding strategy, failing with the reason the texts could not be embedded.
 pub type TextEmbeddingStrategy = fn(
     Vec<String>,
     impl WorkflowCallbacks,
     impl PipelineCache,
     HashMap<String, String>,
-) -> impl Future<Output = TextEmbeddingResult>;
+) -> impl Future<Output = Result<TextEmbeddingResult, String>>;
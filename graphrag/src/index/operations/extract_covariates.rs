@@ -1,3 +1,5 @@
 //! The Indexing Engine text extract claims package root.
 
+pub mod claim_extractor;
 pub mod extract_covariates;
+pub mod typing;
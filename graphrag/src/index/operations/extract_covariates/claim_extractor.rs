@@ -0,0 +1,214 @@
+//! A module containing 'ClaimExtractorResult' and 'ClaimExtractor' models.
+
+use std::collections::HashMap;
+
+use crate::index::operations::extract_covariates::typing::Covariate;
+use crate::index::utils::tuple_records::{RecordDelimiters, glean_records};
+use crate::language_model::protocol::base::ChatModel;
+use crate::prompts::index::extract_claims::{CONTINUE_PROMPT, EXTRACT_CLAIMS_PROMPT, LOOP_PROMPT};
+
+/// Claim extractor result class definition.
+#[derive(Debug, Default)]
+pub struct ClaimExtractorResult {
+    pub output: Vec<Covariate>,
+    pub source_docs: HashMap<String, String>,
+}
+
+/// A function to handle extraction errors, called with the error message and details.
+pub type ErrorHandlerFn<'a> = Box<dyn Fn(String, HashMap<String, String>) + 'a>;
+
+/// Claim extractor class definition.
+pub struct ClaimExtractor<'a, M: ChatModel> {
+    model: M,
+    extraction_prompt: String,
+    delimiters: RecordDelimiters,
+    max_gleanings: usize,
+    on_error: ErrorHandlerFn<'a>,
+}
+
+impl<'a, M: ChatModel> ClaimExtractor<'a, M> {
+    /// Init method definition.
+    pub fn new(
+        model: M,
+        extraction_prompt: Option<String>,
+        tuple_delimiter: Option<String>,
+        record_delimiter: Option<String>,
+        completion_delimiter: Option<String>,
+        max_gleanings: usize,
+        on_error: Option<ErrorHandlerFn<'a>>,
+    ) -> Self {
+        ClaimExtractor {
+            model,
+            extraction_prompt: extraction_prompt.unwrap_or_else(|| EXTRACT_CLAIMS_PROMPT.into()),
+            delimiters: RecordDelimiters::new(tuple_delimiter, record_delimiter, completion_delimiter),
+            max_gleanings,
+            on_error: on_error.unwrap_or_else(|| Box::new(|_, _| {})),
+        }
+    }
+
+    /**
+    Extract claims from each input text.
+
+    Args:
+        texts: The texts to extract claims from.
+        entity_specs: The entity names or types the claims must be about.
+        claim_description: The description of the claims to extract.
+        resolved_entities: A mapping used to resolve subject and object names.
+    */
+    pub async fn extract(
+        &self,
+        texts: &[String],
+        entity_specs: &[String],
+        claim_description: &str,
+        resolved_entities: &HashMap<String, String>,
+    ) -> ClaimExtractorResult {
+        let entity_specs = entity_specs.join(", ");
+        let mut result = ClaimExtractorResult::default();
+
+        for (doc_index, text) in texts.iter().enumerate() {
+            let document_id = format!("d{doc_index}");
+            match self
+                .process_document(text, &entity_specs, claim_description)
+                .await
+            {
+                Ok(claims) => {
+                    result.output.extend(
+                        claims
+                            .into_iter()
+                            .map(|claim| self.clean_claim(claim, &document_id, resolved_entities)),
+                    );
+                    result.source_docs.insert(document_id, text.clone());
+                }
+                Err(error) => {
+                    (self.on_error)(
+                        error,
+                        HashMap::from([
+                            ("doc_index".into(), doc_index.to_string()),
+                            ("text".into(), text.clone()),
+                        ]),
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Resolve the subject and object names and tag the claim with its source document.
+    fn clean_claim(
+        &self,
+        mut claim: Covariate,
+        document_id: &str,
+        resolved_entities: &HashMap<String, String>,
+    ) -> Covariate {
+        let resolve = |name: Option<String>| {
+            name.map(|name| resolved_entities.get(&name).cloned().unwrap_or(name))
+        };
+        claim.object_id = resolve(claim.object_id);
+        claim.subject_id = resolve(claim.subject_id);
+        claim.doc_id = Some(document_id.into());
+        claim
+    }
+
+    /// Prompt the model for claims in a single document, running gleanings if configured.
+    async fn process_document(
+        &self,
+        text: &str,
+        entity_specs: &str,
+        claim_description: &str,
+    ) -> Result<Vec<Covariate>, String> {
+        let prompt = self
+            .delimiters
+            .fill_prompt(&self.extraction_prompt)
+            .replace("{entity_specs}", entity_specs)
+            .replace("{claim_description}", claim_description)
+            .replace("{input_text}", text);
+
+        let claims = glean_records(
+            &self.model,
+            &prompt,
+            CONTINUE_PROMPT,
+            LOOP_PROMPT,
+            self.max_gleanings,
+            &self.delimiters,
+        )
+        .await?;
+
+        Ok(parse_claim_tuples(&claims, &self.delimiters))
+    }
+}
+
+/// Parse claim tuples.
+pub fn parse_claim_tuples(claims: &str, delimiters: &RecordDelimiters) -> Vec<Covariate> {
+    let pull_field = |index: usize, fields: &[String]| -> Option<String> {
+        fields.get(index).map(|field| field.trim().to_string())
+    };
+
+    delimiters
+        .split_records(claims)
+        .into_iter()
+        .map(|claim_fields| Covariate {
+            subject_id: pull_field(0, &claim_fields),
+            object_id: pull_field(1, &claim_fields),
+            r#type: pull_field(2, &claim_fields),
+            status: pull_field(3, &claim_fields),
+            start_date: pull_field(4, &claim_fields),
+            end_date: pull_field(5, &claim_fields),
+            description: pull_field(6, &claim_fields),
+            source_text: pull_field(7, &claim_fields),
+            doc_id: pull_field(8, &claim_fields),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claim_fields_are_read_in_order() {
+        let claims = "(COMPANY A<|>GOVERNMENT AGENCY B<|>ANTI-COMPETITIVE PRACTICES<|>TRUE<|>2022-01-10T00:00:00<|>2022-01-10T00:00:00<|>Company A was fined<|>According to an article, Company A was fined)\n##\n<|COMPLETE|>";
+        let parsed = parse_claim_tuples(claims, &RecordDelimiters::default());
+        assert_eq!(
+            parsed,
+            [Covariate {
+                subject_id: Some("COMPANY A".into()),
+                object_id: Some("GOVERNMENT AGENCY B".into()),
+                r#type: Some("ANTI-COMPETITIVE PRACTICES".into()),
+                status: Some("TRUE".into()),
+                start_date: Some("2022-01-10T00:00:00".into()),
+                end_date: Some("2022-01-10T00:00:00".into()),
+                description: Some("Company A was fined".into()),
+                source_text: Some("According to an article, Company A was fined".into()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_fields_are_none_and_fields_are_trimmed() {
+        let claims = "( PERSON C <|> NONE )##(PERSON D)";
+        let parsed = parse_claim_tuples(claims, &RecordDelimiters::default());
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].subject_id.as_deref(), Some("PERSON C"));
+        assert_eq!(parsed[0].object_id.as_deref(), Some("NONE"));
+        assert_eq!(parsed[0].r#type, None);
+        assert_eq!(parsed[1].subject_id.as_deref(), Some("PERSON D"));
+        assert_eq!(parsed[1].object_id, None);
+    }
+
+    #[test]
+    fn empty_output_has_no_claims() {
+        assert!(parse_claim_tuples("<|COMPLETE|>", &RecordDelimiters::default()).is_empty());
+        assert!(parse_claim_tuples("", &RecordDelimiters::default()).is_empty());
+    }
+
+    #[test]
+    fn custom_delimiters_are_used() {
+        let delimiters = RecordDelimiters::new(Some("|".into()), Some(";".into()), Some("END".into()));
+        let parsed = parse_claim_tuples("(A|B|FRAUD);(C|D|THEFT)END", &delimiters);
+        let types: Vec<_> = parsed.iter().map(|claim| claim.r#type.as_deref()).collect();
+        assert_eq!(types, [Some("FRAUD"), Some("THEFT")]);
+    }
+}
@@ -1,158 +1,175 @@
-# Copyright (c) 2024 Microsoft Corporation.
-# Licensed under the MIT License
-
-"""A module containing the extract_covariates verb definition."""
-
-import logging
-from collections.abc import Iterable
-from dataclasses import asdict
-from typing import Any
-
-import pandas as pd
-
-from graphrag.cache.pipeline_cache import PipelineCache
-from graphrag.callbacks.workflow_callbacks import WorkflowCallbacks
-from graphrag.config.defaults import graphrag_config_defaults
-from graphrag.config.enums import AsyncType
-from graphrag.config.models.language_model_config import LanguageModelConfig
-from graphrag.index.operations.extract_covariates.claim_extractor import ClaimExtractor
-from graphrag.index.operations.extract_covariates.typing import (
-    Covariate,
-    CovariateExtractionResult,
-)
-from graphrag.index.utils.derive_from_rows import derive_from_rows
-from graphrag.language_model.manager import ModelManager
-
-log = logging.getLogger(__name__)
-
-
-DEFAULT_ENTITY_TYPES = ["organization", "person", "geo", "event"]
-
-
-async def extract_covariates(
-    input: pd.DataFrame,
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    column: str,
-    covariate_type: str,
-    strategy: dict[str, Any] | None,
-    async_mode: AsyncType = AsyncType.AsyncIO,
-    entity_types: list[str] | None = None,
-    num_threads: int = 4,
-):
-    """Extract claims from a piece of text."""
-    log.debug("extract_covariates strategy=%s", strategy)
-    if entity_types is None:
-        entity_types = DEFAULT_ENTITY_TYPES
-
-    resolved_entities_map = {}
-
-    strategy = strategy or {}
-    strategy_config = {**strategy}
-
-    # if max_retries is not set, inject a dynamically assigned value based on the total number of expected LLM calls to be made
-    if strategy_config.get("llm") and strategy_config["llm"]["max_retries"] == -1:
-        strategy_config["llm"]["max_retries"] = len(input)
-
-    async def run_strategy(row):
-        text = row[column]
-        result = await run_extract_claims(
-            input=text,
-            entity_types=entity_types,
-            resolved_entities_map=resolved_entities_map,
-            callbacks=callbacks,
-            cache=cache,
-            strategy_config=strategy_config,
-        )
-        return [
-            create_row_from_claim_data(row, item, covariate_type)
-            for item in result.covariate_data
-        ]
-
-    results = await derive_from_rows(
-        input,
-        run_strategy,
-        callbacks,
-        async_type=async_mode,
-        num_threads=num_threads,
-    )
-    return pd.DataFrame([item for row in results for item in row or []])
-
-
-def create_row_from_claim_data(row, covariate_data: Covariate, covariate_type: str):
-    """Create a row from the claim data and the input row."""
-    return {**row, **asdict(covariate_data), "covariate_type": covariate_type}
-
-
-async def run_extract_claims(
-    input: str | Iterable[str],
-    entity_types: list[str],
-    resolved_entities_map: dict[str, str],
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    strategy_config: dict[str, Any],
-) -> CovariateExtractionResult:
-    """Run the Claim extraction chain."""
-    llm_config = LanguageModelConfig(**strategy_config["llm"])
-    llm = ModelManager().get_or_create_chat_model(
-        name="extract_claims",
-        model_type=llm_config.type,
-        config=llm_config,
-        callbacks=callbacks,
-        cache=cache,
-    )
-
-    extraction_prompt = strategy_config.get("extraction_prompt")
-    max_gleanings = strategy_config.get(
-        "max_gleanings", graphrag_config_defaults.extract_claims.max_gleanings
-    )
-    tuple_delimiter = strategy_config.get("tuple_delimiter")
-    record_delimiter = strategy_config.get("record_delimiter")
-    completion_delimiter = strategy_config.get("completion_delimiter")
-    encoding_model = strategy_config.get("encoding_name")
-
-    extractor = ClaimExtractor(
-        model_invoker=llm,
-        extraction_prompt=extraction_prompt,
-        max_gleanings=max_gleanings,
-        encoding_model=encoding_model,
-        on_error=lambda e, s, d: (
-            callbacks.error("Claim Extraction Error", e, s, d) if callbacks else None
-        ),
-    )
-
-    claim_description = strategy_config.get("claim_description")
-    if claim_description is None:
-        msg = "claim_description is required for claim extraction"
-        raise ValueError(msg)
-
-    input = [input] if isinstance(input, str) else input
-
-    results = await extractor({
-        "input_text": input,
-        "entity_specs": entity_types,
-        "resolved_entities": resolved_entities_map,
-        "claim_description": claim_description,
-        "tuple_delimiter": tuple_delimiter,
-        "record_delimiter": record_delimiter,
-        "completion_delimiter": completion_delimiter,
-    })
-
-    claim_data = results.output
-    return CovariateExtractionResult([create_covariate(item) for item in claim_data])
-
-
-def create_covariate(item: dict[str, Any]) -> Covariate:
-    """Create a covariate from the item."""
-    return Covariate(
-        subject_id=item.get("subject_id"),
-        object_id=item.get("object_id"),
-        type=item.get("type"),
-        status=item.get("status"),
-        start_date=item.get("start_date"),
-        end_date=item.get("end_date"),
-        description=item.get("description"),
-        source_text=item.get("source_text"),
-        record_id=item.get("record_id"),
-        id=item.get("id"),
-    )
+//! A module containing the extract_covariates verb definition.
+
+use std::collections::HashMap;
+
+use futures::stream::{self, StreamExt};
+use log::debug;
+use polars::prelude::{DataFrame, IntoLazy, LazyFrame, NamedFrom, Series, col};
+use uuid::Uuid;
+
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::extract_claims_config::ClaimExtractionConfig;
+use crate::data_model::schemas::COVARIATES_FINAL_COLUMNS;
+use crate::index::operations::extract_covariates::claim_extractor::ClaimExtractor;
+use crate::index::operations::extract_covariates::typing::{Covariate, CovariateExtractionResult};
+use crate::language_model::protocol::base::ChatModel;
+use crate::logger::progress::Progress;
+use crate::utils::dataframe::get_string;
+
+pub const DEFAULT_ENTITY_TYPES: [&str; 4] = ["organization", "person", "geo", "event"];
+
+/**
+Extract claims from a piece of text.
+
+## Usage
+```yaml
+args:
+    column: the_document_text_column_to_extract_claims_from
+    covariate_type: the type of covariate to tag each claim with, e.g. claim
+    entity_types:
+        - list
+        - of
+        - entity
+        - types
+        - to
+        - extract
+```
+*/
+pub async fn extract_covariates<M: ChatModel>(
+    text_units: LazyFrame,
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    column: &str, // = "text",
+    covariate_type: &str, // = "claim",
+    config: &ClaimExtractionConfig,
+    extraction_prompt: Option<String>,
+    entity_types: Option<Vec<String>>,
+    num_threads: usize, // = 4,
+) -> LazyFrame {
+    debug!("extract_covariates max_gleanings={}", config.max_gleanings);
+    let entity_types = entity_types
+        .unwrap_or_else(|| DEFAULT_ENTITY_TYPES.iter().map(|t| t.to_string()).collect());
+    let resolved_entities_map = HashMap::new();
+
+    let extractor = ClaimExtractor::new(
+        model,
+        extraction_prompt,
+        None,
+        None,
+        None,
+        config.max_gleanings,
+        Some(Box::new(|message, details| {
+            callbacks.error(format!("Claim Extraction Error: {message}"), None, None, Some(details))
+        })),
+    );
+
+    // text units without an id or a text have nothing to extract
+    let text_units = text_units
+        .select([col("id"), col(column)])
+        .drop_nulls(None)
+        .collect()
+        .unwrap();
+    let total_items = text_units.height();
+    let rows = (0..total_items).map(|row| {
+        (get_string(&text_units, "id", row), get_string(&text_units, column, row))
+    });
+
+    // up to num_threads text units are extracted at once, results keep the order of the text units
+    let extractor = &extractor;
+    let entity_types = &entity_types;
+    let resolved_entities_map = &resolved_entities_map;
+    let mut results = stream::iter(rows)
+        .map(|(id, text)| async move {
+            let result = run_extract_claims(
+                extractor,
+                &text,
+                entity_types,
+                resolved_entities_map,
+                &config.description,
+            )
+            .await;
+            (id, result)
+        })
+        .buffered(num_threads.max(1));
+
+    let mut rows: Vec<(String, Covariate)> = Vec::new();
+    let mut completed_items = 0;
+    while let Some((id, result)) = results.next().await {
+        rows.extend(
+            result
+                .covariate_data
+                .into_iter()
+                .map(|covariate| (id.clone(), create_row_from_claim_data(covariate, covariate_type))),
+        );
+        completed_items += 1;
+        callbacks.progress(Progress {
+            total_items: Some(total_items),
+            completed_items: Some(completed_items),
+            ..Default::default()
+        });
+    }
+
+    create_covariates_table(rows)
+}
+
+/// Create a row from the claim data and the input row.
+fn create_row_from_claim_data(mut covariate_data: Covariate, covariate_type: &str) -> Covariate {
+    covariate_data.covariate_type = Some(covariate_type.into());
+    covariate_data
+}
+
+/// Run the Claim extraction chain.
+async fn run_extract_claims<M: ChatModel>(
+    extractor: &ClaimExtractor<'_, M>,
+    input: &str,
+    entity_types: &[String],
+    resolved_entities_map: &HashMap<String, String>,
+    claim_description: &str,
+) -> CovariateExtractionResult {
+    let results = extractor
+        .extract(&[input.to_string()], entity_types, claim_description, resolved_entities_map)
+        .await;
+
+    CovariateExtractionResult {
+        covariate_data: results.output,
+    }
+}
+
+/// Assemble the final covariates table from the extracted claims and their source text unit ids.
+fn create_covariates_table(rows: Vec<(String, Covariate)>) -> LazyFrame {
+    let field = |name: &str, get: fn(&Covariate) -> Option<String>| -> Series {
+        let values: Vec<Option<String>> = rows.iter().map(|(_, covariate)| get(covariate)).collect();
+        Series::new(name.into(), values)
+    };
+
+    let mut columns: HashMap<&str, Series> = HashMap::from([
+        ("covariate_type", field("covariate_type", |c| c.covariate_type.clone())),
+        ("type", field("type", |c| c.r#type.clone())),
+        ("description", field("description", |c| c.description.clone())),
+        ("subject_id", field("subject_id", |c| c.subject_id.clone())),
+        ("object_id", field("object_id", |c| c.object_id.clone())),
+        ("status", field("status", |c| c.status.clone())),
+        ("start_date", field("start_date", |c| c.start_date.clone())),
+        ("end_date", field("end_date", |c| c.end_date.clone())),
+        ("source_text", field("source_text", |c| c.source_text.clone())),
+    ]);
+    columns.insert(
+        "id",
+        Series::new("id".into(), rows.iter().map(|_| Uuid::new_v4().to_string()).collect::<Vec<_>>()),
+    );
+    columns.insert(
+        "human_readable_id",
+        Series::new("human_readable_id".into(), (0..rows.len() as u64).collect::<Vec<_>>()),
+    );
+    columns.insert(
+        "text_unit_id",
+        Series::new("text_unit_id".into(), rows.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>()),
+    );
+
+    let series = COVARIATES_FINAL_COLUMNS
+        .iter()
+        .map(|name| columns.remove(name).unwrap().into())
+        .collect();
+
+    DataFrame::new(series).unwrap().lazy()
+}
@@ -0,0 +1,26 @@
+//! A module containing 'Covariate' and 'CovariateExtractionResult' models.
+
+use serde::{Deserialize, Serialize};
+
+/// Covariate class definition.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Covariate {
+    pub covariate_type: Option<String>,
+    pub subject_id: Option<String>,
+    pub object_id: Option<String>,
+    pub r#type: Option<String>,
+    pub status: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub description: Option<String>,
+    pub source_text: Option<String>,
+    pub doc_id: Option<String>,
+    pub record_id: Option<usize>,
+    pub id: Option<String>,
+}
+
+/// Covariate extraction result class definition.
+#[derive(Debug, Default)]
+pub struct CovariateExtractionResult {
+    pub covariate_data: Vec<Covariate>,
+}
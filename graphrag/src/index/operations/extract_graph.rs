@@ -1,3 +1,5 @@
 //! The Indexing Engine entities extraction package root.
 
 pub mod extract_graph;
+pub mod graph_extractor;
+pub mod typing;
@@ -1,20 +1,26 @@
 //! A module containing entity_extract methods.
 
-use log;
+use futures::stream::{self, StreamExt};
+use log::debug;
+use polars::prelude::{DataFrame, IntoLazy, LazyFrame, NamedFrom, Series, col};
 
-use polars::prelude::{LazyFrame};
-
-use crate::cache::pipeline_cache::PipelineCache;
 use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
-use crate::config::enums::AsyncType;
-use crate::index::operations::extract_graph::typing::{
-    Document,
-    EntityExtractStrategy,
-    ExtractEntityStrategyType,
+use crate::data_model::schemas::{
+    DESCRIPTION, EDGE_SOURCE, EDGE_TARGET, EDGE_WEIGHT, NODE_FREQUENCY, TEXT_UNIT_IDS, TITLE, TYPE,
+    VALID_FROM, VALID_TO,
+};
+use crate::index::operations::compute_temporal_bounds::{merged_valid_from, merged_valid_to};
+use crate::index::operations::extract_graph::graph_extractor::{
+    ExtractedEntity,
+    ExtractedRelationship,
+    GraphExtractor,
 };
-use crate::index::utils::derive_from_rows::derive_from_rows;
+use crate::index::operations::extract_graph::typing::ExtractGraphStrategy;
+use crate::language_model::protocol::base::ChatModel;
+use crate::logger::progress::Progress;
+use crate::utils::dataframe::get_string;
 
-const DEFAULT_ENTITY_TYPES = ["organization", "person", "geo", "event"];
+pub const DEFAULT_ENTITY_TYPES: [&str; 4] = ["organization", "person", "geo", "event"];
 
 /**
 Extract entities from a piece of text.
@@ -37,133 +43,128 @@ args:
 ```
 
 ## Strategies
-The entity extract verb uses a strategy to extract entities from a document. The strategy is a json object which defines the strategy to use. The following strategies are available:
+The entity extract verb uses a strategy to extract entities from a document. The following strategies are available:
 
 ### graph_intelligence
-This strategy uses the [graph_intelligence] library to extract entities from a document. In particular it uses a LLM to extract entities from a piece of text. The strategy config is as follows:
+This strategy uses a LLM to extract entities and relationships from each text unit. The strategy config is as follows:
 
 ```yml
 strategy:
     type: graph_intelligence
-    extraction_prompt: !include ./extract_graph_prompt.txt # Optional, the prompt to use for extraction
+    extraction_prompt: # Optional, the prompt to use for extraction
     completion_delimiter: "<|COMPLETE|>" # Optional, the delimiter to use for the LLM to mark completion
     tuple_delimiter: "<|>" # Optional, the delimiter to use for the LLM to mark a tuple
     record_delimiter: "##" # Optional, the delimiter to use for the LLM to mark a record
-
-    encoding_name: cl100k_base # Optional, The encoding to use for the LLM with gleanings
-
-    llm: # The configuration for the LLM
-        type: openai # the type of llm to use, available options are: openai, azure, openai_chat, azure_openai_chat.  The last two being chat based LLMs.
-        api_key: !ENV ${GRAPHRAG_OPENAI_API_KEY} # The api key to use for openai
-        model: !ENV ${GRAPHRAG_OPENAI_MODEL:gpt-4-turbo-preview} # The model to use for openai
-        max_tokens: !ENV ${GRAPHRAG_MAX_TOKENS:6000} # The max tokens to use for openai
-        organization: !ENV ${GRAPHRAG_OPENAI_ORGANIZATION} # The organization to use for openai
-
-        # if using azure flavor
-        api_base: !ENV ${GRAPHRAG_OPENAI_API_BASE} # The api base to use for azure
-        api_version: !ENV ${GRAPHRAG_OPENAI_API_VERSION} # The api version to use for azure
-        proxy: !ENV ${GRAPHRAG_OPENAI_PROXY} # The proxy to use for azure
-
+    max_gleanings: 1 # Optional, the maximum number of follow-up extractions per text unit
 ```
- */
-pub async fn extract_graph(
+*/
+pub async fn extract_graph<M: ChatModel>(
     text_units: LazyFrame,
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    text_column: str,
-    id_column: str,
-    strategy: dict[str, Any] | None,
-    async_mode: AsyncType = AsyncType.AsyncIO,
-    entity_types=DEFAULT_ENTITY_TYPES,
-    num_threads: int = 4,
-) -> tuple[LazyFrame, LazyFrame]:
-
-    log.debug("entity_extract strategy=%s", strategy)
-    if entity_types is None {
-        entity_types = DEFAULT_ENTITY_TYPES
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    text_column: &str, // = "text",
+    id_column: &str, // = "id",
+    strategy: &ExtractGraphStrategy,
+    entity_types: Option<Vec<String>>,
+    num_threads: usize, // = 4,
+) -> (LazyFrame, LazyFrame) {
+    debug!("entity_extract strategy={strategy:?}");
+    let entity_types = entity_types
+        .unwrap_or_else(|| DEFAULT_ENTITY_TYPES.iter().map(|t| t.to_string()).collect());
+
+    let extractor = GraphExtractor::new(
+        model,
+        strategy.extraction_prompt.clone(),
+        strategy.tuple_delimiter.clone(),
+        strategy.record_delimiter.clone(),
+        strategy.completion_delimiter.clone(),
+        strategy.max_gleanings,
+        Some(Box::new(|message, details| {
+            callbacks.error(format!("Entity Extraction Error: {message}"), None, None, Some(details))
+        })),
+    );
+
+    let text_units = text_units.select([col(id_column), col(text_column)]).collect().unwrap();
+    let total_items = text_units.height();
+    let rows = (0..total_items).map(|row| {
+        (get_string(&text_units, id_column, row), get_string(&text_units, text_column, row))
+    });
+
+    // every text unit yields its own graph, up to num_threads text units are extracted at once
+    let extractor = &extractor;
+    let entity_types = &entity_types;
+    let mut results = stream::iter(rows)
+        .map(|(id, text)| async move { extractor.extract(&id, &text, entity_types).await })
+        .buffered(num_threads.max(1));
+
+    let mut extracted_entities = Vec::new();
+    let mut extracted_relationships = Vec::new();
+    let mut completed_items = 0;
+    while let Some(result) = results.next().await {
+        extracted_entities.extend(result.entities);
+        extracted_relationships.extend(result.relationships);
+        completed_items += 1;
+        callbacks.progress(Progress {
+            total_items: Some(total_items),
+            completed_items: Some(completed_items),
+            ..Default::default()
+        });
     }
-    strategy = strategy or {}
-    strategy_exec = _load_strategy(
-        strategy.get("type", ExtractEntityStrategyType.graph_intelligence)
-    )
-    strategy_config = {**strategy}
-
-    // if max_retries is not set, inject a dynamically assigned value based on the total number of expected LLM calls to be made
-    if strategy_config.get("llm") and strategy_config["llm"]["max_retries"] == -1:
-        strategy_config["llm"]["max_retries"] = len(text_units)
-
-    num_started = 0
-
-    async def run_strategy(row):
-        nonlocal num_started
-        text = row[text_column]
-        id = row[id_column]
-        result = await strategy_exec(
-            [Document(text=text, id=id)],
-            entity_types,
-            callbacks,
-            cache,
-            strategy_config,
-        )
-        num_started += 1
-        return [result.entities, result.relationships, result.graph]
-
-    results = await derive_from_rows(
-        text_units,
-        run_strategy,
-        callbacks,
-        async_type=async_mode,
-        num_threads=num_threads,
-    )
-
-    entity_dfs = []
-    relationship_dfs = []
-    for result in results:
-        if result:
-            entity_dfs.append(LazyFrame(result[0]))
-            relationship_dfs.append(LazyFrame(result[1]))
-
-    entities = _merge_entities(entity_dfs)
-    relationships = _merge_relationships(relationship_dfs)
-
-    return (entities, relationships)
-
-/// Load strategy method definition.
-def _load_strategy(strategy_type: ExtractEntityStrategyType) -> EntityExtractStrategy:
-    match strategy_type:
-        case ExtractEntityStrategyType.graph_intelligence:
-            use crate::index.operations.extract_graph.graph_intelligence_strategy::(
-                run_graph_intelligence,
-            )
-
-            return run_graph_intelligence
-
-        case _:
-            msg = f"Unknown strategy: {strategy_type}"
-            raise ValueError(msg)
-
-
-def _merge_entities(entity_dfs) -> LazyFrame:
-    all_entities = pd.concat(entity_dfs, ignore_index=True)
-    return (
-        all_entities.groupby(["title", "type"], sort=False)
-        .agg(
-            description=("description", list),
-            text_unit_ids=("source_id", list),
-            frequency=("source_id", "count"),
-        )
-        .reset_index()
-    )
-
-
-def _merge_relationships(relationship_dfs) -> LazyFrame:
-    all_relationships = pd.concat(relationship_dfs, ignore_index=False)
-    return (
-        all_relationships.groupby(["source", "target"], sort=False)
-        .agg(
-            description=("description", list),
-            text_unit_ids=("source_id", list),
-            weight=("weight", "sum"),
-        )
-        .reset_index()
-    )
+
+    (_merge_entities(extracted_entities), _merge_relationships(extracted_relationships))
+}
+
+/// Merge the entities extracted from every text unit by title and type.
+fn _merge_entities(entities: Vec<ExtractedEntity>) -> LazyFrame {
+    let column = |name: &str, get: fn(&ExtractedEntity) -> &str| -> Series {
+        Series::new(name.into(), entities.iter().map(get).collect::<Vec<_>>())
+    };
+    let all_entities = DataFrame::new(vec![
+        column(TITLE, |entity| &entity.title).into(),
+        column(TYPE, |entity| &entity.r#type).into(),
+        column(DESCRIPTION, |entity| &entity.description).into(),
+        column("source_id", |entity| &entity.source_id).into(),
+    ])
+    .unwrap();
+
+    all_entities
+        .lazy()
+        .group_by_stable([col(TITLE), col(TYPE)])
+        .agg([
+            col(DESCRIPTION),
+            col("source_id").alias(TEXT_UNIT_IDS),
+            col("source_id").count().alias(NODE_FREQUENCY),
+        ])
+}
+
+/// Merge the relationships extracted from every text unit by source and target.
+fn _merge_relationships(relationships: Vec<ExtractedRelationship>) -> LazyFrame {
+    let column = |name: &str, get: fn(&ExtractedRelationship) -> &str| -> Series {
+        Series::new(name.into(), relationships.iter().map(get).collect::<Vec<_>>())
+    };
+    let bound = |name: &str, get: fn(&ExtractedRelationship) -> &Option<String>| -> Series {
+        Series::new(name.into(), relationships.iter().map(|r| get(r).clone()).collect::<Vec<_>>())
+    };
+    let all_relationships = DataFrame::new(vec![
+        column(EDGE_SOURCE, |relationship| &relationship.source).into(),
+        column(EDGE_TARGET, |relationship| &relationship.target).into(),
+        column(DESCRIPTION, |relationship| &relationship.description).into(),
+        column("source_id", |relationship| &relationship.source_id).into(),
+        Series::new(EDGE_WEIGHT.into(), relationships.iter().map(|r| r.weight).collect::<Vec<_>>()).into(),
+        bound(VALID_FROM, |relationship| &relationship.valid_from).into(),
+        bound(VALID_TO, |relationship| &relationship.valid_to).into(),
+    ])
+    .unwrap();
+
+    all_relationships
+        .lazy()
+        .group_by_stable([col(EDGE_SOURCE), col(EDGE_TARGET)])
+        .agg([
+            col(DESCRIPTION),
+            col("source_id").alias(TEXT_UNIT_IDS),
+            col(EDGE_WEIGHT).sum(),
+            // keep the widest validity period across duplicate relationships
+            merged_valid_from(),
+            merged_valid_to(),
+        ])
+}
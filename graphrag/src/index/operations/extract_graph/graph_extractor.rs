@@ -0,0 +1,211 @@
+//! A module containing 'GraphExtractionResult' and 'GraphExtractor' models.
+
+use std::collections::HashMap;
+
+use crate::index::operations::compute_temporal_bounds::parse_temporal_bound;
+use crate::index::utils::tuple_records::{RecordDelimiters, glean_records};
+use crate::language_model::protocol::base::ChatModel;
+use crate::prompts::index::extract_graph::{CONTINUE_PROMPT, GRAPH_EXTRACTION_PROMPT, LOOP_PROMPT};
+
+/// An entity extracted from a text unit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractedEntity {
+    pub title: String,
+    pub r#type: String,
+    pub description: String,
+    pub source_id: String,
+}
+
+/// A relationship extracted from a text unit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractedRelationship {
+    pub source: String,
+    pub target: String,
+    pub description: String,
+    pub source_id: String,
+    pub weight: f64,
+    /// ISO-8601 date from which the relationship holds, if the model found one.
+    pub valid_from: Option<String>,
+    /// ISO-8601 date until which the relationship holds, None while it still holds.
+    pub valid_to: Option<String>,
+}
+
+/// Graph extraction result class definition.
+#[derive(Debug, Default)]
+pub struct GraphExtractionResult {
+    pub entities: Vec<ExtractedEntity>,
+    pub relationships: Vec<ExtractedRelationship>,
+}
+
+/// A function to handle extraction errors, called with the error message and details.
+pub type ErrorHandlerFn<'a> = Box<dyn Fn(String, HashMap<String, String>) + 'a>;
+
+/// Graph extractor class definition.
+pub struct GraphExtractor<'a, M: ChatModel> {
+    model: M,
+    extraction_prompt: String,
+    delimiters: RecordDelimiters,
+    max_gleanings: usize,
+    on_error: ErrorHandlerFn<'a>,
+}
+
+impl<'a, M: ChatModel> GraphExtractor<'a, M> {
+    /// Init method definition.
+    pub fn new(
+        model: M,
+        extraction_prompt: Option<String>,
+        tuple_delimiter: Option<String>,
+        record_delimiter: Option<String>,
+        completion_delimiter: Option<String>,
+        max_gleanings: usize,
+        on_error: Option<ErrorHandlerFn<'a>>,
+    ) -> Self {
+        GraphExtractor {
+            model,
+            extraction_prompt: extraction_prompt.unwrap_or_else(|| GRAPH_EXTRACTION_PROMPT.into()),
+            delimiters: RecordDelimiters::new(tuple_delimiter, record_delimiter, completion_delimiter),
+            max_gleanings,
+            on_error: on_error.unwrap_or_else(|| Box::new(|_, _| {})),
+        }
+    }
+
+    /**
+    Extract entities and relationships from a text unit.
+
+    Args:
+        - source_id: The id of the text unit, recorded as the source of every record.
+        - text: The text to extract the graph from.
+        - entity_types: The entity types to extract.
+    */
+    pub async fn extract(
+        &self,
+        source_id: &str,
+        text: &str,
+        entity_types: &[String],
+    ) -> GraphExtractionResult {
+        match self.process_document(text, &entity_types.join(",")).await {
+            Ok(records) => parse_graph_records(&records, &self.delimiters, source_id),
+            Err(error) => {
+                (self.on_error)(
+                    error,
+                    HashMap::from([
+                        ("source_id".into(), source_id.to_string()),
+                        ("text".into(), text.to_string()),
+                    ]),
+                );
+                GraphExtractionResult::default()
+            }
+        }
+    }
+
+    /// Prompt the model for the records of a single document, running gleanings if configured.
+    async fn process_document(&self, text: &str, entity_types: &str) -> Result<String, String> {
+        let prompt = self
+            .delimiters
+            .fill_prompt(&self.extraction_prompt)
+            .replace("{entity_types}", entity_types)
+            .replace("{input_text}", text);
+
+        glean_records(
+            &self.model,
+            &prompt,
+            CONTINUE_PROMPT,
+            LOOP_PROMPT,
+            self.max_gleanings,
+            &self.delimiters,
+        )
+        .await
+    }
+}
+
+/**
+Parse the entity and relationship tuples emitted by the model.
+
+Relationships carry their strength and, when the model found them, the dates bounding the period
+in which they hold; unknown or unparsable dates are left open.
+*/
+pub fn parse_graph_records(
+    records: &str,
+    delimiters: &RecordDelimiters,
+    source_id: &str,
+) -> GraphExtractionResult {
+    let mut result = GraphExtractionResult::default();
+    for fields in delimiters.split_records(records) {
+        match clean_str(&fields[0]).trim_matches('"') {
+            "entity" if fields.len() >= 4 => result.entities.push(ExtractedEntity {
+                title: clean_str(&fields[1]).to_uppercase(),
+                r#type: clean_str(&fields[2]).to_uppercase(),
+                description: clean_str(&fields[3]),
+                source_id: source_id.to_string(),
+            }),
+            "relationship" if fields.len() >= 5 => {
+                result.relationships.push(ExtractedRelationship {
+                    source: clean_str(&fields[1]).to_uppercase(),
+                    target: clean_str(&fields[2]).to_uppercase(),
+                    description: clean_str(&fields[3]),
+                    source_id: source_id.to_string(),
+                    weight: clean_str(&fields[4]).parse().unwrap_or(1.0),
+                    valid_from: fields.get(5).and_then(|field| parse_temporal_bound(field)),
+                    valid_to: fields.get(6).and_then(|field| parse_temporal_bound(field)),
+                })
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Trim a field and remove the control characters the model may have emitted.
+fn clean_str(field: &str) -> String {
+    field.trim().chars().filter(|character| !character.is_control()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entities_and_relationships_are_parsed() {
+        let records = "(\"entity\"<|>alice<|>person<|>Alice runs the lab)##\
+                       (\"relationship\"<|>alice<|>lab<|>Alice runs the lab<|>8<|>2020-01-15<|>unknown)##\
+                       <|COMPLETE|>";
+        let result = parse_graph_records(records, &RecordDelimiters::default(), "t1");
+        assert_eq!(
+            result.entities,
+            [ExtractedEntity {
+                title: "ALICE".into(),
+                r#type: "PERSON".into(),
+                description: "Alice runs the lab".into(),
+                source_id: "t1".into(),
+            }]
+        );
+        assert_eq!(
+            result.relationships,
+            [ExtractedRelationship {
+                source: "ALICE".into(),
+                target: "LAB".into(),
+                description: "Alice runs the lab".into(),
+                source_id: "t1".into(),
+                weight: 8.0,
+                valid_from: Some("2020-01-15".into()),
+                valid_to: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn short_and_unknown_records_are_skipped() {
+        let records = "(entity<|>alice)##(claim<|>a<|>b<|>c<|>d)##(relationship<|>a<|>b<|>c<|>strong)";
+        let result = parse_graph_records(records, &RecordDelimiters::default(), "t1");
+        assert!(result.entities.is_empty());
+        // an unparsable strength falls back to 1 and missing dates are left open
+        assert_eq!(result.relationships.len(), 1);
+        assert_eq!(result.relationships[0].weight, 1.0);
+        assert_eq!(result.relationships[0].valid_from, None);
+    }
+
+    #[test]
+    fn control_characters_are_removed_from_fields() {
+        assert_eq!(clean_str(" New\u{7}York\n"), "NewYork");
+    }
+}
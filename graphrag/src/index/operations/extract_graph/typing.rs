@@ -0,0 +1,39 @@
+//! A module containing the graph extraction strategy models.
+
+/// ExtractEntityStrategyType class definition.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExtractEntityStrategyType {
+    /// Extract entities and relationships with a chat model, gleaning missed records.
+    GraphIntelligence,
+}
+
+impl ExtractEntityStrategyType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ExtractEntityStrategyType::GraphIntelligence => "graph_intelligence",
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtractEntityStrategyType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The resolved graph extraction strategy.
+#[derive(Debug, Clone)]
+pub struct ExtractGraphStrategy {
+    pub r#type: ExtractEntityStrategyType,
+    /// The extraction prompt, None using the default prompt.
+    pub extraction_prompt: Option<String>,
+    /// The delimiter between the fields of a record, None using the default.
+    pub tuple_delimiter: Option<String>,
+    /// The delimiter between records, None using the default.
+    pub record_delimiter: Option<String>,
+    /// The delimiter marking the end of the records, None using the default.
+    pub completion_delimiter: Option<String>,
+    /// The maximum number of follow-up extractions per text unit.
+    pub max_gleanings: usize,
+}
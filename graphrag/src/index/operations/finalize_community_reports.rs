@@ -0,0 +1,78 @@
+//! All the steps to transform final community reports.
+
+use polars::prelude::{
+    DataFrame, IntoLazy, JoinArgs, JoinType, LazyFrame, NamedFrom, Series, col,
+};
+use uuid::Uuid;
+
+use crate::data_model::schemas;
+use crate::index::operations::summarize_communities::typing::CommunityReport;
+
+/// All the steps to transform final community reports.
+pub fn finalize_community_reports(reports: Vec<CommunityReport>, communities: LazyFrame) -> LazyFrame {
+    let field = |get: fn(&CommunityReport) -> String| -> Vec<String> { reports.iter().map(get).collect() };
+
+    let reports_df = DataFrame::new(vec![
+        Series::new(
+            schemas::ID.into(),
+            reports.iter().map(|_| Uuid::new_v4().to_string()).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(
+            schemas::SHORT_ID.into(),
+            reports.iter().map(|report| report.community).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(
+            schemas::COMMUNITY_ID.into(),
+            reports.iter().map(|report| report.community).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(
+            schemas::COMMUNITY_LEVEL.into(),
+            reports.iter().map(|report| report.level).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(schemas::TITLE.into(), field(|report| report.title.clone())).into(),
+        Series::new(schemas::SUMMARY.into(), field(|report| report.summary.clone())).into(),
+        Series::new(schemas::FULL_CONTENT.into(), field(|report| report.full_content.clone())).into(),
+        Series::new(
+            schemas::RATING.into(),
+            reports.iter().map(|report| report.rank).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(
+            schemas::EXPLANATION.into(),
+            field(|report| report.rating_explanation.clone()),
+        )
+        .into(),
+        Series::new(
+            schemas::FINDINGS.into(),
+            field(|report| serde_json::to_string(&report.findings).unwrap()),
+        )
+        .into(),
+        Series::new(
+            schemas::FULL_CONTENT_JSON.into(),
+            field(|report| report.full_content_json.clone()),
+        )
+        .into(),
+    ])
+    .unwrap()
+    .lazy();
+
+    // merge with communities to add size and period
+    reports_df
+        .join(
+            communities.select([
+                col(schemas::COMMUNITY_ID),
+                col(schemas::COMMUNITY_PARENT),
+                col(schemas::COMMUNITY_CHILDREN),
+                col(schemas::SIZE),
+                col(schemas::PERIOD),
+            ]),
+            [col(schemas::COMMUNITY_ID)],
+            [col(schemas::COMMUNITY_ID)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .select(schemas::COMMUNITY_REPORTS_FINAL_COLUMNS.map(col))
+}
@@ -5,8 +5,11 @@ use uuid::Uuid;
 
 use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
 use crate::config::models::embed_graph_config::EmbedGraphConfig;
+use crate::config::models::graph_centrality_config::GraphCentralityConfig;
 use crate::data_model::schemas::ENTITIES_FINAL_COLUMNS;
+use crate::index::operations::compute_centrality::{centrality_columns, compute_centrality};
 use crate::index::operations::compute_degree::compute_degree;
+use crate::index::operations::compute_temporal_bounds::compute_entity_temporal_bounds;
 use crate::index::operations::create_graph::create_graph;
 use crate::index::operations::embed_graph::embed_graph::embed_graph;
 use crate::index::operations::layout_graph::layout_graph::layout_graph;
@@ -18,8 +21,10 @@ pub fn finalize_entities(
     callbacks: impl WorkflowCallbacks,
     embed_config: Option<EmbedGraphConfig>,
     layout_enabled: bool,
+    centrality_config: &GraphCentralityConfig,
 ) -> LazyFrame {
-    let graph = create_graph(relationships);
+    let entities = compute_entity_temporal_bounds(entities, relationships.clone());
+    let graph = create_graph(relationships.clone());
     let mut graph_embeddings = None;
     if let Some(embed_config) = embed_config {
         if embed_config.enabled {
@@ -33,14 +38,18 @@ pub fn finalize_entities(
         graph_embeddings,
     );
     let degrees = compute_degree(graph);
+    let centrality = compute_centrality(relationships, centrality_config);
     let final_entities = entities
         .join(layout, [col("title")], [col("label")], JoinArgs::new(JoinType::Left))
         .join(degrees, [col("title")], [col("title")], JoinArgs::new(JoinType::Left))
+        .join(centrality, [col("title")], [col("title")], JoinArgs::new(JoinType::Left))
         .unique(Some(vec!["title".into()]), UniqueKeepStrategy::First);
     let final_entities = final_entities[entities["title"].is_not_nan()];
     // disconnected nodes and those with no community even at level 0 can be missing degree
     final_entities["degree"] = final_entities["degree"].fillna(0).astype(int);
     final_entities["human_readable_id"] = final_entities.index;
     final_entities["id"] = final_entities["human_readable_id"].apply(|_x| Uuid::new_v4().to_string());
-    final_entities[..][ENTITIES_FINAL_COLUMNS]
+    // the enabled centrality metrics are kept after the final columns
+    let columns = ENTITIES_FINAL_COLUMNS.into_iter().chain(centrality_columns(centrality_config));
+    final_entities.select(columns.map(col).collect::<Vec<_>>())
 }
@@ -1,22 +1,30 @@
-//! A module containing create_graph definition.
+//! All the steps to finalize the entity and relationship formats.
 
-use rustworkx_core::petgraph::graph::UnGraph;
 use polars::prelude::LazyFrame;
 
-/// Create a networkx graph from nodes and edges dataframes.
-pub fn create_graph<N, E>(
-    edges: LazyFrame,
-    edge_attr: Option<Vec<str | int>>,
-    nodes: Option<LazyFrame>,
-    node_id: str,  // = "title"
-) -> UnGraph<N, E> {
-    let graph = nx.from_pandas_edgelist(edges, edge_attr);
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::embed_graph_config::EmbedGraphConfig;
+use crate::config::models::graph_centrality_config::GraphCentralityConfig;
+use crate::index::operations::finalize_entities::finalize_entities;
+use crate::index::operations::finalize_relationships::finalize_relationships;
 
-    if let Some(nodes) = nodes {
-        for (n, d) in nodes.iter_rows() {
-            graph.add_nodes_from((n, dict(d)) );
-        }
-    }
-
-    graph
+/// All the steps to finalize the entity and relationship formats.
+pub fn finalize_graph(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    callbacks: impl WorkflowCallbacks,
+    embed_config: Option<EmbedGraphConfig>,
+    layout_enabled: bool,
+    centrality_config: &GraphCentralityConfig,
+) -> (LazyFrame, LazyFrame) {
+    let final_entities = finalize_entities(
+        entities,
+        relationships.clone(),
+        callbacks,
+        embed_config,
+        layout_enabled,
+        centrality_config,
+    );
+    let final_relationships = finalize_relationships(relationships);
+    (final_entities, final_relationships)
 }
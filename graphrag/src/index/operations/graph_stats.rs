@@ -0,0 +1,352 @@
+//! A module containing compute_graph_stats method definition.
+
+use std::collections::{BTreeMap, HashMap};
+
+use polars::prelude::{DataType, LazyFrame, col};
+use serde::Serialize;
+
+use crate::data_model::schemas;
+use crate::index::typing::stats::GraphStatsSummary;
+use crate::index::update::communities::get_community_memberships;
+use crate::index::utils::weighted_graph::read_relationship_graph;
+
+/// Summary statistics of the entity degrees.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DegreeDistribution {
+    /// The smallest degree.
+    pub min: usize,
+    /// The largest degree.
+    pub max: usize,
+    /// The mean degree.
+    pub mean: f64,
+    /// The median degree.
+    pub median: f64,
+    /// The number of entities with each degree.
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+/// A highly connected entity.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Hub {
+    /// The entity title.
+    pub title: String,
+    /// The number of relationships of the entity.
+    pub degree: usize,
+}
+
+/// Health statistics of the entity graph.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphStats {
+    /// The number of entities.
+    pub node_count: usize,
+    /// The number of relationships.
+    pub edge_count: usize,
+    /// The number of entities of each type.
+    pub nodes_by_type: BTreeMap<String, usize>,
+    /// The number of relationships between each pair of entity types.
+    pub edges_by_type: BTreeMap<String, usize>,
+    /// The distribution of entity degrees.
+    pub degree_distribution: DegreeDistribution,
+    /// The number of connected components, orphan entities included.
+    pub connected_components: usize,
+    /// The number of entities in the largest connected component.
+    pub largest_component_size: usize,
+    /// The fraction of entities in the largest connected component.
+    pub lcc_fraction: f64,
+    /// The modularity of the communities of each level.
+    pub modularity_by_level: BTreeMap<i64, f64>,
+    /// The titles of the entities without any relationship.
+    pub orphan_entities: Vec<String>,
+    /// The most connected entities.
+    pub top_hubs: Vec<Hub>,
+    /// The ids of the relationships without a description.
+    pub relationships_without_description: Vec<String>,
+}
+
+impl From<&GraphStats> for GraphStatsSummary {
+    fn from(stats: &GraphStats) -> Self {
+        GraphStatsSummary {
+            node_count: stats.node_count,
+            edge_count: stats.edge_count,
+            connected_components: stats.connected_components,
+            lcc_fraction: stats.lcc_fraction,
+            modularity_by_level: stats.modularity_by_level.clone(),
+            orphan_entities: stats.orphan_entities.len(),
+            relationships_without_description: stats.relationships_without_description.len(),
+        }
+    }
+}
+
+/**
+Compute health statistics of the entity graph.
+
+Args:
+    - entities: The final entities.
+    - relationships: The final relationships.
+    - communities: The final communities, to measure the modularity of each level (optional).
+    - top_hubs: The number of most connected entities to report.
+*/
+pub fn compute_graph_stats(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    communities: Option<LazyFrame>,
+    top_hubs: usize,
+) -> GraphStats {
+    let entities = entities
+        .select([col(schemas::ID), col(schemas::TITLE), col(schemas::TYPE)])
+        .collect()
+        .unwrap();
+    let relationships = relationships
+        .select([
+            col(schemas::ID),
+            col(schemas::EDGE_SOURCE),
+            col(schemas::EDGE_TARGET),
+            col(schemas::EDGE_WEIGHT).cast(DataType::Float64),
+            col(schemas::DESCRIPTION),
+        ])
+        .collect()
+        .unwrap();
+    let (titles, graph) = read_relationship_graph(&relationships);
+    let nodes: HashMap<&str, usize> = titles.iter().enumerate().map(|(node, title)| (title.as_str(), node)).collect();
+
+    let entity_types: HashMap<&str, &str> = entities
+        .column(schemas::TITLE)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .zip(entities.column(schemas::TYPE).unwrap().str().unwrap())
+        .filter_map(|(title, r#type)| Some((title?, r#type.unwrap_or("UNKNOWN"))))
+        .collect();
+    let entity_nodes: HashMap<&str, usize> = entities
+        .column(schemas::ID)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .zip(entities.column(schemas::TITLE).unwrap().str().unwrap())
+        .filter_map(|(id, title)| Some((id?, *nodes.get(title?)?)))
+        .collect();
+    let mut nodes_by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for r#type in entity_types.values() {
+        *nodes_by_type.entry(r#type.to_string()).or_default() += 1;
+    }
+
+    let sources = relationships.column(schemas::EDGE_SOURCE).unwrap().str().unwrap();
+    let targets = relationships.column(schemas::EDGE_TARGET).unwrap().str().unwrap();
+    let mut edges_by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for (source, target) in sources.into_iter().zip(targets) {
+        let type_of = |title: Option<&str>| title.and_then(|title| entity_types.get(title)).copied().unwrap_or("UNKNOWN");
+        let mut pair = [type_of(source), type_of(target)];
+        pair.sort();
+        *edges_by_type.entry(pair.join(" -- ")).or_default() += 1;
+    }
+
+    let relationships_without_description: Vec<String> = relationships
+        .column(schemas::ID)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .zip(relationships.column(schemas::DESCRIPTION).unwrap().str().unwrap())
+        .filter(|(_, description)| description.is_none_or(|description| description.trim().is_empty()))
+        .map(|(id, _)| id.unwrap_or_default().to_string())
+        .collect();
+
+    // entities without relationships are not in the graph
+    let mut orphan_entities: Vec<String> = entity_types
+        .keys()
+        .filter(|title| !nodes.contains_key(*title))
+        .map(|title| title.to_string())
+        .collect();
+    orphan_entities.sort();
+
+    let mut degrees: Vec<usize> = (0..graph.node_count()).map(|node| graph.neighbors(node).len()).collect();
+    degrees.extend(std::iter::repeat_n(0, orphan_entities.len()));
+    let degree_distribution = degree_distribution(&degrees);
+
+    let mut hubs: Vec<Hub> = titles
+        .iter()
+        .enumerate()
+        .map(|(node, title)| Hub {
+            title: title.clone(),
+            degree: graph.neighbors(node).len(),
+        })
+        .collect();
+    hubs.sort_by(|a, b| b.degree.cmp(&a.degree).then_with(|| a.title.cmp(&b.title)));
+    hubs.truncate(top_hubs);
+
+    let components = graph.connected_components();
+    let node_count = graph.node_count() + orphan_entities.len();
+    let largest_component_size = components.first().map(Vec::len).unwrap_or_default();
+
+    let mut modularity_by_level = BTreeMap::new();
+    if let Some(communities) = communities {
+        let mut levels: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+        for community in get_community_memberships(communities) {
+            // nodes without a community at a level are on their own
+            let membership = levels
+                .entry(community.level)
+                .or_insert_with(|| (0..graph.node_count()).collect());
+            for node in community.entity_ids.iter().filter_map(|id| entity_nodes.get(id.as_str())) {
+                membership[*node] = graph.node_count() + community.community as usize;
+            }
+        }
+        for (level, membership) in levels {
+            modularity_by_level.insert(level, graph.modularity(&membership, 1.0));
+        }
+    }
+
+    GraphStats {
+        node_count,
+        edge_count: relationships.height(),
+        nodes_by_type,
+        edges_by_type,
+        degree_distribution,
+        connected_components: components.len() + orphan_entities.len(),
+        largest_component_size,
+        lcc_fraction: if node_count > 0 {
+            largest_component_size as f64 / node_count as f64
+        } else {
+            0.0
+        },
+        modularity_by_level,
+        orphan_entities,
+        top_hubs: hubs,
+        relationships_without_description,
+    }
+}
+
+/// Summarize a list of degrees.
+fn degree_distribution(degrees: &[usize]) -> DegreeDistribution {
+    if degrees.is_empty() {
+        return DegreeDistribution::default();
+    }
+    let mut sorted = degrees.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) as f64 / 2.0
+    } else {
+        sorted[middle] as f64
+    };
+    let mut histogram = BTreeMap::new();
+    for degree in &sorted {
+        *histogram.entry(*degree).or_default() += 1;
+    }
+
+    DegreeDistribution {
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean: sorted.iter().sum::<usize>() as f64 / sorted.len() as f64,
+        median,
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataFrame, IntoLazy, NamedFrom, Series};
+
+    use super::*;
+
+    /// Entities A to E, E without relationships; A-B, B-C, A-C and C-D, the last two without a description.
+    fn graph() -> (LazyFrame, LazyFrame) {
+        let entities = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["a", "b", "c", "d", "e"]).into(),
+            Series::new(schemas::TITLE.into(), ["A", "B", "C", "D", "E"]).into(),
+            Series::new(schemas::TYPE.into(), ["PERSON", "PERSON", "GEO", "GEO", "EVENT"]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        let relationships = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["r1", "r2", "r3", "r4"]).into(),
+            Series::new(schemas::EDGE_SOURCE.into(), ["A", "B", "A", "C"]).into(),
+            Series::new(schemas::EDGE_TARGET.into(), ["B", "C", "C", "D"]).into(),
+            Series::new(schemas::EDGE_WEIGHT.into(), [1.0, 1.0, 1.0, 1.0]).into(),
+            Series::new(schemas::DESCRIPTION.into(), [Some("knows"), Some("lives in"), Some(" "), None]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        (entities, relationships)
+    }
+
+    fn communities(levels: &[(i64, i64, &[&str])]) -> LazyFrame {
+        let entity_ids: Vec<Series> = levels.iter().map(|row| Series::new("".into(), row.2)).collect();
+        DataFrame::new(vec![
+            Series::new(schemas::COMMUNITY_ID.into(), levels.iter().map(|row| row.0).collect::<Vec<_>>()).into(),
+            Series::new(schemas::COMMUNITY_LEVEL.into(), levels.iter().map(|row| row.1).collect::<Vec<_>>()).into(),
+            Series::new(schemas::ENTITY_IDS.into(), entity_ids).into(),
+        ])
+        .unwrap()
+        .lazy()
+    }
+
+    #[test]
+    fn graph_stats_count_nodes_edges_and_components() {
+        let (entities, relationships) = graph();
+        let stats = compute_graph_stats(entities, relationships, None, 2);
+
+        assert_eq!((stats.node_count, stats.edge_count), (5, 4));
+        assert_eq!(
+            stats.nodes_by_type,
+            BTreeMap::from([("EVENT".to_string(), 1), ("GEO".to_string(), 2), ("PERSON".to_string(), 2)])
+        );
+        assert_eq!(
+            stats.edges_by_type,
+            BTreeMap::from([
+                ("GEO -- GEO".to_string(), 1),
+                ("GEO -- PERSON".to_string(), 2),
+                ("PERSON -- PERSON".to_string(), 1),
+            ])
+        );
+        assert_eq!((stats.connected_components, stats.largest_component_size), (2, 4));
+        assert_eq!(stats.lcc_fraction, 0.8);
+        assert_eq!(stats.orphan_entities, ["E"]);
+        let hubs: Vec<(&str, usize)> = stats.top_hubs.iter().map(|hub| (hub.title.as_str(), hub.degree)).collect();
+        assert_eq!(hubs, [("C", 3), ("A", 2)]);
+        assert_eq!(stats.relationships_without_description, ["r3", "r4"]);
+        assert!(stats.modularity_by_level.is_empty());
+    }
+
+    #[test]
+    fn graph_stats_include_orphans_in_the_degree_distribution() {
+        let (entities, relationships) = graph();
+        let distribution = compute_graph_stats(entities, relationships, None, 2).degree_distribution;
+
+        assert_eq!((distribution.min, distribution.max), (0, 3));
+        assert_eq!((distribution.mean, distribution.median), (1.6, 2.0));
+        assert_eq!(distribution.histogram, BTreeMap::from([(0, 1), (1, 1), (2, 2), (3, 1)]));
+    }
+
+    #[test]
+    fn modularity_is_measured_per_level() {
+        let (entities, relationships) = graph();
+        let communities = communities(&[
+            (0, 0, &["a", "b"]),
+            (1, 0, &["c", "d"]),
+            (2, 1, &["a", "b", "c"]),
+            (3, 1, &["d"]),
+        ]);
+        let stats = compute_graph_stats(entities, relationships, Some(communities), 2);
+
+        // level 0: each community holds 1 of the 4 edges and half of the degree, 2 * (1/4 - 1/4)
+        // level 1: 3/4 - (7/8)^2 for the first community and -(1/8)^2 for the second
+        assert!(stats.modularity_by_level[&0].abs() < 1e-12);
+        assert!((stats.modularity_by_level[&1] - -0.03125).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degree_distribution_of_an_even_count_averages_the_median() {
+        let distribution = degree_distribution(&[4, 1, 3, 2]);
+        assert_eq!((distribution.min, distribution.max), (1, 4));
+        assert_eq!((distribution.mean, distribution.median), (2.5, 2.5));
+    }
+
+    #[test]
+    fn degree_distribution_of_nothing_is_empty() {
+        let distribution = degree_distribution(&[]);
+        assert_eq!((distribution.min, distribution.max, distribution.median), (0, 0, 0.0));
+        assert!(distribution.histogram.is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! Graph pruning.
+
+use std::collections::HashSet;
+
+use rustworkx_core::petgraph::graph::{NodeIndex, UnGraph};
+use rustworkx_core::petgraph::stable_graph::StableUnGraph;
+use rustworkx_core::petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use serde::Serialize;
+
+use crate::config::models::prune_graph_config::PruneGraphConfig;
+use crate::index::utils::stable_lcc::stable_largest_connected_component;
+
+/// Number of nodes and edges removed by a single pruning rule.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneRuleStats {
+    /// The name of the rule, matching the PruneGraphConfig field.
+    pub rule: String,
+    /// The number of nodes removed by the rule.
+    pub nodes_removed: usize,
+    /// The number of edges removed by the rule, including edges dropped along with their nodes.
+    pub edges_removed: usize,
+}
+
+/// Removal counts for each pruning rule, in the order the rules were applied.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneGraphStats {
+    /// The number of nodes in the input graph.
+    pub input_nodes: usize,
+    /// The number of edges in the input graph.
+    pub input_edges: usize,
+    /// The number of nodes in the pruned graph.
+    pub output_nodes: usize,
+    /// The number of edges in the pruned graph.
+    pub output_edges: usize,
+    /// The per-rule removal counts.
+    pub rules: Vec<PruneRuleStats>,
+}
+
+/**
+Prune graph by removing nodes that are out of frequency/degree ranges and edges with low weights.
+
+Node degrees are computed once on the input graph, before any node is removed.
+Frequencies and edge weights are read with the `node_frequency` and `edge_weight` accessors.
+
+Args:
+    - graph: The graph to prune.
+    - node_frequency: Read the frequency of a node.
+    - edge_weight: Read the weight of an edge.
+    - config: The pruning thresholds: the minimum node frequency and degree, the maximum node frequency
+      and degree in standard deviations above the mean, the minimum edge weight percentile, whether to
+      remove the ego node and whether to keep only the largest connected component.
+*/
+pub fn prune_graph<N, E>(
+    graph: UnGraph<N, E>,
+    node_frequency: impl Fn(&N) -> f64,
+    edge_weight: impl Fn(&E) -> f64,
+    config: &PruneGraphConfig,
+) -> (UnGraph<N, E>, PruneGraphStats) {
+    let mut stats = PruneGraphStats {
+        input_nodes: graph.node_count(),
+        input_edges: graph.edge_count(),
+        ..Default::default()
+    };
+
+    // a stable graph keeps node indices valid while nodes are removed
+    let mut graph: StableUnGraph<N, E> = StableUnGraph::from(graph);
+    let degrees: Vec<(NodeIndex, usize)> = graph
+        .node_indices()
+        .map(|node| (node, graph.edges(node).count()))
+        .collect();
+
+    // remove ego node if needed, the first node with the highest degree
+    if config.remove_ego_nodes {
+        let ego_node = degrees
+            .iter()
+            .copied()
+            .reduce(|ego, node| if node.1 > ego.1 { node } else { ego })
+            .map(|(node, _)| node);
+        let removed = remove_nodes(&mut graph, ego_node.into_iter().collect());
+        stats.rules.push(removed.named("remove_ego_nodes"));
+    }
+
+    // remove nodes that are not within the predefined degree range
+    let low_degree_nodes = degrees
+        .iter()
+        .filter(|(_, degree)| *degree < config.min_node_degree)
+        .map(|(node, _)| *node)
+        .collect();
+    let removed = remove_nodes(&mut graph, low_degree_nodes);
+    stats.rules.push(removed.named("min_node_degree"));
+
+    if let Some(std_trim) = config.max_node_degree_std {
+        let values: Vec<f64> = degrees.iter().map(|(_, degree)| *degree as f64).collect();
+        let upper_threshold = get_upper_threshold_by_std(&values, std_trim);
+        let high_degree_nodes = degrees
+            .iter()
+            .filter(|(_, degree)| *degree as f64 > upper_threshold)
+            .map(|(node, _)| *node)
+            .collect();
+        let removed = remove_nodes(&mut graph, high_degree_nodes);
+        stats.rules.push(removed.named("max_node_degree_std"));
+    }
+
+    // remove nodes that are not within the predefined frequency range
+    let low_freq_nodes = graph
+        .node_indices()
+        .filter(|node| node_frequency(&graph[*node]) < config.min_node_freq as f64)
+        .collect();
+    let removed = remove_nodes(&mut graph, low_freq_nodes);
+    stats.rules.push(removed.named("min_node_freq"));
+
+    if let Some(std_trim) = config.max_node_freq_std {
+        let values: Vec<f64> = graph
+            .node_indices()
+            .map(|node| node_frequency(&graph[node]))
+            .collect();
+        let upper_threshold = get_upper_threshold_by_std(&values, std_trim);
+        let high_freq_nodes = graph
+            .node_indices()
+            .filter(|node| node_frequency(&graph[*node]) > upper_threshold)
+            .collect();
+        let removed = remove_nodes(&mut graph, high_freq_nodes);
+        stats.rules.push(removed.named("max_node_freq_std"));
+    }
+
+    // remove edges by min weight
+    if config.min_edge_weight_pct > 0.0 {
+        let edge_weights: Vec<f64> = graph
+            .edge_references()
+            .map(|edge| edge_weight(edge.weight()))
+            .collect();
+        let mut removed = PruneRuleStats::default();
+        if !edge_weights.is_empty() {
+            let min_edge_weight = percentile(&edge_weights, config.min_edge_weight_pct);
+            let low_weight_edges: Vec<_> = graph
+                .edge_references()
+                .filter(|edge| edge_weight(edge.weight()) < min_edge_weight)
+                .map(|edge| edge.id())
+                .collect();
+            removed.edges_removed = low_weight_edges.len();
+            for edge in low_weight_edges {
+                graph.remove_edge(edge);
+            }
+        }
+        stats.rules.push(removed.named("min_edge_weight_pct"));
+    }
+
+    let mut graph = UnGraph::from(graph);
+    if config.lcc_only {
+        let (nodes_before, edges_before) = (graph.node_count(), graph.edge_count());
+        graph = stable_largest_connected_component(graph);
+        stats.rules.push(PruneRuleStats {
+            rule: "lcc_only".into(),
+            nodes_removed: nodes_before - graph.node_count(),
+            edges_removed: edges_before - graph.edge_count(),
+        });
+    }
+
+    stats.output_nodes = graph.node_count();
+    stats.output_edges = graph.edge_count();
+    (graph, stats)
+}
+
+impl PruneRuleStats {
+    fn named(mut self, rule: &str) -> Self {
+        self.rule = rule.into();
+        self
+    }
+}
+
+/// Remove the given nodes, skipping any that were already removed, and count what was dropped.
+fn remove_nodes<N, E>(graph: &mut StableUnGraph<N, E>, nodes: HashSet<NodeIndex>) -> PruneRuleStats {
+    let nodes: HashSet<NodeIndex> = nodes
+        .into_iter()
+        .filter(|node| graph.contains_node(*node))
+        .collect();
+    let edges_removed = graph
+        .edge_references()
+        .filter(|edge| nodes.contains(&edge.source()) || nodes.contains(&edge.target()))
+        .count();
+    for node in &nodes {
+        graph.remove_node(*node);
+    }
+    PruneRuleStats {
+        rule: String::new(),
+        nodes_removed: nodes.len(),
+        edges_removed,
+    }
+}
+
+/// Get upper threshold by standard deviation.
+fn get_upper_threshold_by_std(data: &[f64], std_trim: f64) -> f64 {
+    if data.is_empty() {
+        return f64::INFINITY;
+    }
+    let mean = data.iter().sum::<f64>() / data.len() as f64;
+    let variance = data.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / data.len() as f64;
+    mean + std_trim * variance.sqrt()
+}
+
+/// Compute the q-th percentile of the data, using linear interpolation between closest ranks.
+fn percentile(data: &[f64], q: f64) -> f64 {
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = (q / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A star around hub "A" with leaves "B" to "E", and an edge between "D" and "E".
+    fn star() -> UnGraph<(&'static str, f64), f64> {
+        let mut graph = UnGraph::new_undirected();
+        let nodes: Vec<_> = [("A", 5.0), ("B", 1.0), ("C", 2.0), ("D", 3.0), ("E", 3.0)]
+            .into_iter()
+            .map(|node| graph.add_node(node))
+            .collect();
+        for (leaf, weight) in [(1, 1.0), (2, 2.0), (3, 3.0), (4, 4.0)] {
+            graph.add_edge(nodes[0], nodes[leaf], weight);
+        }
+        graph.add_edge(nodes[3], nodes[4], 5.0);
+        graph
+    }
+
+    fn prune(graph: UnGraph<(&'static str, f64), f64>, config: &PruneGraphConfig) -> (Vec<&'static str>, PruneGraphStats) {
+        let (pruned, stats) = prune_graph(graph, |node| node.1, |edge| *edge, config);
+        let mut titles: Vec<&str> = pruned.node_weights().map(|node| node.0).collect();
+        titles.sort();
+        (titles, stats)
+    }
+
+    fn no_pruning() -> PruneGraphConfig {
+        PruneGraphConfig {
+            min_node_freq: 0,
+            min_node_degree: 0,
+            min_edge_weight_pct: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nodes_below_the_minimum_frequency_and_degree_are_removed() {
+        let config = PruneGraphConfig {
+            min_node_freq: 2,
+            min_node_degree: 2,
+            ..no_pruning()
+        };
+        let (titles, stats) = prune(star(), &config);
+        assert_eq!(titles, ["A", "D", "E"]);
+
+        let rules: Vec<(&str, usize, usize)> = stats
+            .rules
+            .iter()
+            .map(|rule| (rule.rule.as_str(), rule.nodes_removed, rule.edges_removed))
+            .collect();
+        // B and C have degree 1, so the frequency rule has nothing left to remove
+        assert_eq!(rules, [("min_node_degree", 2, 2), ("min_node_freq", 0, 0)]);
+        assert_eq!((stats.input_nodes, stats.input_edges), (5, 5));
+        assert_eq!((stats.output_nodes, stats.output_edges), (3, 3));
+    }
+
+    #[test]
+    fn nodes_above_the_standard_deviation_thresholds_are_removed() {
+        let config = PruneGraphConfig {
+            max_node_degree_std: Some(1.0),
+            ..no_pruning()
+        };
+        // degrees 4, 1, 1, 2, 2 have mean 2 and deviation 1.1, only the hub is above 3.1
+        assert_eq!(prune(star(), &config).0, ["B", "C", "D", "E"]);
+
+        let config = PruneGraphConfig {
+            max_node_freq_std: Some(0.5),
+            ..no_pruning()
+        };
+        // frequencies 5, 1, 2, 3, 3 have mean 2.8 and deviation 1.33, only the hub is above 3.47
+        assert_eq!(prune(star(), &config).0, ["B", "C", "D", "E"]);
+    }
+
+    #[test]
+    fn edges_below_the_weight_percentile_are_removed() {
+        let config = PruneGraphConfig {
+            min_edge_weight_pct: 50.0,
+            ..no_pruning()
+        };
+        let (pruned, stats) = prune_graph(star(), |node| node.1, |edge| *edge, &config);
+        let mut weights: Vec<f64> = pruned.edge_weights().copied().collect();
+        weights.sort_by(f64::total_cmp);
+        // the median weight is 3, the edges of weight 1 and 2 are removed and their nodes kept
+        assert_eq!(weights, [3.0, 4.0, 5.0]);
+        assert_eq!(pruned.node_count(), 5);
+        assert_eq!(stats.rules.last().unwrap().edges_removed, 2);
+    }
+
+    #[test]
+    fn the_ego_node_is_the_first_node_with_the_highest_degree() {
+        let mut graph = UnGraph::new_undirected();
+        let nodes: Vec<_> = [("A", 1.0), ("B", 1.0), ("C", 1.0), ("D", 1.0)]
+            .into_iter()
+            .map(|node| graph.add_node(node))
+            .collect();
+        // A and B both have the highest degree, 2
+        graph.add_edge(nodes[0], nodes[1], 1.0);
+        graph.add_edge(nodes[0], nodes[2], 1.0);
+        graph.add_edge(nodes[1], nodes[3], 1.0);
+        let config = PruneGraphConfig {
+            remove_ego_nodes: true,
+            ..no_pruning()
+        };
+        let (titles, stats) = prune(graph, &config);
+        assert_eq!(titles, ["B", "C", "D"]);
+        assert_eq!(stats.rules[0].rule, "remove_ego_nodes");
+        assert_eq!((stats.rules[0].nodes_removed, stats.rules[0].edges_removed), (1, 2));
+    }
+}
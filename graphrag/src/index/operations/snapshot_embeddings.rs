@@ -0,0 +1,262 @@
+//! A module containing snapshot_embeddings method definition.
+
+use std::fmt::Write;
+
+use log::warn;
+use polars::prelude::{Column, DataFrame, DataType, LazyFrame, PolarsError, col};
+
+use crate::config::enums::EmbeddingSnapshotFormat;
+use crate::data_model::schemas;
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::utils::storage::write_table_to_storage;
+
+/// The column holding the vector of every embedded row.
+pub const EMBEDDING_COLUMN: &str = "embedding";
+
+/// The magic string and format version 1.0 opening every `.npy` file.
+const NPY_MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+/// An embedded row of the snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotEmbedding {
+    /// The id of the embedded row.
+    pub id: String,
+    /// The embedded text.
+    pub text: String,
+    /// The embedding of the text.
+    pub vector: Vec<f32>,
+}
+
+/**
+Write the embeddings of an embedded field to storage, as `embeddings.{name}` files in the given format.
+
+- parquet: `embeddings.{name}.parquet`, holding the id and embedding of every row.
+- npy: `embeddings.{name}.npy`, a float32 matrix with a row per embedding, and
+  `embeddings.{name}.ids.txt` with the id of every row, one per line.
+- tsv: `embeddings.{name}.vectors.tsv` and `embeddings.{name}.metadata.tsv`, the files loaded by the
+  TensorBoard embedding projector, with the id and embedded text of every row as metadata.
+
+Rows without an embedding are left out of the npy and tsv snapshots, as are rows whose embedding
+does not have as many dimensions as the first one. Tabs and line breaks of the ids and texts are
+replaced with spaces, so every row stays on its line.
+
+Args:
+    - embeddings: The embedded rows, with the id, embedding and embedded text columns.
+    - embed_column: The name of the embedded text column.
+    - name: The name of the embedded field, such as `entity.description`.
+    - storage: The storage to write the snapshots to, as bytes for the binary formats and text otherwise.
+    - format: The file format of the snapshots.
+*/
+pub async fn snapshot_embeddings(
+    embeddings: LazyFrame,
+    embed_column: &str,
+    name: &str,
+    storage: &mut (impl PipelineStorage<String> + PipelineStorage<Vec<u8>>),
+    format: EmbeddingSnapshotFormat,
+) -> Result<(), String> {
+    let name = format!("embeddings.{name}");
+    match format {
+        EmbeddingSnapshotFormat::Parquet => {
+            let table = embeddings.select([col(schemas::ID), col(EMBEDDING_COLUMN)]);
+            write_table_to_storage(table, &name, storage).await;
+        }
+        EmbeddingSnapshotFormat::Npy => {
+            let rows = read_snapshot(embeddings, embed_column, &name)?;
+            PipelineStorage::<Vec<u8>>::set(storage, &format!("{name}.npy"), to_npy(&rows), None).await;
+            PipelineStorage::<String>::set(storage, &format!("{name}.ids.txt"), to_ids(&rows), None).await;
+        }
+        EmbeddingSnapshotFormat::Tsv => {
+            let rows = read_snapshot(embeddings, embed_column, &name)?;
+            let (vectors, metadata) = to_projector_tsv(&rows);
+            PipelineStorage::<String>::set(storage, &format!("{name}.vectors.tsv"), vectors, None).await;
+            PipelineStorage::<String>::set(storage, &format!("{name}.metadata.tsv"), metadata, None).await;
+        }
+    }
+    Ok(())
+}
+
+/// Read the embedded rows of a snapshot, keeping the rows whose embedding matches the first one in size.
+///
+/// Fails if the embeddings cannot be collected or the id, text or embedding column is missing or of another type.
+pub fn read_snapshot(embeddings: LazyFrame, embed_column: &str, name: &str) -> Result<Vec<SnapshotEmbedding>, String> {
+    let error = |e: PolarsError| format!("Could not read the embeddings of the {name} snapshot: {e}");
+    let embeddings: DataFrame = embeddings.collect().map_err(error)?;
+    let ids = embeddings.column(schemas::ID).and_then(Column::str).map_err(error)?;
+    let texts = embeddings.column(embed_column).and_then(Column::str).map_err(error)?;
+    let vectors = embeddings.column(EMBEDDING_COLUMN).and_then(Column::list).map_err(error)?;
+
+    let mut rows: Vec<SnapshotEmbedding> = Vec::with_capacity(embeddings.height());
+    let mut skipped = 0;
+    for (row, vector) in vectors.into_iter().enumerate() {
+        let Some(vector) = vector else {
+            skipped += 1;
+            continue;
+        };
+        let vector: Vec<f32> = vector
+            .cast(&DataType::Float32)
+            .map_err(error)?
+            .f32()
+            .map_err(error)?
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+        // every row of the matrix has the same number of dimensions
+        if rows.first().is_some_and(|first| first.vector.len() != vector.len()) {
+            skipped += 1;
+            continue;
+        }
+        rows.push(SnapshotEmbedding {
+            id: ids.get(row).unwrap_or_default().to_string(),
+            text: texts.get(row).unwrap_or_default().to_string(),
+            vector,
+        });
+    }
+    if skipped > 0 {
+        warn!("Left {skipped} rows without a matching embedding out of the {name} snapshot");
+    }
+    Ok(rows)
+}
+
+/// Serialize the embeddings to a NumPy `.npy` float32 matrix.
+pub fn to_npy(rows: &[SnapshotEmbedding]) -> Vec<u8> {
+    let dimensions = rows.first().map_or(0, |row| row.vector.len());
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        rows.len(),
+        dimensions
+    );
+    // the header is padded with spaces and ends with a newline, so the data is 64-byte aligned
+    let unpadded = NPY_MAGIC.len() + 2 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(NPY_MAGIC.len() + 2 + header.len() + rows.len() * dimensions * 4);
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for row in rows {
+        for value in &row.vector {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Serialize the ids of the embeddings, one per line in the order of the matrix rows.
+pub fn to_ids(rows: &[SnapshotEmbedding]) -> String {
+    let mut ids = String::new();
+    for row in rows {
+        writeln!(ids, "{}", escape(&row.id)).unwrap();
+    }
+    ids
+}
+
+/// Serialize the embeddings to the vectors and metadata TSV files of the TensorBoard embedding projector.
+pub fn to_projector_tsv(rows: &[SnapshotEmbedding]) -> (String, String) {
+    let mut vectors = String::new();
+    // the projector expects a header row when the metadata has more than one column
+    let mut metadata = String::from("id\ttext\n");
+    for row in rows {
+        let values: Vec<String> = row.vector.iter().map(f32::to_string).collect();
+        writeln!(vectors, "{}", values.join("\t")).unwrap();
+        writeln!(metadata, "{}\t{}", escape(&row.id), escape(&row.text)).unwrap();
+    }
+    (vectors, metadata)
+}
+
+/// Replace the tabs and line breaks of an id or metadata value, which would split its line or cell.
+fn escape(text: &str) -> String {
+    text.replace(['\t', '\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{IntoLazy, NamedFrom, Series, df};
+
+    use super::*;
+
+    fn row(id: &str, vector: Vec<f32>) -> SnapshotEmbedding {
+        SnapshotEmbedding {
+            id: id.to_string(),
+            text: format!("text of {id}"),
+            vector,
+        }
+    }
+
+    /// The magic string, the little-endian header length and the header, padded with spaces up to its newline.
+    fn npy_header(dictionary: &str, padding: usize) -> Vec<u8> {
+        let header = format!("{dictionary}{}\n", " ".repeat(padding));
+        let mut bytes = NPY_MAGIC.to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn npy_is_byte_exact() {
+        let bytes = to_npy(&[row("a", vec![1.0, -2.5]), row("b", vec![0.5, 3.0])]);
+        // 10 bytes of magic string and length and a 59 byte dictionary, padded to 128 bytes
+        let mut expected = npy_header("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }", 58);
+        assert_eq!(expected.len(), 128);
+        assert_eq!(&expected[..10], b"\x93NUMPY\x01\x00\x76\x00");
+        for value in [1.0f32, -2.5, 0.5, 3.0] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn npy_data_starts_on_a_multiple_of_64_bytes() {
+        let rows: Vec<SnapshotEmbedding> = (0..1000).map(|index| row(&index.to_string(), vec![index as f32; 3])).collect();
+        let bytes = to_npy(&rows);
+        // the wider shape takes 3 bytes of the padding
+        let expected = npy_header("{'descr': '<f4', 'fortran_order': False, 'shape': (1000, 3), }", 55);
+        assert_eq!(&bytes[..128], expected.as_slice());
+        assert_eq!(bytes.len(), 128 + 1000 * 3 * 4);
+        assert_eq!(&bytes[bytes.len() - 4..], &999.0f32.to_le_bytes());
+    }
+
+    #[test]
+    fn npy_of_no_rows_is_an_empty_matrix() {
+        let expected = npy_header("{'descr': '<f4', 'fortran_order': False, 'shape': (0, 0), }", 58);
+        assert_eq!(to_npy(&[]), expected);
+    }
+
+    #[test]
+    fn ids_and_projector_files_follow_the_matrix_rows() {
+        let rows = [row("a", vec![1.0, 2.0]), row("b\tc", vec![0.5, -1.0]), row("d\r\ne", vec![0.0, 0.0])];
+        // ids are escaped like the metadata, so every line of the ids file is a row of the matrix
+        assert_eq!(to_ids(&rows), "a\nb c\nd  e\n");
+        let (vectors, metadata) = to_projector_tsv(&rows);
+        assert_eq!(vectors, "1\t2\n0.5\t-1\n0\t0\n");
+        assert_eq!(metadata, "id\ttext\na\ttext of a\nb c\ttext of b c\nd  e\ttext of d  e\n");
+    }
+
+    fn embeddings(vectors: &[Option<&[f32]>]) -> LazyFrame {
+        let ids: Vec<String> = (0..vectors.len()).map(|index| format!("id-{index}")).collect();
+        let texts: Vec<String> = ids.iter().map(|id| format!("text of {id}")).collect();
+        let vectors: Vec<Option<Series>> = vectors
+            .iter()
+            .map(|vector| vector.map(|vector| Series::new("".into(), vector)))
+            .collect();
+        df!(schemas::ID => ids, "text" => texts, EMBEDDING_COLUMN => vectors).unwrap().lazy()
+    }
+
+    #[test]
+    fn rows_without_a_matching_embedding_are_left_out() {
+        let rows = read_snapshot(embeddings(&[Some(&[1.0, 2.0]), None, Some(&[3.0]), Some(&[4.0, 5.0])]), "text", "test")
+            .unwrap();
+        let ids: Vec<&str> = rows.iter().map(|row| row.id.as_str()).collect();
+        assert_eq!(ids, ["id-0", "id-3"]);
+        assert_eq!(rows[1].text, "text of id-3");
+        assert_eq!(rows[1].vector, vec![4.0, 5.0]);
+    }
+
+    #[test]
+    fn missing_columns_are_an_error() {
+        let error = read_snapshot(embeddings(&[Some(&[1.0])]), "description", "test").unwrap_err();
+        assert!(error.contains("test"), "{error}");
+        let without_embeddings = embeddings(&[Some(&[1.0])]).drop([EMBEDDING_COLUMN]);
+        assert!(read_snapshot(without_embeddings, "text", "test").is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! A module containing snapshot_graph method definition.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use polars::prelude::{DataFrame, DataType, LazyFrame, col};
+
+use crate::data_model::schemas;
+use crate::index::update::communities::get_community_memberships;
+use crate::storage::pipeline_storage::PipelineStorage;
+
+/// An entity of the graph snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotNode {
+    /// The entity title, used as the node id.
+    pub title: String,
+    /// The entity type.
+    pub r#type: String,
+    /// The entity description.
+    pub description: String,
+    /// The number of relationships of the entity.
+    pub degree: i64,
+    /// The level 0 community of the entity, if the graph was clustered.
+    pub community: Option<i64>,
+    /// The layout position of the entity, if the graph was laid out.
+    pub position: Option<(f64, f64)>,
+}
+
+/// A relationship of the graph snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotEdge {
+    /// The relationship id.
+    pub id: String,
+    /// The source entity title.
+    pub source: String,
+    /// The target entity title.
+    pub target: String,
+    /// The relationship weight.
+    pub weight: f64,
+    /// The relationship description.
+    pub description: String,
+}
+
+/**
+Write the finalized graph to `{name}.graphml` and `{name}.gexf` in storage.
+
+Args:
+    - entities: The final entities.
+    - relationships: The final relationships.
+    - communities: The final communities, to add the community of every entity (optional).
+    - name: The base name of the snapshot files.
+    - storage: The storage to write the snapshots to.
+    - graphml: Whether to write the GraphML snapshot.
+    - gexf: Whether to write the GEXF snapshot.
+*/
+pub async fn snapshot_graph(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    communities: Option<LazyFrame>,
+    name: &str,
+    storage: PipelineStorage,
+    graphml: bool,
+    gexf: bool,
+) {
+    let (nodes, edges) = read_snapshot(entities, relationships, communities);
+    if graphml {
+        storage.set(&format!("{name}.graphml"), to_graphml(&nodes, &edges), None).await;
+    }
+    if gexf {
+        storage.set(&format!("{name}.gexf"), to_gexf(&nodes, &edges), None).await;
+    }
+}
+
+/// Read the nodes and edges of the finalized graph.
+pub fn read_snapshot(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    communities: Option<LazyFrame>,
+) -> (Vec<SnapshotNode>, Vec<SnapshotEdge>) {
+    let entities = entities.collect().unwrap();
+    let relationships = relationships
+        .select([
+            col(schemas::ID),
+            col(schemas::EDGE_SOURCE),
+            col(schemas::EDGE_TARGET),
+            col(schemas::EDGE_WEIGHT).cast(DataType::Float64),
+            col(schemas::DESCRIPTION),
+        ])
+        .collect()
+        .unwrap();
+
+    let mut entity_communities: HashMap<String, i64> = HashMap::new();
+    for community in communities
+        .map(get_community_memberships)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|community| community.level == 0)
+    {
+        for entity_id in community.entity_ids {
+            entity_communities.insert(entity_id, community.community);
+        }
+    }
+
+    let string = |df: &DataFrame, column: &str, row: usize| {
+        df.column(column)
+            .ok()
+            .and_then(|values| values.str().ok()?.get(row).map(|value| value.to_string()))
+            .unwrap_or_default()
+    };
+    let float = |df: &DataFrame, column: &str, row: usize| {
+        df.column(column)
+            .ok()?
+            .cast(&DataType::Float64)
+            .ok()?
+            .f64()
+            .ok()?
+            .get(row)
+    };
+
+    let nodes = (0..entities.height())
+        .map(|row| SnapshotNode {
+            title: string(&entities, schemas::TITLE, row),
+            r#type: string(&entities, schemas::TYPE, row),
+            description: string(&entities, schemas::DESCRIPTION, row),
+            degree: float(&entities, schemas::NODE_DEGREE, row).unwrap_or_default() as i64,
+            community: entity_communities.get(&string(&entities, schemas::ID, row)).copied(),
+            position: float(&entities, schemas::NODE_X, row).zip(float(&entities, schemas::NODE_Y, row)),
+        })
+        .collect();
+    let edges = (0..relationships.height())
+        .map(|row| SnapshotEdge {
+            id: string(&relationships, schemas::ID, row),
+            source: string(&relationships, schemas::EDGE_SOURCE, row),
+            target: string(&relationships, schemas::EDGE_TARGET, row),
+            weight: float(&relationships, schemas::EDGE_WEIGHT, row).unwrap_or(1.0),
+            description: string(&relationships, schemas::DESCRIPTION, row),
+        })
+        .collect();
+
+    (nodes, edges)
+}
+
+/// Serialize a graph to GraphML.
+pub fn to_graphml(nodes: &[SnapshotNode], edges: &[SnapshotEdge]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (id, domain, r#type) in [
+        ("type", "node", "string"),
+        ("description", "node", "string"),
+        ("degree", "node", "long"),
+        ("community", "node", "long"),
+        ("x", "node", "double"),
+        ("y", "node", "double"),
+        ("weight", "edge", "double"),
+        ("description", "edge", "string"),
+    ] {
+        writeln!(
+            xml,
+            "  <key id=\"{domain}_{id}\" for=\"{domain}\" attr.name=\"{id}\" attr.type=\"{type}\"/>"
+        )
+        .unwrap();
+    }
+    xml.push_str("  <graph edgedefault=\"undirected\">\n");
+
+    for node in nodes {
+        writeln!(xml, "    <node id=\"{}\">", escape(&node.title)).unwrap();
+        writeln!(xml, "      <data key=\"node_type\">{}</data>", escape(&node.r#type)).unwrap();
+        writeln!(xml, "      <data key=\"node_description\">{}</data>", escape(&node.description)).unwrap();
+        writeln!(xml, "      <data key=\"node_degree\">{}</data>", node.degree).unwrap();
+        if let Some(community) = node.community {
+            writeln!(xml, "      <data key=\"node_community\">{community}</data>").unwrap();
+        }
+        if let Some((x, y)) = node.position {
+            writeln!(xml, "      <data key=\"node_x\">{x}</data>").unwrap();
+            writeln!(xml, "      <data key=\"node_y\">{y}</data>").unwrap();
+        }
+        xml.push_str("    </node>\n");
+    }
+    for edge in edges {
+        writeln!(
+            xml,
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+            escape(&edge.id),
+            escape(&edge.source),
+            escape(&edge.target)
+        )
+        .unwrap();
+        writeln!(xml, "      <data key=\"edge_weight\">{}</data>", edge.weight).unwrap();
+        writeln!(xml, "      <data key=\"edge_description\">{}</data>", escape(&edge.description)).unwrap();
+        xml.push_str("    </edge>\n");
+    }
+
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// Serialize a graph to GEXF, the Gephi exchange format.
+pub fn to_gexf(nodes: &[SnapshotNode], edges: &[SnapshotEdge]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n",
+    );
+    xml.push_str("  <graph mode=\"static\" defaultedgetype=\"undirected\">\n");
+    xml.push_str("    <attributes class=\"node\">\n");
+    for (id, title, r#type) in [
+        (0, "type", "string"),
+        (1, "description", "string"),
+        (2, "degree", "long"),
+        (3, "community", "long"),
+    ] {
+        writeln!(xml, "      <attribute id=\"{id}\" title=\"{title}\" type=\"{type}\"/>").unwrap();
+    }
+    xml.push_str("    </attributes>\n");
+    xml.push_str("    <attributes class=\"edge\">\n");
+    xml.push_str("      <attribute id=\"0\" title=\"description\" type=\"string\"/>\n");
+    xml.push_str("    </attributes>\n");
+
+    xml.push_str("    <nodes>\n");
+    for node in nodes {
+        let title = escape(&node.title);
+        writeln!(xml, "      <node id=\"{title}\" label=\"{title}\">").unwrap();
+        xml.push_str("        <attvalues>\n");
+        writeln!(xml, "          <attvalue for=\"0\" value=\"{}\"/>", escape(&node.r#type)).unwrap();
+        writeln!(xml, "          <attvalue for=\"1\" value=\"{}\"/>", escape(&node.description)).unwrap();
+        writeln!(xml, "          <attvalue for=\"2\" value=\"{}\"/>", node.degree).unwrap();
+        if let Some(community) = node.community {
+            writeln!(xml, "          <attvalue for=\"3\" value=\"{community}\"/>").unwrap();
+        }
+        xml.push_str("        </attvalues>\n");
+        if let Some((x, y)) = node.position {
+            writeln!(xml, "        <viz:position x=\"{x}\" y=\"{y}\" z=\"0.0\"/>").unwrap();
+        }
+        xml.push_str("      </node>\n");
+    }
+    xml.push_str("    </nodes>\n");
+
+    xml.push_str("    <edges>\n");
+    for edge in edges {
+        writeln!(
+            xml,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\">",
+            escape(&edge.id),
+            escape(&edge.source),
+            escape(&edge.target),
+            edge.weight
+        )
+        .unwrap();
+        writeln!(
+            xml,
+            "        <attvalues><attvalue for=\"0\" value=\"{}\"/></attvalues>",
+            escape(&edge.description)
+        )
+        .unwrap();
+        xml.push_str("      </edge>\n");
+    }
+    xml.push_str("    </edges>\n");
+
+    xml.push_str("  </graph>\n</gexf>\n");
+    xml
+}
+
+/// Escape the XML special characters of a text, dropping the characters XML 1.0 does not allow.
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || (*c >= ' ' && !matches!(c, '\u{FFFE}' | '\u{FFFF}')))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> (Vec<SnapshotNode>, Vec<SnapshotEdge>) {
+        let nodes = vec![
+            SnapshotNode {
+                title: "A & B".into(),
+                r#type: "ORGANIZATION".into(),
+                description: "Says \"hi\"".into(),
+                degree: 1,
+                community: Some(0),
+                position: Some((1.5, -2.0)),
+            },
+            SnapshotNode {
+                title: "C".into(),
+                r#type: "PERSON".into(),
+                description: "".into(),
+                degree: 1,
+                community: None,
+                position: None,
+            },
+        ];
+        let edges = vec![SnapshotEdge {
+            id: "r1".into(),
+            source: "A & B".into(),
+            target: "C".into(),
+            weight: 2.0,
+            description: "<employs>".into(),
+        }];
+        (nodes, edges)
+    }
+
+    #[test]
+    fn graphml_matches_the_golden_output() {
+        let (nodes, edges) = graph();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="node_type" for="node" attr.name="type" attr.type="string"/>
+  <key id="node_description" for="node" attr.name="description" attr.type="string"/>
+  <key id="node_degree" for="node" attr.name="degree" attr.type="long"/>
+  <key id="node_community" for="node" attr.name="community" attr.type="long"/>
+  <key id="node_x" for="node" attr.name="x" attr.type="double"/>
+  <key id="node_y" for="node" attr.name="y" attr.type="double"/>
+  <key id="edge_weight" for="edge" attr.name="weight" attr.type="double"/>
+  <key id="edge_description" for="edge" attr.name="description" attr.type="string"/>
+  <graph edgedefault="undirected">
+    <node id="A &amp; B">
+      <data key="node_type">ORGANIZATION</data>
+      <data key="node_description">Says &quot;hi&quot;</data>
+      <data key="node_degree">1</data>
+      <data key="node_community">0</data>
+      <data key="node_x">1.5</data>
+      <data key="node_y">-2</data>
+    </node>
+    <node id="C">
+      <data key="node_type">PERSON</data>
+      <data key="node_description"></data>
+      <data key="node_degree">1</data>
+    </node>
+    <edge id="r1" source="A &amp; B" target="C">
+      <data key="edge_weight">2</data>
+      <data key="edge_description">&lt;employs&gt;</data>
+    </edge>
+  </graph>
+</graphml>
+"#;
+        assert_eq!(to_graphml(&nodes, &edges), expected);
+    }
+
+    #[test]
+    fn gexf_matches_the_golden_output() {
+        let (nodes, edges) = graph();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">
+  <graph mode="static" defaultedgetype="undirected">
+    <attributes class="node">
+      <attribute id="0" title="type" type="string"/>
+      <attribute id="1" title="description" type="string"/>
+      <attribute id="2" title="degree" type="long"/>
+      <attribute id="3" title="community" type="long"/>
+    </attributes>
+    <attributes class="edge">
+      <attribute id="0" title="description" type="string"/>
+    </attributes>
+    <nodes>
+      <node id="A &amp; B" label="A &amp; B">
+        <attvalues>
+          <attvalue for="0" value="ORGANIZATION"/>
+          <attvalue for="1" value="Says &quot;hi&quot;"/>
+          <attvalue for="2" value="1"/>
+          <attvalue for="3" value="0"/>
+        </attvalues>
+        <viz:position x="1.5" y="-2" z="0.0"/>
+      </node>
+      <node id="C" label="C">
+        <attvalues>
+          <attvalue for="0" value="PERSON"/>
+          <attvalue for="1" value=""/>
+          <attvalue for="2" value="1"/>
+        </attvalues>
+      </node>
+    </nodes>
+    <edges>
+      <edge id="r1" source="A &amp; B" target="C" weight="2">
+        <attvalues><attvalue for="0" value="&lt;employs&gt;"/></attvalues>
+      </edge>
+    </edges>
+  </graph>
+</gexf>
+"#;
+        assert_eq!(to_gexf(&nodes, &edges), expected);
+    }
+
+    #[test]
+    fn escape_drops_characters_invalid_in_xml() {
+        assert_eq!(escape("a\u{0}b\u{1b}c\u{FFFF}"), "abc");
+        assert_eq!(escape("tab\tnew\nline\r"), "tab\tnew\nline\r");
+        assert_eq!(escape("it's <x>"), "it&apos;s &lt;x&gt;");
+    }
+}
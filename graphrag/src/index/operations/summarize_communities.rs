@@ -1,4 +1,11 @@
 //! Community summarization modules.
 
+pub mod build_mixed_context;
+pub mod community_reports_extractor;
+pub mod explode_communities;
+pub mod graph_context;
+pub mod level_context;
 pub mod summarize_communities;
+pub mod text_unit_context;
 pub mod typing;
+pub mod utils;
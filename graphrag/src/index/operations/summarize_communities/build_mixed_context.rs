@@ -0,0 +1,85 @@
+//! A module containing the build_mixed_context method definition.
+
+use std::cmp::Reverse;
+
+use crate::index::operations::summarize_communities::typing::SubCommunityReport;
+use crate::index::operations::summarize_communities::utils::reports_to_csv;
+use crate::query::llm::text_utils::num_tokens;
+
+/// The local context of a sub-community, with its report if one was generated.
+#[derive(Debug, Clone)]
+pub struct SubCommunityContext<T> {
+    pub sub_community: i64,
+    pub full_content: Option<String>,
+    pub all_context: Vec<T>,
+    pub context_size: usize,
+}
+
+/**
+Build parent context by concatenating all sub-communities' contexts.
+
+If the context exceeds the limit, we use sub-community reports instead,
+starting from the biggest sub-community. `sort_context` renders the remaining local context
+together with the substituted reports.
+*/
+pub fn build_mixed_context<T: Clone>(
+    mut context: Vec<SubCommunityContext<T>>,
+    max_context_tokens: usize,
+    sort_context: impl Fn(&[T], &[SubCommunityReport], Option<usize>) -> String,
+) -> String {
+    // sort by context size, biggest sub-communities first
+    context.sort_by_key(|sub_community_context| Reverse(sub_community_context.context_size));
+
+    // replace local context with sub-community reports, starting from the biggest sub-community
+    let mut substitute_reports: Vec<SubCommunityReport> = Vec::new();
+    let mut final_local_contexts: Vec<T> = Vec::new();
+    let mut exceeded_limit = true;
+    let mut context_string = String::new();
+
+    for (idx, sub_community_context) in context.iter().enumerate() {
+        if !exceeded_limit {
+            break;
+        }
+
+        match &sub_community_context.full_content {
+            Some(full_content) => substitute_reports.push(SubCommunityReport {
+                community: sub_community_context.sub_community,
+                full_content: full_content.clone(),
+            }),
+            // this sub-community has no report, so we will use its local context
+            None => final_local_contexts.extend(sub_community_context.all_context.iter().cloned()),
+        }
+
+        // add local context for the remaining sub-communities
+        let mut remaining_local_context = final_local_contexts.clone();
+        for remaining in &context[idx + 1..] {
+            remaining_local_context.extend(remaining.all_context.iter().cloned());
+        }
+        let new_context_string = sort_context(&remaining_local_context, &substitute_reports, None);
+        if num_tokens(&new_context_string, None) <= max_context_tokens {
+            exceeded_limit = false;
+            context_string = new_context_string;
+        }
+    }
+
+    if exceeded_limit {
+        // if all sub-community reports exceed the limit, we add reports until context is full
+        substitute_reports.clear();
+        for sub_community_context in &context {
+            let Some(full_content) = &sub_community_context.full_content else {
+                continue;
+            };
+            substitute_reports.push(SubCommunityReport {
+                community: sub_community_context.sub_community,
+                full_content: full_content.clone(),
+            });
+            let new_context_string = reports_to_csv(&substitute_reports);
+            if num_tokens(&new_context_string, None) > max_context_tokens {
+                break;
+            }
+            context_string = new_context_string;
+        }
+    }
+
+    context_string
+}
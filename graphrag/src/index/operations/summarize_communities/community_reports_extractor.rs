@@ -0,0 +1,145 @@
+//! A module containing 'CommunityReportsResult' and 'CommunityReportsExtractor' models.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::index::operations::summarize_communities::typing::Finding;
+use crate::language_model::protocol::base::ChatModel;
+use crate::prompts::index::community_report::COMMUNITY_REPORT_PROMPT;
+
+/// A function to handle report generation errors, called with the error message and details.
+pub type ErrorHandlerFn<'a> = Box<dyn Fn(String, HashMap<String, String>) + 'a>;
+
+/// A model for the expected LLM response shape.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommunityReportResponse {
+    pub title: String,
+    pub summary: String,
+    pub findings: Vec<Finding>,
+    pub rating: f64,
+    pub rating_explanation: String,
+}
+
+/// Community reports result class definition.
+#[derive(Debug, Default)]
+pub struct CommunityReportsResult {
+    pub output: String,
+    pub structured_output: Option<CommunityReportResponse>,
+}
+
+/// Community reports extractor class definition.
+pub struct CommunityReportsExtractor<'a, M: ChatModel> {
+    model: M,
+    extraction_prompt: String,
+    max_report_length: usize,
+    on_error: ErrorHandlerFn<'a>,
+}
+
+impl<'a, M: ChatModel> CommunityReportsExtractor<'a, M> {
+    /// Init method definition.
+    pub fn new(
+        model: M,
+        extraction_prompt: Option<String>,
+        max_report_length: usize, // = 1500,
+        on_error: Option<ErrorHandlerFn<'a>>,
+    ) -> Self {
+        CommunityReportsExtractor {
+            model,
+            extraction_prompt: extraction_prompt.unwrap_or_else(|| COMMUNITY_REPORT_PROMPT.into()),
+            max_report_length,
+            on_error: on_error.unwrap_or_else(|| Box::new(|_, _| {})),
+        }
+    }
+
+    /// Generate a report for the given community context.
+    pub async fn extract(&self, input_text: &str) -> CommunityReportsResult {
+        match self.generate(input_text).await {
+            Ok(report) => CommunityReportsResult {
+                output: self.get_text_output(&report),
+                structured_output: Some(report),
+            },
+            Err(error) => {
+                (self.on_error)(
+                    error,
+                    HashMap::from([("input_text".into(), input_text.to_string())]),
+                );
+                CommunityReportsResult::default()
+            }
+        }
+    }
+
+    /// Prompt the model and parse its JSON response.
+    async fn generate(&self, input_text: &str) -> Result<CommunityReportResponse, String> {
+        // the prompt escapes the braces of its JSON examples
+        let prompt = self
+            .extraction_prompt
+            .replace("{{", "{")
+            .replace("}}", "}")
+            .replace("{input_text}", input_text)
+            .replace("{max_report_length}", &self.max_report_length.to_string());
+
+        let response = self.model.achat(&prompt, None).await?;
+        parse_report(response.output().content())
+    }
+
+    /// Render the report as markdown.
+    fn get_text_output(&self, report: &CommunityReportResponse) -> String {
+        let report_sections = report
+            .findings
+            .iter()
+            .map(|finding| format!("## {}\n\n{}", finding.summary, finding.explanation))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        format!("# {}\n\n{}\n\n{}", report.title, report.summary, report_sections)
+    }
+}
+
+/// Parse the JSON object in a model response, ignoring any text around it.
+fn parse_report(content: &str) -> Result<CommunityReportResponse, String> {
+    let (Some(start), Some(end)) = (content.find('{'), content.rfind('}')) else {
+        return Err("No JSON object found in the community report response".into());
+    };
+    if end < start {
+        return Err("No JSON object found in the community report response".into());
+    }
+    serde_json::from_str(&content[start..=end])
+        .map_err(|error| format!("Error parsing community report: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "title": "River Town",
+        "summary": "The town by the river.",
+        "findings": [{"summary": "Bridge", "explanation": "The {bridge} joins both banks."}],
+        "rating": 6.5,
+        "rating_explanation": "Moderate impact."
+    }"#;
+
+    #[test]
+    fn report_is_parsed() {
+        let report = parse_report(REPORT).unwrap();
+        assert_eq!(report.title, "River Town");
+        assert_eq!(report.rating, 6.5);
+        assert_eq!(
+            report.findings,
+            [Finding { summary: "Bridge".into(), explanation: "The {bridge} joins both banks.".into() }]
+        );
+    }
+
+    #[test]
+    fn text_around_the_report_is_ignored() {
+        let content = format!("Here is the report:\n```json\n{REPORT}\n```\nLet me know if you need more.");
+        assert_eq!(parse_report(&content).unwrap().title, "River Town");
+    }
+
+    #[test]
+    fn missing_or_invalid_json_is_an_error() {
+        assert!(parse_report("no report today").is_err());
+        assert!(parse_report("} backwards {").is_err());
+        assert!(parse_report(r#"{"title": "River Town"}"#).is_err());
+    }
+}
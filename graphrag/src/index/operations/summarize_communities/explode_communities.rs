@@ -0,0 +1,55 @@
+//! Explode a list of communities into nodes for reporting.
+
+use polars::prelude::{DataType, JoinArgs, JoinType, LazyFrame, col, lit};
+
+use crate::data_model::schemas;
+use crate::index::operations::summarize_communities::typing::{CommunityNode, NodeDetails};
+use crate::utils::dataframe::{get_i64, get_string};
+
+/// Explode a list of communities into nodes for reporting.
+pub fn explode_communities(communities: LazyFrame, entities: LazyFrame) -> LazyFrame {
+    let community_join = communities
+        .select([
+            col(schemas::COMMUNITY_ID),
+            col(schemas::COMMUNITY_LEVEL),
+            col(schemas::ENTITY_IDS),
+        ])
+        .explode([col(schemas::ENTITY_IDS)]);
+
+    entities
+        .join(
+            community_join,
+            [col(schemas::ID)],
+            [col(schemas::ENTITY_IDS)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .filter(col(schemas::COMMUNITY_ID).neq(lit(-1)))
+}
+
+/// Prepare the community nodes, filling in missing descriptions.
+pub fn prep_community_nodes(input: LazyFrame) -> Vec<CommunityNode> {
+    let nodes = input
+        .select([
+            col(schemas::COMMUNITY_ID).cast(DataType::Int64),
+            col(schemas::COMMUNITY_LEVEL).cast(DataType::Int64),
+            col(schemas::SHORT_ID).cast(DataType::Int64),
+            col(schemas::TITLE),
+            col(schemas::DESCRIPTION).fill_null(lit("No Description")),
+            col(schemas::NODE_DEGREE).cast(DataType::Int64),
+        ])
+        .collect()
+        .unwrap();
+
+    (0..nodes.height())
+        .map(|row| CommunityNode {
+            community: get_i64(&nodes, schemas::COMMUNITY_ID, row),
+            level: get_i64(&nodes, schemas::COMMUNITY_LEVEL, row),
+            details: NodeDetails {
+                human_readable_id: get_i64(&nodes, schemas::SHORT_ID, row),
+                title: get_string(&nodes, schemas::TITLE, row),
+                description: get_string(&nodes, schemas::DESCRIPTION, row),
+                degree: get_i64(&nodes, schemas::NODE_DEGREE, row),
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,4 @@
+//! Graph-based context builders for community reports.
+
+pub mod context_builder;
+pub mod sort_context;
@@ -0,0 +1,108 @@
+//! Context builders for graphs.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::index::operations::summarize_communities::graph_context::sort_context::sort_context;
+use crate::index::operations::summarize_communities::level_context;
+use crate::index::operations::summarize_communities::typing::{
+    ClaimDetails, CommunityContext, CommunityHierarchy, CommunityNode, CommunityReport, EdgeDetails,
+    NodeContext,
+};
+use crate::index::operations::summarize_communities::utils::get_levels;
+use crate::query::llm::text_utils::num_tokens;
+
+/// Prep communities for report generation.
+pub fn build_local_context(
+    nodes: &[CommunityNode],
+    edges: &[EdgeDetails],
+    claims: Option<&[ClaimDetails]>,
+    max_context_tokens: usize, // = 16_000,
+) -> Vec<CommunityContext> {
+    get_levels(nodes)
+        .into_iter()
+        .flat_map(|level| prepare_reports_at_level(nodes, edges, claims, level, max_context_tokens))
+        .collect()
+}
+
+/// Prepare reports at a given level.
+fn prepare_reports_at_level(
+    nodes: &[CommunityNode],
+    edges: &[EdgeDetails],
+    claims: Option<&[ClaimDetails]>,
+    level: i64,
+    max_context_tokens: usize,
+) -> Vec<CommunityContext> {
+    // filter and prepare node details
+    let level_nodes: Vec<&CommunityNode> = nodes.iter().filter(|node| node.level == level).collect();
+    let nodes_set: HashSet<&str> = level_nodes
+        .iter()
+        .map(|node| node.details.title.as_str())
+        .collect();
+
+    // filter edges to those where both source and target are at this level
+    let mut node_edges: HashMap<&str, Vec<EdgeDetails>> = HashMap::new();
+    for edge in edges
+        .iter()
+        .filter(|edge| nodes_set.contains(edge.source.as_str()) && nodes_set.contains(edge.target.as_str()))
+    {
+        node_edges.entry(edge.source.as_str()).or_default().push(edge.clone());
+        node_edges.entry(edge.target.as_str()).or_default().push(edge.clone());
+    }
+
+    let mut node_claims: HashMap<&str, Vec<ClaimDetails>> = HashMap::new();
+    for claim in claims
+        .unwrap_or_default()
+        .iter()
+        .filter(|claim| nodes_set.contains(claim.subject_id.as_str()))
+    {
+        node_claims.entry(claim.subject_id.as_str()).or_default().push(claim.clone());
+    }
+
+    // group the node contexts by community
+    let mut communities: BTreeMap<i64, Vec<NodeContext>> = BTreeMap::new();
+    for node in level_nodes {
+        let title = node.details.title.as_str();
+        communities.entry(node.community).or_default().push(NodeContext {
+            title: title.to_string(),
+            degree: node.details.degree,
+            node_details: node.details.clone(),
+            edge_details: node_edges.get(title).cloned().unwrap_or_default(),
+            claim_details: node_claims.get(title).cloned().unwrap_or_default(),
+        });
+    }
+
+    // generate community-level context strings
+    communities
+        .into_iter()
+        .map(|(community, all_context)| {
+            let context_string = sort_context(&all_context, &[], None);
+            let context_size = num_tokens(&context_string, None);
+            CommunityContext {
+                community,
+                level,
+                all_context,
+                context_string,
+                context_size,
+                context_exceed_limit: context_size > max_context_tokens,
+            }
+        })
+        .collect()
+}
+
+/// Prep context for each community in a given level, substituting sub-community reports for oversized local contexts.
+pub fn build_level_context(
+    reports: &[CommunityReport],
+    community_hierarchy: &[CommunityHierarchy],
+    local_contexts: &[CommunityContext],
+    level: i64,
+    max_context_tokens: usize,
+) -> Vec<CommunityContext> {
+    level_context::build_level_context(
+        reports,
+        community_hierarchy,
+        local_contexts,
+        level,
+        max_context_tokens,
+        sort_context,
+    )
+}
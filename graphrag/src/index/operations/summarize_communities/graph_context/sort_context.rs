@@ -0,0 +1,148 @@
+//! Sort context by degree in descending order.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::index::operations::summarize_communities::typing::{
+    ClaimDetails, EdgeDetails, NodeContext, NodeDetails, SubCommunityReport,
+};
+use crate::index::operations::summarize_communities::utils::{reports_to_csv, to_csv};
+use crate::query::llm::text_utils::num_tokens;
+
+/**
+Sort context by degree in descending order, optimizing for performance.
+
+Edges are added from the highest degree down, each bringing in its source and target entities and their claims,
+until the rendered context exceeds `max_context_tokens`.
+*/
+pub fn sort_context(
+    local_context: &[NodeContext],
+    sub_community_reports: &[SubCommunityReport],
+    max_context_tokens: Option<usize>,
+) -> String {
+    // preprocess local context
+    let mut edges: Vec<&EdgeDetails> = local_context
+        .iter()
+        .flat_map(|record| record.edge_details.iter())
+        .collect();
+    let node_details: HashMap<&str, &NodeDetails> = local_context
+        .iter()
+        .map(|record| (record.title.as_str(), &record.node_details))
+        .collect();
+    let claim_details: HashMap<&str, &Vec<ClaimDetails>> = local_context
+        .iter()
+        .filter(|record| !record.claim_details.is_empty())
+        .map(|record| (record.title.as_str(), &record.claim_details))
+        .collect();
+
+    // sort edges by degree (desc) and id (asc)
+    edges.sort_by_key(|edge| (-edge.combined_degree, edge.human_readable_id));
+
+    // deduplicate and build context incrementally
+    let mut edge_ids = HashSet::new();
+    let mut node_ids = HashSet::new();
+    let mut claim_ids = HashSet::new();
+    let mut sorted_edges: Vec<&EdgeDetails> = Vec::new();
+    let mut sorted_nodes: Vec<&NodeDetails> = Vec::new();
+    let mut sorted_claims: Vec<&ClaimDetails> = Vec::new();
+    let mut context_string = String::new();
+
+    for edge in edges {
+        let endpoints = [edge.source.as_str(), edge.target.as_str()];
+
+        // add source and target node details
+        for node in endpoints.iter().filter_map(|name| node_details.get(name)) {
+            if node_ids.insert(node.human_readable_id) {
+                sorted_nodes.push(node);
+            }
+        }
+
+        // add claims related to source and target
+        for claims in endpoints.iter().filter_map(|name| claim_details.get(name)) {
+            for claim in claims.iter() {
+                if claim_ids.insert(claim.human_readable_id) {
+                    sorted_claims.push(claim);
+                }
+            }
+        }
+
+        // add the edge
+        if edge_ids.insert(edge.human_readable_id) {
+            sorted_edges.push(edge);
+        }
+
+        // generate new context string
+        let new_context_string =
+            get_context_string(&sorted_nodes, &sorted_edges, &sorted_claims, sub_community_reports);
+        if max_context_tokens.is_some_and(|max_tokens| num_tokens(&new_context_string, None) > max_tokens) {
+            break;
+        }
+        context_string = new_context_string;
+    }
+
+    if context_string.is_empty() {
+        return get_context_string(&sorted_nodes, &sorted_edges, &sorted_claims, sub_community_reports);
+    }
+    context_string
+}
+
+/// Concatenate structured data into a context string.
+fn get_context_string(
+    entities: &[&NodeDetails],
+    edges: &[&EdgeDetails],
+    claims: &[&ClaimDetails],
+    sub_community_reports: &[SubCommunityReport],
+) -> String {
+    let mut contexts = Vec::new();
+
+    if !sub_community_reports.is_empty() {
+        contexts.push(format!("----Reports-----\n{}", reports_to_csv(sub_community_reports)));
+    }
+
+    let entity_rows: Vec<Vec<String>> = entities
+        .iter()
+        .map(|node| {
+            vec![
+                node.human_readable_id.to_string(),
+                node.title.clone(),
+                node.description.clone(),
+                node.degree.to_string(),
+            ]
+        })
+        .collect();
+    let claim_rows: Vec<Vec<String>> = claims
+        .iter()
+        .map(|claim| {
+            vec![
+                claim.human_readable_id.to_string(),
+                claim.subject_id.clone(),
+                claim.r#type.clone(),
+                claim.status.clone(),
+                claim.description.clone(),
+            ]
+        })
+        .collect();
+    let edge_rows: Vec<Vec<String>> = edges
+        .iter()
+        .map(|edge| {
+            vec![
+                edge.human_readable_id.to_string(),
+                edge.source.clone(),
+                edge.target.clone(),
+                edge.description.clone(),
+                edge.combined_degree.to_string(),
+            ]
+        })
+        .collect();
+
+    for (label, headers, rows) in [
+        ("Entities", &["id", "entity", "description", "degree"][..], entity_rows),
+        ("Claims", &["id", "entity", "type", "status", "description"][..], claim_rows),
+        ("Relationships", &["id", "source", "target", "description", "degree"][..], edge_rows),
+    ] {
+        if !rows.is_empty() {
+            contexts.push(format!("-----{label}-----\n{}", to_csv(headers, rows)));
+        }
+    }
+
+    contexts.join("\n\n")
+}
@@ -0,0 +1,213 @@
+//! A module containing the level context builder shared by the graph and text unit context builders.
+
+use std::collections::HashMap;
+
+use crate::index::operations::summarize_communities::build_mixed_context::{
+    SubCommunityContext, build_mixed_context,
+};
+use crate::index::operations::summarize_communities::typing::{
+    CommunityContext, CommunityHierarchy, CommunityReport, SubCommunityReport,
+};
+use crate::query::llm::text_utils::num_tokens;
+
+/**
+Prep context for each community in a given level.
+
+For each community:
+- Check if local context fits within the limit, if yes use local context
+- If local context exceeds the limit, iteratively replace local context with sub-community reports, starting from the biggest sub-community
+
+`sort_context` renders a local context, with any substituted sub-community reports, into a context string.
+*/
+pub fn build_level_context<T: Clone>(
+    reports: &[CommunityReport],
+    community_hierarchy: &[CommunityHierarchy],
+    local_contexts: &[CommunityContext<T>],
+    level: i64,
+    max_context_tokens: usize,
+    sort_context: impl Fn(&[T], &[SubCommunityReport], Option<usize>) -> String,
+) -> Vec<CommunityContext<T>> {
+    // filter by community level
+    let level_contexts: Vec<&CommunityContext<T>> = local_contexts
+        .iter()
+        .filter(|context| context.level == level)
+        .collect();
+
+    // filter valid and invalid contexts
+    let (valid_contexts, invalid_contexts): (Vec<&CommunityContext<T>>, Vec<&CommunityContext<T>>) =
+        level_contexts
+            .into_iter()
+            .partition(|context| !context.context_exceed_limit);
+
+    let mut result: Vec<CommunityContext<T>> = valid_contexts.into_iter().cloned().collect();
+    if invalid_contexts.is_empty() {
+        return result;
+    }
+
+    // there is no report to substitute with, so we just trim the local context of the invalid context records
+    // this case should only happen at the bottom level of the community hierarchy where there are no sub-communities
+    if reports.is_empty() {
+        result.extend(
+            invalid_contexts
+                .into_iter()
+                .map(|context| trim_context(context, max_context_tokens, &sort_context)),
+        );
+        return result;
+    }
+
+    // for each invalid context, we will try to substitute with sub-community reports
+    // first get local context and report (if available) for each sub-community
+    let report_contents: HashMap<i64, &str> = reports
+        .iter()
+        .map(|report| (report.community, report.full_content.as_str()))
+        .collect();
+    let sub_contexts: HashMap<i64, &CommunityContext<T>> = local_contexts
+        .iter()
+        .filter(|context| context.level == level + 1)
+        .map(|context| (context.community, context))
+        .collect();
+
+    for context in invalid_contexts {
+        let sub_community_contexts: Vec<SubCommunityContext<T>> = community_hierarchy
+            .iter()
+            .filter(|edge| edge.community == context.community && edge.level == level)
+            .filter_map(|edge| {
+                let sub_context = sub_contexts.get(&edge.sub_community)?;
+                Some(SubCommunityContext {
+                    sub_community: edge.sub_community,
+                    full_content: report_contents
+                        .get(&edge.sub_community)
+                        .map(|content| content.to_string()),
+                    all_context: sub_context.all_context.clone(),
+                    context_size: sub_context.context_size,
+                })
+            })
+            .collect();
+
+        // handle any remaining invalid records that can't be substituted with sub-community reports
+        // this should be rare, but if it happens, we will just trim the local context to fit the limit
+        if sub_community_contexts.is_empty() {
+            result.push(trim_context(context, max_context_tokens, &sort_context));
+            continue;
+        }
+
+        let context_string = build_mixed_context(sub_community_contexts, max_context_tokens, &sort_context);
+        result.push(CommunityContext {
+            context_size: num_tokens(&context_string, None),
+            context_string,
+            context_exceed_limit: false,
+            ..context.clone()
+        });
+    }
+
+    result
+}
+
+/// Trim the local context of a community to fit within the token limit.
+fn trim_context<T: Clone>(
+    context: &CommunityContext<T>,
+    max_context_tokens: usize,
+    sort_context: impl Fn(&[T], &[SubCommunityReport], Option<usize>) -> String,
+) -> CommunityContext<T> {
+    let context_string = sort_context(&context.all_context, &[], Some(max_context_tokens));
+    CommunityContext {
+        context_size: num_tokens(&context_string, None),
+        context_string,
+        context_exceed_limit: false,
+        ..context.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Render the reports, then the local context, keeping at most `max_tokens` words.
+    fn sort_context(items: &[String], reports: &[SubCommunityReport], max_tokens: Option<usize>) -> String {
+        reports
+            .iter()
+            .map(|report| report.full_content.as_str())
+            .chain(items.iter().map(String::as_str))
+            .flat_map(str::split_whitespace)
+            .take(max_tokens.unwrap_or(usize::MAX))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn context(community: i64, level: i64, word: &str, words: usize, exceed: bool) -> CommunityContext<String> {
+        let all_context = vec![vec![word; words].join(" ")];
+        CommunityContext {
+            community,
+            level,
+            context_string: all_context[0].clone(),
+            all_context,
+            context_size: words,
+            context_exceed_limit: exceed,
+        }
+    }
+
+    fn report(community: i64, full_content: &str) -> CommunityReport {
+        CommunityReport {
+            community,
+            title: String::new(),
+            summary: String::new(),
+            full_content: full_content.into(),
+            full_content_json: String::new(),
+            rank: 0.0,
+            level: 1,
+            rating_explanation: String::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    fn hierarchy() -> Vec<CommunityHierarchy> {
+        vec![
+            CommunityHierarchy { community: 0, level: 0, sub_community: 1 },
+            CommunityHierarchy { community: 0, level: 0, sub_community: 2 },
+        ]
+    }
+
+    #[test]
+    fn contexts_within_the_limit_are_kept() {
+        let local_contexts = [context(0, 0, "river", 10, false), context(1, 1, "bridge", 10, false)];
+        let level_context = build_level_context(&[], &hierarchy(), &local_contexts, 0, 40, sort_context);
+        assert_eq!(level_context.len(), 1);
+        assert_eq!(level_context[0].community, 0);
+        assert_eq!(level_context[0].context_string, local_contexts[0].context_string);
+    }
+
+    #[test]
+    fn contexts_over_the_limit_are_trimmed_without_reports() {
+        let local_contexts = [context(0, 0, "river", 100, true)];
+        let level_context = build_level_context(&[], &hierarchy(), &local_contexts, 0, 40, sort_context);
+        assert_eq!(level_context[0].context_string.split_whitespace().count(), 40);
+        assert!(!level_context[0].context_exceed_limit);
+    }
+
+    #[test]
+    fn sub_community_reports_replace_the_biggest_local_contexts() {
+        let local_contexts = [
+            context(0, 0, "river", 60, true),
+            context(1, 1, "bridge", 30, false),
+            context(2, 1, "town", 20, false),
+        ];
+        let reports = [report(1, "bridge report")];
+        let level_context = build_level_context(&reports, &hierarchy(), &local_contexts, 0, 40, sort_context);
+
+        assert_eq!(level_context.len(), 1);
+        let context_string = &level_context[0].context_string;
+        assert!(context_string.starts_with("bridge report town"));
+        assert!(!context_string.contains("bridge bridge"));
+        assert_eq!(context_string.split_whitespace().filter(|word| *word == "town").count(), 20);
+        assert!(!level_context[0].context_exceed_limit);
+    }
+
+    #[test]
+    fn contexts_without_sub_communities_are_trimmed() {
+        let local_contexts = [context(3, 0, "river", 100, true)];
+        let reports = [report(1, "bridge report")];
+        let level_context = build_level_context(&reports, &hierarchy(), &local_contexts, 0, 40, sort_context);
+        assert_eq!(level_context[0].community, 3);
+        assert_eq!(level_context[0].context_string.split_whitespace().count(), 40);
+    }
+}
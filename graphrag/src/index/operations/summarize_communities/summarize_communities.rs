@@ -1,124 +1,130 @@
-/// A module containing create_community_reports and load_strategy methods definition.
+//! A module containing summarize_communities and generate_report methods definition.
 
-use log;
+use std::collections::HashSet;
 
-use polars::prelude::LazyFrame;
+use futures::stream::{self, StreamExt};
+use log::debug;
 
-use crate::data_model::schemas;
-use crate::cache::pipeline_cache::PipelineCache;
-use crate::callbacks::noop_workflow_callbacks::NoopWorkflowCallbacks;
 use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
-use crate::config::enums::AsyncType;
+use crate::index::operations::summarize_communities::community_reports_extractor::CommunityReportsExtractor;
 use crate::index::operations::summarize_communities::typing::{
-    CommunityReport,
-    CommunityReportsStrategy,
-    CreateCommunityReportsStrategyType,
+    CommunityContext, CommunityHierarchy, CommunityNode, CommunityReport,
 };
 use crate::index::operations::summarize_communities::utils::get_levels;
-use crate::index::utils::derive_from_rows::derive_from_rows;
-use crate::logger::progress::progress_ticker;
+use crate::language_model::protocol::base::ChatModel;
+use crate::logger::progress::Progress;
 
-/// Generate community summaries.
-pub async fn summarize_communities(
-    nodes: LazyFrame,
-    communities: LazyFrame,
-    local_contexts,
-    level_context_builder: Callable,
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    strategy: dict,
-    max_input_length: int,
-    async_mode: AsyncType, // = AsyncType.AsyncIO,
-    num_threads: usize, // = 4,
-) {
-    reports: list[CommunityReport | None] = []
-    tick = progress_ticker(callbacks.progress, len(local_contexts))
-    strategy_exec = load_strategy(strategy["type"])
-    strategy_config = {**strategy}
+/**
+Generate community summaries.
 
-    // if max_retries is not set, inject a dynamically assigned value based on the total number of expected LLM calls to be made
-    if strategy_config.get("llm") and strategy_config["llm"]["max_retries"] == -1:
-        strategy_config["llm"]["max_retries"] = len(nodes)
+Levels are summarized bottom-up, so that the context of each level can substitute
+the reports of its sub-communities for local context that does not fit in `max_input_length`.
 
-    community_hierarchy = (
-        communities.explode("children")
-        .rename({"children": "sub_community"}, axis=1)
-        .loc[:, ["community", "level", "sub_community"]]
-    ).dropna()
+Args:
+    nodes: The entities assigned to each community, used to find the community levels.
+    community_hierarchy: The parent/child edges between communities.
+    local_contexts: The local context of every community, passed through to the level context builder.
+    level_context_builder: Builds the context of each community at a level, given the reports generated so far.
+    existing_reports: Reports to reuse; communities that already have a report are not summarized again.
+    num_threads: The number of reports of a level generated at once.
+*/
+pub async fn summarize_communities<M: ChatModel, T>(
+    nodes: &[CommunityNode],
+    community_hierarchy: &[CommunityHierarchy],
+    local_contexts: &[CommunityContext<T>],
+    level_context_builder: impl Fn(
+        &[CommunityReport],
+        &[CommunityHierarchy],
+        &[CommunityContext<T>],
+        i64,
+        usize,
+    ) -> Vec<CommunityContext<T>>,
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    extraction_prompt: Option<String>,
+    max_report_length: usize,
+    max_input_length: usize,
+    existing_reports: Vec<CommunityReport>,
+    num_threads: usize, // = 4,
+) -> Vec<CommunityReport> {
+    let extractor = CommunityReportsExtractor::new(
+        model,
+        extraction_prompt,
+        max_report_length,
+        Some(Box::new(|message, details| {
+            callbacks.error(format!("Community Report Extraction Error: {message}"), None, None, Some(details))
+        })),
+    );
 
-    levels = get_levels(nodes)
+    let reused: HashSet<i64> = existing_reports.iter().map(|report| report.community).collect();
+    let total_items = nodes
+        .iter()
+        .filter(|node| node.level != -1 && !reused.contains(&node.community))
+        .map(|node| (node.level, node.community))
+        .collect::<HashSet<_>>()
+        .len();
+    let mut completed_items = 0;
 
-    level_contexts = []
-    for level in levels:
-        level_context = level_context_builder(
-            LazyFrame(reports),
-            community_hierarchy_df=community_hierarchy,
-            local_context_df=local_contexts,
-            level=level,
-            max_tokens=max_input_length,
-        )
-        level_contexts.append(level_context)
+    // reused reports are available for substitution into the context of their parent communities
+    let mut reports: Vec<CommunityReport> = existing_reports;
+    for level in get_levels(nodes) {
+        let level_context = level_context_builder(
+            &reports,
+            community_hierarchy,
+            local_contexts,
+            level,
+            max_input_length,
+        );
+        debug!("summarize_communities level={level} communities={}", level_context.len());
 
-    for level_context in level_contexts:
+        // the communities of a level are independent, up to num_threads reports are generated at once
+        let extractor = &extractor;
+        let mut generated = stream::iter(
+            level_context
+                .into_iter()
+                .filter(|record| !reused.contains(&record.community)),
+        )
+        .map(|record| async move {
+            generate_report(extractor, record.community, record.level, &record.context_string).await
+        })
+        .buffered(num_threads.max(1));
 
-        async def run_generate(record):
-            result = await _generate_report(
-                strategy_exec,
-                community_id=record[schemas.COMMUNITY_ID],
-                community_level=record[schemas.COMMUNITY_LEVEL],
-                community_context=record[schemas.CONTEXT_STRING],
-                callbacks=callbacks,
-                cache=cache,
-                strategy=strategy_config,
-            )
-            tick()
-            return result
+        let mut local_reports = Vec::new();
+        while let Some(report) = generated.next().await {
+            local_reports.extend(report);
 
-        local_reports = await derive_from_rows(
-            level_context,
-            run_generate,
-            callbacks=NoopWorkflowCallbacks(),
-            num_threads=num_threads,
-            async_type=async_mode,
-        )
-        reports.extend([lr for lr in local_reports if lr is not None])
+            completed_items += 1;
+            callbacks.progress(Progress {
+                total_items: Some(total_items),
+                completed_items: Some(completed_items),
+                ..Default::default()
+            });
+        }
+        reports.extend(local_reports);
+    }
 
-    return LazyFrame(reports)
+    reports
 }
 
 /// Generate a report for a single community.
-pub async fn _generate_report(
-    runner: CommunityReportsStrategy,
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    strategy: dict,
-    community_id: int,
-    community_level: int,
-    community_context: str,
+async fn generate_report<M: ChatModel>(
+    extractor: &CommunityReportsExtractor<'_, M>,
+    community_id: i64,
+    community_level: i64,
+    community_context: &str,
 ) -> Option<CommunityReport> {
-    return await runner(
-        community_id,
-        community_context,
-        community_level,
-        callbacks,
-        cache,
-        strategy,
-    )
-}
+    let results = extractor.extract(community_context).await;
+    let report = results.structured_output?;
 
-/// Load strategy method definition.
-pub fn load_strategy(
-    strategy: CreateCommunityReportsStrategyType,
-) -> CommunityReportsStrategy {
-    match strategy {
-        CreateCommunityReportsStrategyType::GraphIntelligence:
-            use crate::index.operations.summarize_communities.strategies::(
-                run_graph_intelligence,
-            )
-
-            return run_graph_intelligence
-        case _:
-            msg = f"Unknown strategy: {strategy}"
-            raise ValueError(msg)
-    }
+    Some(CommunityReport {
+        community: community_id,
+        full_content: results.output,
+        full_content_json: serde_json::to_string_pretty(&report).unwrap(),
+        level: community_level,
+        rank: report.rating,
+        title: report.title,
+        rating_explanation: report.rating_explanation,
+        summary: report.summary,
+        findings: report.findings,
+    })
 }
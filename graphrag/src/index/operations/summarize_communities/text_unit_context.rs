@@ -0,0 +1,5 @@
+//! Text unit based context builders for community reports.
+
+pub mod context_builder;
+pub mod prep_text_units;
+pub mod sort_context;
@@ -0,0 +1,180 @@
+//! Context builders for text units.
+
+use std::collections::BTreeMap;
+
+use polars::prelude::{DataType, JoinArgs, JoinType, LazyFrame, col};
+
+use crate::data_model::schemas;
+use crate::index::operations::summarize_communities::level_context;
+use crate::index::operations::summarize_communities::text_unit_context::prep_text_units::prep_text_units;
+use crate::index::operations::summarize_communities::text_unit_context::sort_context::sort_context;
+use crate::index::operations::summarize_communities::typing::{
+    CommunityContext, CommunityHierarchy, CommunityReport, TextUnitDetails,
+};
+use crate::query::llm::text_utils::num_tokens;
+
+/**
+Prep context data for community report generation using text unit data.
+
+Community membership has columns [community, level, entity_ids, relationship_ids, text_unit_ids].
+*/
+pub fn build_local_context(
+    community_membership: LazyFrame,
+    text_units: LazyFrame,
+    nodes: LazyFrame,
+    max_context_tokens: usize, // = 16_000,
+) -> Vec<CommunityContext<TextUnitDetails>> {
+    // stack the text units of each community, with their degree within that community
+    let text_units = prep_text_units(text_units, nodes);
+    let context = community_membership
+        .select([
+            col(schemas::COMMUNITY_ID),
+            col(schemas::COMMUNITY_LEVEL),
+            col(schemas::TEXT_UNIT_IDS),
+        ])
+        .explode([col(schemas::TEXT_UNIT_IDS)])
+        .join(
+            text_units,
+            [col(schemas::TEXT_UNIT_IDS), col(schemas::COMMUNITY_ID)],
+            [col(schemas::ID), col(schemas::COMMUNITY_ID)],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .select([
+            col(schemas::COMMUNITY_ID).cast(DataType::Int64),
+            col(schemas::COMMUNITY_LEVEL).cast(DataType::Int64),
+            col(schemas::SHORT_ID).cast(DataType::Int64),
+            col(schemas::TEXT),
+            col(schemas::ENTITY_DEGREE),
+        ])
+        .collect()
+        .unwrap();
+
+    let column = |name: &str| context.column(name).unwrap();
+    let communities = column(schemas::COMMUNITY_ID).i64().unwrap();
+    let levels = column(schemas::COMMUNITY_LEVEL).i64().unwrap();
+    let ids = column(schemas::SHORT_ID).i64().unwrap();
+    let texts = column(schemas::TEXT).str().unwrap();
+    let degrees = column(schemas::ENTITY_DEGREE).i64().unwrap();
+
+    let mut grouped: BTreeMap<(i64, i64), Vec<TextUnitDetails>> = BTreeMap::new();
+    for row in 0..context.height() {
+        let (Some(community), Some(level)) = (communities.get(row), levels.get(row)) else {
+            continue;
+        };
+        grouped.entry((community, level)).or_default().push(TextUnitDetails {
+            human_readable_id: ids.get(row).unwrap_or_default(),
+            text: texts.get(row).unwrap_or_default().to_string(),
+            entity_degree: degrees.get(row).unwrap_or_default(),
+        });
+    }
+
+    grouped
+        .into_iter()
+        .map(|((community, level), all_context)| {
+            let context_string = sort_context(&all_context, &[], None);
+            let context_size = num_tokens(&context_string, None);
+            CommunityContext {
+                community,
+                level,
+                all_context,
+                context_string,
+                context_size,
+                context_exceed_limit: context_size > max_context_tokens,
+            }
+        })
+        .collect()
+}
+
+/// Prep context for each community in a given level, substituting sub-community reports for oversized local contexts.
+pub fn build_level_context(
+    reports: &[CommunityReport],
+    community_hierarchy: &[CommunityHierarchy],
+    local_contexts: &[CommunityContext<TextUnitDetails>],
+    level: i64,
+    max_context_tokens: usize,
+) -> Vec<CommunityContext<TextUnitDetails>> {
+    level_context::build_level_context(
+        reports,
+        community_hierarchy,
+        local_contexts,
+        level,
+        max_context_tokens,
+        sort_context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataFrame, IntoLazy, NamedFrom, Series};
+
+    use super::*;
+
+    fn ids(values: &[&str]) -> Series {
+        Series::new("".into(), values)
+    }
+
+    fn frames() -> (LazyFrame, LazyFrame, LazyFrame) {
+        let communities = DataFrame::new(vec![
+            Series::new(schemas::COMMUNITY_ID.into(), [0i64, 1]).into(),
+            Series::new(schemas::COMMUNITY_LEVEL.into(), [0i64, 1]).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), [ids(&["t1", "t2"]), ids(&["t2"])]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        let text_units = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["t1", "t2", "t3"]).into(),
+            Series::new(schemas::SHORT_ID.into(), [1i64, 2, 3]).into(),
+            Series::new(schemas::TEXT.into(), ["the river", "the bridge", "the town"]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        // exploded community nodes, the entities of each community with their degree and text units
+        let nodes = DataFrame::new(vec![
+            Series::new(schemas::COMMUNITY_ID.into(), [0i64, 0, 1]).into(),
+            Series::new(schemas::NODE_DEGREE.into(), [2i64, 3, 3]).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), [ids(&["t1"]), ids(&["t1", "t2"]), ids(&["t2"])]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        (communities, text_units, nodes)
+    }
+
+    #[test]
+    fn text_unit_degree_sums_the_community_entities() {
+        let (_, text_units, nodes) = frames();
+        let prepped = prep_text_units(text_units, nodes)
+            .sort([schemas::COMMUNITY_ID, schemas::ID], Default::default())
+            .collect()
+            .unwrap();
+        let degrees: Vec<Option<i64>> = prepped.column(schemas::ENTITY_DEGREE).unwrap().i64().unwrap().into_iter().collect();
+        let communities: Vec<Option<i64>> = prepped.column(schemas::COMMUNITY_ID).unwrap().i64().unwrap().into_iter().collect();
+        // t3 has no community entity, t1 is in community 0 twice and t2 once per community
+        assert_eq!(communities, [None, Some(0), Some(0), Some(1)]);
+        assert_eq!(degrees, [Some(0), Some(5), Some(3), Some(3)]);
+    }
+
+    #[test]
+    fn local_context_groups_text_units_by_community() {
+        let (communities, text_units, nodes) = frames();
+        let contexts = build_local_context(communities, text_units, nodes, 16_000);
+
+        assert_eq!(contexts.len(), 2);
+        assert_eq!((contexts[0].community, contexts[0].level), (0, 0));
+        assert_eq!(
+            contexts[0].all_context,
+            [
+                TextUnitDetails { human_readable_id: 1, text: "the river".into(), entity_degree: 5 },
+                TextUnitDetails { human_readable_id: 2, text: "the bridge".into(), entity_degree: 3 },
+            ]
+        );
+        assert_eq!(contexts[1].all_context.len(), 1);
+        assert!(contexts.iter().all(|context| !context.context_exceed_limit));
+    }
+
+    #[test]
+    fn local_context_over_the_limit_is_flagged() {
+        let (communities, text_units, nodes) = frames();
+        let contexts = build_local_context(communities, text_units, nodes, 1);
+        assert!(contexts.iter().all(|context| context.context_exceed_limit));
+    }
+}
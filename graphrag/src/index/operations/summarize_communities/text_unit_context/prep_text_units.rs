@@ -0,0 +1,37 @@
+//! Prepare text units for community reports.
+
+use polars::prelude::{DataType, JoinArgs, JoinType, LazyFrame, col, lit};
+
+use crate::data_model::schemas;
+
+/**
+Calculate text unit degree and concatenate text unit details.
+
+Returns a table with one row per (community, text unit), with the summed degree of
+the community entities that appear in the text unit.
+*/
+pub fn prep_text_units(text_units: LazyFrame, nodes: LazyFrame) -> LazyFrame {
+    let text_unit_degrees = nodes
+        .select([
+            col(schemas::COMMUNITY_ID),
+            col(schemas::NODE_DEGREE),
+            col(schemas::TEXT_UNIT_IDS).alias(schemas::ID),
+        ])
+        .explode([col(schemas::ID)])
+        .group_by([col(schemas::COMMUNITY_ID), col(schemas::ID)])
+        .agg([col(schemas::NODE_DEGREE).sum().alias(schemas::ENTITY_DEGREE)]);
+
+    text_units
+        .select([col(schemas::ID), col(schemas::SHORT_ID), col(schemas::TEXT)])
+        .join(
+            text_unit_degrees,
+            [col(schemas::ID)],
+            [col(schemas::ID)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column(
+            col(schemas::ENTITY_DEGREE)
+                .cast(DataType::Int64)
+                .fill_null(lit(0)),
+        )
+}
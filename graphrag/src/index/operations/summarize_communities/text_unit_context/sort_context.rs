@@ -0,0 +1,103 @@
+//! Sort local context by total degree of associated nodes in descending order.
+
+use crate::index::operations::summarize_communities::typing::{SubCommunityReport, TextUnitDetails};
+use crate::index::operations::summarize_communities::utils::{reports_to_csv, to_csv};
+use crate::query::llm::text_utils::num_tokens;
+
+/**
+Sort local context (a list of text units) by total degree of associated nodes in descending order.
+
+Text units are added from the highest degree down until the rendered context exceeds `max_context_tokens`.
+*/
+pub fn sort_context(
+    local_context: &[TextUnitDetails],
+    sub_community_reports: &[SubCommunityReport],
+    max_context_tokens: Option<usize>,
+) -> String {
+    // sort text units by degree (desc) and id (asc)
+    let mut sorted_text_units: Vec<&TextUnitDetails> = local_context.iter().collect();
+    sorted_text_units.sort_by_key(|text_unit| (-text_unit.entity_degree, text_unit.human_readable_id));
+
+    let Some(max_tokens) = max_context_tokens else {
+        return get_context_string(&sorted_text_units, sub_community_reports);
+    };
+
+    let mut context_string = String::new();
+    for count in 1..=sorted_text_units.len() {
+        let new_context_string = get_context_string(&sorted_text_units[..count], sub_community_reports);
+        if num_tokens(&new_context_string, None) > max_tokens {
+            break;
+        }
+        context_string = new_context_string;
+    }
+
+    if context_string.is_empty() {
+        return get_context_string(&sorted_text_units, sub_community_reports);
+    }
+    context_string
+}
+
+/// Concatenate structured data into a context string.
+fn get_context_string(text_units: &[&TextUnitDetails], sub_community_reports: &[SubCommunityReport]) -> String {
+    let mut contexts = Vec::new();
+
+    if !sub_community_reports.is_empty() {
+        contexts.push(format!("----REPORTS-----\n{}", reports_to_csv(sub_community_reports)));
+    }
+
+    let rows: Vec<Vec<String>> = text_units
+        .iter()
+        .map(|text_unit| vec![text_unit.human_readable_id.to_string(), text_unit.text.clone()])
+        .collect();
+    if !rows.is_empty() {
+        contexts.push(format!("-----SOURCES-----\n{}", to_csv(&["id", "text"], rows)));
+    }
+
+    contexts.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_unit(human_readable_id: i64, text: &str, entity_degree: i64) -> TextUnitDetails {
+        TextUnitDetails { human_readable_id, text: text.into(), entity_degree }
+    }
+
+    #[test]
+    fn text_units_are_sorted_by_degree_then_id() {
+        let local_context = [text_unit(2, "b", 1), text_unit(3, "c", 5), text_unit(1, "a", 1)];
+        assert_eq!(
+            sort_context(&local_context, &[], None),
+            "-----SOURCES-----\nid,text\n3,c\n1,a\n2,b\n"
+        );
+    }
+
+    #[test]
+    fn text_units_are_added_until_the_limit() {
+        let local_context = [
+            text_unit(1, &"river ".repeat(100), 3),
+            text_unit(2, &"bridge ".repeat(100), 2),
+            text_unit(3, &"town ".repeat(100), 1),
+        ];
+        let context = sort_context(&local_context, &[], Some(250));
+        assert!(context.contains("river"));
+        assert!(context.contains("bridge"));
+        assert!(!context.contains("town"));
+    }
+
+    #[test]
+    fn the_full_context_is_kept_when_nothing_fits() {
+        let local_context = [text_unit(1, &"river ".repeat(100), 3)];
+        assert_eq!(sort_context(&local_context, &[], Some(1)), sort_context(&local_context, &[], None));
+    }
+
+    #[test]
+    fn sub_community_reports_come_first() {
+        let reports = [SubCommunityReport { community: 4, full_content: "report".into() }];
+        assert_eq!(
+            sort_context(&[text_unit(1, "a", 1)], &reports, None),
+            "----REPORTS-----\ncommunity,full_content\n4,report\n\n\n-----SOURCES-----\nid,text\n1,a\n"
+        );
+    }
+}
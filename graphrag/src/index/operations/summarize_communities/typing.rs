@@ -1,56 +1,125 @@
 //! A module containing 'Finding' and 'CommunityReport' models.
 
-from typing_extensions import TypedDict
+use serde::{Deserialize, Serialize};
 
-from graphrag.cache.pipeline_cache import PipelineCache
-from graphrag.callbacks.workflow_callbacks import WorkflowCallbacks
+/// Finding class definition.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub summary: String,
+    pub explanation: String,
+}
 
-ExtractedEntity = dict[str, Any]
-StrategyConfig = dict[str, Any]
-RowContext = dict[str, Any]
-EntityTypes = list[str]
-Claim = dict[str, Any]
+/// Community report class definition.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommunityReport {
+    pub community: i64,
+    pub title: String,
+    pub summary: String,
+    pub full_content: String,
+    pub full_content_json: String,
+    pub rank: f64,
+    pub level: i64,
+    pub rating_explanation: String,
+    pub findings: Vec<Finding>,
+}
 
+/// The details of an entity, as rendered in the community context.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NodeDetails {
+    pub human_readable_id: i64,
+    pub title: String,
+    pub description: String,
+    pub degree: i64,
+}
 
-class Finding(TypedDict):
-    """Finding class definition."""
+/// The details of a relationship, as rendered in the community context.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EdgeDetails {
+    pub human_readable_id: i64,
+    pub source: String,
+    pub target: String,
+    pub description: String,
+    pub combined_degree: i64,
+}
 
-    summary: str
-    explanation: str
+/// The details of a claim, as rendered in the community context.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ClaimDetails {
+    pub human_readable_id: i64,
+    pub subject_id: String,
+    pub r#type: String,
+    pub status: String,
+    pub description: String,
+}
 
+/// An entity assigned to a community at a given level.
+#[derive(Debug, Clone)]
+pub struct CommunityNode {
+    pub community: i64,
+    pub level: i64,
+    pub details: NodeDetails,
+}
 
-class CommunityReport(TypedDict):
-    """Community report class definition."""
+/// All the details of an entity, including its relationships and claims.
+#[derive(Debug, Clone)]
+pub struct NodeContext {
+    pub title: String,
+    pub degree: i64,
+    pub node_details: NodeDetails,
+    pub edge_details: Vec<EdgeDetails>,
+    pub claim_details: Vec<ClaimDetails>,
+}
 
-    community: str | int
-    title: str
-    summary: str
-    full_content: str
-    full_content_json: str
-    rank: float
-    level: int
-    rating_explanation: str
-    findings: list[Finding]
+/// A text unit of a community, with the summed degree of the community entities it mentions.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TextUnitDetails {
+    pub human_readable_id: i64,
+    pub text: String,
+    pub entity_degree: i64,
+}
 
+/// The context of a community, ready to be sent to the model.
+#[derive(Debug, Clone)]
+pub struct CommunityContext<T = NodeContext> {
+    pub community: i64,
+    pub level: i64,
+    pub all_context: Vec<T>,
+    pub context_string: String,
+    pub context_size: usize,
+    pub context_exceed_limit: bool,
+}
 
-CommunityReportsStrategy = Callable[
-    [
-        str | int,
-        str,
-        int,
-        WorkflowCallbacks,
-        PipelineCache,
-        StrategyConfig,
-    ],
-    Awaitable[CommunityReport | None],
-]
+/// A sub-community report used in place of the sub-community local context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubCommunityReport {
+    pub community: i64,
+    pub full_content: String,
+}
 
+/// A parent/child edge in the community hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommunityHierarchy {
+    pub community: i64,
+    pub level: i64,
+    pub sub_community: i64,
+}
 
-class CreateCommunityReportsStrategyType(str, Enum):
-    """CreateCommunityReportsStrategyType class definition."""
+/// CreateCommunityReportsStrategyType class definition.
+pub enum CreateCommunityReportsStrategyType {
+    GraphIntelligence,
+}
 
-    graph_intelligence = "graph_intelligence"
+impl CreateCommunityReportsStrategyType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CreateCommunityReportsStrategyType::GraphIntelligence => "graph_intelligence",
+        }
+    }
+}
 
-    def __repr__(self):
-        """Get a string representation."""
-        return f'"{self.value}"'
+impl std::fmt::Debug for CreateCommunityReportsStrategyType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
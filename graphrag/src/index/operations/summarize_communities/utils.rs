@@ -0,0 +1,135 @@
+//! A module containing community report generation utilities.
+
+use std::collections::{BTreeSet, HashSet};
+
+use polars::prelude::{DataType, LazyFrame, col};
+
+use crate::data_model::schemas;
+use crate::index::operations::summarize_communities::typing::{
+    CommunityHierarchy, CommunityNode, SubCommunityReport,
+};
+
+/// Get the levels of the communities, from the lowest in the hierarchy up.
+pub fn get_levels(nodes: &[CommunityNode]) -> Vec<i64> {
+    let levels: BTreeSet<i64> = nodes
+        .iter()
+        .map(|node| node.level)
+        .filter(|level| *level != -1)
+        .collect();
+    levels.into_iter().rev().collect()
+}
+
+/// Get the parent/child edges of the community hierarchy.
+pub fn get_community_hierarchy(communities: LazyFrame) -> Vec<CommunityHierarchy> {
+    let hierarchy = communities
+        .select([
+            col(schemas::COMMUNITY_ID).cast(DataType::Int64),
+            col(schemas::COMMUNITY_LEVEL).cast(DataType::Int64),
+            col(schemas::COMMUNITY_CHILDREN).alias(schemas::SUB_COMMUNITY),
+        ])
+        .explode([col(schemas::SUB_COMMUNITY)])
+        .with_column(col(schemas::SUB_COMMUNITY).cast(DataType::Int64))
+        .drop_nulls(None)
+        .collect()
+        .unwrap();
+
+    let communities = hierarchy.column(schemas::COMMUNITY_ID).unwrap().i64().unwrap();
+    let levels = hierarchy.column(schemas::COMMUNITY_LEVEL).unwrap().i64().unwrap();
+    let sub_communities = hierarchy.column(schemas::SUB_COMMUNITY).unwrap().i64().unwrap();
+    communities
+        .into_iter()
+        .zip(levels)
+        .zip(sub_communities)
+        .filter_map(|((community, level), sub_community)| {
+            Some(CommunityHierarchy {
+                community: community?,
+                level: level?,
+                sub_community: sub_community?,
+            })
+        })
+        .collect()
+}
+
+/// Render sub-community reports as a csv table.
+pub fn reports_to_csv(reports: &[SubCommunityReport]) -> String {
+    let rows = reports
+        .iter()
+        .map(|report| vec![report.community.to_string(), report.full_content.clone()])
+        .collect();
+    to_csv(&["community", "full_content"], rows)
+}
+
+/// Render rows as a comma-separated table, dropping duplicate rows and quoting fields where needed.
+pub fn to_csv(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let escape = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut lines = vec![headers.join(",")];
+    for row in rows {
+        if seen.insert(row.clone()) {
+            lines.push(row.iter().map(|field| escape(field)).collect::<Vec<_>>().join(","));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::operations::summarize_communities::typing::NodeDetails;
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_has_a_header_and_one_line_per_row() {
+        let csv = to_csv(&["id", "title"], vec![row(&["1", "RIVER"]), row(&["2", "TOWN"])]);
+        assert_eq!(csv, "id,title\n1,RIVER\n2,TOWN\n");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_where_needed() {
+        let csv = to_csv(
+            &["id", "description"],
+            vec![row(&["1", "a, b"]), row(&["2", "say \"hi\""]), row(&["3", "two\nlines"])],
+        );
+        assert_eq!(
+            csv,
+            "id,description\n1,\"a, b\"\n2,\"say \"\"hi\"\"\"\n3,\"two\nlines\"\n"
+        );
+    }
+
+    #[test]
+    fn csv_drops_duplicate_rows() {
+        let csv = to_csv(&["id"], vec![row(&["1"]), row(&["2"]), row(&["1"])]);
+        assert_eq!(csv, "id\n1\n2\n");
+    }
+
+    #[test]
+    fn reports_render_as_community_and_content() {
+        let reports = [SubCommunityReport { community: 3, full_content: "# Report".into() }];
+        assert_eq!(reports_to_csv(&reports), "community,full_content\n3,# Report\n");
+    }
+
+    #[test]
+    fn levels_go_bottom_up_without_unassigned_nodes() {
+        let node = |level: i64| CommunityNode {
+            community: 0,
+            level,
+            details: NodeDetails {
+                human_readable_id: 0,
+                title: String::new(),
+                description: String::new(),
+                degree: 0,
+            },
+        };
+        assert_eq!(get_levels(&[node(0), node(2), node(-1), node(1), node(2)]), [2, 1, 0]);
+    }
+}
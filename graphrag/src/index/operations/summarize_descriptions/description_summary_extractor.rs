@@ -0,0 +1,274 @@
+//! A module containing 'SummarizeExtractor' model.
+
+use std::collections::HashMap;
+
+use tokio::sync::Mutex;
+
+use crate::cache::pipeline_cache::PipelineCache;
+use crate::index::operations::summarize_descriptions::typing::SummarizedDescriptionResult;
+use crate::index::text_splitting::check_token_limit::check_token_limit;
+use crate::index::utils::hashing::gen_sha512_hash;
+use crate::language_model::protocol::base::ChatModel;
+use crate::prompts::index::summarize_descriptions::SUMMARIZE_PROMPT;
+
+/// A function to handle summarization errors, called with the error message and details.
+pub type ErrorHandlerFn<'a> = Box<dyn Fn(String, HashMap<String, String>) + 'a>;
+
+/**
+Unipartite graph extractor class definition.
+
+Descriptions that fit in `max_input_tokens` are summarized in a single call.
+Longer lists are summarized map-reduce style: descriptions are batched by token budget,
+each batch is summarized, and the batch summaries are summarized again until one remains.
+Every batch summary is cached on its own, so a re-run only re-summarizes the batches that changed.
+Batches end at descriptions chosen by their content, so adding a description only changes its own batch.
+*/
+pub struct SummarizeExtractor<'a, M: ChatModel, C: PipelineCache<String>> {
+    model: M,
+    cache: Mutex<&'a mut C>,
+    summarization_prompt: String,
+    max_summary_length: usize,
+    max_input_tokens: usize,
+    on_error: ErrorHandlerFn<'a>,
+}
+
+impl<'a, M: ChatModel, C: PipelineCache<String>> SummarizeExtractor<'a, M, C> {
+    /// Init method definition.
+    pub fn new(
+        model: M,
+        cache: &'a mut C,
+        max_summary_length: usize,
+        max_input_tokens: usize,
+        summarization_prompt: Option<String>,
+        on_error: Option<ErrorHandlerFn<'a>>,
+    ) -> Self {
+        SummarizeExtractor {
+            model,
+            cache: Mutex::new(cache),
+            summarization_prompt: summarization_prompt.unwrap_or_else(|| SUMMARIZE_PROMPT.into()),
+            max_summary_length,
+            max_input_tokens,
+            on_error: on_error.unwrap_or_else(|| Box::new(|_, _| {})),
+        }
+    }
+
+    /**
+    Summarize the descriptions of an entity or a (source, target) relationship.
+
+    If the model fails, the error is reported and the descriptions are kept unsummarized, one per line.
+    */
+    pub async fn summarize(
+        &self,
+        id: Vec<String>,
+        descriptions: Vec<String>,
+    ) -> SummarizedDescriptionResult {
+        let description = match descriptions.len() {
+            0 => String::new(),
+            1 => descriptions[0].clone(),
+            _ => match self.summarize_descriptions(&id, descriptions.clone()).await {
+                Ok(description) => description,
+                Err(error) => {
+                    (self.on_error)(error, HashMap::from([("id".into(), id.join(", "))]));
+                    descriptions.join("\n")
+                }
+            },
+        };
+        SummarizedDescriptionResult { id, description }
+    }
+
+    /// Reduce the descriptions level by level until a single summary remains.
+    async fn summarize_descriptions(&self, id: &[String], descriptions: Vec<String>) -> Result<String, String> {
+        let mut descriptions = descriptions;
+        descriptions.sort();
+
+        loop {
+            // a list that fits in the budget is summarized in a single call
+            if check_token_limit(descriptions.clone(), self.max_input_tokens) == 1 {
+                return self.summarize_batch(id, descriptions).await;
+            }
+
+            let mut batches = batch_by_token_limit(&descriptions, |batch| {
+                check_token_limit(batch.to_vec(), self.max_input_tokens) == 1
+            });
+            // every description fills the budget on its own; pair them up so the reduction still converges
+            if batches.len() == descriptions.len() && batches.len() > 1 {
+                batches = descriptions.chunks(2).map(|chunk| chunk.to_vec()).collect();
+            }
+
+            let mut summaries = Vec::with_capacity(batches.len());
+            for batch in batches {
+                summaries.push(self.summarize_batch(id, batch).await?);
+            }
+
+            if summaries.len() == 1 {
+                return Ok(summaries.remove(0));
+            }
+            descriptions = summaries;
+        }
+    }
+
+    /// Summarize a single batch, reusing the cached summary if the batch is unchanged.
+    async fn summarize_batch(&self, id: &[String], descriptions: Vec<String>) -> Result<String, String> {
+        if descriptions.len() == 1 {
+            return Ok(descriptions[0].clone());
+        }
+
+        let entity_name = if id.len() == 1 {
+            id[0].clone()
+        } else {
+            serde_json::to_string(id).unwrap()
+        };
+        let prompt = self
+            .summarization_prompt
+            .replace("{entity_name}", &entity_name)
+            .replace("{description_list}", &serde_json::to_string(&descriptions).unwrap())
+            .replace("{max_length}", &self.max_summary_length.to_string());
+
+        let cache_key = format!(
+            "summarize_descriptions-{}",
+            gen_sha512_hash(HashMap::from([("prompt".into(), prompt.clone())]), ["prompt".into()].into_iter())
+        );
+        {
+            let cache = self.cache.lock().await;
+            if cache.has(&cache_key).await {
+                return Ok(cache.get(&cache_key).await);
+            }
+        }
+
+        let response = self.model.achat(&prompt, None).await?;
+        let summary = response.output().content().trim().to_string();
+
+        self.cache
+            .lock()
+            .await
+            .set(
+                &cache_key,
+                summary.clone(),
+                Some(HashMap::from([
+                    ("id".into(), entity_name),
+                    ("batch_size".into(), descriptions.len().to_string()),
+                ])),
+            )
+            .await;
+        Ok(summary)
+    }
+}
+
+/**
+Split the descriptions into consecutive batches that each fit in the input token budget.
+
+A batch ends after every description whose hash is a boundary, or earlier when the next description
+would not fit. Boundaries depend only on the descriptions themselves, so inserting or removing a
+description only moves the edges of its own batch and the other batches keep their cached summaries.
+
+Args:
+    - descriptions: The sorted descriptions.
+    - fits: Whether a batch fits in the input token budget.
+*/
+fn batch_by_token_limit(descriptions: &[String], fits: impl Fn(&[String]) -> bool) -> Vec<Vec<String>> {
+    let mut batches: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+
+    for description in descriptions {
+        current.push(description.clone());
+        if current.len() > 1 && !fits(&current) {
+            let description = current.pop().unwrap();
+            batches.push(std::mem::take(&mut current));
+            current.push(description);
+        }
+        if is_batch_boundary(description) {
+            batches.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// The average number of descriptions per batch when the token budget allows it.
+const BATCH_BOUNDARY_INTERVAL: u64 = 8;
+
+/// Whether a batch ends after the description, true for one description in `BATCH_BOUNDARY_INTERVAL` on average.
+fn is_batch_boundary(description: &str) -> bool {
+    let hash = gen_sha512_hash(
+        HashMap::from([("description".into(), description.to_string())]),
+        ["description".into()].into_iter(),
+    );
+    u64::from_str_radix(&hash[hash.len() - 8..], 16).is_ok_and(|value| value % BATCH_BOUNDARY_INTERVAL == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptions(count: usize) -> Vec<String> {
+        let mut descriptions: Vec<String> = (0..count).map(|i| format!("description {i}")).collect();
+        descriptions.sort();
+        descriptions
+    }
+
+    /// A budget of `limit` characters per batch.
+    fn fits_in(limit: usize) -> impl Fn(&[String]) -> bool {
+        move |batch: &[String]| batch.iter().map(String::len).sum::<usize>() <= limit
+    }
+
+    #[test]
+    fn batches_keep_every_description_in_order() {
+        let descriptions = descriptions(100);
+        let batches = batch_by_token_limit(&descriptions, fits_in(200));
+        assert_eq!(batches.concat(), descriptions);
+        assert!(batches.iter().all(|batch| !batch.is_empty()));
+    }
+
+    #[test]
+    fn batches_fit_in_the_budget() {
+        let descriptions = descriptions(100);
+        let batches = batch_by_token_limit(&descriptions, fits_in(60));
+        assert!(batches.len() > 1);
+        assert!(batches.iter().all(|batch| batch.len() == 1 || fits_in(60)(batch)));
+    }
+
+    #[test]
+    fn descriptions_over_the_budget_get_their_own_batch() {
+        let descriptions = descriptions(10);
+        let batches = batch_by_token_limit(&descriptions, fits_in(0));
+        assert_eq!(batches.len(), descriptions.len());
+    }
+
+    #[test]
+    fn batches_end_at_boundaries_without_a_budget() {
+        let descriptions = descriptions(200);
+        let batches = batch_by_token_limit(&descriptions, fits_in(usize::MAX));
+        for batch in &batches[..batches.len() - 1] {
+            assert!(is_batch_boundary(batch.last().unwrap()));
+            assert!(batch[..batch.len() - 1].iter().all(|description| !is_batch_boundary(description)));
+        }
+    }
+
+    #[test]
+    fn inserting_a_description_only_changes_its_own_batch() {
+        let before = descriptions(200);
+        let inserted = (0..)
+            .map(|i| format!("description 5{i} new"))
+            .find(|description| !is_batch_boundary(description))
+            .unwrap();
+        let mut after = before.clone();
+        after.push(inserted.clone());
+        after.sort();
+
+        let before = batch_by_token_limit(&before, fits_in(usize::MAX));
+        let after = batch_by_token_limit(&after, fits_in(usize::MAX));
+        assert_eq!(before.len(), after.len());
+        let changed: Vec<_> = before.iter().zip(&after).filter(|(a, b)| a != b).collect();
+        assert_eq!(changed.len(), 1);
+        assert!(changed[0].1.contains(&inserted));
+    }
+
+    #[test]
+    fn boundaries_occur_once_per_interval_on_average() {
+        let boundaries = descriptions(8000).iter().filter(|description| is_batch_boundary(description)).count();
+        let expected = 8000 / BATCH_BOUNDARY_INTERVAL as usize;
+        assert!(boundaries.abs_diff(expected) < expected / 5, "{boundaries} boundaries");
+    }
+}
@@ -1,164 +1,135 @@
-# Copyright (c) 2024 Microsoft Corporation.
-# Licensed under the MIT License
-
-"""A module containing the summarize_descriptions verb."""
-
-import asyncio
-import logging
-from typing import Any
-
-import pandas as pd
-
-from graphrag.cache.pipeline_cache import PipelineCache
-from graphrag.callbacks.workflow_callbacks import WorkflowCallbacks
-from graphrag.index.operations.summarize_descriptions.typing import (
-    SummarizationStrategy,
-    SummarizeStrategyType,
-)
-from graphrag.logger.progress import ProgressTicker, progress_ticker
-
-log = logging.getLogger(__name__)
-
-
-async def summarize_descriptions(
-    entities_df: pd.DataFrame,
-    relationships_df: pd.DataFrame,
-    callbacks: WorkflowCallbacks,
-    cache: PipelineCache,
-    strategy: dict[str, Any] | None = None,
-    num_threads: int = 4,
-) -> tuple[pd.DataFrame, pd.DataFrame]:
-    """
-    Summarize entity and relationship descriptions from an entity graph.
-
-    ## Usage
-
-    To turn this feature ON please set the environment variable `GRAPHRAG_SUMMARIZE_DESCRIPTIONS_ENABLED=True`.
-
-    ### yaml
-
-    ```yaml
-    args:
-        strategy: <strategy_config>, see strategies section below
-    ```
-
-    ## Strategies
-
-    The summarize descriptions verb uses a strategy to summarize descriptions for entities. The strategy is a json object which defines the strategy to use. The following strategies are available:
-
-    ### graph_intelligence
-
-    This strategy uses the [graph_intelligence] library to summarize descriptions for entities. The strategy config is as follows:
-
-    ```yml
-    strategy:
-        type: graph_intelligence
-        summarize_prompt: # Optional, the prompt to use for extraction
-
-
-        llm: # The configuration for the LLM
-            type: openai # the type of llm to use, available options are: openai, azure, openai_chat, azure_openai_chat.  The last two being chat based LLMs.
-            api_key: !ENV ${GRAPHRAG_OPENAI_API_KEY} # The api key to use for openai
-            model: !ENV ${GRAPHRAG_OPENAI_MODEL:gpt-4-turbo-preview} # The model to use for openai
-            max_tokens: !ENV ${GRAPHRAG_MAX_TOKENS:6000} # The max tokens to use for openai
-            organization: !ENV ${GRAPHRAG_OPENAI_ORGANIZATION} # The organization to use for openai
-
-            # if using azure flavor
-            api_base: !ENV ${GRAPHRAG_OPENAI_API_BASE} # The api base to use for azure
-            api_version: !ENV ${GRAPHRAG_OPENAI_API_VERSION} # The api version to use for azure
-            proxy: !ENV ${GRAPHRAG_OPENAI_PROXY} # The proxy to use for azure
-    ```
-    """
-    log.debug("summarize_descriptions strategy=%s", strategy)
-    strategy = strategy or {}
-    strategy_exec = load_strategy(
-        strategy.get("type", SummarizeStrategyType.graph_intelligence)
-    )
-    strategy_config = {**strategy}
-
-    # if max_retries is not set, inject a dynamically assigned value based on the maximum number of expected LLM calls to be made
-    if strategy_config.get("llm") and strategy_config["llm"]["max_retries"] == -1:
-        strategy_config["llm"]["max_retries"] = len(entities_df) + len(relationships_df)
-
-    async def get_summarized(
-        nodes: pd.DataFrame, edges: pd.DataFrame, semaphore: asyncio.Semaphore
-    ):
-        ticker_length = len(nodes) + len(edges)
-
-        ticker = progress_ticker(callbacks.progress, ticker_length)
-
-        node_futures = [
-            do_summarize_descriptions(
-                str(row.title),  # type: ignore
-                sorted(set(row.description)),  # type: ignore
-                ticker,
-                semaphore,
-            )
-            for row in nodes.itertuples(index=False)
-        ]
-
-        node_results = await asyncio.gather(*node_futures)
-
-        node_descriptions = [
-            {
-                "title": result.id,
-                "description": result.description,
-            }
-            for result in node_results
-        ]
-
-        edge_futures = [
-            do_summarize_descriptions(
-                (str(row.source), str(row.target)),  # type: ignore
-                sorted(set(row.description)),  # type: ignore
-                ticker,
-                semaphore,
-            )
-            for row in edges.itertuples(index=False)
-        ]
-
-        edge_results = await asyncio.gather(*edge_futures)
-
-        edge_descriptions = [
-            {
-                "source": result.id[0],
-                "target": result.id[1],
-                "description": result.description,
-            }
-            for result in edge_results
-        ]
-
-        entity_descriptions = pd.DataFrame(node_descriptions)
-        relationship_descriptions = pd.DataFrame(edge_descriptions)
-        return entity_descriptions, relationship_descriptions
-
-    async def do_summarize_descriptions(
-        id: str | tuple[str, str],
-        descriptions: list[str],
-        ticker: ProgressTicker,
-        semaphore: asyncio.Semaphore,
-    ):
-        async with semaphore:
-            results = await strategy_exec(
-                id, descriptions, callbacks, cache, strategy_config
-            )
-            ticker(1)
-        return results
-
-    semaphore = asyncio.Semaphore(num_threads)
-
-    return await get_summarized(entities_df, relationships_df, semaphore)
-
-
-def load_strategy(strategy_type: SummarizeStrategyType) -> SummarizationStrategy:
-    """Load strategy method definition."""
-    match strategy_type:
-        case SummarizeStrategyType.graph_intelligence:
-            from graphrag.index.operations.summarize_descriptions.graph_intelligence_strategy import (
-                run_graph_intelligence,
-            )
-
-            return run_graph_intelligence
-        case _:
-            msg = f"Unknown strategy: {strategy_type}"
-            raise ValueError(msg)
+//! A module containing the summarize_descriptions verb.
+
+use std::collections::BTreeSet;
+
+use futures::stream::{self, StreamExt};
+use log::debug;
+use polars::prelude::{DataFrame, IntoLazy, LazyFrame, NamedFrom, Series, col};
+
+use crate::cache::pipeline_cache::PipelineCache;
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::index::operations::summarize_descriptions::description_summary_extractor::SummarizeExtractor;
+use crate::index::operations::summarize_descriptions::typing::{
+    SummarizeStrategy,
+    SummarizedDescriptionResult,
+};
+use crate::language_model::protocol::base::ChatModel;
+use crate::logger::progress::Progress;
+use crate::utils::dataframe::get_string;
+
+/**
+Summarize entity and relationship descriptions from an entity graph.
+
+## Usage
+
+### yaml
+
+```yaml
+summarize_descriptions:
+    prompt: # Optional, the prompt to use for summarization
+    max_length: 500 # The maximum length of each summary, in words
+    max_input_tokens: 4000 # The token budget of each summarization call; longer description lists are summarized hierarchically
+    strategy: # Optional, overrides type, summarize_prompt, max_summary_length and max_input_tokens
+```
+*/
+pub async fn summarize_descriptions<M: ChatModel, C: PipelineCache<String>>(
+    entities_df: LazyFrame,
+    relationships_df: LazyFrame,
+    callbacks: &impl WorkflowCallbacks,
+    cache: &mut C,
+    model: M,
+    strategy: &SummarizeStrategy,
+    num_threads: usize, // = 4,
+) -> (LazyFrame, LazyFrame) {
+    debug!("summarize_descriptions strategy={strategy:?}");
+    let extractor = SummarizeExtractor::new(
+        model,
+        cache,
+        strategy.max_summary_length,
+        strategy.max_input_tokens,
+        strategy.summarize_prompt.clone(),
+        Some(Box::new(|message, details| {
+            callbacks.error(format!("Description Summarization Error: {message}"), None, None, Some(details))
+        })),
+    );
+
+    let nodes = entities_df
+        .select([col("title"), col("description")])
+        .collect()
+        .unwrap();
+    let edges = relationships_df
+        .select([col("source"), col("target"), col("description")])
+        .collect()
+        .unwrap();
+    let total_items = nodes.height() + edges.height();
+
+    let node_items = (0..nodes.height()).map(|row| {
+        (vec![get_string(&nodes, "title", row)], get_descriptions(&nodes, row))
+    });
+    let edge_items = (0..edges.height()).map(|row| {
+        (
+            vec![get_string(&edges, "source", row), get_string(&edges, "target", row)],
+            get_descriptions(&edges, row),
+        )
+    });
+
+    // up to num_threads items are summarized at once, results keep the order of the items
+    let mut summaries = stream::iter(node_items.chain(edge_items))
+        .map(|(id, descriptions)| extractor.summarize(id, descriptions))
+        .buffered(num_threads.max(1));
+    let mut results = Vec::with_capacity(total_items);
+    while let Some(result) = summaries.next().await {
+        results.push(result);
+        report_progress(callbacks, results.len(), total_items);
+    }
+    let edge_results = results.split_off(nodes.height());
+    let id_part = |results: &[SummarizedDescriptionResult], part: usize| -> Vec<String> {
+        results.iter().map(|result| result.id[part].clone()).collect()
+    };
+    let descriptions = |results: &[SummarizedDescriptionResult]| -> Vec<String> {
+        results.iter().map(|result| result.description.clone()).collect()
+    };
+
+    let entity_descriptions = DataFrame::new(vec![
+        Series::new("title".into(), id_part(&results, 0)).into(),
+        Series::new("description".into(), descriptions(&results)).into(),
+    ])
+    .unwrap()
+    .lazy();
+    let relationship_descriptions = DataFrame::new(vec![
+        Series::new("source".into(), id_part(&edge_results, 0)).into(),
+        Series::new("target".into(), id_part(&edge_results, 1)).into(),
+        Series::new("description".into(), descriptions(&edge_results)).into(),
+    ])
+    .unwrap()
+    .lazy();
+
+    (entity_descriptions, relationship_descriptions)
+}
+
+
+/// Get the sorted, de-duplicated description list of a row.
+fn get_descriptions(df: &DataFrame, row: usize) -> Vec<String> {
+    let descriptions = df.column("description").unwrap().list().unwrap().get_as_series(row);
+    descriptions
+        .map(|series| {
+            series
+                .str()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(|description| description.to_string())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn report_progress(callbacks: &impl WorkflowCallbacks, completed_items: usize, total_items: usize) {
+    callbacks.progress(Progress {
+        total_items: Some(total_items),
+        completed_items: Some(completed_items),
+        ..Default::default()
+    });
+}
@@ -0,0 +1,43 @@
+//! A module containing 'SummarizedDescriptionResult' model.
+
+/// Entity summarization result class definition.
+#[derive(Debug, Clone)]
+pub struct SummarizedDescriptionResult {
+    /// The entity title, or the (source, target) titles of a relationship.
+    pub id: Vec<String>,
+    pub description: String,
+}
+
+/// SummarizeStrategyType class definition.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SummarizeStrategyType {
+    /// Summarize with a chat model, batching long description lists hierarchically.
+    GraphIntelligence,
+}
+
+impl SummarizeStrategyType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SummarizeStrategyType::GraphIntelligence => "graph_intelligence",
+        }
+    }
+}
+
+impl std::fmt::Debug for SummarizeStrategyType {
+    /// Get a string representation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The resolved description summarization strategy.
+#[derive(Debug, Clone)]
+pub struct SummarizeStrategy {
+    pub r#type: SummarizeStrategyType,
+    /// The summarization prompt, None using the default prompt.
+    pub summarize_prompt: Option<String>,
+    /// The maximum length of each summary, in words.
+    pub max_summary_length: usize,
+    /// The token budget of each summarization call.
+    pub max_input_tokens: usize,
+}
@@ -16,6 +16,7 @@ use crate::index::run::utils::create_run_context;
 use crate::index::typing::context::PipelineRunContext;
 use crate::index::typing::pipeline::Pipeline;
 use crate::index::typing::pipeline_run_result::PipelineRunResult;
+use crate::index::update::documents::remove_deleted_documents;
 use crate::index::update::incremental_index::{
     get_delta_docs,
     update_dataframe_outputs,
@@ -47,8 +48,8 @@ pub async fn run_pipeline(
         delta_dataset = await get_delta_docs(dataset, storage)
 
         // warn on empty delta dataset
-        if delta_dataset.new_inputs.empty:
-            warning_msg = "Incremental indexing found no new documents, exiting."
+        if delta_dataset.is_empty():
+            warning_msg = "Incremental indexing found no new, modified or deleted documents, exiting."
             logger.warning(warning_msg)
         else:
             update_storage = create_storage_from_config(config.update_index_output)
@@ -60,29 +61,39 @@ pub async fn run_pipeline(
             previous_storage = timestamped_storage.child("previous")
             await _copy_previous_output(storage, previous_storage)
 
-            // Run the pipeline on the new documents
-            async for table in _run_pipeline(
-                pipeline=pipeline,
-                config=config,
-                dataset=delta_dataset.new_inputs,
-                cache=cache,
-                storage=delta_storage,
-                callbacks=callbacks,
-                logger=logger,
-            ):
-                yield table
+            // drop deleted and superseded documents, and everything derived only from them, from the previous index
+            pruned_index = await remove_deleted_documents(
+                previous_storage, delta_dataset.deleted_inputs
+            )
 
-            logger.success("Finished running workflows on new documents.")
+            // Run the pipeline on the new and modified documents
+            has_delta = delta_dataset.new_inputs.collect().unwrap().height() > 0
+            if has_delta:
+                async for table in _run_pipeline(
+                    pipeline=pipeline,
+                    config=config,
+                    dataset=delta_dataset.new_inputs,
+                    cache=cache,
+                    storage=delta_storage,
+                    callbacks=callbacks,
+                    logger=logger,
+                    // embeddings are refreshed once the delta is merged, so unchanged rows are not embedded again
+                    skipped_workflows=&["generate_text_embeddings"],
+                ):
+                    yield table
+
+                logger.success("Finished running workflows on new documents.")
 
             await update_dataframe_outputs(
                 previous_storage=previous_storage,
-                delta_storage=delta_storage,
+                delta_storage=Some(delta_storage) if has_delta else None,
                 output_storage=storage,
                 config=config,
                 cache=cache,
                 callbacks=NoopWorkflowCallbacks(),
                 progress_logger=logger,
-            )
+                pruned_index=pruned_index,
+            )?
 
     } else {
         logger.info("Running standard indexing.");
@@ -95,6 +106,7 @@ pub async fn run_pipeline(
             storage=storage,
             callbacks=callbacks,
             logger=logger,
+            skipped_workflows=&[],
         ):
             yield table
     }
@@ -108,6 +120,7 @@ async fn _run_pipeline(
     storage: PipelineStorage,
     callbacks: WorkflowCallbacks,
     logger: ProgressLogger,
+    skipped_workflows: &[&str],
 ) -> AsyncIterable[PipelineRunResult] {
     start_time = time.time()
 
@@ -128,6 +141,8 @@ async fn _run_pipeline(
         await write_table_to_storage(dataset, "documents", context.storage)
 
         for name, workflow_function in pipeline.run():
+            if skipped_workflows.contains(&name):
+                continue
             last_workflow = name
             progress = logger.child(name, transient=False)
             callbacks.workflow_start(name, None)
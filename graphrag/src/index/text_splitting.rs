@@ -1,4 +1,5 @@
 //! The Indexing Engine Text Splitting package root.
 
 pub mod check_token_limit;
+pub mod segments;
 pub mod text_splitting;
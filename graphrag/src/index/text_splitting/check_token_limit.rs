@@ -15,8 +15,8 @@ pub fn check_token_limit(text: Vec<String>, max_token: usize) -> usize {
         None,
     );
     let docs = text_splitter.split_text(text);
-    if !docs.is_empty() {
+    if docs.len() > 1 {
         return 0;
     }
-    return 1;
+    1
 }
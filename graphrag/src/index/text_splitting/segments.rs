@@ -0,0 +1,152 @@
+//! Splitting texts into the segments of multi-vector embeddings.
+
+use crate::config::enums::MultiVectorSplitType;
+use crate::config::models::multi_vector_config::MultiVectorConfig;
+
+/**
+Split a text into the segments embedded with a vector each.
+
+Sentences longer than `window_size` words are split into windows. When a text has more than
+`max_vectors` segments, consecutive segments are merged so every vector covers a similar share of
+the text.
+*/
+pub fn split_segments(text: &str, config: &MultiVectorConfig) -> Vec<String> {
+    let segments = match config.split {
+        MultiVectorSplitType::Sentence => split_sentences(text)
+            .iter()
+            .flat_map(|sentence| split_windows(sentence, config.window_size, config.window_overlap))
+            .collect(),
+        MultiVectorSplitType::Window => split_windows(text, config.window_size, config.window_overlap),
+    };
+    merge_segments(segments, config.max_vectors)
+}
+
+/// Split a text into sentences, ending at `.`, `!` or `?` followed by whitespace, or at a line break.
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut push = |sentence: &str| {
+        let sentence = sentence.trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence.to_string());
+        }
+    };
+
+    let mut start = 0;
+    let mut characters = text.char_indices().peekable();
+    while let Some((position, character)) = characters.next() {
+        let end = match character {
+            '\n' => position,
+            '.' | '!' | '?' if characters.peek().is_none_or(|(_, next)| next.is_whitespace()) => {
+                position + character.len_utf8()
+            }
+            _ => continue,
+        };
+        push(&text[start..end]);
+        start = end;
+    }
+    push(&text[start..]);
+    sentences
+}
+
+/// Split a text into windows of `size` words, consecutive windows sharing `overlap` words.
+pub fn split_windows(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let size = size.max(1);
+    let step = size - overlap.min(size - 1);
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let end = (start + size).min(words.len());
+        windows.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+        start += step;
+    }
+    windows
+}
+
+/// Merge consecutive segments into at most `max_segments` segments, 0 keeping every segment.
+fn merge_segments(segments: Vec<String>, max_segments: usize) -> Vec<String> {
+    if max_segments == 0 || segments.len() <= max_segments {
+        return segments;
+    }
+    let count = segments.len();
+    let mut merged = vec![String::new(); max_segments];
+    for (position, segment) in segments.into_iter().enumerate() {
+        let group = &mut merged[position * max_segments / count];
+        if !group.is_empty() {
+            group.push(' ');
+        }
+        group.push_str(&segment);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(split: MultiVectorSplitType, window_size: usize, window_overlap: usize, max_vectors: usize) -> MultiVectorConfig {
+        MultiVectorConfig {
+            split,
+            window_size,
+            window_overlap,
+            max_vectors,
+            ..Default::default()
+        }
+    }
+
+    fn words(count: usize) -> String {
+        (0..count).map(|word| format!("w{word}")).collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn sentences_end_at_punctuation_and_line_breaks() {
+        assert_eq!(
+            split_sentences("First one. Second, v1.2 included!\nThird?  Last"),
+            vec!["First one.", "Second, v1.2 included!", "Third?", "Last"],
+        );
+        assert!(split_sentences(" \n ").is_empty());
+    }
+
+    #[test]
+    fn windows_share_their_overlap() {
+        assert_eq!(split_windows(&words(7), 3, 1), vec!["w0 w1 w2", "w2 w3 w4", "w4 w5 w6"]);
+        // the last window ends with the text instead of repeating its end
+        assert_eq!(split_windows(&words(4), 3, 1), vec!["w0 w1 w2", "w2 w3"]);
+        // an overlap as large as the window still moves forward a word at a time
+        assert_eq!(split_windows(&words(3), 2, 5), vec!["w0 w1", "w1 w2"]);
+        assert!(split_windows("", 3, 1).is_empty());
+    }
+
+    #[test]
+    fn long_sentences_are_split_into_windows() {
+        let text = format!("Short sentence. {}.", words(5));
+        assert_eq!(
+            split_segments(&text, &config(MultiVectorSplitType::Sentence, 3, 0, 0)),
+            vec!["Short sentence.", "w0 w1 w2", "w3 w4."],
+        );
+    }
+
+    #[test]
+    fn segments_are_merged_evenly_into_the_maximum() {
+        let text = "One. Two. Three. Four. Five. Six. Seven.";
+        assert_eq!(
+            split_segments(text, &config(MultiVectorSplitType::Sentence, 64, 0, 3)),
+            vec!["One. Two. Three.", "Four. Five.", "Six. Seven."],
+        );
+        // merged segments keep the order of the text and every one of its words
+        let merged = split_segments(&words(100), &config(MultiVectorSplitType::Window, 4, 0, 6));
+        assert_eq!(merged.len(), 6);
+        assert_eq!(merged.join(" "), words(100));
+    }
+
+    #[test]
+    fn texts_within_the_maximum_keep_every_segment() {
+        let text = "One. Two. Three.";
+        assert_eq!(split_segments(text, &config(MultiVectorSplitType::Sentence, 64, 0, 3)).len(), 3);
+        assert_eq!(split_segments(text, &config(MultiVectorSplitType::Sentence, 64, 0, 0)).len(), 3);
+    }
+}
@@ -1,6 +1,6 @@
 //! Pipeline stats types.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Pipeline running stats.
 #[derive(Default)]
@@ -16,4 +16,32 @@ pub struct PipelineRunStats {
 
     /// A dictionary of workflows.
     workflows: HashMap<String, HashMap<String, f64>>,
+
+    /// A summary of the graph quality report, if it was generated.
+    pub graph_stats: Option<GraphStatsSummary>,
+}
+
+/// A summary of the graph quality report.
+#[derive(Debug, Clone, Default)]
+pub struct GraphStatsSummary {
+    /// The number of entities.
+    pub node_count: usize,
+
+    /// The number of relationships.
+    pub edge_count: usize,
+
+    /// The number of connected components.
+    pub connected_components: usize,
+
+    /// The fraction of entities in the largest connected component.
+    pub lcc_fraction: f64,
+
+    /// The modularity of the communities of each level.
+    pub modularity_by_level: BTreeMap<i64, f64>,
+
+    /// The number of entities without any relationship.
+    pub orphan_entities: usize,
+
+    /// The number of relationships without a description.
+    pub relationships_without_description: usize,
 }
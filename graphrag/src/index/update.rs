@@ -1,6 +1,7 @@
 //! Incremental Indexing main module definition.
 
 pub mod communities;
+pub mod documents;
 pub mod entities;
 pub mod incremental_index;
 pub mod relationships;
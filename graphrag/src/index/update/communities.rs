@@ -0,0 +1,297 @@
+//! Dataframe operations and utils for Incremental Indexing.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use polars::prelude::{
+    DataFrame, DataType, IntoLazy, LazyFrame, ListChunked, NamedFrom, Series, col,
+};
+
+use crate::data_model::schemas;
+use crate::index::operations::summarize_communities::typing::{CommunityReport, Finding};
+use crate::utils::dataframe::{get_f64, get_i64, get_string};
+
+/// The member entities of a community.
+#[derive(Debug, Clone)]
+pub struct CommunityMembership {
+    pub community: i64,
+    pub level: i64,
+    pub entity_ids: HashSet<String>,
+}
+
+/// Read the member entities of every community.
+pub fn get_community_memberships(communities: LazyFrame) -> Vec<CommunityMembership> {
+    let communities = communities
+        .select([
+            col(schemas::COMMUNITY_ID).cast(DataType::Int64),
+            col(schemas::COMMUNITY_LEVEL).cast(DataType::Int64),
+            col(schemas::ENTITY_IDS),
+        ])
+        .collect()
+        .unwrap();
+
+    let ids = communities.column(schemas::COMMUNITY_ID).unwrap().i64().unwrap();
+    let levels = communities.column(schemas::COMMUNITY_LEVEL).unwrap().i64().unwrap();
+    let entity_ids = communities.column(schemas::ENTITY_IDS).unwrap().list().unwrap();
+    ids.into_iter()
+        .zip(levels)
+        .zip(entity_ids)
+        .filter_map(|((community, level), members)| {
+            Some(CommunityMembership {
+                community: community?,
+                level: level?,
+                entity_ids: members
+                    .map(|members| {
+                        members
+                            .str()
+                            .unwrap()
+                            .into_iter()
+                            .flatten()
+                            .map(|id| id.to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/**
+Map re-clustered communities to the ids of the previous communities they overlap the most.
+
+Communities are matched within the same level, greedily by descending Jaccard similarity of
+their member entities. New communities without a match get ids after the largest previous id.
+
+Returns the mapping from re-clustered community id to the id to use in the updated index.
+*/
+pub fn map_communities_by_overlap(
+    old_communities: &[CommunityMembership],
+    new_communities: &[CommunityMembership],
+) -> HashMap<i64, i64> {
+    let mut candidates: Vec<(f64, i64, i64)> = Vec::new();
+    for new in new_communities {
+        for old in old_communities.iter().filter(|old| old.level == new.level) {
+            let intersection = new.entity_ids.intersection(&old.entity_ids).count();
+            if intersection == 0 {
+                continue;
+            }
+            let union = new.entity_ids.union(&old.entity_ids).count();
+            candidates.push((intersection as f64 / union as f64, new.community, old.community));
+        }
+    }
+    // most similar pairs first; ties resolved by id so the mapping is deterministic
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut mapping: HashMap<i64, i64> = HashMap::new();
+    let mut used: HashSet<i64> = HashSet::new();
+    for (_, new, old) in candidates {
+        if mapping.contains_key(&new) || used.contains(&old) {
+            continue;
+        }
+        mapping.insert(new, old);
+        used.insert(old);
+    }
+
+    let mut next_id = old_communities
+        .iter()
+        .map(|community| community.community)
+        .max()
+        .unwrap_or(-1)
+        + 1;
+    let mut unmatched: Vec<i64> = new_communities
+        .iter()
+        .map(|community| community.community)
+        .filter(|community| !mapping.contains_key(community))
+        .collect();
+    unmatched.sort();
+    for community in unmatched {
+        mapping.insert(community, next_id);
+        next_id += 1;
+    }
+
+    mapping
+}
+
+/**
+Apply a community id mapping to the community, parent and children columns.
+
+Mapped communities keep the id of the previous community they were matched to.
+*/
+pub fn remap_communities(
+    communities: LazyFrame,
+    old_communities: LazyFrame,
+    community_id_mapping: &HashMap<i64, i64>,
+) -> LazyFrame {
+    let remap = |id: i64| community_id_mapping.get(&id).copied().unwrap_or(id);
+
+    let mut communities = communities.collect().unwrap();
+    let community: Vec<i64> = communities
+        .column(schemas::COMMUNITY_ID)
+        .unwrap()
+        .cast(&DataType::Int64)
+        .unwrap()
+        .i64()
+        .unwrap()
+        .into_iter()
+        .map(|id| remap(id.unwrap_or(-1)))
+        .collect();
+    let parent: Vec<i64> = communities
+        .column(schemas::COMMUNITY_PARENT)
+        .unwrap()
+        .cast(&DataType::Int64)
+        .unwrap()
+        .i64()
+        .unwrap()
+        .into_iter()
+        .map(|id| match id {
+            Some(-1) | None => -1,
+            Some(id) => remap(id),
+        })
+        .collect();
+    let children: ListChunked = communities
+        .column(schemas::COMMUNITY_CHILDREN)
+        .unwrap()
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|children| {
+            children.map(|children| {
+                let children: Vec<i64> = children
+                    .cast(&DataType::Int64)
+                    .unwrap()
+                    .i64()
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .map(remap)
+                    .collect();
+                Series::new("".into(), children)
+            })
+        })
+        .collect();
+
+    // keep the previous ids of the matched communities
+    let old_ids = old_community_ids(old_communities);
+    let ids: Vec<String> = communities
+        .column(schemas::ID)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .zip(&community)
+        .map(|(id, community)| {
+            old_ids
+                .get(community)
+                .cloned()
+                .unwrap_or_else(|| id.unwrap_or_default().to_string())
+        })
+        .collect();
+    let titles: Vec<String> = community.iter().map(|id| format!("Community {id}")).collect();
+
+    communities.with_column(Series::new(schemas::ID.into(), ids)).unwrap();
+    communities
+        .with_column(Series::new(schemas::SHORT_ID.into(), community.clone()))
+        .unwrap();
+    communities
+        .with_column(Series::new(schemas::COMMUNITY_ID.into(), community))
+        .unwrap();
+    communities
+        .with_column(Series::new(schemas::COMMUNITY_PARENT.into(), parent))
+        .unwrap();
+    communities
+        .with_column(children.into_series().with_name(schemas::COMMUNITY_CHILDREN.into()))
+        .unwrap();
+    communities.with_column(Series::new(schemas::TITLE.into(), titles)).unwrap();
+
+    communities.lazy()
+}
+
+/**
+Find the communities whose report has to be regenerated.
+
+A community changed when it is new, when its member entities differ from the previous
+community with the same id, or when any of its members is in `changed_entity_ids`.
+*/
+pub fn find_changed_communities(
+    old_communities: &[CommunityMembership],
+    new_communities: &[CommunityMembership],
+    changed_entity_ids: &HashSet<String>,
+) -> HashSet<i64> {
+    let old_members: HashMap<i64, &HashSet<String>> = old_communities
+        .iter()
+        .map(|community| (community.community, &community.entity_ids))
+        .collect();
+
+    new_communities
+        .iter()
+        .filter(|community| {
+            old_members
+                .get(&community.community)
+                .is_none_or(|members| **members != community.entity_ids)
+                || !community.entity_ids.is_disjoint(changed_entity_ids)
+        })
+        .map(|community| community.community)
+        .collect()
+}
+
+/// Read the community reports of the previous index that can be reused as they are.
+pub fn get_reusable_reports(
+    old_reports: LazyFrame,
+    new_communities: &[CommunityMembership],
+    changed_communities: &HashSet<i64>,
+) -> Vec<CommunityReport> {
+    let reusable: HashSet<i64> = new_communities
+        .iter()
+        .map(|community| community.community)
+        .filter(|community| !changed_communities.contains(community))
+        .collect();
+
+    let reports = old_reports
+        .select([
+            col(schemas::COMMUNITY_ID).cast(DataType::Int64),
+            col(schemas::COMMUNITY_LEVEL).cast(DataType::Int64),
+            col(schemas::TITLE),
+            col(schemas::SUMMARY),
+            col(schemas::FULL_CONTENT),
+            col(schemas::FULL_CONTENT_JSON),
+            col(schemas::RATING).cast(DataType::Float64),
+            col(schemas::EXPLANATION),
+            col(schemas::FINDINGS),
+        ])
+        .collect()
+        .unwrap();
+
+    (0..reports.height())
+        .filter(|row| reusable.contains(&get_i64(&reports, schemas::COMMUNITY_ID, *row)))
+        .map(|row| CommunityReport {
+            community: get_i64(&reports, schemas::COMMUNITY_ID, row),
+            level: get_i64(&reports, schemas::COMMUNITY_LEVEL, row),
+            title: get_string(&reports, schemas::TITLE, row),
+            summary: get_string(&reports, schemas::SUMMARY, row),
+            full_content: get_string(&reports, schemas::FULL_CONTENT, row),
+            full_content_json: get_string(&reports, schemas::FULL_CONTENT_JSON, row),
+            rank: get_f64(&reports, schemas::RATING, row),
+            rating_explanation: get_string(&reports, schemas::EXPLANATION, row),
+            findings: serde_json::from_str::<Vec<Finding>>(&get_string(
+                &reports,
+                schemas::FINDINGS,
+                row,
+            ))
+            .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Get the ids of the previous communities, keyed by community.
+fn old_community_ids(old_communities: LazyFrame) -> BTreeMap<i64, String> {
+    let communities: DataFrame = old_communities
+        .select([col(schemas::COMMUNITY_ID).cast(DataType::Int64), col(schemas::ID)])
+        .collect()
+        .unwrap();
+    let community = communities.column(schemas::COMMUNITY_ID).unwrap().i64().unwrap();
+    let ids = communities.column(schemas::ID).unwrap().str().unwrap();
+    community
+        .into_iter()
+        .zip(ids)
+        .filter_map(|(community, id)| Some((community?, id?.to_string())))
+        .collect()
+}
@@ -0,0 +1,446 @@
+//! Document deletion operations and utils for Incremental Indexing.
+
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+use polars::prelude::{
+    BooleanChunked, DataFrame, DataType, IntoLazy, IntoSeries, LazyFrame, ListChunked,
+    ListNameSpaceImpl, NamedFrom, Series,
+};
+
+use crate::data_model::schemas;
+use crate::index::update::incremental_index::InputDelta;
+use crate::index::utils::hashing::gen_sha512_hash;
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::utils::storage::{load_table_from_storage, storage_has_table, write_table_to_storage};
+
+/// Hash the content of a document, so edited documents can be told apart from unchanged ones.
+pub fn content_hash(text: &str) -> String {
+    gen_sha512_hash(
+        HashMap::from([("text".into(), text.to_string())]),
+        ["text".into()].into_iter(),
+    )
+}
+
+/// Get the content hash of every document, keyed by title.
+pub fn content_hashes_by_title(documents: &DataFrame) -> HashMap<String, String> {
+    let titles = documents.column(schemas::TITLE).unwrap().str().unwrap();
+    let texts = documents.column(schemas::TEXT).unwrap().str().unwrap();
+    titles
+        .into_iter()
+        .zip(texts)
+        .filter_map(|(title, text)| Some((title?.to_string(), content_hash(text.unwrap_or_default()))))
+        .collect()
+}
+
+/**
+Diff the input dataset against the documents of the previous index.
+
+Documents are matched by title; a document whose content hash differs from the previous
+version is modified, and is both re-indexed and removed in its previous version.
+*/
+pub fn diff_documents(input_dataset: &DataFrame, final_docs: &DataFrame) -> InputDelta {
+    let previous_hashes = content_hashes_by_title(final_docs);
+    let dataset_hashes = content_hashes_by_title(input_dataset);
+
+    // classify each input by whether its title is known and its content unchanged
+    let input_titles = input_dataset.column(schemas::TITLE).unwrap().str().unwrap();
+    let is_new: BooleanChunked = input_titles
+        .into_iter()
+        .map(|title| title.is_none_or(|title| !previous_hashes.contains_key(title)))
+        .collect();
+    let is_modified: BooleanChunked = input_titles
+        .into_iter()
+        .map(|title| {
+            title.is_some_and(|title| {
+                previous_hashes
+                    .get(title)
+                    .is_some_and(|previous| dataset_hashes.get(title) != Some(previous))
+            })
+        })
+        .collect();
+
+    // previous documents that are gone from the input, or superseded by a modified version
+    let previous_titles = final_docs.column(schemas::TITLE).unwrap().str().unwrap();
+    let is_deleted: BooleanChunked = previous_titles
+        .into_iter()
+        .map(|title| {
+            title.is_none_or(|title| {
+                dataset_hashes
+                    .get(title)
+                    .is_none_or(|current| previous_hashes.get(title) != Some(current))
+            })
+        })
+        .collect();
+
+    let new_inputs = input_dataset.filter(&(&is_new | &is_modified)).unwrap();
+    let modified_inputs = input_dataset.filter(&is_modified).unwrap();
+    let deleted_inputs = final_docs.filter(&is_deleted).unwrap();
+    info!(
+        "Incremental indexing found {} new, {} modified and {} deleted documents",
+        new_inputs.height() - modified_inputs.height(),
+        modified_inputs.height(),
+        deleted_inputs.height() - modified_inputs.height(),
+    );
+
+    InputDelta {
+        new_inputs: new_inputs.lazy(),
+        modified_inputs: modified_inputs.lazy(),
+        deleted_inputs: deleted_inputs.lazy(),
+    }
+}
+
+/// The tables of an index derived from its documents.
+pub struct DocumentTables {
+    pub documents: DataFrame,
+    pub text_units: DataFrame,
+    pub entities: DataFrame,
+    pub relationships: DataFrame,
+    pub covariates: Option<DataFrame>,
+}
+
+/// What removing deleted documents changed in the previous index.
+#[derive(Debug, Default, PartialEq)]
+pub struct PrunedIndex {
+    /// The entities that were deleted, lost source text or lost a relationship.
+    pub affected_entity_ids: HashSet<String>,
+    /// The titles of the remaining entities that lost source text, their summarized description is stale.
+    pub stale_entity_titles: HashSet<String>,
+    /// The source and target of the remaining relationships that lost source text, their description and weight are stale.
+    pub stale_relationships: HashSet<(String, String)>,
+    /// The remaining text units of the stale entities and relationships, to extract them again from.
+    pub stale_text_unit_ids: HashSet<String>,
+}
+
+/**
+Remove deleted documents from the previous index, cascading to everything derived from them.
+
+The pruned tables are written back to `storage`; see `prune_deleted_documents`.
+*/
+pub async fn remove_deleted_documents(
+    storage: PipelineStorage,
+    deleted_documents: LazyFrame,
+) -> PrunedIndex {
+    let deleted_document_ids = string_set(&deleted_documents.collect().unwrap(), schemas::ID);
+    if deleted_document_ids.is_empty() {
+        return PrunedIndex::default();
+    }
+
+    let covariates = if storage_has_table("covariates", storage).await {
+        Some(load_table_from_storage("covariates", storage).await.collect().unwrap())
+    } else {
+        None
+    };
+    let tables = DocumentTables {
+        documents: load_table_from_storage("documents", storage).await.collect().unwrap(),
+        text_units: load_table_from_storage("text_units", storage).await.collect().unwrap(),
+        entities: load_table_from_storage("entities", storage).await.collect().unwrap(),
+        relationships: load_table_from_storage("relationships", storage).await.collect().unwrap(),
+        covariates,
+    };
+    let (tables, pruned_index) = prune_deleted_documents(tables, &deleted_document_ids);
+
+    write_table_to_storage(tables.documents.lazy(), "documents", storage).await;
+    write_table_to_storage(tables.text_units.lazy(), "text_units", storage).await;
+    write_table_to_storage(tables.entities.lazy(), "entities", storage).await;
+    write_table_to_storage(tables.relationships.lazy(), "relationships", storage).await;
+    if let Some(covariates) = tables.covariates {
+        write_table_to_storage(covariates.lazy(), "covariates", storage).await;
+    }
+    pruned_index
+}
+
+/**
+Remove deleted documents from the tables of an index, cascading to everything derived from them.
+
+- Text units only sourced from deleted documents are dropped.
+- Entities, relationships and covariates lose the dropped text units; the ones left without any
+  text unit are orphaned and deleted, as are relationships to a deleted entity.
+
+The summarized descriptions and relationship weights of the remaining rows that lost text units
+still account for the deleted text; they are reported as stale, with the text units left to
+extract them from again.
+*/
+pub fn prune_deleted_documents(
+    tables: DocumentTables,
+    deleted_document_ids: &HashSet<String>,
+) -> (DocumentTables, PrunedIndex) {
+    let DocumentTables { documents, mut text_units, mut entities, mut relationships, covariates } = tables;
+
+    // Documents
+    let deleted = is_in(&documents, schemas::ID, deleted_document_ids);
+    let documents = documents.filter(&!deleted).unwrap();
+
+    // Text units
+    prune_id_list(&mut text_units, schemas::DOCUMENT_IDS, deleted_document_ids);
+    let orphaned = is_empty_list(&text_units, schemas::DOCUMENT_IDS);
+    let removed_text_unit_ids = string_set(&text_units.filter(&orphaned).unwrap(), schemas::ID);
+    let text_units = text_units.filter(&!&orphaned).unwrap();
+
+    // Entities
+    let changed = prune_id_list(&mut entities, schemas::TEXT_UNIT_IDS, &removed_text_unit_ids);
+    let mut affected_entity_ids = string_set(&entities.filter(&changed).unwrap(), schemas::ID);
+    let orphaned = is_empty_list(&entities, schemas::TEXT_UNIT_IDS);
+    let deleted_titles = string_set(&entities.filter(&orphaned).unwrap(), schemas::TITLE);
+    let stale_entities = entities.filter(&(&changed & &!&orphaned)).unwrap();
+    let mut entities = entities.filter(&!&orphaned).unwrap();
+    let frequency = entities
+        .column(schemas::TEXT_UNIT_IDS)
+        .unwrap()
+        .list()
+        .unwrap()
+        .lst_lengths()
+        .into_series()
+        .cast(&DataType::Int64)
+        .unwrap()
+        .with_name(schemas::NODE_FREQUENCY.into());
+    entities.with_column(frequency).unwrap();
+
+    // Relationships
+    let changed = prune_id_list(&mut relationships, schemas::TEXT_UNIT_IDS, &removed_text_unit_ids);
+    let dangling = &is_in(&relationships, schemas::EDGE_SOURCE, &deleted_titles)
+        | &is_in(&relationships, schemas::EDGE_TARGET, &deleted_titles);
+    let orphaned = &is_empty_list(&relationships, schemas::TEXT_UNIT_IDS) | &dangling;
+    let affected_relationships = relationships.filter(&(&changed | &orphaned)).unwrap();
+    let mut affected_titles = string_set(&affected_relationships, schemas::EDGE_SOURCE);
+    affected_titles.extend(string_set(&affected_relationships, schemas::EDGE_TARGET));
+    let stale_relationships = relationships.filter(&(&changed & &!&orphaned)).unwrap();
+    let relationships = relationships.filter(&!&orphaned).unwrap();
+
+    // entities at either end of a pruned relationship changed too
+    let titles = entities.column(schemas::TITLE).unwrap().str().unwrap();
+    let ids = entities.column(schemas::ID).unwrap().str().unwrap();
+    affected_entity_ids.extend(
+        titles
+            .into_iter()
+            .zip(ids)
+            .filter_map(|(title, id)| match (title, id) {
+                (Some(title), Some(id)) if affected_titles.contains(title) => Some(id.to_string()),
+                _ => None,
+            }),
+    );
+
+    // Covariates
+    let covariates = covariates.map(|covariates| {
+        let removed = is_in(&covariates, "text_unit_id", &removed_text_unit_ids);
+        covariates.filter(&!removed).unwrap()
+    });
+
+    let mut stale_text_unit_ids = list_string_set(&stale_entities, schemas::TEXT_UNIT_IDS);
+    stale_text_unit_ids.extend(list_string_set(&stale_relationships, schemas::TEXT_UNIT_IDS));
+    let sources = stale_relationships.column(schemas::EDGE_SOURCE).unwrap().str().unwrap();
+    let targets = stale_relationships.column(schemas::EDGE_TARGET).unwrap().str().unwrap();
+    let pruned_index = PrunedIndex {
+        affected_entity_ids,
+        stale_entity_titles: string_set(&stale_entities, schemas::TITLE),
+        stale_relationships: sources
+            .into_iter()
+            .zip(targets)
+            .filter_map(|(source, target)| Some((source?.to_string(), target?.to_string())))
+            .collect(),
+        stale_text_unit_ids,
+    };
+    info!(
+        "Removed {} documents, {} text units, {} entities; {} entities affected, {} entities and {} relationships stale",
+        deleted_document_ids.len(),
+        removed_text_unit_ids.len(),
+        deleted_titles.len(),
+        pruned_index.affected_entity_ids.len(),
+        pruned_index.stale_entity_titles.len(),
+        pruned_index.stale_relationships.len(),
+    );
+
+    let tables = DocumentTables { documents, text_units, entities, relationships, covariates };
+    (tables, pruned_index)
+}
+
+/// Remove the given ids from a list column, returning which rows changed.
+fn prune_id_list(df: &mut DataFrame, column: &str, removed: &HashSet<String>) -> BooleanChunked {
+    let mut changed = Vec::with_capacity(df.height());
+    let pruned: ListChunked = df
+        .column(column)
+        .unwrap()
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|ids| {
+            let ids: Vec<String> = ids
+                .map(|ids| ids.str().unwrap().into_iter().flatten().map(|id| id.to_string()).collect())
+                .unwrap_or_default();
+            let kept: Vec<String> = ids.iter().filter(|id| !removed.contains(*id)).cloned().collect();
+            changed.push(kept.len() != ids.len());
+            Some(Series::new("".into(), kept))
+        })
+        .collect();
+    df.with_column(pruned.into_series().with_name(column.into())).unwrap();
+    BooleanChunked::new("changed".into(), changed)
+}
+
+/// Rows whose list column is empty.
+fn is_empty_list(df: &DataFrame, column: &str) -> BooleanChunked {
+    df.column(column)
+        .unwrap()
+        .list()
+        .unwrap()
+        .lst_lengths()
+        .into_iter()
+        .map(|length| length.unwrap_or_default() == 0)
+        .collect()
+}
+
+/// Rows whose string column value is in `values`.
+fn is_in(df: &DataFrame, column: &str, values: &HashSet<String>) -> BooleanChunked {
+    df.column(column)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .map(|value| value.is_some_and(|value| values.contains(value)))
+        .collect()
+}
+
+/// The distinct values of a string column.
+fn string_set(df: &DataFrame, column: &str) -> HashSet<String> {
+    df.column(column)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .map(|value| value.to_string())
+        .collect()
+}
+
+/// The distinct values of a list of strings column.
+fn list_string_set(df: &DataFrame, column: &str) -> HashSet<String> {
+    df.column(column)
+        .unwrap()
+        .list()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .flat_map(|values| {
+            values.str().unwrap().into_iter().flatten().map(|value| value.to_string()).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{LazyFrame, NamedFrom, Series};
+
+    use super::*;
+
+    fn ids(values: &[&str]) -> Series {
+        Series::new("".into(), values)
+    }
+
+    fn id_lists(values: &[&[&str]]) -> Vec<Series> {
+        values.iter().map(|values| ids(values)).collect()
+    }
+
+    fn documents(rows: &[(&str, &str, &str)]) -> DataFrame {
+        DataFrame::new(vec![
+            Series::new(schemas::ID.into(), rows.iter().map(|row| row.0).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TITLE.into(), rows.iter().map(|row| row.1).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TEXT.into(), rows.iter().map(|row| row.2).collect::<Vec<_>>()).into(),
+        ])
+        .unwrap()
+    }
+
+    fn strings(frame: LazyFrame, column: &str) -> Vec<String> {
+        let df = frame.collect().unwrap();
+        df.column(column).unwrap().str().unwrap().into_iter().flatten().map(str::to_string).collect()
+    }
+
+    fn set(values: &[&str]) -> HashSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn documents_are_diffed_by_title_and_content() {
+        let previous = documents(&[("d1", "A", "alpha"), ("d2", "B", "beta"), ("d3", "C", "gamma")]);
+        let input = documents(&[("n1", "A", "alpha"), ("n2", "B", "beta, edited"), ("n4", "D", "delta")]);
+        let delta = diff_documents(&input, &previous);
+
+        assert_eq!(strings(delta.new_inputs, schemas::TITLE), ["B", "D"]);
+        assert_eq!(strings(delta.modified_inputs, schemas::TITLE), ["B"]);
+        // the previous version of a modified document is removed along with the deleted ones
+        assert_eq!(strings(delta.deleted_inputs, schemas::ID), ["d2", "d3"]);
+    }
+
+    #[test]
+    fn unchanged_documents_have_an_empty_delta() {
+        let previous = documents(&[("d1", "A", "alpha"), ("d2", "B", "beta")]);
+        let input = documents(&[("n2", "B", "beta"), ("n1", "A", "alpha")]);
+        assert!(diff_documents(&input, &previous).is_empty());
+    }
+
+    /**
+    An index of two documents; d2 is the only source of t3, which is the only source of C.
+
+    B and A-B are sourced from t1 and t3, B-C from t3, and A, D and A-D from t1 only.
+    */
+    fn index_tables() -> DocumentTables {
+        let text_units = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["t1", "t2", "t3"]).into(),
+            Series::new(schemas::DOCUMENT_IDS.into(), id_lists(&[&["d1"], &["d1", "d2"], &["d2"]])).into(),
+        ])
+        .unwrap();
+        let entities = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["a", "b", "c", "d"]).into(),
+            Series::new(schemas::TITLE.into(), ["A", "B", "C", "D"]).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), id_lists(&[&["t1"], &["t1", "t3"], &["t3"], &["t1"]])).into(),
+            Series::new(schemas::NODE_FREQUENCY.into(), [1i64, 2, 1, 1]).into(),
+        ])
+        .unwrap();
+        let relationships = DataFrame::new(vec![
+            Series::new(schemas::EDGE_SOURCE.into(), ["A", "B", "A"]).into(),
+            Series::new(schemas::EDGE_TARGET.into(), ["B", "C", "D"]).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), id_lists(&[&["t1", "t3"], &["t3"], &["t1"]])).into(),
+        ])
+        .unwrap();
+        let covariates = DataFrame::new(vec![
+            Series::new(schemas::ID.into(), ["c1", "c2"]).into(),
+            Series::new("text_unit_id".into(), ["t1", "t3"]).into(),
+        ])
+        .unwrap();
+
+        DocumentTables {
+            documents: documents(&[("d1", "First", "one"), ("d2", "Second", "two")]),
+            text_units,
+            entities,
+            relationships,
+            covariates: Some(covariates),
+        }
+    }
+
+    #[test]
+    fn deleted_documents_cascade_to_derived_tables() {
+        let (tables, _) = prune_deleted_documents(index_tables(), &set(&["d2"]));
+
+        assert_eq!(strings(tables.documents.lazy(), schemas::ID), ["d1"]);
+        assert_eq!(strings(tables.text_units.lazy(), schemas::ID), ["t1", "t2"]);
+        assert_eq!(strings(tables.entities.clone().lazy(), schemas::TITLE), ["A", "B", "D"]);
+        let frequencies: Vec<Option<i64>> =
+            tables.entities.column(schemas::NODE_FREQUENCY).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(frequencies, [Some(1), Some(1), Some(1)]);
+        assert_eq!(strings(tables.relationships.lazy(), schemas::EDGE_TARGET), ["B", "D"]);
+        assert_eq!(strings(tables.covariates.unwrap().lazy(), schemas::ID), ["c1"]);
+    }
+
+    #[test]
+    fn rows_that_lost_source_text_are_stale() {
+        let (_, pruned_index) = prune_deleted_documents(index_tables(), &set(&["d2"]));
+
+        assert_eq!(
+            pruned_index,
+            PrunedIndex {
+                affected_entity_ids: set(&["a", "b", "c"]),
+                stale_entity_titles: set(&["B"]),
+                stale_relationships: HashSet::from([("A".to_string(), "B".to_string())]),
+                stale_text_unit_ids: set(&["t1"]),
+            }
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Entity related operations and utils for Incremental Indexing.
+
+use std::collections::{HashMap, HashSet};
+
+use polars::prelude::{DataType, JoinArgs, JoinType, LazyFrame, UnionArgs, col, concat, lit};
+
+use crate::data_model::schemas;
+use crate::index::operations::compute_temporal_bounds::{merged_valid_from, merged_valid_to};
+use crate::index::update::incremental_index::max_human_readable_id;
+
+/**
+Group and resolve entities.
+
+Delta entities whose title already exists in the old index are merged into the old entity,
+keeping its id; their descriptions are collected into a list for re-summarization.
+
+Returns the merged entities and the mapping from delta entity ids to the ids they resolved to.
+*/
+pub fn group_and_resolve_entities(
+    old_entities: LazyFrame,
+    delta_entities: LazyFrame,
+) -> (LazyFrame, HashMap<String, String>) {
+    // If a title exists in A and B, make a dictionary for {B.id : A.id}
+    let merged = delta_entities
+        .clone()
+        .select([col(schemas::ID), col(schemas::TITLE)])
+        .join(
+            old_entities.clone().select([col(schemas::ID), col(schemas::TITLE)]),
+            [col(schemas::TITLE)],
+            [col(schemas::TITLE)],
+            JoinArgs::new(JoinType::Inner).with_suffix(Some("_old".into())),
+        )
+        .collect()
+        .unwrap();
+    let delta_ids = merged.column(schemas::ID).unwrap().str().unwrap();
+    let old_ids = merged.column("id_old").unwrap().str().unwrap();
+    let id_mapping: HashMap<String, String> = delta_ids
+        .into_iter()
+        .zip(old_ids)
+        .filter_map(|(delta_id, old_id)| Some((delta_id?.to_string(), old_id?.to_string())))
+        .collect();
+
+    // Increment human readable id in b by the max of a
+    let initial_id = max_human_readable_id(old_entities.clone()) + 1;
+    let delta_entities = delta_entities.with_column(
+        (col(schemas::SHORT_ID).cast(DataType::Int64) + lit(initial_id)).alias(schemas::SHORT_ID),
+    );
+
+    // Concat A and B, old entities first so their ids win
+    let columns = [
+        col(schemas::ID),
+        col(schemas::SHORT_ID).cast(DataType::Int64),
+        col(schemas::TITLE),
+        col(schemas::TYPE),
+        col(schemas::DESCRIPTION),
+        col(schemas::TEXT_UNIT_IDS),
+        col(schemas::NODE_DEGREE),
+        col(schemas::NODE_X),
+        col(schemas::NODE_Y),
+        col(schemas::VALID_FROM),
+        col(schemas::VALID_TO),
+    ];
+    let combined = concat(
+        [old_entities.select(columns.clone()), delta_entities.select(columns)],
+        UnionArgs::default(),
+    )
+    .unwrap();
+
+    // Group by title and resolve conflicts
+    let resolved = combined
+        .group_by_stable([col(schemas::TITLE)])
+        .agg([
+            col(schemas::ID).first(),
+            col(schemas::SHORT_ID).first(),
+            col(schemas::TYPE).first(),
+            col(schemas::DESCRIPTION),
+            col(schemas::TEXT_UNIT_IDS).flatten(),
+            col(schemas::NODE_DEGREE).first(),
+            col(schemas::NODE_X).first(),
+            col(schemas::NODE_Y).first(),
+            merged_valid_from(),
+            merged_valid_to(),
+        ])
+        .with_column(
+            col(schemas::TEXT_UNIT_IDS)
+                .list()
+                .len()
+                .cast(DataType::Int64)
+                .alias(schemas::NODE_FREQUENCY),
+        );
+
+    (resolved, id_mapping)
+}
+
+/// Recompute the degree of each entity from the merged relationships.
+pub fn update_entity_degrees(entities: LazyFrame, relationships: LazyFrame) -> LazyFrame {
+    let degrees = concat(
+        [
+            relationships.clone().select([col(schemas::EDGE_SOURCE).alias(schemas::TITLE)]),
+            relationships.select([col(schemas::EDGE_TARGET).alias(schemas::TITLE)]),
+        ],
+        UnionArgs::default(),
+    )
+    .unwrap()
+    .group_by([col(schemas::TITLE)])
+    .agg([col(schemas::TITLE).count().cast(DataType::Int64).alias("new_degree")]);
+
+    entities
+        .join(
+            degrees,
+            [col(schemas::TITLE)],
+            [col(schemas::TITLE)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column(col("new_degree").fill_null(lit(0)).alias(schemas::NODE_DEGREE))
+        .drop([col("new_degree")])
+}
+
+/**
+Find the entities whose summarized description differs from the previous index.
+
+Entities that did not exist in the previous index are reported as changed too.
+*/
+pub fn find_changed_entities(old_entities: LazyFrame, merged_entities: LazyFrame) -> HashSet<String> {
+    let compared = merged_entities
+        .select([col(schemas::ID), col(schemas::DESCRIPTION)])
+        .join(
+            old_entities.select([col(schemas::ID), col(schemas::DESCRIPTION)]),
+            [col(schemas::ID)],
+            [col(schemas::ID)],
+            JoinArgs::new(JoinType::Left).with_suffix(Some("_old".into())),
+        )
+        .filter(
+            col("description_old")
+                .is_null()
+                .or(col(schemas::DESCRIPTION).neq(col("description_old"))),
+        )
+        .select([col(schemas::ID)])
+        .collect()
+        .unwrap();
+
+    compared
+        .column(schemas::ID)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Look up the entity ids of a set of entity titles.
+pub fn entity_ids_by_title(entities: LazyFrame, titles: &HashSet<String>) -> HashSet<String> {
+    let entities = entities
+        .select([col(schemas::ID), col(schemas::TITLE)])
+        .collect()
+        .unwrap();
+    let ids = entities.column(schemas::ID).unwrap().str().unwrap();
+    let entity_titles = entities.column(schemas::TITLE).unwrap().str().unwrap();
+    ids.into_iter()
+        .zip(entity_titles)
+        .filter_map(|(id, title)| match (id, title) {
+            (Some(id), Some(title)) if titles.contains(title) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataFrame, IntoLazy, NamedFrom, Series};
+
+    use super::*;
+
+    /// An entity row: id, human readable id, title, description, text unit ids, valid_from and valid_to.
+    type Row<'a> = (&'a str, i64, &'a str, &'a str, &'a [&'a str], Option<&'a str>, Option<&'a str>);
+
+    fn entities(rows: &[Row]) -> LazyFrame {
+        let text_unit_ids: Vec<Series> = rows.iter().map(|row| Series::new("".into(), row.4)).collect();
+        DataFrame::new(vec![
+            Series::new(schemas::ID.into(), rows.iter().map(|row| row.0).collect::<Vec<_>>()).into(),
+            Series::new(schemas::SHORT_ID.into(), rows.iter().map(|row| row.1).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TITLE.into(), rows.iter().map(|row| row.2).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TYPE.into(), vec!["GEO"; rows.len()]).into(),
+            Series::new(schemas::DESCRIPTION.into(), rows.iter().map(|row| row.3).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), text_unit_ids).into(),
+            Series::new(schemas::NODE_DEGREE.into(), vec![1i64; rows.len()]).into(),
+            Series::new(schemas::NODE_X.into(), vec![0.0; rows.len()]).into(),
+            Series::new(schemas::NODE_Y.into(), vec![0.0; rows.len()]).into(),
+            Series::new(schemas::VALID_FROM.into(), rows.iter().map(|row| row.5).collect::<Vec<_>>()).into(),
+            Series::new(schemas::VALID_TO.into(), rows.iter().map(|row| row.6).collect::<Vec<_>>()).into(),
+        ])
+        .unwrap()
+        .lazy()
+    }
+
+    fn strings(df: &DataFrame, column: &str) -> Vec<Option<String>> {
+        df.column(column).unwrap().str().unwrap().into_iter().map(|value| value.map(str::to_string)).collect()
+    }
+
+    fn string_lists(df: &DataFrame, column: &str) -> Vec<Vec<String>> {
+        df.column(column)
+            .unwrap()
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|values| {
+                values.unwrap().str().unwrap().into_iter().flatten().map(str::to_string).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn delta_entities_merge_into_old_entities_by_title() {
+        let old = entities(&[
+            ("a1", 0, "RIVER", "old river", &["t1"], None, None),
+            ("b1", 1, "BRIDGE", "old bridge", &["t1"], None, None),
+        ]);
+        let delta = entities(&[
+            ("d1", 0, "RIVER", "delta river", &["t2"], None, None),
+            ("d2", 1, "TOWN", "delta town", &["t2"], None, None),
+        ]);
+        let (merged, id_mapping) = group_and_resolve_entities(old, delta);
+        let merged = merged.collect().unwrap();
+
+        assert_eq!(id_mapping, HashMap::from([("d1".to_string(), "a1".to_string())]));
+        assert_eq!(strings(&merged, schemas::TITLE), [Some("RIVER".into()), Some("BRIDGE".into()), Some("TOWN".into())]);
+        assert_eq!(strings(&merged, schemas::ID), [Some("a1".into()), Some("b1".into()), Some("d2".into())]);
+        let short_ids: Vec<Option<i64>> = merged.column(schemas::SHORT_ID).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(short_ids, [Some(0), Some(1), Some(3)]);
+        assert_eq!(string_lists(&merged, schemas::DESCRIPTION)[0], ["old river", "delta river"]);
+        assert_eq!(string_lists(&merged, schemas::TEXT_UNIT_IDS)[0], ["t1", "t2"]);
+        let frequencies: Vec<Option<i64>> =
+            merged.column(schemas::NODE_FREQUENCY).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(frequencies, [Some(2), Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn merged_entities_keep_the_widest_validity() {
+        let old = entities(&[("a1", 0, "RIVER", "old", &["t1"], Some("2020-01-01"), Some("2021-01-01"))]);
+        let delta = entities(&[("d1", 0, "RIVER", "delta", &["t2"], Some("2019-06-01"), None)]);
+        let merged = group_and_resolve_entities(old, delta).0.collect().unwrap();
+
+        assert_eq!(strings(&merged, schemas::VALID_FROM), [Some("2019-06-01".into())]);
+        assert_eq!(strings(&merged, schemas::VALID_TO), [None]);
+    }
+
+    #[test]
+    fn degrees_are_recomputed_from_the_relationships() {
+        let merged = entities(&[
+            ("a1", 0, "RIVER", "", &[], None, None),
+            ("b1", 1, "BRIDGE", "", &[], None, None),
+            ("c1", 2, "TOWN", "", &[], None, None),
+            ("d1", 3, "HILL", "", &[], None, None),
+        ]);
+        let relationships = DataFrame::new(vec![
+            Series::new(schemas::EDGE_SOURCE.into(), ["RIVER", "RIVER"]).into(),
+            Series::new(schemas::EDGE_TARGET.into(), ["BRIDGE", "TOWN"]).into(),
+        ])
+        .unwrap()
+        .lazy();
+        let updated = update_entity_degrees(merged, relationships)
+            .sort([schemas::SHORT_ID], Default::default())
+            .collect()
+            .unwrap();
+
+        let degrees: Vec<Option<i64>> = updated.column(schemas::NODE_DEGREE).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(degrees, [Some(2), Some(1), Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn new_and_redescribed_entities_are_changed() {
+        let old = entities(&[
+            ("a1", 0, "RIVER", "a river", &[], None, None),
+            ("b1", 1, "BRIDGE", "a bridge", &[], None, None),
+        ]);
+        let merged = entities(&[
+            ("a1", 0, "RIVER", "a river", &[], None, None),
+            ("b1", 1, "BRIDGE", "a stone bridge", &[], None, None),
+            ("c1", 2, "TOWN", "a town", &[], None, None),
+        ]);
+
+        assert_eq!(find_changed_entities(old, merged), HashSet::from(["b1".to_string(), "c1".to_string()]));
+    }
+
+    #[test]
+    fn entity_ids_are_looked_up_by_title() {
+        let merged = entities(&[
+            ("a1", 0, "RIVER", "", &[], None, None),
+            ("b1", 1, "BRIDGE", "", &[], None, None),
+        ]);
+        let titles = HashSet::from(["BRIDGE".to_string(), "UNKNOWN".to_string()]);
+        assert_eq!(entity_ids_by_title(merged, &titles), HashSet::from(["b1".to_string()]));
+    }
+}
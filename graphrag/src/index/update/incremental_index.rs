@@ -0,0 +1,612 @@
+//! Dataframe operations and utils for Incremental Indexing.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use log::info;
+use polars::prelude::{
+    BooleanChunked, DataType, IntoLazy, JoinArgs, JoinType, LazyFrame, ListChunked, NamedFrom,
+    Series, UnionArgs, col, concat, lit, when,
+};
+
+use crate::cache::pipeline_cache::PipelineCache;
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::embeddings::{get_embedded_fields, get_embedding_settings};
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::data_model::schemas;
+use crate::index::operations::compute_centrality::{centrality_columns, compute_centrality};
+use crate::index::operations::extract_graph::extract_graph::extract_graph;
+use crate::index::operations::summarize_descriptions::summarize_descriptions;
+use crate::index::update::communities::{
+    find_changed_communities, get_community_memberships, get_reusable_reports,
+    map_communities_by_overlap, remap_communities,
+};
+use crate::index::update::documents::{PrunedIndex, diff_documents};
+use crate::index::update::entities::{
+    entity_ids_by_title, find_changed_entities, group_and_resolve_entities, update_entity_degrees,
+};
+use crate::index::update::relationships::{
+    find_changed_relationship_endpoints, update_and_merge_relationships,
+};
+use crate::index::workflows::create_communities::create_communities;
+use crate::index::workflows::create_community_reports::create_community_reports;
+use crate::index::workflows::generate_text_embeddings::generate_text_embeddings;
+use crate::language_model::manager::ModelManager;
+use crate::logger::base::ProgressLogger;
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::utils::storage::{load_table_from_storage, storage_has_table, write_table_to_storage};
+
+/// Dataclass to hold the input delta.
+pub struct InputDelta {
+    /// The inputs to index: new documents and the current version of modified documents.
+    pub new_inputs: LazyFrame,
+    /// The current version of the modified documents, also part of `new_inputs`.
+    pub modified_inputs: LazyFrame,
+    /// The previous documents to remove from the index: deleted documents and the previous version of modified documents.
+    pub deleted_inputs: LazyFrame,
+}
+
+impl InputDelta {
+    /// Whether the input differs from the previous index at all.
+    pub fn is_empty(&self) -> bool {
+        let height = |frame: &LazyFrame| frame.clone().collect().unwrap().height();
+        height(&self.new_inputs) == 0 && height(&self.deleted_inputs) == 0
+    }
+}
+
+/**
+Get the delta between the input dataset and the final documents, see `diff_documents`.
+
+Args:
+    input_dataset: The input dataset.
+    storage: The Pipeline storage.
+*/
+pub async fn get_delta_docs(input_dataset: LazyFrame, storage: PipelineStorage) -> InputDelta {
+    let final_docs = load_table_from_storage("documents", storage).await.collect().unwrap();
+    diff_documents(&input_dataset.collect().unwrap(), &final_docs)
+}
+
+/**
+Update the mergeable outputs.
+
+The previous index must already be pruned of deleted documents (see `remove_deleted_documents`).
+The delta index is merged into the previous one: entities and relationships are merged and the
+ones that gained a description re-summarized, the merged graph is re-clustered, the new communities take the ids of
+the previous communities they overlap the most, and only the reports of communities whose
+membership or member descriptions changed are regenerated. Embeddings are refreshed last, only
+embedding the rows whose content hash changed and deleting the vectors of removed rows. Fails if
+a configured vector store type is not registered.
+
+Args:
+    previous_storage: The storage used for previous outputs.
+    delta_storage: The storage used for delta outputs, if any document was added or modified.
+    output_storage: The storage used for final outputs.
+    pruned_index: What pruning deleted documents from the previous index changed.
+*/
+pub async fn update_dataframe_outputs<C: PipelineCache<String>>(
+    previous_storage: PipelineStorage,
+    delta_storage: Option<PipelineStorage>,
+    mut output_storage: PipelineStorage,
+    config: &GraphRagConfig,
+    cache: &mut C,
+    callbacks: &impl WorkflowCallbacks,
+    progress_logger: &impl ProgressLogger,
+    pruned_index: PrunedIndex,
+) -> Result<(), String> {
+    progress_logger.info("Updating Documents");
+    let old_documents = load_table_from_storage("documents", previous_storage).await;
+    let delta_documents = load_delta_table("documents", &old_documents, delta_storage).await;
+    let final_documents = concat_with_offset(old_documents, delta_documents);
+    write_table_to_storage(final_documents.clone(), "documents", output_storage).await;
+
+    // Update entities and merge them
+    progress_logger.info("Updating Entities and Relationships");
+    let (merged_entities, merged_relationships, entity_id_mapping, mut changed_entity_ids) =
+        update_entities_and_relationships(
+            previous_storage,
+            delta_storage,
+            output_storage,
+            config,
+            cache,
+            callbacks,
+            &pruned_index,
+        )
+        .await?;
+    changed_entity_ids.extend(pruned_index.affected_entity_ids);
+
+    // Update text units, resolving the delta entity ids to the merged ones
+    progress_logger.info("Updating Text Units");
+    let old_text_units = load_table_from_storage("text_units", previous_storage).await;
+    let delta_text_units = load_delta_table("text_units", &old_text_units, delta_storage).await;
+    let merged_text_units = update_text_units(old_text_units, delta_text_units, &entity_id_mapping);
+    write_table_to_storage(merged_text_units.clone(), "text_units", output_storage).await;
+
+    // Merge covariates, if any
+    if storage_has_table("covariates", previous_storage).await {
+        progress_logger.info("Updating Covariates");
+        let old_covariates = load_table_from_storage("covariates", previous_storage).await;
+        let delta_covariates = load_delta_table("covariates", &old_covariates, delta_storage).await;
+        let merged_covariates = concat_with_offset(old_covariates, delta_covariates);
+        write_table_to_storage(merged_covariates, "covariates", output_storage).await;
+    }
+
+    // Re-cluster the merged graph
+    progress_logger.info("Updating Communities");
+    let old_communities = load_table_from_storage("communities", previous_storage).await;
+    let (merged_communities, changed_communities) = update_communities(
+        old_communities.clone(),
+        merged_entities.clone(),
+        merged_relationships.clone(),
+        config,
+        &changed_entity_ids,
+    );
+    write_table_to_storage(merged_communities.clone(), "communities", output_storage).await;
+
+    // Regenerate the reports of the changed communities only
+    progress_logger.info("Updating Community Reports");
+    let old_reports = load_table_from_storage("community_reports", previous_storage).await;
+    let existing_reports = get_reusable_reports(
+        old_reports,
+        &get_community_memberships(merged_communities.clone()),
+        &changed_communities,
+    );
+    info!(
+        "Regenerating {} community reports, reusing {}",
+        changed_communities.len(),
+        existing_reports.len()
+    );
+
+    let community_reports_llm_settings = config.get_language_model_config(
+        &config.community_reports.model_id
+    );
+    let num_threads = community_reports_llm_settings.concurrent_requests;
+    let model = ModelManager::get_instance().get_or_create_chat_model(
+        "community_reporting",
+        community_reports_llm_settings.r#type.as_str(),
+        community_reports_llm_settings,
+        callbacks,
+        cache,
+    );
+    let extraction_prompt = match &config.community_reports.graph_prompt {
+        Some(prompt) => {
+            let path = Path::new(&config.root_dir).join(prompt);
+            let prompt = std::fs::read_to_string(&path).map_err(|error| {
+                format!("Failed to read the community report prompt {}: {error}", path.display())
+            })?;
+            Some(prompt)
+        }
+        None => None,
+    };
+    let claims = if config.extract_claims.enabled && storage_has_table("covariates", output_storage).await {
+        Some(load_table_from_storage("covariates", output_storage).await)
+    } else {
+        None
+    };
+
+    let merged_reports = create_community_reports(
+        merged_relationships.clone(),
+        merged_entities.clone(),
+        merged_communities,
+        claims,
+        callbacks,
+        model,
+        &config.community_reports,
+        extraction_prompt,
+        existing_reports,
+        num_threads,
+    ).await;
+    write_table_to_storage(merged_reports.clone(), "community_reports", output_storage).await;
+
+    // Refresh the embeddings of the merged tables, only new and changed rows are embedded
+    progress_logger.info("Updating Text Embeddings");
+    generate_text_embeddings(
+        Some(final_documents),
+        Some(merged_relationships),
+        Some(merged_text_units),
+        Some(merged_entities),
+        Some(merged_reports),
+        callbacks,
+        cache,
+        &mut output_storage,
+        get_embedding_settings(config),
+        get_embedded_fields(config),
+        config.snapshots.embeddings.then_some(config.snapshots.embeddings_format),
+    ).await?;
+    Ok(())
+}
+
+/**
+Merge the delta entities and relationships into the previous ones and re-summarize the merged descriptions.
+
+The previous entities and relationships left stale by pruning deleted documents are refreshed first.
+
+Returns the merged entities and relationships, the mapping from delta entity ids to merged entity ids,
+and the ids of the entities whose description or relationships changed.
+*/
+async fn update_entities_and_relationships<C: PipelineCache<String>>(
+    previous_storage: PipelineStorage,
+    delta_storage: Option<PipelineStorage>,
+    output_storage: PipelineStorage,
+    config: &GraphRagConfig,
+    cache: &mut C,
+    callbacks: &impl WorkflowCallbacks,
+    pruned_index: &PrunedIndex,
+) -> Result<(LazyFrame, LazyFrame, HashMap<String, String>, HashSet<String>), String> {
+    let old_entities = load_table_from_storage("entities", previous_storage).await;
+    let old_relationships = load_table_from_storage("relationships", previous_storage).await;
+    let delta_entities = load_delta_table("entities", &old_entities, delta_storage).await;
+    let delta_relationships = load_delta_table("relationships", &old_relationships, delta_storage).await;
+
+    // the previous rows left stale by pruning deleted documents are extracted and summarized again
+    let (old_entities, old_relationships) = if pruned_index.stale_text_unit_ids.is_empty() {
+        (old_entities, old_relationships)
+    } else {
+        let text_units = load_table_from_storage("text_units", previous_storage).await;
+        let (entity_summaries, relationship_summaries) =
+            refresh_stale_descriptions(text_units, pruned_index, config, cache, callbacks).await?;
+        replace_stale_descriptions(old_entities, old_relationships, entity_summaries, relationship_summaries)
+    };
+
+    let summarization_llm_settings = config.get_language_model_config(
+        &config.summarize_descriptions.model_id
+    );
+    let summarization_num_threads = summarization_llm_settings.concurrent_requests;
+    let summarization_model = ModelManager::get_instance().get_or_create_chat_model(
+        "summarize_descriptions",
+        summarization_llm_settings.r#type.as_str(),
+        summarization_llm_settings,
+        callbacks,
+        cache,
+    );
+    let summarization_strategy = config.summarize_descriptions.resolved_strategy(&config.root_dir)?;
+
+    let (merged_entities, entity_id_mapping) =
+        group_and_resolve_entities(old_entities.clone(), delta_entities);
+    let merged_relationships =
+        update_and_merge_relationships(old_relationships.clone(), delta_relationships);
+
+    // only the rows that gained descriptions from the delta index are re-summarized
+    let (entities_to_summarize, kept_entity_descriptions) =
+        split_merged_descriptions(merged_entities.clone(), &[schemas::TITLE]);
+    let (relationships_to_summarize, kept_relationship_descriptions) = split_merged_descriptions(
+        merged_relationships.clone(),
+        &[schemas::EDGE_SOURCE, schemas::EDGE_TARGET],
+    );
+    let (entity_summaries, relationship_summaries) = summarize_descriptions(
+        entities_to_summarize,
+        relationships_to_summarize,
+        callbacks,
+        cache,
+        summarization_model,
+        &summarization_strategy,
+        summarization_num_threads,
+    ).await;
+    let entity_summaries =
+        concat([entity_summaries, kept_entity_descriptions], UnionArgs::default()).unwrap();
+    let relationship_summaries =
+        concat([relationship_summaries, kept_relationship_descriptions], UnionArgs::default()).unwrap();
+
+    let merged_relationships = merged_relationships
+        .drop([col(schemas::DESCRIPTION)])
+        .join(
+            relationship_summaries,
+            [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+            [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .select(schemas::RELATIONSHIPS_FINAL_COLUMNS.map(col));
+    let merged_entities = merged_entities
+        .drop([col(schemas::DESCRIPTION)])
+        .join(
+            entity_summaries,
+            [col(schemas::TITLE)],
+            [col(schemas::TITLE)],
+            JoinArgs::new(JoinType::Left),
+        );
+    // the centrality metrics depend on the whole graph, they are recomputed over the merged relationships
+    let centrality = compute_centrality(merged_relationships.clone(), &config.graph_centrality);
+    let columns = schemas::ENTITIES_FINAL_COLUMNS
+        .into_iter()
+        .chain(centrality_columns(&config.graph_centrality));
+    let merged_entities = update_entity_degrees(merged_entities, merged_relationships.clone())
+        .join(
+            centrality,
+            [col(schemas::TITLE)],
+            [col(schemas::TITLE)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .select(columns.map(col).collect::<Vec<_>>());
+
+    // an entity is changed when its description changed or any of its relationships did
+    let mut changed_entity_ids = find_changed_entities(old_entities, merged_entities.clone());
+    changed_entity_ids.extend(entity_ids_by_title(
+        merged_entities.clone(),
+        &find_changed_relationship_endpoints(old_relationships, merged_relationships.clone()),
+    ));
+
+    write_table_to_storage(merged_entities.clone(), "entities", output_storage).await;
+    write_table_to_storage(merged_relationships.clone(), "relationships", output_storage).await;
+
+    Ok((merged_entities, merged_relationships, entity_id_mapping, changed_entity_ids))
+}
+
+/**
+Extract and summarize the descriptions of the stale entities and relationships again.
+
+The remaining text units of the stale rows are run through graph extraction, most of them hitting
+the cache of the previous run, and only the extracted stale entities and relationships are
+summarized. Returns the new entity descriptions, and the new relationship descriptions with the
+weights summed over the remaining text units.
+*/
+async fn refresh_stale_descriptions<C: PipelineCache<String>>(
+    text_units: LazyFrame,
+    pruned_index: &PrunedIndex,
+    config: &GraphRagConfig,
+    cache: &mut C,
+    callbacks: &impl WorkflowCallbacks,
+) -> Result<(LazyFrame, LazyFrame), String> {
+    let extract_graph_llm_settings = config.get_language_model_config(&config.extract_graph.model_id);
+    let extraction_num_threads = extract_graph_llm_settings.concurrent_requests;
+    let extraction_model = ModelManager::get_instance().get_or_create_chat_model(
+        "extract_graph",
+        extract_graph_llm_settings.r#type.as_str(),
+        extract_graph_llm_settings,
+        callbacks,
+        cache,
+    );
+    let extraction_strategy = config.extract_graph.resolved_strategy(&config.root_dir)?;
+
+    let text_units = text_units.collect().unwrap();
+    let is_stale: BooleanChunked = text_units
+        .column(schemas::ID)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .map(|id| id.is_some_and(|id| pruned_index.stale_text_unit_ids.contains(id)))
+        .collect();
+    let (entities, relationships) = extract_graph(
+        text_units.filter(&is_stale).unwrap().lazy(),
+        callbacks,
+        extraction_model,
+        schemas::TEXT,
+        schemas::ID,
+        &extraction_strategy,
+        Some(config.extract_graph.entity_types.clone()),
+        extraction_num_threads,
+    ).await;
+
+    // the same title may be extracted with different types, its descriptions are summarized together
+    let entities = entities
+        .group_by_stable([col(schemas::TITLE)])
+        .agg([col(schemas::DESCRIPTION).flatten()])
+        .collect()
+        .unwrap();
+    let is_stale: BooleanChunked = entities
+        .column(schemas::TITLE)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .map(|title| title.is_some_and(|title| pruned_index.stale_entity_titles.contains(title)))
+        .collect();
+    let entities = entities.filter(&is_stale).unwrap();
+
+    let relationships = relationships.collect().unwrap();
+    let sources = relationships.column(schemas::EDGE_SOURCE).unwrap().str().unwrap();
+    let targets = relationships.column(schemas::EDGE_TARGET).unwrap().str().unwrap();
+    let is_stale: BooleanChunked = sources
+        .into_iter()
+        .zip(targets)
+        .map(|(source, target)| match (source, target) {
+            (Some(source), Some(target)) => pruned_index
+                .stale_relationships
+                .contains(&(source.to_string(), target.to_string())),
+            _ => false,
+        })
+        .collect();
+    let relationships = relationships.filter(&is_stale).unwrap().lazy();
+
+    let summarization_llm_settings = config.get_language_model_config(
+        &config.summarize_descriptions.model_id
+    );
+    let summarization_num_threads = summarization_llm_settings.concurrent_requests;
+    let summarization_model = ModelManager::get_instance().get_or_create_chat_model(
+        "summarize_descriptions",
+        summarization_llm_settings.r#type.as_str(),
+        summarization_llm_settings,
+        callbacks,
+        cache,
+    );
+    let summarization_strategy = config.summarize_descriptions.resolved_strategy(&config.root_dir)?;
+    let (entity_summaries, relationship_summaries) = summarize_descriptions(
+        entities.lazy(),
+        relationships.clone(),
+        callbacks,
+        cache,
+        summarization_model,
+        &summarization_strategy,
+        summarization_num_threads,
+    ).await;
+    let relationship_summaries = relationship_summaries.join(
+        relationships.select([col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET), col(schemas::EDGE_WEIGHT)]),
+        [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+        [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+        JoinArgs::new(JoinType::Left),
+    );
+
+    Ok((entity_summaries, relationship_summaries))
+}
+
+/**
+Replace the stale descriptions and weights of the previous entities and relationships with the refreshed ones.
+
+Rows without a new summary, such as entities the extraction did not find again, keep their
+previous description and weight.
+*/
+fn replace_stale_descriptions(
+    old_entities: LazyFrame,
+    old_relationships: LazyFrame,
+    entity_summaries: LazyFrame,
+    relationship_summaries: LazyFrame,
+) -> (LazyFrame, LazyFrame) {
+    let refreshed = |column: &str| {
+        let fresh = format!("{column}_fresh");
+        when(col(fresh.as_str()).is_not_null())
+            .then(col(fresh.as_str()))
+            .otherwise(col(column))
+            .alias(column)
+    };
+
+    let entities = old_entities
+        .join(
+            entity_summaries,
+            [col(schemas::TITLE)],
+            [col(schemas::TITLE)],
+            JoinArgs::new(JoinType::Left).with_suffix(Some("_fresh".into())),
+        )
+        .with_column(refreshed(schemas::DESCRIPTION))
+        .drop([col("description_fresh")]);
+    let relationships = old_relationships
+        .join(
+            relationship_summaries,
+            [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+            [col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)],
+            JoinArgs::new(JoinType::Left).with_suffix(Some("_fresh".into())),
+        )
+        .with_columns([refreshed(schemas::DESCRIPTION), refreshed(schemas::EDGE_WEIGHT)])
+        .drop([col("description_fresh"), col("weight_fresh")]);
+
+    (entities, relationships)
+}
+
+/**
+Split merged rows into the ones to re-summarize and the description of the others.
+
+A row holding more than one description merged an old and a delta row, and needs a new summary.
+The other rows keep their single description, returned with their key columns in the shape of the
+summaries.
+*/
+fn split_merged_descriptions(merged: LazyFrame, keys: &[&str]) -> (LazyFrame, LazyFrame) {
+    let is_merged = col(schemas::DESCRIPTION).list().len().gt(lit(1));
+    let kept_columns: Vec<_> = keys
+        .iter()
+        .map(|key| col(*key))
+        .chain([col(schemas::DESCRIPTION).list().first()])
+        .collect();
+    let kept = merged.clone().filter(is_merged.clone().not()).select(kept_columns);
+    (merged.filter(is_merged), kept)
+}
+
+/**
+Re-cluster the merged graph and map the new communities onto the previous community ids.
+
+Returns the merged communities and the ids of the communities whose report must be regenerated.
+*/
+fn update_communities(
+    old_communities: LazyFrame,
+    merged_entities: LazyFrame,
+    merged_relationships: LazyFrame,
+    config: &GraphRagConfig,
+    changed_entity_ids: &HashSet<String>,
+) -> (LazyFrame, HashSet<i64>) {
+    let new_communities = create_communities(
+        merged_entities,
+        merged_relationships,
+        &config.cluster_graph.algorithm,
+        config.cluster_graph.max_cluster_size,
+        config.cluster_graph.use_lcc,
+        Some(config.cluster_graph.seed),
+        &config.cluster_graph.resolutions,
+        config.cluster_graph.max_depth,
+        config.cluster_graph.min_community_size,
+    );
+
+    let old_memberships = get_community_memberships(old_communities.clone());
+    let community_id_mapping = map_communities_by_overlap(
+        &old_memberships,
+        &get_community_memberships(new_communities.clone()),
+    );
+    let merged_communities = remap_communities(new_communities, old_communities, &community_id_mapping)
+        .select(schemas::COMMUNITIES_FINAL_COLUMNS.map(col));
+
+    let changed_communities = find_changed_communities(
+        &old_memberships,
+        &get_community_memberships(merged_communities.clone()),
+        changed_entity_ids,
+    );
+
+    (merged_communities, changed_communities)
+}
+
+/// Merge the text units, resolving the entity ids of the delta text units to the merged entities.
+fn update_text_units(
+    old_text_units: LazyFrame,
+    delta_text_units: LazyFrame,
+    entity_id_mapping: &HashMap<String, String>,
+) -> LazyFrame {
+    let mut delta_text_units = delta_text_units.collect().unwrap();
+    let entity_ids: ListChunked = delta_text_units
+        .column(schemas::ENTITY_IDS)
+        .unwrap()
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|entity_ids| {
+            entity_ids.map(|entity_ids| {
+                let resolved: Vec<String> = entity_ids
+                    .str()
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .map(|id| entity_id_mapping.get(id).cloned().unwrap_or_else(|| id.to_string()))
+                    .collect();
+                Series::new("".into(), resolved)
+            })
+        })
+        .collect();
+    delta_text_units
+        .with_column(entity_ids.into_series().with_name(schemas::ENTITY_IDS.into()))
+        .unwrap();
+
+    concat_with_offset(old_text_units, delta_text_units.lazy())
+}
+
+/// Load a delta table, or an empty table shaped like the previous one when there is no delta index or table.
+async fn load_delta_table(
+    name: &str,
+    old_table: &LazyFrame,
+    delta_storage: Option<PipelineStorage>,
+) -> LazyFrame {
+    match delta_storage {
+        Some(delta_storage) if storage_has_table(name, delta_storage).await => {
+            load_table_from_storage(name, delta_storage).await
+        }
+        _ => old_table.clone().limit(0),
+    }
+}
+
+/// Concatenate a previous and a delta table, shifting the delta human readable ids past the previous ones.
+fn concat_with_offset(old_table: LazyFrame, delta_table: LazyFrame) -> LazyFrame {
+    let initial_id = max_human_readable_id(old_table.clone()) + 1;
+    let delta_table = delta_table.with_column(
+        (col(schemas::SHORT_ID).cast(DataType::Int64) + lit(initial_id)).alias(schemas::SHORT_ID),
+    );
+    let old_table = old_table.with_column(col(schemas::SHORT_ID).cast(DataType::Int64));
+
+    concat([old_table, delta_table], UnionArgs::default()).unwrap()
+}
+
+/// Get the largest human readable id of a table, or -1 if it is empty.
+pub fn max_human_readable_id(table: LazyFrame) -> i64 {
+    table
+        .select([col(schemas::SHORT_ID).cast(DataType::Int64).max()])
+        .collect()
+        .unwrap()
+        .column(schemas::SHORT_ID)
+        .unwrap()
+        .i64()
+        .unwrap()
+        .get(0)
+        .unwrap_or(-1)
+}
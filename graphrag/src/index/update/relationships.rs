@@ -0,0 +1,202 @@
+//! Relationship related operations and utils for Incremental Indexing.
+
+use std::collections::HashSet;
+
+use polars::prelude::{DataType, JoinArgs, JoinType, LazyFrame, UnionArgs, col, concat, lit};
+
+use crate::data_model::schemas;
+use crate::index::operations::compute_temporal_bounds::{merged_valid_from, merged_valid_to};
+use crate::index::update::incremental_index::max_human_readable_id;
+
+/**
+Update and merge relationships.
+
+Relationships are merged on their (source, target) pair, keeping the id of the old relationship
+and collecting the descriptions into a list for re-summarization. The combined degree is
+recomputed over the merged graph.
+*/
+pub fn update_and_merge_relationships(
+    old_relationships: LazyFrame,
+    delta_relationships: LazyFrame,
+) -> LazyFrame {
+    // Increment the human readable id in b by the max of a
+    let initial_id = max_human_readable_id(old_relationships.clone()) + 1;
+    let delta_relationships = delta_relationships.with_column(
+        (col(schemas::SHORT_ID).cast(DataType::Int64) + lit(initial_id)).alias(schemas::SHORT_ID),
+    );
+
+    // Merge the final relationships, old relationships first so their ids win
+    let columns = [
+        col(schemas::ID),
+        col(schemas::SHORT_ID).cast(DataType::Int64),
+        col(schemas::EDGE_SOURCE),
+        col(schemas::EDGE_TARGET),
+        col(schemas::DESCRIPTION),
+        col(schemas::EDGE_WEIGHT).cast(DataType::Float64),
+        col(schemas::TEXT_UNIT_IDS),
+        col(schemas::VALID_FROM),
+        col(schemas::VALID_TO),
+    ];
+    let merged = concat(
+        [old_relationships.select(columns.clone()), delta_relationships.select(columns)],
+        UnionArgs::default(),
+    )
+    .unwrap();
+
+    // Group by source and target and aggregate
+    let aggregated = merged
+        .group_by_stable([col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET)])
+        .agg([
+            col(schemas::ID).first(),
+            col(schemas::SHORT_ID).first(),
+            col(schemas::DESCRIPTION),
+            col(schemas::EDGE_WEIGHT).mean(),
+            col(schemas::TEXT_UNIT_IDS).flatten(),
+            merged_valid_from(),
+            merged_valid_to(),
+        ]);
+
+    // Recalculate the combined degree over the merged graph
+    let degrees = concat(
+        [
+            aggregated.clone().select([col(schemas::EDGE_SOURCE).alias(schemas::TITLE)]),
+            aggregated.clone().select([col(schemas::EDGE_TARGET).alias(schemas::TITLE)]),
+        ],
+        UnionArgs::default(),
+    )
+    .unwrap()
+    .group_by([col(schemas::TITLE)])
+    .agg([col(schemas::TITLE).count().cast(DataType::Int64).alias(schemas::NODE_DEGREE)]);
+
+    aggregated
+        .join(
+            degrees.clone().select([
+                col(schemas::TITLE).alias(schemas::EDGE_SOURCE),
+                col(schemas::NODE_DEGREE).alias("source_degree"),
+            ]),
+            [col(schemas::EDGE_SOURCE)],
+            [col(schemas::EDGE_SOURCE)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .join(
+            degrees.select([
+                col(schemas::TITLE).alias(schemas::EDGE_TARGET),
+                col(schemas::NODE_DEGREE).alias("target_degree"),
+            ]),
+            [col(schemas::EDGE_TARGET)],
+            [col(schemas::EDGE_TARGET)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column((col("source_degree") + col("target_degree")).alias(schemas::EDGE_DEGREE))
+        .drop([col("source_degree"), col("target_degree")])
+}
+
+/**
+Find the endpoints of relationships whose summarized description differs from the previous index.
+
+Relationships that did not exist in the previous index are reported as changed too.
+*/
+pub fn find_changed_relationship_endpoints(
+    old_relationships: LazyFrame,
+    merged_relationships: LazyFrame,
+) -> HashSet<String> {
+    let changed = merged_relationships
+        .select([col(schemas::ID), col(schemas::EDGE_SOURCE), col(schemas::EDGE_TARGET), col(schemas::DESCRIPTION)])
+        .join(
+            old_relationships.select([col(schemas::ID), col(schemas::DESCRIPTION)]),
+            [col(schemas::ID)],
+            [col(schemas::ID)],
+            JoinArgs::new(JoinType::Left).with_suffix(Some("_old".into())),
+        )
+        .filter(
+            col("description_old")
+                .is_null()
+                .or(col(schemas::DESCRIPTION).neq(col("description_old"))),
+        )
+        .collect()
+        .unwrap();
+
+    [schemas::EDGE_SOURCE, schemas::EDGE_TARGET]
+        .into_iter()
+        .flat_map(|column| {
+            changed
+                .column(column)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(|title| title.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataFrame, IntoLazy, NamedFrom, Series};
+
+    use super::*;
+
+    /// A relationship row: id, human readable id, source, target, description, weight and text unit ids.
+    type Row<'a> = (&'a str, i64, &'a str, &'a str, &'a str, f64, &'a [&'a str]);
+
+    fn relationships(rows: &[Row]) -> LazyFrame {
+        let text_unit_ids: Vec<Series> = rows.iter().map(|row| Series::new("".into(), row.6)).collect();
+        DataFrame::new(vec![
+            Series::new(schemas::ID.into(), rows.iter().map(|row| row.0).collect::<Vec<_>>()).into(),
+            Series::new(schemas::SHORT_ID.into(), rows.iter().map(|row| row.1).collect::<Vec<_>>()).into(),
+            Series::new(schemas::EDGE_SOURCE.into(), rows.iter().map(|row| row.2).collect::<Vec<_>>()).into(),
+            Series::new(schemas::EDGE_TARGET.into(), rows.iter().map(|row| row.3).collect::<Vec<_>>()).into(),
+            Series::new(schemas::DESCRIPTION.into(), rows.iter().map(|row| row.4).collect::<Vec<_>>()).into(),
+            Series::new(schemas::EDGE_WEIGHT.into(), rows.iter().map(|row| row.5).collect::<Vec<_>>()).into(),
+            Series::new(schemas::TEXT_UNIT_IDS.into(), text_unit_ids).into(),
+            Series::new(schemas::VALID_FROM.into(), vec![None::<&str>; rows.len()]).into(),
+            Series::new(schemas::VALID_TO.into(), vec![None::<&str>; rows.len()]).into(),
+        ])
+        .unwrap()
+        .lazy()
+    }
+
+    #[test]
+    fn delta_relationships_merge_on_source_and_target() {
+        let old = relationships(&[("r1", 0, "RIVER", "BRIDGE", "old", 1.0, &["t1"])]);
+        let delta = relationships(&[
+            ("r2", 0, "RIVER", "BRIDGE", "delta", 3.0, &["t2"]),
+            ("r3", 1, "BRIDGE", "TOWN", "delta", 2.0, &["t2"]),
+        ]);
+        let merged = update_and_merge_relationships(old, delta).collect().unwrap();
+
+        let ids: Vec<Option<&str>> = merged.column(schemas::ID).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(ids, [Some("r1"), Some("r3")]);
+        let short_ids: Vec<Option<i64>> = merged.column(schemas::SHORT_ID).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(short_ids, [Some(0), Some(2)]);
+        let weights: Vec<Option<f64>> = merged.column(schemas::EDGE_WEIGHT).unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(weights, [Some(2.0), Some(2.0)]);
+        let descriptions = merged.column(schemas::DESCRIPTION).unwrap().list().unwrap().get_as_series(0).unwrap();
+        let descriptions: Vec<Option<&str>> = descriptions.str().unwrap().into_iter().collect();
+        assert_eq!(descriptions, [Some("old"), Some("delta")]);
+        // RIVER has degree 1, BRIDGE 2 and TOWN 1 in the merged graph
+        let combined_degrees: Vec<Option<i64>> =
+            merged.column(schemas::EDGE_DEGREE).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(combined_degrees, [Some(3), Some(3)]);
+    }
+
+    #[test]
+    fn endpoints_of_new_and_redescribed_relationships_are_changed() {
+        let old = relationships(&[
+            ("r1", 0, "RIVER", "BRIDGE", "crosses", 1.0, &[]),
+            ("r2", 1, "BRIDGE", "TOWN", "leads to", 1.0, &[]),
+        ]);
+        let merged = relationships(&[
+            ("r1", 0, "RIVER", "BRIDGE", "crosses", 1.0, &[]),
+            ("r2", 1, "BRIDGE", "TOWN", "leads into", 1.0, &[]),
+            ("r3", 2, "HILL", "TOWN", "overlooks", 1.0, &[]),
+        ]);
+
+        assert_eq!(
+            find_changed_relationship_endpoints(old, merged),
+            HashSet::from(["BRIDGE".to_string(), "TOWN".to_string(), "HILL".to_string()])
+        );
+    }
+}
@@ -1,5 +1,12 @@
 //! Utils methods definition.
 
+pub mod centrality;
 pub mod hashing;
 pub mod is_null;
+pub mod infomap;
+pub mod label_propagation;
+pub mod leiden;
+pub mod louvain;
 pub mod stable_lcc;
+pub mod tuple_records;
+pub mod weighted_graph;
@@ -0,0 +1,194 @@
+//! Native implementations of node centrality metrics.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::index::utils::weighted_graph::{SplitMix64, WeightedGraph};
+
+/// The maximum number of power iterations.
+const MAX_ITERATIONS: usize = 100;
+
+/// The total change below which power iterations have converged.
+const TOLERANCE: f64 = 1e-6;
+
+/**
+Compute the PageRank of every node, using edge weights as transition weights.
+
+Args:
+    - graph: The graph to rank.
+    - damping: The probability of following an edge rather than jumping to a random node.
+
+Returns the PageRank of every node, summing to 1.
+*/
+pub fn pagerank(graph: &WeightedGraph, damping: f64) -> Vec<f64> {
+    let node_count = graph.node_count();
+    if node_count == 0 {
+        return Vec::new();
+    }
+    let out_weights: Vec<f64> = (0..node_count)
+        .map(|node| graph.neighbors(node).iter().map(|(_, weight)| weight).sum())
+        .collect();
+
+    let mut ranks = vec![1.0 / node_count as f64; node_count];
+    for _ in 0..MAX_ITERATIONS {
+        // the rank of nodes without edges is spread over all nodes
+        let dangling: f64 = (0..node_count)
+            .filter(|node| out_weights[*node] <= 0.0)
+            .map(|node| ranks[node])
+            .sum();
+        let base = (1.0 - damping + damping * dangling) / node_count as f64;
+        let mut next = vec![base; node_count];
+        for node in (0..node_count).filter(|node| out_weights[*node] > 0.0) {
+            let share = damping * ranks[node] / out_weights[node];
+            for (neighbor, weight) in graph.neighbors(node) {
+                next[*neighbor] += share * weight;
+            }
+        }
+
+        let change: f64 = next.iter().zip(&ranks).map(|(next, rank)| (next - rank).abs()).sum();
+        ranks = next;
+        if change < TOLERANCE * node_count as f64 {
+            break;
+        }
+    }
+    ranks
+}
+
+/**
+Compute the betweenness centrality of every node, ignoring edge weights.
+
+Shortest paths are only counted from `samples` randomly chosen source nodes and scaled up, which
+approximates the exact metric on large graphs at a fraction of the cost.
+
+Args:
+    - graph: The graph to measure.
+    - samples: The number of source nodes, all nodes are used when the graph is smaller.
+    - seed: The seed of the source node sampling.
+
+Returns the normalized betweenness centrality of every node.
+*/
+pub fn betweenness(graph: &WeightedGraph, samples: usize, seed: u64) -> Vec<f64> {
+    let node_count = graph.node_count();
+    let mut centrality = vec![0.0; node_count];
+    if node_count < 3 {
+        return centrality;
+    }
+
+    let mut sources: Vec<usize> = (0..node_count).collect();
+    if samples < node_count {
+        SplitMix64::new(seed).shuffle(&mut sources);
+        sources.truncate(samples.max(1));
+    }
+
+    // Brandes' algorithm, accumulating the dependencies of every source
+    for source in &sources {
+        let mut stack: Vec<usize> = Vec::new();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+        let mut paths = vec![0.0; node_count];
+        let mut distances: Vec<Option<usize>> = vec![None; node_count];
+        paths[*source] = 1.0;
+        distances[*source] = Some(0);
+
+        let mut queue = VecDeque::from([*source]);
+        while let Some(node) = queue.pop_front() {
+            stack.push(node);
+            let distance = distances[node].unwrap();
+            for (neighbor, _) in graph.neighbors(node) {
+                if distances[*neighbor].is_none() {
+                    distances[*neighbor] = Some(distance + 1);
+                    queue.push_back(*neighbor);
+                }
+                if distances[*neighbor] == Some(distance + 1) {
+                    paths[*neighbor] += paths[node];
+                    predecessors[*neighbor].push(node);
+                }
+            }
+        }
+
+        let mut dependencies = vec![0.0; node_count];
+        while let Some(node) = stack.pop() {
+            for predecessor in &predecessors[node] {
+                dependencies[*predecessor] += paths[*predecessor] / paths[node] * (1.0 + dependencies[node]);
+            }
+            if node != *source {
+                centrality[node] += dependencies[node];
+            }
+        }
+    }
+
+    let scale = node_count as f64 / sources.len() as f64 / ((node_count - 1) * (node_count - 2)) as f64;
+    for value in centrality.iter_mut() {
+        *value *= scale;
+    }
+    centrality
+}
+
+/**
+Compute the eigenvector centrality of every node by power iteration, using edge weights.
+
+Returns the eigenvector centrality of every node, with unit Euclidean norm.
+*/
+pub fn eigenvector(graph: &WeightedGraph) -> Vec<f64> {
+    let node_count = graph.node_count();
+    if node_count == 0 {
+        return Vec::new();
+    }
+
+    let mut centrality = vec![1.0 / node_count as f64; node_count];
+    for _ in 0..MAX_ITERATIONS {
+        // iterate on (A + I), which converges on bipartite graphs too
+        let mut next = centrality.clone();
+        for node in 0..node_count {
+            for (neighbor, weight) in graph.neighbors(node) {
+                next[*neighbor] += centrality[node] * weight;
+            }
+        }
+        let norm = next.iter().map(|value| value * value).sum::<f64>().sqrt();
+        if norm <= 0.0 {
+            break;
+        }
+        for value in next.iter_mut() {
+            *value /= norm;
+        }
+
+        let change: f64 = next.iter().zip(&centrality).map(|(next, value)| (next - value).abs()).sum();
+        centrality = next;
+        if change < TOLERANCE * node_count as f64 {
+            break;
+        }
+    }
+    centrality
+}
+
+/**
+Compute the k-core number of every node, ignoring edge weights.
+
+The k-core number of a node is the largest k for which the node belongs to a subgraph where
+every node has at least k neighbors.
+*/
+pub fn core_number(graph: &WeightedGraph) -> Vec<usize> {
+    let node_count = graph.node_count();
+    let mut degrees: Vec<usize> = (0..node_count).map(|node| graph.neighbors(node).len()).collect();
+    let mut cores = vec![0; node_count];
+    let mut removed = vec![false; node_count];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> =
+        (0..node_count).map(|node| Reverse((degrees[node], node))).collect();
+
+    // repeatedly peel the node with the fewest remaining neighbors
+    let mut core = 0;
+    while let Some(Reverse((degree, node))) = heap.pop() {
+        if removed[node] || degree != degrees[node] {
+            continue;
+        }
+        core = core.max(degree);
+        cores[node] = core;
+        removed[node] = true;
+        for (neighbor, _) in graph.neighbors(node) {
+            if !removed[*neighbor] {
+                degrees[*neighbor] -= 1;
+                heap.push(Reverse((degrees[*neighbor], *neighbor)));
+            }
+        }
+    }
+    cores
+}
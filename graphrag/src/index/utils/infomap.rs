@@ -0,0 +1,160 @@
+//! A native implementation of the Infomap community detection algorithm.
+
+use crate::index::utils::leiden::renumber;
+use crate::index::utils::weighted_graph::{SplitMix64, WeightedGraph};
+
+/// The maximum number of move and aggregate passes.
+const MAX_PASSES: usize = 64;
+
+/// The maximum number of node moving rounds in a pass.
+const MAX_ROUNDS: usize = 100;
+
+/// The smallest codelength improvement for a node to move.
+const MIN_IMPROVEMENT: f64 = 1e-10;
+
+/**
+Partition a graph into communities with the two-level Infomap algorithm.
+
+Communities are the modules that minimize the map equation, i.e. the description length of a
+random walk on the graph. Modules are found by moving nodes between neighboring modules and
+aggregating them, like Louvain does for modularity.
+
+Args:
+    - graph: The graph to partition.
+    - seed: The seed of the node visiting order, the same seed always gives the same partition.
+
+Returns the community of every node, numbered from 0 in order of first appearance.
+*/
+pub fn infomap(graph: &WeightedGraph, seed: u64) -> Vec<usize> {
+    let node_count = graph.node_count();
+    if graph.total_weight() <= 0.0 {
+        return (0..node_count).collect();
+    }
+
+    let mut rng = SplitMix64::new(seed);
+    let mut communities: Vec<usize> = (0..node_count).collect();
+    let mut aggregate = graph.clone();
+
+    for _ in 0..MAX_PASSES {
+        let mut membership: Vec<usize> = (0..aggregate.node_count()).collect();
+        move_nodes(&aggregate, &mut membership, &mut rng);
+        let community_count = renumber(&mut membership);
+        for community in communities.iter_mut() {
+            *community = membership[*community];
+        }
+        if community_count == aggregate.node_count() {
+            break;
+        }
+        aggregate = aggregate.aggregate(&membership, community_count);
+    }
+
+    renumber(&mut communities);
+    communities
+}
+
+/// Move nodes to the neighboring module that shortens the map equation the most, until no move does.
+fn move_nodes(graph: &WeightedGraph, membership: &mut [usize], rng: &mut SplitMix64) {
+    let node_count = graph.node_count();
+    let total_weight = graph.total_weight();
+    let plogp = |weight: f64| {
+        let p = weight / total_weight;
+        if p > 0.0 { p * p.ln() } else { 0.0 }
+    };
+    // the part of the map equation that depends on a single module
+    let module_codelength = |exit: f64, flow: f64| plogp(exit + flow) - 2.0 * plogp(exit);
+
+    // the flow through every module and the weight of the edges leaving it
+    let mut flows = vec![0.0; node_count];
+    let mut exits = vec![0.0; node_count];
+    for node in 0..node_count {
+        flows[membership[node]] += graph.degree(node);
+        for (neighbor, weight) in graph.neighbors(node) {
+            if membership[*neighbor] != membership[node] {
+                exits[membership[node]] += weight;
+            }
+        }
+    }
+    let mut exit_sum: f64 = exits.iter().sum();
+
+    let mut order: Vec<usize> = (0..node_count).collect();
+    let mut edge_weights = vec![0.0; node_count];
+    let mut touched: Vec<usize> = Vec::new();
+    for _ in 0..MAX_ROUNDS {
+        rng.shuffle(&mut order);
+        let mut moved = false;
+        for node in &order {
+            let node = *node;
+            let current = membership[node];
+            let degree = graph.degree(node);
+            let external = degree - graph.self_weight(node);
+
+            for (neighbor, weight) in graph.neighbors(node) {
+                let module = membership[*neighbor];
+                if edge_weights[module] == 0.0 {
+                    touched.push(module);
+                }
+                edge_weights[module] += weight;
+            }
+
+            let current_exit = exits[current] - external + 2.0 * edge_weights[current];
+            let current_flow = flows[current] - degree;
+            let mut best = None;
+            let mut best_delta = -MIN_IMPROVEMENT;
+            for target in touched.iter().filter(|target| **target != current) {
+                let target_exit = exits[*target] + external - 2.0 * edge_weights[*target];
+                let target_flow = flows[*target] + degree;
+                let new_exit_sum = exit_sum - exits[current] - exits[*target] + current_exit + target_exit;
+                let delta = plogp(new_exit_sum) - plogp(exit_sum)
+                    + module_codelength(current_exit, current_flow)
+                    + module_codelength(target_exit, target_flow)
+                    - module_codelength(exits[current], flows[current])
+                    - module_codelength(exits[*target], flows[*target]);
+                if delta < best_delta {
+                    best = Some((*target, target_exit, target_flow));
+                    best_delta = delta;
+                }
+            }
+
+            if let Some((target, target_exit, target_flow)) = best {
+                exit_sum += current_exit + target_exit - exits[current] - exits[target];
+                exits[current] = current_exit;
+                flows[current] = current_flow;
+                exits[target] = target_exit;
+                flows[target] = target_flow;
+                membership[node] = target;
+                moved = true;
+            }
+
+            for module in touched.drain(..) {
+                edge_weights[module] = 0.0;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::utils::weighted_graph::tests::{bridged_cliques, groups};
+
+    #[test]
+    fn finds_bridged_cliques() {
+        let membership = infomap(&bridged_cliques(&[5, 5]), 42);
+        assert_eq!(groups(&membership), vec![(0..5).collect::<Vec<_>>(), (5..10).collect()]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_partition() {
+        let graph = bridged_cliques(&[4, 5, 6, 3]);
+        assert_eq!(infomap(&graph, 7), infomap(&graph, 7));
+    }
+
+    #[test]
+    fn nodes_without_edges_are_singletons() {
+        let graph = WeightedGraph::from_edges(3, []);
+        assert_eq!(infomap(&graph, 1), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,81 @@
+//! A native implementation of the label propagation community detection algorithm.
+
+use std::collections::BTreeMap;
+
+use crate::index::utils::leiden::renumber;
+use crate::index::utils::weighted_graph::{SplitMix64, WeightedGraph};
+
+/// The maximum number of propagation rounds.
+const MAX_ROUNDS: usize = 100;
+
+/**
+Partition a graph into communities with asynchronous label propagation.
+
+Every node starts with its own label and repeatedly adopts the label with the largest edge weight
+among its neighbors, until no label changes. It runs in near linear time, which makes it a good fit
+for very large graphs, at the cost of less stable communities than modularity based algorithms.
+
+Args:
+    - graph: The graph to partition.
+    - seed: The seed of the node visiting order, the same seed always gives the same partition.
+
+Returns the community of every node, numbered from 0 in order of first appearance.
+*/
+pub fn label_propagation(graph: &WeightedGraph, seed: u64) -> Vec<usize> {
+    let node_count = graph.node_count();
+    let mut rng = SplitMix64::new(seed);
+    let mut labels: Vec<usize> = (0..node_count).collect();
+    let mut order: Vec<usize> = (0..node_count).collect();
+
+    for _ in 0..MAX_ROUNDS {
+        rng.shuffle(&mut order);
+        let mut changed = false;
+        for node in &order {
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for (neighbor, weight) in graph.neighbors(*node) {
+                *weights.entry(labels[*neighbor]).or_default() += weight;
+            }
+            let Some(best_weight) = weights.values().copied().reduce(f64::max) else {
+                continue;
+            };
+            // keep the current label on ties, so the propagation settles
+            let current = labels[*node];
+            if weights.get(&current).is_some_and(|weight| *weight >= best_weight) {
+                continue;
+            }
+            let (label, _) = weights.into_iter().find(|(_, weight)| *weight >= best_weight).unwrap();
+            labels[*node] = label;
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    renumber(&mut labels);
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::utils::weighted_graph::tests::{bridged_cliques, groups};
+
+    #[test]
+    fn finds_bridged_cliques() {
+        let membership = label_propagation(&bridged_cliques(&[6, 6]), 42);
+        assert_eq!(groups(&membership), vec![(0..6).collect::<Vec<_>>(), (6..12).collect()]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_partition() {
+        let graph = bridged_cliques(&[4, 5, 6, 3]);
+        assert_eq!(label_propagation(&graph, 7), label_propagation(&graph, 7));
+    }
+
+    #[test]
+    fn nodes_without_edges_keep_their_label() {
+        let graph = WeightedGraph::from_edges(3, []);
+        assert_eq!(label_propagation(&graph, 1), vec![0, 1, 2]);
+    }
+}
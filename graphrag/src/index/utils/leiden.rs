@@ -0,0 +1,261 @@
+//! A native implementation of the Leiden community detection algorithm.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::index::utils::weighted_graph::{SplitMix64, WeightedGraph};
+
+/// The maximum number of move, refine and aggregate passes.
+const MAX_PASSES: usize = 64;
+
+/**
+Partition a graph into communities with the Leiden algorithm, maximizing modularity.
+
+Args:
+    - graph: The graph to partition.
+    - resolution: The modularity resolution, higher values produce more and smaller communities.
+    - seed: The seed of the node visiting order, the same seed always gives the same partition.
+
+Returns the community of every node, numbered from 0 in order of first appearance.
+*/
+pub fn leiden(graph: &WeightedGraph, resolution: f64, seed: u64) -> Vec<usize> {
+    let node_count = graph.node_count();
+    if graph.total_weight() <= 0.0 {
+        return (0..node_count).collect();
+    }
+
+    let mut rng = SplitMix64::new(seed);
+    let mut node_to_aggregate: Vec<usize> = (0..node_count).collect();
+    let mut aggregate = graph.clone();
+    let mut membership: Vec<usize> = (0..node_count).collect();
+
+    for _ in 0..MAX_PASSES {
+        move_nodes(&aggregate, &mut membership, resolution, &mut rng);
+        let community_count = renumber(&mut membership);
+        if community_count == aggregate.node_count() {
+            break;
+        }
+
+        let mut refined = refine(&aggregate, &membership, resolution, &mut rng);
+        let mut refined_count = renumber(&mut refined);
+        if refined_count == aggregate.node_count() {
+            // nothing could be merged during refinement, aggregate the communities themselves
+            refined = membership.clone();
+            refined_count = community_count;
+        }
+
+        // every aggregated node starts in the community its members were moved to
+        let mut next_membership = vec![0; refined_count];
+        for node in 0..aggregate.node_count() {
+            next_membership[refined[node]] = membership[node];
+        }
+        for node in node_to_aggregate.iter_mut() {
+            *node = refined[*node];
+        }
+        aggregate = aggregate.aggregate(&refined, refined_count);
+        membership = next_membership;
+    }
+
+    let mut communities: Vec<usize> = node_to_aggregate.iter().map(|node| membership[*node]).collect();
+    renumber(&mut communities);
+    communities
+}
+
+/// Move nodes to the neighboring community with the largest modularity gain until no move improves it.
+pub fn move_nodes(graph: &WeightedGraph, membership: &mut [usize], resolution: f64, rng: &mut SplitMix64) {
+    let node_count = graph.node_count();
+    let scale = resolution / graph.total_weight();
+    let mut community_weights = vec![0.0; node_count];
+    let mut community_sizes = vec![0usize; node_count];
+    for node in 0..node_count {
+        community_weights[membership[node]] += graph.degree(node);
+        community_sizes[membership[node]] += 1;
+    }
+    let mut empty: Vec<usize> = (0..node_count).filter(|community| community_sizes[*community] == 0).collect();
+
+    let mut order: Vec<usize> = (0..node_count).collect();
+    rng.shuffle(&mut order);
+    let mut queue: VecDeque<usize> = order.into();
+    let mut queued = vec![true; node_count];
+    let mut edge_weights = vec![0.0; node_count];
+    let mut touched: Vec<usize> = Vec::new();
+
+    while let Some(node) = queue.pop_front() {
+        queued[node] = false;
+        let current = membership[node];
+        let degree = graph.degree(node);
+
+        for (neighbor, weight) in graph.neighbors(node) {
+            let community = membership[*neighbor];
+            if edge_weights[community] == 0.0 {
+                touched.push(community);
+            }
+            edge_weights[community] += weight;
+        }
+
+        community_weights[current] -= degree;
+        community_sizes[current] -= 1;
+        let mut best = current;
+        let mut best_gain = edge_weights[current] - degree * community_weights[current] * scale;
+        for community in &touched {
+            let gain = edge_weights[*community] - degree * community_weights[*community] * scale;
+            if gain > best_gain {
+                best = *community;
+                best_gain = gain;
+            }
+        }
+        if best_gain < 0.0 {
+            // the node is better off on its own
+            best = if community_sizes[current] == 0 { current } else { empty.pop().unwrap() };
+        }
+
+        community_weights[best] += degree;
+        community_sizes[best] += 1;
+        membership[node] = best;
+        if best != current {
+            if community_sizes[current] == 0 {
+                empty.push(current);
+            }
+            for (neighbor, _) in graph.neighbors(node) {
+                if !queued[*neighbor] && membership[*neighbor] != best {
+                    queued[*neighbor] = true;
+                    queue.push_back(*neighbor);
+                }
+            }
+        }
+
+        for community in touched.drain(..) {
+            edge_weights[community] = 0.0;
+        }
+    }
+}
+
+/**
+Refine every community into well connected sub-communities.
+
+Every node starts on its own and may only join a sub-community of its own community, so the
+communities found by `move_nodes` are never made of disconnected parts.
+*/
+fn refine(graph: &WeightedGraph, membership: &[usize], resolution: f64, rng: &mut SplitMix64) -> Vec<usize> {
+    let node_count = graph.node_count();
+    let scale = resolution / graph.total_weight();
+    let mut community_weights = vec![0.0; node_count];
+    for node in 0..node_count {
+        community_weights[membership[node]] += graph.degree(node);
+    }
+
+    let mut refined: Vec<usize> = (0..node_count).collect();
+    let mut refined_weights: Vec<f64> = (0..node_count).map(|node| graph.degree(node)).collect();
+    let mut singleton = vec![true; node_count];
+    // the weight of the edges from every sub-community to the rest of its community
+    let mut external_weights: Vec<f64> = (0..node_count)
+        .map(|node| {
+            graph.neighbors(node)
+                .iter()
+                .filter(|(neighbor, _)| membership[*neighbor] == membership[node])
+                .map(|(_, weight)| weight)
+                .sum()
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..node_count).collect();
+    rng.shuffle(&mut order);
+    let mut edge_weights = vec![0.0; node_count];
+    let mut touched: Vec<usize> = Vec::new();
+
+    for node in order {
+        if !singleton[node] {
+            continue;
+        }
+        let community = membership[node];
+        let degree = graph.degree(node);
+        let well_connected =
+            |external: f64, weight: f64| external >= weight * (community_weights[community] - weight) * scale;
+        if !well_connected(external_weights[node], degree) {
+            continue;
+        }
+
+        for (neighbor, weight) in graph.neighbors(node) {
+            if membership[*neighbor] == community {
+                let target = refined[*neighbor];
+                if edge_weights[target] == 0.0 {
+                    touched.push(target);
+                }
+                edge_weights[target] += weight;
+            }
+        }
+
+        let mut best = None;
+        let mut best_gain = 0.0;
+        for target in &touched {
+            if !well_connected(external_weights[*target], refined_weights[*target]) {
+                continue;
+            }
+            let gain = edge_weights[*target] - degree * refined_weights[*target] * scale;
+            if gain > best_gain {
+                best = Some(*target);
+                best_gain = gain;
+            }
+        }
+        if let Some(target) = best {
+            external_weights[target] += external_weights[node] - 2.0 * edge_weights[target];
+            refined_weights[target] += degree;
+            refined[node] = target;
+            singleton[node] = false;
+            singleton[target] = false;
+        }
+
+        for target in touched.drain(..) {
+            edge_weights[target] = 0.0;
+        }
+    }
+
+    refined
+}
+
+/// Renumber community ids from 0 in order of first appearance, returning the number of communities.
+pub fn renumber(membership: &mut [usize]) -> usize {
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    for community in membership.iter_mut() {
+        let next = ids.len();
+        *community = *ids.entry(*community).or_insert(next);
+    }
+    ids.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::utils::weighted_graph::tests::{bridged_cliques, groups};
+
+    #[test]
+    fn finds_bridged_cliques() {
+        let membership = leiden(&bridged_cliques(&[5, 5]), 1.0, 42);
+        assert_eq!(groups(&membership), vec![(0..5).collect::<Vec<_>>(), (5..10).collect()]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_partition() {
+        let graph = bridged_cliques(&[4, 5, 6, 3]);
+        assert_eq!(leiden(&graph, 1.0, 7), leiden(&graph, 1.0, 7));
+        assert_eq!(groups(&leiden(&graph, 1.0, 7)).len(), 4);
+    }
+
+    #[test]
+    fn zero_resolution_merges_connected_nodes() {
+        let membership = leiden(&bridged_cliques(&[4, 4]), 0.0, 1);
+        assert_eq!(membership, vec![0; 8]);
+    }
+
+    #[test]
+    fn nodes_without_edges_are_singletons() {
+        let graph = WeightedGraph::from_edges(3, []);
+        assert_eq!(leiden(&graph, 1.0, 1), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn renumber_follows_first_appearance() {
+        let mut membership = vec![5, 5, 2, 9, 2];
+        assert_eq!(renumber(&mut membership), 3);
+        assert_eq!(membership, vec![0, 0, 1, 2, 1]);
+    }
+}
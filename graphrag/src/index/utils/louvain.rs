@@ -0,0 +1,72 @@
+//! A native implementation of the Louvain community detection algorithm.
+
+use crate::index::utils::leiden::{move_nodes, renumber};
+use crate::index::utils::weighted_graph::{SplitMix64, WeightedGraph};
+
+/// The maximum number of move and aggregate passes.
+const MAX_PASSES: usize = 64;
+
+/**
+Partition a graph into communities with the Louvain algorithm, maximizing modularity.
+
+Unlike Leiden, communities are aggregated as they are found, without refinement, which is faster
+but may leave communities that are internally disconnected.
+
+Args:
+    - graph: The graph to partition.
+    - resolution: The modularity resolution, higher values produce more and smaller communities.
+    - seed: The seed of the node visiting order, the same seed always gives the same partition.
+
+Returns the community of every node, numbered from 0 in order of first appearance.
+*/
+pub fn louvain(graph: &WeightedGraph, resolution: f64, seed: u64) -> Vec<usize> {
+    let node_count = graph.node_count();
+    if graph.total_weight() <= 0.0 {
+        return (0..node_count).collect();
+    }
+
+    let mut rng = SplitMix64::new(seed);
+    let mut communities: Vec<usize> = (0..node_count).collect();
+    let mut aggregate = graph.clone();
+
+    for _ in 0..MAX_PASSES {
+        let mut membership: Vec<usize> = (0..aggregate.node_count()).collect();
+        move_nodes(&aggregate, &mut membership, resolution, &mut rng);
+        let community_count = renumber(&mut membership);
+        for community in communities.iter_mut() {
+            *community = membership[*community];
+        }
+        if community_count == aggregate.node_count() {
+            break;
+        }
+        aggregate = aggregate.aggregate(&membership, community_count);
+    }
+
+    renumber(&mut communities);
+    communities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::utils::weighted_graph::tests::{bridged_cliques, groups};
+
+    #[test]
+    fn finds_bridged_cliques() {
+        let membership = louvain(&bridged_cliques(&[5, 5]), 1.0, 42);
+        assert_eq!(groups(&membership), vec![(0..5).collect::<Vec<_>>(), (5..10).collect()]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_partition() {
+        let graph = bridged_cliques(&[4, 5, 6, 3]);
+        assert_eq!(louvain(&graph, 1.0, 7), louvain(&graph, 1.0, 7));
+        assert_eq!(groups(&louvain(&graph, 1.0, 7)).len(), 4);
+    }
+
+    #[test]
+    fn nodes_without_edges_are_singletons() {
+        let graph = WeightedGraph::from_edges(3, []);
+        assert_eq!(louvain(&graph, 1.0, 1), vec![0, 1, 2]);
+    }
+}
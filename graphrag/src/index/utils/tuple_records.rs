@@ -0,0 +1,145 @@
+//! The delimited tuple records emitted by the extraction prompts, shared by the graph and claim extractors.
+
+use crate::language_model::protocol::base::ChatModel;
+
+pub const DEFAULT_TUPLE_DELIMITER: &str = "<|>";
+pub const DEFAULT_RECORD_DELIMITER: &str = "##";
+pub const DEFAULT_COMPLETION_DELIMITER: &str = "<|COMPLETE|>";
+
+/// The delimiters the model is asked to use between fields, between records and after the last record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordDelimiters {
+    pub tuple: String,
+    pub record: String,
+    pub completion: String,
+}
+
+impl Default for RecordDelimiters {
+    fn default() -> Self {
+        RecordDelimiters::new(None, None, None)
+    }
+}
+
+impl RecordDelimiters {
+    /// Init method definition, using the default delimiters for the ones not set.
+    pub fn new(tuple: Option<String>, record: Option<String>, completion: Option<String>) -> Self {
+        RecordDelimiters {
+            tuple: tuple.unwrap_or_else(|| DEFAULT_TUPLE_DELIMITER.into()),
+            record: record.unwrap_or_else(|| DEFAULT_RECORD_DELIMITER.into()),
+            completion: completion.unwrap_or_else(|| DEFAULT_COMPLETION_DELIMITER.into()),
+        }
+    }
+
+    /// Fill the delimiter placeholders of a prompt.
+    pub fn fill_prompt(&self, prompt: &str) -> String {
+        prompt
+            .replace("{tuple_delimiter}", &self.tuple)
+            .replace("{record_delimiter}", &self.record)
+            .replace("{completion_delimiter}", &self.completion)
+    }
+
+    /// Trim the content and strip the trailing completion delimiter.
+    pub fn strip_completion(&self, content: &str) -> String {
+        let content = content.trim();
+        content
+            .strip_suffix(self.completion.as_str())
+            .unwrap_or(content)
+            .trim()
+            .to_string()
+    }
+
+    /**
+    Split the model output into records and every record into its fields.
+
+    Records may be wrapped in parentheses; empty records and a stray completion delimiter are skipped.
+    Fields are returned as emitted, untrimmed.
+    */
+    pub fn split_records(&self, records: &str) -> Vec<Vec<String>> {
+        self.strip_completion(records)
+            .split(self.record.as_str())
+            .map(|record| record.trim())
+            .map(|record| record.strip_prefix('(').unwrap_or(record))
+            .map(|record| record.strip_suffix(')').unwrap_or(record))
+            .filter(|record| !record.is_empty() && *record != self.completion)
+            .map(|record| record.split(self.tuple.as_str()).map(str::to_string).collect())
+            .collect()
+    }
+}
+
+/**
+Prompt the model for records, then ask it for more until it has none left or `max_gleanings` is reached.
+
+Args:
+    - model: The chat model to prompt.
+    - prompt: The filled extraction prompt.
+    - continue_prompt: The prompt asking for the records missed so far.
+    - loop_prompt: The prompt asking whether records are still missing, answered with Y or N.
+    - max_gleanings: The maximum number of follow-up extractions.
+    - delimiters: The delimiters of the records.
+
+Returns the records of every answer, joined by the record delimiter.
+*/
+pub async fn glean_records<M: ChatModel>(
+    model: &M,
+    prompt: &str,
+    continue_prompt: &str,
+    loop_prompt: &str,
+    max_gleanings: usize,
+    delimiters: &RecordDelimiters,
+) -> Result<String, String> {
+    let mut response = model.achat(prompt, None).await?;
+    let mut records = delimiters.strip_completion(response.output().content());
+
+    // there are two exit criteria: (a) we hit the configured max, (b) the model says there are no more records
+    for i in 0..max_gleanings {
+        response = model.achat(continue_prompt, Some(response.history())).await?;
+        let extension = delimiters.strip_completion(response.output().content());
+        records.push_str(&delimiters.record);
+        records.push_str(&extension);
+
+        // if this is the final glean, don't bother updating the continuation flag
+        if i >= max_gleanings - 1 {
+            break;
+        }
+
+        response = model.achat(loop_prompt, Some(response.history())).await?;
+        if response.output().content().trim() != "Y" {
+            break;
+        }
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_split_into_fields() {
+        let delimiters = RecordDelimiters::default();
+        let records = "(entity<|>A<|>PERSON)##\n(entity<|>B<|>GEO)\n<|COMPLETE|>";
+        assert_eq!(
+            delimiters.split_records(records),
+            [vec!["entity", "A", "PERSON"], vec!["entity", "B", "GEO"]]
+        );
+    }
+
+    #[test]
+    fn empty_records_and_stray_completions_are_skipped() {
+        let delimiters = RecordDelimiters::default();
+        let records = "(a<|>b)####<|COMPLETE|>##(c)";
+        assert_eq!(delimiters.split_records(records), [vec!["a", "b"], vec!["c"]]);
+        assert!(delimiters.split_records("  <|COMPLETE|>  ").is_empty());
+    }
+
+    #[test]
+    fn custom_delimiters_fill_the_prompt() {
+        let delimiters = RecordDelimiters::new(Some("|".into()), Some(";".into()), None);
+        assert_eq!(
+            delimiters.fill_prompt("{tuple_delimiter} {record_delimiter} {completion_delimiter}"),
+            "| ; <|COMPLETE|>"
+        );
+        assert_eq!(delimiters.split_records("x|y;z"), [vec!["x", "y"], vec!["z"]]);
+    }
+}
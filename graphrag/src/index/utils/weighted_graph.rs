@@ -0,0 +1,326 @@
+//! A weighted undirected graph and seeded random generator shared by the native graph algorithms.
+
+use std::collections::{HashMap, VecDeque};
+
+use polars::prelude::DataFrame;
+
+use crate::data_model::schemas;
+
+/// A weighted undirected graph in adjacency list form.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedGraph {
+    /// The neighbors of every node with the weight of the edge to them, without self loops.
+    neighbors: Vec<Vec<(usize, f64)>>,
+    /// The weight of the self loop of every node, counted at both ends.
+    self_weights: Vec<f64>,
+    /// The weighted degree of every node.
+    degrees: Vec<f64>,
+    /// The sum of all weighted degrees, i.e. twice the total edge weight.
+    total_weight: f64,
+}
+
+impl WeightedGraph {
+    /// Build a graph from a list of (source, target, weight) edges, parallel edges are summed.
+    pub fn from_edges(node_count: usize, edges: impl IntoIterator<Item = (usize, usize, f64)>) -> Self {
+        let mut adjacency: Vec<HashMap<usize, f64>> = vec![HashMap::new(); node_count];
+        let mut self_weights = vec![0.0; node_count];
+        for (source, target, weight) in edges {
+            if source == target {
+                self_weights[source] += 2.0 * weight;
+            } else {
+                *adjacency[source].entry(target).or_default() += weight;
+                *adjacency[target].entry(source).or_default() += weight;
+            }
+        }
+        Self::from_adjacency(adjacency, self_weights)
+    }
+
+    /// Build a graph from the neighbor weights and self loop weights of every node.
+    pub fn from_adjacency(adjacency: Vec<HashMap<usize, f64>>, self_weights: Vec<f64>) -> Self {
+        let neighbors: Vec<Vec<(usize, f64)>> = adjacency
+            .into_iter()
+            .map(|neighbors| {
+                let mut neighbors: Vec<(usize, f64)> = neighbors.into_iter().collect();
+                neighbors.sort_by_key(|(neighbor, _)| *neighbor);
+                neighbors
+            })
+            .collect();
+        let degrees: Vec<f64> = neighbors
+            .iter()
+            .zip(&self_weights)
+            .map(|(neighbors, self_weight)| neighbors.iter().map(|(_, weight)| weight).sum::<f64>() + self_weight)
+            .collect();
+        let total_weight = degrees.iter().sum();
+        WeightedGraph {
+            neighbors,
+            self_weights,
+            degrees,
+            total_weight,
+        }
+    }
+
+    /// The number of nodes in the graph.
+    pub fn node_count(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    /// The weighted degree of a node.
+    pub fn degree(&self, node: usize) -> f64 {
+        self.degrees[node]
+    }
+
+    /// The weight of the self loop of a node, counted at both ends.
+    pub fn self_weight(&self, node: usize) -> f64 {
+        self.self_weights[node]
+    }
+
+    /// The sum of all weighted degrees, i.e. twice the total edge weight.
+    pub fn total_weight(&self) -> f64 {
+        self.total_weight
+    }
+
+    /// The neighbors of a node with the weight of the edge to them.
+    pub fn neighbors(&self, node: usize) -> &[(usize, f64)] {
+        &self.neighbors[node]
+    }
+
+    /// The subgraph induced by `nodes`, where node `i` of the subgraph is `nodes[i]`.
+    pub fn subgraph(&self, nodes: &[usize]) -> WeightedGraph {
+        let index: HashMap<usize, usize> = nodes.iter().enumerate().map(|(i, node)| (*node, i)).collect();
+        let adjacency = nodes
+            .iter()
+            .map(|node| {
+                self.neighbors[*node]
+                    .iter()
+                    .filter_map(|(neighbor, weight)| Some((*index.get(neighbor)?, *weight)))
+                    .collect()
+            })
+            .collect();
+        let self_weights = nodes.iter().map(|node| self.self_weights[*node]).collect();
+        Self::from_adjacency(adjacency, self_weights)
+    }
+
+    /**
+    Compute the modularity of a partition of the graph.
+
+    Args:
+        - membership: The community of every node.
+        - resolution: The modularity resolution.
+    */
+    pub fn modularity(&self, membership: &[usize], resolution: f64) -> f64 {
+        if self.total_weight <= 0.0 {
+            return 0.0;
+        }
+        let mut internal_weights: HashMap<usize, f64> = HashMap::new();
+        let mut community_weights: HashMap<usize, f64> = HashMap::new();
+        for node in 0..self.node_count() {
+            let community = membership[node];
+            *community_weights.entry(community).or_default() += self.degrees[node];
+            *internal_weights.entry(community).or_default() += self.self_weights[node]
+                + self.neighbors[node]
+                    .iter()
+                    .filter(|(neighbor, _)| membership[*neighbor] == community)
+                    .map(|(_, weight)| weight)
+                    .sum::<f64>();
+        }
+        community_weights
+            .into_iter()
+            .map(|(community, weight)| {
+                internal_weights[&community] / self.total_weight
+                    - resolution * (weight / self.total_weight).powi(2)
+            })
+            .sum()
+    }
+
+    /// The connected components of the graph, largest first.
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.node_count()];
+        let mut components = Vec::new();
+        for start in 0..self.node_count() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                component.push(node);
+                for (neighbor, _) in &self.neighbors[node] {
+                    if !visited[*neighbor] {
+                        visited[*neighbor] = true;
+                        queue.push_back(*neighbor);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        components
+    }
+
+    /// Collapse every community of `membership` into a single node, keeping internal edges as self loops.
+    pub fn aggregate(&self, membership: &[usize], community_count: usize) -> WeightedGraph {
+        let mut adjacency: Vec<HashMap<usize, f64>> = vec![HashMap::new(); community_count];
+        let mut self_weights = vec![0.0; community_count];
+        for node in 0..self.node_count() {
+            let community = membership[node];
+            self_weights[community] += self.self_weights[node];
+            for (neighbor, weight) in &self.neighbors[node] {
+                let other = membership[*neighbor];
+                if other == community {
+                    self_weights[community] += weight;
+                } else {
+                    *adjacency[community].entry(other).or_default() += weight;
+                }
+            }
+        }
+        Self::from_adjacency(adjacency, self_weights)
+    }
+}
+
+/// Read the graph of a relationships frame with source, target and Float64 weight columns, numbering nodes in order of first appearance.
+pub fn read_relationship_graph(relationships: &DataFrame) -> (Vec<String>, WeightedGraph) {
+    let sources = relationships.column(schemas::EDGE_SOURCE).unwrap().str().unwrap();
+    let targets = relationships.column(schemas::EDGE_TARGET).unwrap().str().unwrap();
+    let weights = relationships.column(schemas::EDGE_WEIGHT).unwrap().f64().unwrap();
+
+    let mut titles: Vec<String> = Vec::new();
+    let mut nodes: HashMap<String, usize> = HashMap::new();
+    let mut node = |title: &str| {
+        *nodes.entry(title.to_string()).or_insert_with(|| {
+            titles.push(title.to_string());
+            titles.len() - 1
+        })
+    };
+    let edges: Vec<(usize, usize, f64)> = sources
+        .into_iter()
+        .zip(targets)
+        .zip(weights)
+        .filter_map(|((source, target), weight)| Some((node(source?), node(target?), weight.unwrap_or(1.0))))
+        .collect();
+
+    let graph = WeightedGraph::from_edges(titles.len(), edges);
+    (titles, graph)
+}
+
+/// A small seeded pseudo random generator (SplitMix64), so clustering is reproducible.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    /// The next pseudo random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffle a slice in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Unit weight cliques of the given sizes, every clique joined to the next one by a single bridge edge.
+    pub fn bridged_cliques(sizes: &[usize]) -> WeightedGraph {
+        let mut edges = Vec::new();
+        let mut start = 0;
+        for size in sizes {
+            for source in start..start + size {
+                for target in source + 1..start + size {
+                    edges.push((source, target, 1.0));
+                }
+            }
+            if start > 0 {
+                edges.push((start - 1, start, 1.0));
+            }
+            start += size;
+        }
+        WeightedGraph::from_edges(start, edges)
+    }
+
+    /// The nodes of every community of a partition, in order of their first node.
+    pub fn groups(membership: &[usize]) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        for (node, community) in membership.iter().enumerate() {
+            let group = *index.entry(*community).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(node);
+        }
+        groups
+    }
+
+    #[test]
+    fn parallel_edges_are_summed_and_self_loops_counted_at_both_ends() {
+        let graph = WeightedGraph::from_edges(2, [(0, 1, 1.0), (1, 0, 2.0), (1, 1, 0.5)]);
+        assert_eq!(graph.neighbors(0), &[(1, 3.0)]);
+        assert_eq!(graph.self_weight(1), 1.0);
+        assert_eq!(graph.degree(1), 4.0);
+        assert_eq!(graph.total_weight(), 7.0);
+    }
+
+    #[test]
+    fn modularity_is_highest_for_the_cliques() {
+        let graph = bridged_cliques(&[4, 4]);
+        let cliques = [0, 0, 0, 0, 1, 1, 1, 1];
+        let whole = [0; 8];
+        let singletons: Vec<usize> = (0..8).collect();
+        let modularity = graph.modularity(&cliques, 1.0);
+        assert!(modularity > graph.modularity(&whole, 1.0));
+        assert!(modularity > graph.modularity(&singletons, 1.0));
+        assert!(graph.modularity(&whole, 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn subgraph_keeps_the_edges_between_its_nodes() {
+        let graph = bridged_cliques(&[3, 3]);
+        let subgraph = graph.subgraph(&[2, 3, 4]);
+        assert_eq!(subgraph.node_count(), 3);
+        assert_eq!(subgraph.neighbors(0), &[(1, 1.0)]);
+        assert_eq!(subgraph.total_weight(), 4.0);
+    }
+
+    #[test]
+    fn aggregate_keeps_the_total_weight() {
+        let graph = bridged_cliques(&[3, 4]);
+        let aggregate = graph.aggregate(&[0, 0, 0, 1, 1, 1, 1], 2);
+        assert_eq!(aggregate.node_count(), 2);
+        assert_eq!(aggregate.neighbors(0), &[(1, 1.0)]);
+        assert_eq!(aggregate.self_weight(0), 6.0);
+        assert_eq!(aggregate.total_weight(), graph.total_weight());
+    }
+
+    #[test]
+    fn connected_components_are_sorted_largest_first() {
+        let graph = WeightedGraph::from_edges(6, [(0, 1, 1.0), (2, 3, 1.0), (3, 4, 1.0)]);
+        assert_eq!(graph.connected_components(), vec![vec![2, 3, 4], vec![0, 1], vec![5]]);
+    }
+
+    #[test]
+    fn split_mix_is_reproducible() {
+        let mut first = SplitMix64::new(7);
+        let mut second = SplitMix64::new(7);
+        let mut items: Vec<usize> = (0..10).collect();
+        first.shuffle(&mut items);
+        let mut same: Vec<usize> = (0..10).collect();
+        second.shuffle(&mut same);
+        assert_eq!(items, same);
+        assert_ne!(items, (0..10).collect::<Vec<_>>());
+    }
+}
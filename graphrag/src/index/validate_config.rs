@@ -36,18 +36,19 @@ pub fn validate_config_names(logger: ProgressLogger, parameters: GraphRagConfig)
     )
     if embedding_llm_settings.max_retries == -1:
         embedding_llm_settings.max_retries = language_model_defaults.max_retries
-    embed_llm = ModelManager().register_embedding(
+    embedding_test = ModelManager().register_embedding(
         name="test-embed-llm",
         model_type=embedding_llm_settings.type,
         config=embedding_llm_settings,
         callbacks=NoopWorkflowCallbacks(),
         cache=None,
-    )
+    ).and_then(|embed_llm| asyncio.run(embed_llm.aembed_batch(["This is an LLM Embedding Test String"])))
 
-    try:
-        asyncio.run(embed_llm.aembed_batch(["This is an LLM Embedding Test String"]))
-        logger.success("Embedding LLM Config Params Validated")
-    except Exception as e:
-        logger.error(f"Embedding LLM configuration error detected. Exiting...\n{e}")
-        sys.exit(1)
+    match embedding_test {
+        Ok(_) => logger.success("Embedding LLM Config Params Validated"),
+        Err(e) => {
+            logger.error(f"Embedding LLM configuration error detected. Exiting...\n{e}")
+            sys.exit(1)
+        }
+    }
 }
@@ -2,8 +2,16 @@
 
 pub mod create_base_text_units;
 pub mod create_communities;
+pub mod create_community_reports;
+pub mod create_community_reports_text;
+pub mod create_graph_stats;
+pub mod extract_covariates;
 pub mod extract_graph;
+pub mod extract_graph_nlp;
+pub mod finalize_graph;
 pub mod generate_text_embeddings;
+pub mod prune_graph;
+pub mod snapshot_graph;
 
 // use crate::index.workflows.factory::PipelineFactory
 
@@ -19,6 +27,9 @@ pub mod generate_text_embeddings;
 // from .create_community_reports_text::(
 //     run_workflow as run_create_community_reports_text,
 // )
+// from .create_graph_stats::(
+//     run_workflow as run_create_graph_stats,
+// )
 // from .create_final_documents::(
 //     run_workflow as run_create_final_documents,
 // )
@@ -43,6 +54,9 @@ pub mod generate_text_embeddings;
 // from .prune_graph::(
 //     run_workflow as run_prune_graph,
 // )
+// from .snapshot_graph::(
+//     run_workflow as run_snapshot_graph,
+// )
 
 // # register all of our built-in workflows at once
 // PipelineFactory.register_all({
@@ -51,6 +65,7 @@ pub mod generate_text_embeddings;
 //     "create_community_reports_text": run_create_community_reports_text,
 //     "create_community_reports": run_create_community_reports,
 //     "extract_covariates": run_extract_covariates,
+//     "create_graph_stats": run_create_graph_stats,
 //     "create_final_documents": run_create_final_documents,
 //     "create_final_text_units": run_create_final_text_units,
 //     "extract_graph_nlp": run_extract_graph_nlp,
@@ -58,4 +73,5 @@ pub mod generate_text_embeddings;
 //     "finalize_graph": run_finalize_graph,
 //     "generate_text_embeddings": run_generate_text_embeddings,
 //     "prune_graph": run_prune_graph,
+//     "snapshot_graph": run_snapshot_graph,
 // })
@@ -1,15 +1,16 @@
 //! A module containing run_workflow method definition.
 
-// from datetime::datetime, timezone
-// from uuid::uuid4
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use ndarray::Array;
-use polars::prelude::{LazyFrame, col, JoinArgs, JoinType};
+use chrono::Utc;
+use polars::prelude::{DataFrame, DataType, IntoLazy, LazyFrame, NamedFrom, Series, col};
+use rustworkx_core::petgraph::graph::{NodeIndex, UnGraph};
+use uuid::Uuid;
 
+use crate::config::enums::ClusteringAlgorithmType;
 use crate::config::models::graph_rag_config::GraphRagConfig;
-use crate::data_model::schemas::COMMUNITIES_FINAL_COLUMNS;
+use crate::data_model::schemas;
 use crate::index::operations::cluster_graph::cluster_graph;
-use crate::index::operations::create_graph::create_graph;
 use crate::index::typing::context::PipelineRunContext;
 use crate::index::typing::workflow::WorkflowFunctionOutput;
 use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
@@ -29,114 +30,217 @@ pub async fn run_workflow(
     let output = create_communities(
         entities,
         relationships,
+        &config.cluster_graph.algorithm,
         max_cluster_size,
         use_lcc,
         Some(seed),
+        &config.cluster_graph.resolutions,
+        config.cluster_graph.max_depth,
+        config.cluster_graph.min_community_size,
     );
 
-    write_table_to_storage(output, "communities", context.storage).await;
+    write_table_to_storage(output.clone(), "communities", context.storage).await;
 
     WorkflowFunctionOutput {
         result: output
     }
 }
 
-/// All the steps to transform final communities.
+/**
+All the steps to transform final communities.
+
+Every community keeps its parent and children, even when none of its relationships are internal to
+it, so the `parent` and `children` columns always describe the same tree.
+*/
 pub fn create_communities(
     entities: LazyFrame,
     relationships: LazyFrame,
+    algorithm: &ClusteringAlgorithmType,
     max_cluster_size: usize,
     use_lcc: bool,
     seed: Option<usize>,
+    resolutions: &[f64],
+    max_depth: Option<usize>,
+    min_community_size: usize,
 ) -> LazyFrame {
-    let graph = create_graph(relationships);
+    let entities = entities
+        .select([col(schemas::ID), col(schemas::TITLE)])
+        .collect()
+        .unwrap();
+    let relationships = relationships
+        .select([
+            col(schemas::ID),
+            col(schemas::EDGE_SOURCE),
+            col(schemas::EDGE_TARGET),
+            col(schemas::EDGE_WEIGHT).cast(DataType::Float64),
+            col(schemas::TEXT_UNIT_IDS),
+        ])
+        .collect()
+        .unwrap();
+    let relationships = read_relationships(&relationships);
+
+    let mut graph: UnGraph<String, f64> = UnGraph::new_undirected();
+    let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
+    for relationship in &relationships {
+        let mut node = |title: &String| {
+            *nodes
+                .entry(title.clone())
+                .or_insert_with(|| graph.add_node(title.clone()))
+        };
+        let (source, target) = (node(&relationship.source), node(&relationship.target));
+        graph.add_edge(source, target, relationship.weight);
+    }
 
     let clusters = cluster_graph(
         graph,
+        algorithm,
         max_cluster_size,
         use_lcc,
-        seed=seed,
+        seed,
+        resolutions,
+        max_depth,
+        min_community_size,
     );
 
-    let mut communities = LazyFrame::from(
-        clusters, columns=pd.Index(["level", "community", "parent", "title"])
-    ).explode("title");
-    communities["community"] = communities["community"].astype(int);
+    let entity_ids: HashMap<&str, &str> = entities
+        .column(schemas::TITLE)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .zip(entities.column(schemas::ID).unwrap().str().unwrap())
+        .filter_map(|(title, id)| Some((title?, id?)))
+        .collect();
 
-    // aggregate entity ids for each community
-    let entity_ids = communities.join(entities, [col("title")], [col("title")], JoinArgs::new(JoinType::Inner));
-    let entity_ids = (
-        entity_ids.group_by("community").agg(entity_ids=("id", list)).reset_index()
-    );
+    // collect the children so we have a tree going both ways
+    let mut children: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for (_, community, parent, _) in &clusters {
+        children.entry(*parent).or_default().push(*community);
+    }
 
-    // aggregate relationships ids for each community
-    // these are limited to only those where the source and target are in the same community
-    let max_level = communities["level"].max();
-    let all_grouped = LazyFrame::from(
-        columns=["community", "level", "relationship_ids", "text_unit_ids"]
-    );
-    for level in 0..(max_level + 1) {
-        let communities_at_level = communities.loc[communities["level"] == level];
-        let sources = relationships.join(
-            communities_at_level, [col("source")], [col("title")], JoinArgs::new(JoinType::Inner)
-        );
-        let targets = sources.join(
-            communities_at_level, [col("target")], [col("title")], JoinArgs::new(JoinType::Inner)
-        );
-        let matched = targets.loc[targets["community_x"] == targets["community_y"]];
-        let text_units = matched.explode("text_unit_ids");
-        let grouped = text_units.group_by(["community_x", "level_x", "parent_x"])
-        .agg(relationship_ids=("id", list), text_unit_ids=("text_unit_ids", list))
-        .reset_index();
-        let grouped.rename(
-            columns={
-                "community_x": "community",
-                "level_x": "level",
-                "parent_x": "parent",
-            },
-            inplace=True,
-        );
-        let all_grouped = pd.concat([
-            all_grouped,
-            grouped[..][
-                ["community", "level", "parent", "relationship_ids", "text_unit_ids"]
-            ],
-        ]);
+    let period = Utc::now().date_naive().to_string();
+    let mut rows = CommunityRows::default();
+    for (level, community, parent, titles) in clusters {
+        let members: HashSet<&str> = titles.iter().map(String::as_str).collect();
+
+        // relationships are limited to only those where the source and target are in the same community
+        let mut relationship_ids = BTreeSet::new();
+        let mut text_unit_ids = BTreeSet::new();
+        for relationship in relationships.iter().filter(|relationship| {
+            members.contains(relationship.source.as_str()) && members.contains(relationship.target.as_str())
+        }) {
+            relationship_ids.insert(relationship.id.clone());
+            text_unit_ids.extend(relationship.text_unit_ids.iter().cloned());
+        }
+        let community_entity_ids: Vec<String> = titles
+            .iter()
+            .filter_map(|title| entity_ids.get(title.as_str()).map(|id| id.to_string()))
+            .collect();
+
+        rows.community.push(community);
+        rows.level.push(level);
+        rows.parent.push(parent);
+        rows.children.push(children.get(&community).cloned().unwrap_or_default());
+        rows.size.push(community_entity_ids.len() as i64);
+        rows.entity_ids.push(community_entity_ids);
+        rows.relationship_ids.push(relationship_ids.into_iter().collect());
+        rows.text_unit_ids.push(text_unit_ids.into_iter().collect());
     }
 
-    // deduplicate the lists
-    all_grouped["relationship_ids"] = all_grouped["relationship_ids"].apply(
-        |x| sorted(set(x))
-    );
-    all_grouped["text_unit_ids"] = all_grouped["text_unit_ids"].apply(
-        |x| sorted(set(x))
-    );
+    let list_column = |name: &str, lists: Vec<Vec<String>>| -> Series {
+        let lists: Vec<Series> = lists.into_iter().map(|list| Series::new("".into(), list)).collect();
+        Series::new(name.into(), lists)
+    };
+    let count = rows.community.len();
+    DataFrame::new(vec![
+        Series::new(
+            schemas::ID.into(),
+            (0..count).map(|_| Uuid::new_v4().to_string()).collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(schemas::SHORT_ID.into(), rows.community.clone()).into(),
+        Series::new(schemas::COMMUNITY_ID.into(), rows.community.clone()).into(),
+        Series::new(schemas::COMMUNITY_LEVEL.into(), rows.level).into(),
+        Series::new(schemas::COMMUNITY_PARENT.into(), rows.parent).into(),
+        Series::new(
+            schemas::COMMUNITY_CHILDREN.into(),
+            rows.children
+                .into_iter()
+                .map(|children| Series::new("".into(), children))
+                .collect::<Vec<_>>(),
+        )
+        .into(),
+        Series::new(
+            schemas::TITLE.into(),
+            rows.community.iter().map(|community| format!("Community {community}")).collect::<Vec<_>>(),
+        )
+        .into(),
+        list_column(schemas::ENTITY_IDS, rows.entity_ids).into(),
+        list_column(schemas::RELATIONSHIP_IDS, rows.relationship_ids).into(),
+        list_column(schemas::TEXT_UNIT_IDS, rows.text_unit_ids).into(),
+        // add fields for incremental update tracking
+        Series::new(schemas::PERIOD.into(), vec![period; count]).into(),
+        Series::new(schemas::SIZE.into(), rows.size).into(),
+    ])
+    .unwrap()
+    .lazy()
+    .select(schemas::COMMUNITIES_FINAL_COLUMNS.map(col))
+}
 
-    // join it all up and add some new fields
-    let mut final_communities = all_grouped.join(entity_ids, [col("community")], [col("community")], JoinArgs::new(JoinType::Inner));
-    final_communities["id"] = [str(uuid4()) for _ in range(len(final_communities))];
-    final_communities["human_readable_id"] = final_communities["community"];
-    final_communities["title"] = "Community " + final_communities["community"].astype(
-        str
-    );
-    final_communities["parent"] = final_communities["parent"].astype(int);
-    // collect the children so we have a tree going both ways
-    let parent_grouped = final_communities.group_by("parent").agg(children=("community", "unique"));
-    let final_communities = final_communities.join(
-        parent_grouped,
-        [col("community")],
-        [col("parent")],
-        JoinArgs::new(JoinType::Left),
-    );
-    // replace NaN children with empty list
-    final_communities["children"] = final_communities["children"].apply(
-        |x| x if isinstance(x, np.ndarray) else []
-    );
-    // add fields for incremental update tracking
-    final_communities["period"] = datetime.now(timezone.utc).date().isoformat();
-    final_communities["size"] = final_communities.loc[:, "entity_ids"].apply(len);
+/// A relationship, as needed to cluster the graph and aggregate communities.
+struct Relationship {
+    id: String,
+    source: String,
+    target: String,
+    weight: f64,
+    text_unit_ids: Vec<String>,
+}
+
+/// The columns of the final communities, one value per community.
+#[derive(Default)]
+struct CommunityRows {
+    community: Vec<i64>,
+    level: Vec<i64>,
+    parent: Vec<i64>,
+    children: Vec<Vec<i64>>,
+    entity_ids: Vec<Vec<String>>,
+    relationship_ids: Vec<Vec<String>>,
+    text_unit_ids: Vec<Vec<String>>,
+    size: Vec<i64>,
+}
 
-    final_communities[..][
-        COMMUNITIES_FINAL_COLUMNS,
-    ]
+/// Read the relationships of the graph.
+fn read_relationships(relationships: &DataFrame) -> Vec<Relationship> {
+    let string = |column: &str, row: usize| {
+        relationships
+            .column(column)
+            .unwrap()
+            .str()
+            .unwrap()
+            .get(row)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let weights = relationships.column(schemas::EDGE_WEIGHT).unwrap().f64().unwrap();
+    let text_unit_ids = relationships.column(schemas::TEXT_UNIT_IDS).unwrap().list().unwrap();
+
+    (0..relationships.height())
+        .map(|row| Relationship {
+            id: string(schemas::ID, row),
+            source: string(schemas::EDGE_SOURCE, row),
+            target: string(schemas::EDGE_TARGET, row),
+            weight: weights.get(row).unwrap_or(1.0),
+            text_unit_ids: text_unit_ids
+                .get_as_series(row)
+                .map(|ids| {
+                    ids.str()
+                        .unwrap()
+                        .into_iter()
+                        .flatten()
+                        .map(|id| id.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect()
 }
@@ -0,0 +1,182 @@
+//! A module containing run_workflow method definition.
+
+use std::path::Path;
+
+use polars::prelude::{DataType, LazyFrame, col, lit};
+
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::community_reports_config::CommunityReportsConfig;
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::data_model::schemas;
+use crate::index::operations::finalize_community_reports::finalize_community_reports;
+use crate::index::operations::summarize_communities::explode_communities::{
+    explode_communities, prep_community_nodes,
+};
+use crate::index::operations::summarize_communities::graph_context::context_builder::{
+    build_level_context, build_local_context,
+};
+use crate::index::operations::summarize_communities::summarize_communities::summarize_communities;
+use crate::index::operations::summarize_communities::typing::{
+    ClaimDetails, CommunityReport, EdgeDetails,
+};
+use crate::index::operations::summarize_communities::utils::get_community_hierarchy;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::language_model::manager::ModelManager;
+use crate::language_model::protocol::base::ChatModel;
+use crate::utils::dataframe::{get_i64, get_string};
+use crate::utils::storage::{load_table_from_storage, storage_has_table, write_table_to_storage};
+
+/// All the steps to transform community reports.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    let edges = load_table_from_storage("relationships", context.storage).await;
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let communities = load_table_from_storage("communities", context.storage).await;
+    let claims = if config.extract_claims.enabled && storage_has_table("covariates", context.storage).await {
+        Some(load_table_from_storage("covariates", context.storage).await)
+    } else {
+        None
+    };
+
+    let community_reports_llm_settings = config.get_language_model_config(
+        &config.community_reports.model_id
+    );
+    let num_threads = community_reports_llm_settings.concurrent_requests;
+    let model = ModelManager::get_instance().get_or_create_chat_model(
+        "community_reporting",
+        community_reports_llm_settings.r#type.as_str(),
+        community_reports_llm_settings,
+        context.callbacks,
+        context.cache,
+    );
+    let extraction_prompt = match &config.community_reports.graph_prompt {
+        Some(prompt) => {
+            let path = Path::new(&config.root_dir).join(prompt);
+            match std::fs::read_to_string(&path) {
+                Ok(prompt) => Some(prompt),
+                Err(error) => {
+                    let error_msg = format!("Failed to read the community report prompt {}: {error}", path.display());
+                    context.callbacks.error(&error_msg);
+                    raise ValueError(error_msg)
+                }
+            }
+        }
+        None => None,
+    };
+
+    let output = create_community_reports(
+        edges,
+        entities,
+        communities,
+        claims,
+        &context.callbacks,
+        model,
+        &config.community_reports,
+        extraction_prompt,
+        Vec::new(),
+        num_threads,
+    ).await;
+
+    write_table_to_storage(output, "community_reports", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: output
+    }
+}
+
+/**
+All the steps to transform community reports.
+
+Communities with a report in `existing_reports` keep it instead of being summarized again.
+*/
+pub async fn create_community_reports<M: ChatModel>(
+    edges_input: LazyFrame,
+    entities: LazyFrame,
+    communities: LazyFrame,
+    claims_input: Option<LazyFrame>,
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    config: &CommunityReportsConfig,
+    extraction_prompt: Option<String>,
+    existing_reports: Vec<CommunityReport>,
+    num_threads: usize, // = 4,
+) -> LazyFrame {
+    let nodes = prep_community_nodes(explode_communities(communities.clone(), entities));
+    let edges = prep_edges(edges_input);
+    let claims = claims_input.map(prep_claims);
+
+    let local_contexts = build_local_context(
+        &nodes,
+        &edges,
+        claims.as_deref(),
+        config.max_input_length,
+    );
+    let community_hierarchy = get_community_hierarchy(communities.clone());
+
+    let community_reports = summarize_communities(
+        &nodes,
+        &community_hierarchy,
+        local_contexts.as_slice(),
+        build_level_context,
+        callbacks,
+        model,
+        extraction_prompt,
+        config.max_length,
+        config.max_input_length,
+        existing_reports,
+        num_threads,
+    ).await;
+
+    finalize_community_reports(community_reports, communities)
+}
+
+/// Prepare the relationship details, filling in missing descriptions.
+fn prep_edges(input: LazyFrame) -> Vec<EdgeDetails> {
+    let edges = input
+        .select([
+            col(schemas::SHORT_ID).cast(DataType::Int64),
+            col(schemas::EDGE_SOURCE),
+            col(schemas::EDGE_TARGET),
+            col(schemas::DESCRIPTION).fill_null(lit("No Description")),
+            col(schemas::EDGE_DEGREE).cast(DataType::Int64),
+        ])
+        .collect()
+        .unwrap();
+
+    (0..edges.height())
+        .map(|row| EdgeDetails {
+            human_readable_id: get_i64(&edges, schemas::SHORT_ID, row),
+            source: get_string(&edges, schemas::EDGE_SOURCE, row),
+            target: get_string(&edges, schemas::EDGE_TARGET, row),
+            description: get_string(&edges, schemas::DESCRIPTION, row),
+            combined_degree: get_i64(&edges, schemas::EDGE_DEGREE, row),
+        })
+        .collect()
+}
+
+/// Prepare the claim details, filling in missing descriptions.
+fn prep_claims(input: LazyFrame) -> Vec<ClaimDetails> {
+    let claims = input
+        .select([
+            col(schemas::SHORT_ID).cast(DataType::Int64),
+            col(schemas::CLAIM_SUBJECT),
+            col(schemas::TYPE),
+            col(schemas::CLAIM_STATUS),
+            col(schemas::DESCRIPTION).fill_null(lit("No Description")),
+        ])
+        .collect()
+        .unwrap();
+
+    (0..claims.height())
+        .map(|row| ClaimDetails {
+            human_readable_id: get_i64(&claims, schemas::SHORT_ID, row),
+            subject_id: get_string(&claims, schemas::CLAIM_SUBJECT, row),
+            r#type: get_string(&claims, schemas::TYPE, row),
+            status: get_string(&claims, schemas::CLAIM_STATUS, row),
+            description: get_string(&claims, schemas::DESCRIPTION, row),
+        })
+        .collect()
+}
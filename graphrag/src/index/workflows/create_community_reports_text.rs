@@ -0,0 +1,121 @@
+//! A module containing run_workflow method definition.
+
+use std::path::Path;
+
+use polars::prelude::LazyFrame;
+
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::community_reports_config::CommunityReportsConfig;
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::finalize_community_reports::finalize_community_reports;
+use crate::index::operations::summarize_communities::explode_communities::{
+    explode_communities, prep_community_nodes,
+};
+use crate::index::operations::summarize_communities::summarize_communities::summarize_communities;
+use crate::index::operations::summarize_communities::text_unit_context::context_builder::{
+    build_level_context, build_local_context,
+};
+use crate::index::operations::summarize_communities::utils::get_community_hierarchy;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::language_model::manager::ModelManager;
+use crate::language_model::protocol::base::ChatModel;
+use crate::prompts::index::community_report_text_units::COMMUNITY_REPORT_TEXT_PROMPT;
+use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+
+/// All the steps to transform community reports.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let communities = load_table_from_storage("communities", context.storage).await;
+    let text_units = load_table_from_storage("text_units", context.storage).await;
+
+    let community_reports_llm_settings = config.get_language_model_config(
+        &config.community_reports.model_id
+    );
+    let num_threads = community_reports_llm_settings.concurrent_requests;
+    let model = ModelManager::get_instance().get_or_create_chat_model(
+        "community_reporting",
+        community_reports_llm_settings.r#type.as_str(),
+        community_reports_llm_settings,
+        context.callbacks,
+        context.cache,
+    );
+    let extraction_prompt = match config.community_reports.text_prompt.as_ref() {
+        Some(prompt) => {
+            let path = Path::new(&config.root_dir).join(prompt);
+            match std::fs::read_to_string(&path) {
+                Ok(prompt) => Some(prompt),
+                Err(error) => {
+                    let error_msg = format!("Failed to read the community report prompt {}: {error}", path.display());
+                    context.callbacks.error(&error_msg);
+                    raise ValueError(error_msg)
+                }
+            }
+        }
+        None => None,
+    };
+
+    let output = create_community_reports_text(
+        entities,
+        communities,
+        text_units,
+        &context.callbacks,
+        model,
+        &config.community_reports,
+        extraction_prompt,
+        num_threads,
+    ).await;
+
+    write_table_to_storage(output, "community_reports", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: output
+    }
+}
+
+/**
+All the steps to transform community reports.
+
+The community context is built from the raw text units of the member entities rather than
+from entity and relationship descriptions, so it works for graphs extracted without a model.
+*/
+pub async fn create_community_reports_text<M: ChatModel>(
+    entities: LazyFrame,
+    communities: LazyFrame,
+    text_units: LazyFrame,
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    config: &CommunityReportsConfig,
+    extraction_prompt: Option<String>,
+    num_threads: usize, // = 4,
+) -> LazyFrame {
+    let nodes_df = explode_communities(communities.clone(), entities);
+    let nodes = prep_community_nodes(nodes_df.clone());
+
+    let local_contexts = build_local_context(
+        communities.clone(),
+        text_units,
+        nodes_df,
+        config.max_input_length,
+    );
+    let community_hierarchy = get_community_hierarchy(communities.clone());
+
+    let community_reports = summarize_communities(
+        &nodes,
+        &community_hierarchy,
+        local_contexts.as_slice(),
+        build_level_context,
+        callbacks,
+        model,
+        Some(extraction_prompt.unwrap_or_else(|| COMMUNITY_REPORT_TEXT_PROMPT.into())),
+        config.max_length,
+        config.max_input_length,
+        Vec::new(),
+        num_threads,
+    ).await;
+
+    finalize_community_reports(community_reports, communities)
+}
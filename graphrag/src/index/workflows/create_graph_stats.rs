@@ -0,0 +1,47 @@
+//! A module containing run_workflow method definition.
+
+use log::info;
+
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::graph_stats::compute_graph_stats;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::stats::GraphStatsSummary;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::utils::storage::{load_table_from_storage, storage_has_table};
+
+/// Write a quality report of the entity graph to `graph_stats.json`.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    mut context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    if !config.graph_stats.enabled {
+        return WorkflowFunctionOutput { result: None };
+    }
+
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let relationships = load_table_from_storage("relationships", context.storage).await;
+    let communities = if storage_has_table("communities", context.storage).await {
+        Some(load_table_from_storage("communities", context.storage).await)
+    } else {
+        None
+    };
+
+    let stats = compute_graph_stats(entities, relationships, communities, config.graph_stats.top_hubs);
+    info!(
+        "Graph has {} entities, {} relationships, {} connected components and {} orphan entities",
+        stats.node_count,
+        stats.edge_count,
+        stats.connected_components,
+        stats.orphan_entities.len(),
+    );
+
+    context
+        .storage
+        .set("graph_stats.json", serde_json::to_string_pretty(&stats).unwrap(), None)
+        .await;
+    context.stats.graph_stats = Some(GraphStatsSummary::from(&stats));
+
+    WorkflowFunctionOutput {
+        result: Some(stats)
+    }
+}
@@ -0,0 +1,94 @@
+//! A module containing run_workflow method definition.
+
+use std::path::Path;
+
+use polars::prelude::LazyFrame;
+
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::extract_covariates::extract_covariates::extract_covariates as extractor;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::language_model::manager::ModelManager;
+use crate::language_model::protocol::base::ChatModel;
+use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+
+/// All the steps to extract and format covariates.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    if !config.extract_claims.enabled {
+        return WorkflowFunctionOutput { result: None };
+    }
+
+    let text_units = load_table_from_storage("text_units", context.storage).await;
+
+    let extract_claims_llm_settings = config.get_language_model_config(
+        &config.extract_claims.model_id
+    );
+    let num_threads = extract_claims_llm_settings.concurrent_requests;
+    let model = ModelManager::get_instance().get_or_create_chat_model(
+        "extract_claims",
+        extract_claims_llm_settings.r#type.as_str(),
+        extract_claims_llm_settings,
+        context.callbacks,
+        context.cache,
+    );
+    let extraction_prompt = match &config.extract_claims.prompt {
+        Some(prompt) => {
+            let path = Path::new(&config.root_dir).join(prompt);
+            match std::fs::read_to_string(&path) {
+                Ok(prompt) => Some(prompt),
+                Err(error) => {
+                    let error_msg = format!("Failed to read the claim extraction prompt {}: {error}", path.display());
+                    context.callbacks.error(&error_msg);
+                    raise ValueError(error_msg)
+                }
+            }
+        }
+        None => None,
+    };
+
+    let output = extract_covariates(
+        text_units,
+        &context.callbacks,
+        model,
+        "claim",
+        &config,
+        extraction_prompt,
+        Some(config.extract_graph.entity_types.clone()),
+        num_threads,
+    ).await;
+
+    write_table_to_storage(output, "covariates", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: Some(output)
+    }
+}
+
+/// All the steps to extract and format covariates.
+pub async fn extract_covariates<M: ChatModel>(
+    text_units: LazyFrame,
+    callbacks: &impl WorkflowCallbacks,
+    model: M,
+    covariate_type: &str,
+    config: &GraphRagConfig,
+    extraction_prompt: Option<String>,
+    entity_types: Option<Vec<String>>,
+    num_threads: usize, // = 4,
+) -> LazyFrame {
+    // the text unit id is copied to the output covariate table as text_unit_id
+    extractor(
+        text_units,
+        callbacks,
+        model,
+        "text",
+        covariate_type,
+        &config.extract_claims,
+        extraction_prompt,
+        entity_types,
+        num_threads,
+    ).await
+}
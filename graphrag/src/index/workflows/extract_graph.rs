@@ -1,17 +1,18 @@
 //! A module containing run_workflow method definition.
 
-use std::collections::HashMap;
-
 use polars::prelude::LazyFrame;
 
 use crate::cache::pipeline_cache::PipelineCache;
 use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
-use crate::config::enums::AsyncType;
 use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::compute_temporal_bounds::fill_valid_from_with_creation_date;
 use crate::index::operations::extract_graph::extract_graph::extract_graph as extractor;
-use crate::index::operations::summarize_descriptions::summarize_descriptions;
+use crate::index::operations::extract_graph::typing::ExtractGraphStrategy;
+use crate::index::operations::summarize_descriptions::{SummarizeStrategy, summarize_descriptions};
 use crate::index::typing::context::PipelineRunContext;
 use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::language_model::manager::ModelManager;
+use crate::language_model::protocol::base::ChatModel;
 use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
 
 
@@ -25,29 +26,58 @@ pub async fn run_workflow(
     let extract_graph_llm_settings = config.get_language_model_config(
         &config.extract_graph.model_id
     );
-    let extraction_strategy = config.extract_graph.resolved_strategy(
-        config.root_dir, extract_graph_llm_settings
+    let extraction_strategy = match config.extract_graph.resolved_strategy(&config.root_dir) {
+        Ok(strategy) => strategy,
+        Err(error_msg) => {
+            context.callbacks.error(&error_msg);
+            raise ValueError(error_msg)
+        }
+    };
+    let extraction_num_threads = extract_graph_llm_settings.concurrent_requests;
+    let extraction_model = ModelManager::get_instance().get_or_create_chat_model(
+        "extract_graph",
+        extract_graph_llm_settings.r#type.as_str(),
+        extract_graph_llm_settings,
+        context.callbacks,
+        context.cache,
     );
 
     let summarization_llm_settings = config.get_language_model_config(
-        config.summarize_descriptions.model_id
-    )
-    let summarization_strategy = config.summarize_descriptions.resolved_strategy(
-        config.root_dir, summarization_llm_settings
+        &config.summarize_descriptions.model_id
+    );
+    let summarization_num_threads = summarization_llm_settings.concurrent_requests;
+    let summarization_model = ModelManager::get_instance().get_or_create_chat_model(
+        "summarize_descriptions",
+        summarization_llm_settings.r#type.as_str(),
+        summarization_llm_settings,
+        context.callbacks,
+        context.cache,
     );
+    let summarization_strategy = match config.summarize_descriptions.resolved_strategy(&config.root_dir) {
+        Ok(strategy) => strategy,
+        Err(error_msg) => {
+            context.callbacks.error(&error_msg);
+            raise ValueError(error_msg)
+        }
+    };
 
     let (entities, relationships) = extract_graph(
-        text_units=text_units,
-        callbacks=context.callbacks,
-        cache=context.cache,
-        extraction_strategy=extraction_strategy,
-        extraction_num_threads=extract_graph_llm_settings.concurrent_requests,
-        extraction_async_mode=extract_graph_llm_settings.async_mode,
-        entity_types=config.extract_graph.entity_types,
-        summarization_strategy=summarization_strategy,
-        summarization_num_threads=summarization_llm_settings.concurrent_requests,
+        text_units,
+        context.callbacks,
+        context.cache,
+        extraction_model,
+        &extraction_strategy,
+        extraction_num_threads,
+        Some(config.extract_graph.entity_types.clone()),
+        summarization_model,
+        &summarization_strategy,
+        summarization_num_threads,
     ).await;
 
+    // relationships without an extracted start date are assumed valid from their source document creation date
+    let documents = load_table_from_storage("documents", context.storage).await;
+    let relationships = fill_valid_from_with_creation_date(relationships, text_units, documents);
+
     write_table_to_storage(entities, "entities", context.storage).await;
     write_table_to_storage(relationships, "relationships", context.storage).await;
 
@@ -64,24 +94,24 @@ pub async fn extract_graph(
     text_units: LazyFrame,
     callbacks: WorkflowCallbacks,
     cache: PipelineCache,
-    extraction_strategy: Option<HashMap<String, Box<dyn Any>>>, // = None,
+    extraction_model: impl ChatModel,
+    extraction_strategy: &ExtractGraphStrategy,
     extraction_num_threads: usize, // = 4,
-    extraction_async_mode: AsyncType, // = AsyncType::AsyncIO,
     entity_types: Option<Vec<String>>, // = None,
-    summarization_strategy: Option<HashMap<String, Box<dyn Any>>>, // = None,
+    summarization_model: impl ChatModel,
+    summarization_strategy: &SummarizeStrategy,
     summarization_num_threads: usize, // = 4,
 ) -> (LazyFrame, LazyFrame) {
     // this returns a graph for each text unit, to be merged later
     let (extracted_entities, extracted_relationships) = extractor(
-        text_units=text_units,
-        callbacks=callbacks,
-        cache=cache,
-        text_column="text",
-        id_column="id",
-        strategy=extraction_strategy,
-        async_mode=extraction_async_mode,
-        entity_types=entity_types,
-        num_threads=extraction_num_threads,
+        text_units,
+        &callbacks,
+        extraction_model,
+        "text",
+        "id",
+        extraction_strategy,
+        entity_types,
+        extraction_num_threads,
     ).await;
 
     if !_validate_data(extracted_entities) {
@@ -103,6 +133,7 @@ pub async fn extract_graph(
         extracted_relationships=extracted_relationships,
         callbacks=callbacks,
         cache=cache,
+        summarization_model=summarization_model,
         summarization_strategy=summarization_strategy,
         summarization_num_threads=summarization_num_threads,
     ).await;
@@ -116,14 +147,16 @@ pub async fn get_summarized_entities_relationships(
     extracted_relationships: LazyFrame,
     callbacks: WorkflowCallbacks,
     cache: PipelineCache,
-    summarization_strategy: Option<HashMap<String, Box<dyn Any>>>, // = None,
+    summarization_model: impl ChatModel,
+    summarization_strategy: &SummarizeStrategy,
     summarization_num_threads: usize, // = 4,
 ) -> (LazyFrame, LazyFrame) {
     let (entity_summaries, relationship_summaries) = summarize_descriptions(
-        extracted_entities,
-        extracted_relationships,
-        callbacks=callbacks,
-        cache=cache,
+        extracted_entities.clone(),
+        extracted_relationships.clone(),
+        &callbacks,
+        &mut cache,
+        summarization_model,
         summarization_strategy,
         summarization_num_threads,
     ).await;
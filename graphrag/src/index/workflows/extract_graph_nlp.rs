@@ -0,0 +1,66 @@
+//! A module containing run_workflow method definition.
+
+use polars::prelude::{LazyFrame, lit};
+
+use crate::cache::pipeline_cache::PipelineCache;
+use crate::callbacks::workflow_callbacks::WorkflowCallbacks;
+use crate::config::models::extract_graph_nlp_config::ExtractGraphNLPConfig;
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::data_model::schemas;
+use crate::index::operations::build_noun_graph::build_noun_graph::build_noun_graph;
+use crate::index::operations::build_noun_graph::np_extractors::factory::create_noun_phrase_extractor;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+
+/// All the steps to create the base entity graph.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    let text_units = load_table_from_storage("text_units", context.storage).await;
+
+    let output = extract_graph_nlp(text_units, &mut context.cache, &config.extract_graph_nlp).await;
+    let (entities, relationships) = match output {
+        Ok(output) => output,
+        Err(error_msg) => {
+            context.callbacks.error(&error_msg);
+            raise ValueError(error_msg)
+        }
+    };
+
+    write_table_to_storage(entities, "entities", context.storage).await;
+    write_table_to_storage(relationships, "relationships", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: {
+            "entities": entities,
+            "relationships": relationships,
+        }
+    }
+}
+
+/// All the steps to create the base entity graph, failing if the configured noun phrase extractor is not available.
+pub async fn extract_graph_nlp<C: PipelineCache<String>>(
+    text_units: LazyFrame,
+    cache: &mut C,
+    extraction_config: &ExtractGraphNLPConfig,
+) -> Result<(LazyFrame, LazyFrame), String> {
+    let text_analyzer = create_noun_phrase_extractor(&extraction_config.text_analyzer)?;
+    let (extracted_nodes, extracted_edges) = build_noun_graph(
+        text_units,
+        text_analyzer.as_ref(),
+        extraction_config.normalize_edge_weights,
+        extraction_config.concurrent_requests,
+        cache,
+    ).await;
+
+    // add in any other columns required by downstream workflows
+    let extracted_nodes = extracted_nodes.with_columns([
+        lit("NOUN PHRASE").alias(schemas::TYPE),
+        lit("").alias(schemas::DESCRIPTION),
+    ]);
+    let extracted_edges = extracted_edges.with_column(lit("").alias(schemas::DESCRIPTION));
+
+    Ok((extracted_nodes, extracted_edges))
+}
@@ -0,0 +1,35 @@
+//! A module containing run_workflow method definition.
+
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::finalize_graph::finalize_graph;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+
+/// All the steps to finalize the entity graph.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let relationships = load_table_from_storage("relationships", context.storage).await;
+
+    let (final_entities, final_relationships) = finalize_graph(
+        entities,
+        relationships,
+        context.callbacks,
+        Some(config.embed_graph),
+        config.umap.enabled,
+        &config.graph_centrality,
+    );
+
+    write_table_to_storage(final_entities.clone(), "entities", context.storage).await;
+    write_table_to_storage(final_relationships.clone(), "relationships", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: {
+            "entities": final_entities,
+            "relationships": final_relationships,
+        }
+    }
+}
@@ -20,11 +20,14 @@ use crate::config::embeddings::{
     RELATIONSHIP_DESCRIPTION_EMBEDDING,
     TEXT_UNIT_TEXT_EMBEDDING,
 };
+use crate::config::enums::EmbeddingSnapshotFormat;
 use crate::config::models::graph_rag_config::GraphRagConfig;
 use crate::index::operations::embed_text::embed_text;
+use crate::index::operations::snapshot_embeddings::snapshot_embeddings;
 use crate::index::typing::context::PipelineRunContext;
 use crate::index::typing::workflow::WorkflowFunctionOutput;
-use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::utils::storage::load_table_from_storage;
 
 /// All the steps to transform community reports.
 pub async fn run_workflow(
@@ -50,19 +53,18 @@ pub async fn run_workflow(
         community_reports,
         context.callbacks,
         context.cache,
+        &mut context.storage,
         text_embed,
         embedded_fields,
+        config.snapshots.embeddings.then_some(config.snapshots.embeddings_format),
     ).await;
-
-    if config.snapshots.embeddings {
-        for (name, table) in output.items() {
-            write_table_to_storage(
-                table,
-                format!("embeddings.{name}"),
-                context.storage,
-            ).await;
+    let output = match output {
+        Ok(output) => output,
+        Err(error_msg) => {
+            context.callbacks.error(&error_msg);
+            raise ValueError(error_msg)
         }
-    }
+    };
 
     WorkflowFunctionOutput {
         result: output
@@ -78,9 +80,11 @@ pub async fn generate_text_embeddings(
     community_reports: Option<LazyFrame>,
     callbacks: WorkflowCallbacks,
     cache: PipelineCache,
+    storage: &mut (impl PipelineStorage<String> + PipelineStorage<Vec<u8>>),
     text_embed_config: dict,
     embedded_fields: HashSet<String>,
-) -> HashMap<&str, LazyFrame> {
+    snapshot_format: Option<EmbeddingSnapshotFormat>,
+) -> Result<HashMap<&str, LazyFrame>, String> {
     let embedding_param_map = {
         DOCUMENT_TEXT_EMBEDDING: {
             "data": documents.loc[:, ["id", "text"]] if documents is not None else None,
@@ -103,7 +107,7 @@ pub async fn generate_text_embeddings(
             "embed_column": "title",
         },
         ENTITY_DESCRIPTION_EMBEDDING: {
-            "data": entities.loc[:, ["id", "title", "description"]].assign(
+            "data": entities.loc[:, ["id", "title", "description", "type", "valid_from", "valid_to"]].assign(
                 title_description=lambda df: df["title"] + ":" + df["description"]
             )
             if entities is not None
@@ -137,30 +141,39 @@ pub async fn generate_text_embeddings(
             field,
             callbacks,
             cache,
+            storage,
             text_embed_config,
+            snapshot_format,
             **embedding_param_map[field],
-        ).await;
+        ).await?;
     }
-    outputs
+    Ok(outputs)
 }
 
-/// All the steps to generate single embedding.
+/// All the steps to generate single embedding, writing its snapshot when a format is given.
 async fn _run_and_snapshot_embeddings(
     name: &str,
     data: LazyFrame,
     embed_column: &str,
     callbacks: WorkflowCallbacks,
     cache: PipelineCache,
+    storage: &mut (impl PipelineStorage<String> + PipelineStorage<Vec<u8>>),
     text_embed_config: dict,
-) -> LazyFrame {
+    snapshot_format: Option<EmbeddingSnapshotFormat>,
+) -> Result<LazyFrame, String> {
     data["embedding"] = embed_text(
         data,
         callbacks,
         cache,
+        storage,
         embed_column,
         text_embed_config["strategy"],
         name,
-    ).await;
+    ).await?;
+
+    if let Some(format) = snapshot_format {
+        snapshot_embeddings(data.clone(), embed_column, name, storage, format).await?;
+    }
 
-    data[..][["id", "embedding"]]
+    Ok(data[..][["id", "embedding"]])
 }
@@ -0,0 +1,98 @@
+//! A module containing run_workflow method definition.
+
+use log::info;
+use polars::prelude::{DataFrame, JoinArgs, JoinType, LazyFrame, Series, col, lit, IntoLazy};
+
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::config::models::prune_graph_config::PruneGraphConfig;
+use crate::data_model::schemas::{EDGE_SOURCE, EDGE_TARGET, EDGE_WEIGHT, NODE_FREQUENCY, TITLE};
+use crate::index::operations::create_graph::create_graph;
+use crate::index::operations::prune_graph::{PruneGraphStats, prune_graph as prune_graph_operation};
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::utils::storage::{load_table_from_storage, write_table_to_storage};
+
+/// All the steps to prune the entity graph.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let relationships = load_table_from_storage("relationships", context.storage).await;
+
+    let (pruned_entities, pruned_relationships, stats) = prune_graph(
+        entities,
+        relationships,
+        &config.prune_graph,
+    );
+
+    for rule in &stats.rules {
+        info!(
+            "Graph pruning rule {} removed {} nodes and {} edges",
+            rule.rule, rule.nodes_removed, rule.edges_removed
+        );
+    }
+
+    write_table_to_storage(pruned_entities, "entities", context.storage).await;
+    write_table_to_storage(pruned_relationships, "relationships", context.storage).await;
+
+    WorkflowFunctionOutput {
+        result: {
+            "entities": pruned_entities,
+            "relationships": pruned_relationships,
+            "stats": stats,
+        }
+    }
+}
+
+/// Prune a full graph based on graph statistics.
+pub fn prune_graph(
+    entities: LazyFrame,
+    relationships: LazyFrame,
+    pruning_config: &PruneGraphConfig,
+) -> (LazyFrame, LazyFrame, PruneGraphStats) {
+    // create a temporary graph to prune, then turn it back into dataframes
+    let graph = create_graph(
+        relationships.clone(),
+        Some(vec![EDGE_WEIGHT.into()]),
+        Some(entities.clone()),
+        TITLE,
+    );
+
+    let (pruned, stats) = prune_graph_operation(
+        graph,
+        |node| node[NODE_FREQUENCY],
+        |edge| edge[EDGE_WEIGHT],
+        pruning_config,
+    );
+
+    let pruned_titles = Series::new(
+        TITLE.into(),
+        pruned.node_weights().map(|node| node[TITLE]).collect::<Vec<String>>(),
+    );
+    let pruned_nodes = entities.filter(col(TITLE).is_in(lit(pruned_titles)));
+
+    // keep only relationships whose (source, target) pair survived, in either direction
+    let (sources, targets): (Vec<String>, Vec<String>) = pruned
+        .edge_indices()
+        .filter_map(|edge| pruned.edge_endpoints(edge))
+        .flat_map(|(source, target)| {
+            let (source, target) = (pruned[source][TITLE], pruned[target][TITLE]);
+            [(source.clone(), target.clone()), (target, source)]
+        })
+        .unzip();
+    let pruned_edge_pairs = DataFrame::new(vec![
+        Series::new(EDGE_SOURCE.into(), sources).into(),
+        Series::new(EDGE_TARGET.into(), targets).into(),
+    ])
+    .unwrap()
+    .lazy();
+    let pruned_edges = relationships.join(
+        pruned_edge_pairs,
+        [col(EDGE_SOURCE), col(EDGE_TARGET)],
+        [col(EDGE_SOURCE), col(EDGE_TARGET)],
+        JoinArgs::new(JoinType::Semi),
+    );
+
+    (pruned_nodes, pruned_edges, stats)
+}
@@ -0,0 +1,40 @@
+//! A module containing run_workflow method definition.
+
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::index::operations::snapshot_graph::snapshot_graph;
+use crate::index::typing::context::PipelineRunContext;
+use crate::index::typing::workflow::WorkflowFunctionOutput;
+use crate::utils::storage::{load_table_from_storage, storage_has_table};
+
+/// Write the finalized graph to `graph.graphml` and `graph.gexf`, with the community of every entity once clustered.
+pub async fn run_workflow(
+    config: GraphRagConfig,
+    context: PipelineRunContext,
+) -> WorkflowFunctionOutput {
+    if !config.snapshots.graphml && !config.snapshots.gexf {
+        return WorkflowFunctionOutput { result: None };
+    }
+
+    let entities = load_table_from_storage("entities", context.storage).await;
+    let relationships = load_table_from_storage("relationships", context.storage).await;
+    let communities = if storage_has_table("communities", context.storage).await {
+        Some(load_table_from_storage("communities", context.storage).await)
+    } else {
+        None
+    };
+
+    snapshot_graph(
+        entities,
+        relationships,
+        communities,
+        "graph",
+        context.storage,
+        config.snapshots.graphml,
+        config.snapshots.gexf,
+    )
+    .await;
+
+    WorkflowFunctionOutput {
+        result: None
+    }
+}
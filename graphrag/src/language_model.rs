@@ -3,4 +3,5 @@
 pub mod factory;
 pub mod manager;
 pub mod protocol;
+pub mod providers;
 pub mod response;
@@ -8,20 +8,21 @@ use crate::language_model::providers::fnllm::models::{
     OpenAIChatFNLLM,
     OpenAIEmbeddingFNLLM,
 };
+use crate::language_model::providers::onnx::OnnxEmbeddingModel;
 
 /// A factory for creating Model instances.
 pub struct ModelFactory {
     _chat_registry: ClassVar[dict[str, Callable[..., ChatModel]]] = {}
-    _embedding_registry: ClassVar[dict[str, Callable[..., EmbeddingModel]]] = {}
+    _embedding_registry: ClassVar[dict[str, Callable[..., Result<EmbeddingModel, String>]]] = {}
 
     /// Register a ChatModel implementation.
     pub fn register_chat(cls, model_type: str, creator: Callable[..., ChatModel]) {
         cls._chat_registry[model_type] = creator
     }
 
-    /// Register an EmbeddingModel implementation.
+    /// Register an EmbeddingModel implementation, created or failing with the reason it could not be.
     pub fn register_embedding(
-        cls, model_type: str, creator: Callable[..., EmbeddingModel]
+        cls, model_type: str, creator: Callable[..., Result<EmbeddingModel, String>]
     ) {
         cls._embedding_registry[model_type] = creator
     }
@@ -54,12 +55,11 @@ pub struct ModelFactory {
 
     Returns
     -------
-        An EmbeddingLLM instance.
+        An EmbeddingLLM instance, or an error if the type is not registered or the model could not be created.
     */
-    pub fn create_embedding_model(cls, model_type: str, **kwargs: Any) -> EmbeddingModel {
+    pub fn create_embedding_model(cls, model_type: str, **kwargs: Any) -> Result<EmbeddingModel, String> {
         if model_type not in cls._embedding_registry {
-            msg = format!("EmbeddingModel implementation '{model_type}' is not registered.")
-            raise ValueError(msg)
+            return Err(format!("EmbeddingModel implementation '{model_type}' is not registered."));
         }
         cls._embedding_registry[model_type](**kwargs)
     }
@@ -101,8 +101,12 @@ ModelFactory::register_chat(
 )
 
 ModelFactory::register_embedding(
-    ModelType::AzureOpenAIEmbedding, lambda **kwargs: AzureOpenAIEmbeddingFNLLM(**kwargs)
+    ModelType::AzureOpenAIEmbedding, lambda **kwargs: Ok(AzureOpenAIEmbeddingFNLLM(**kwargs))
+)
+ModelFactory::register_embedding(
+    ModelType::OpenAIEmbedding, lambda **kwargs: Ok(OpenAIEmbeddingFNLLM(**kwargs))
 )
 ModelFactory::register_embedding(
-    ModelType::OpenAIEmbedding, lambda **kwargs: OpenAIEmbeddingFNLLM(**kwargs)
+    ModelType::OnnxEmbedding,
+    lambda **kwargs: OnnxEmbeddingModel::new(kwargs["name"], kwargs["config"]),
 )
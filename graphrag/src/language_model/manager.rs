@@ -60,15 +60,17 @@ impl ModelManager {
         name: Unique identifier for the EmbeddingsLLM instance.
         embedding_key: Key for the EmbeddingsLLM implementation in LLMFactory.
         **embedding_kwargs: Additional parameters for instantiation.
+
+    Fails, registering nothing, if the model could not be created.
      */
     pub fn register_embedding(
         self, name: str, model_type: str, **embedding_kwargs: Any
-    ) -> EmbeddingModel {
+    ) -> Result<EmbeddingModel, String> {
         embedding_kwargs["name"] = name
         self.embedding_models[name] = ModelFactory.create_embedding_model(
             model_type, **embedding_kwargs
-        )
-        return self.embedding_models[name]
+        )?;
+        Ok(self.embedding_models[name])
     }
 
     /**
@@ -129,10 +131,10 @@ impl ModelManager {
      */
     pub fn get_or_create_embedding_model(
         &self, name: &str, model_type: &str, **embedding_kwargs: Any
-    ) -> EmbeddingModel {
+    ) -> Result<EmbeddingModel, String> {
         if name not in self.embedding_models:
             return self.register_embedding(name, model_type, **embedding_kwargs)
-        return self.embedding_models[name]
+        Ok(self.embedding_models[name])
     }
 
     /// Remove the ChatLLM instance registered under the given name.
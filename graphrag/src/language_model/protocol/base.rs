@@ -13,54 +13,50 @@ pub trait EmbeddingModel {
 
     Args:
         text: The text to generate an embedding for.
-        **kwargs: Additional keyword arguments (e.g., model parameters).
 
     Returns
     -------
-        A collections of list of floats representing the embedding vector for each item in the batch.
+        A collections of list of floats representing the embedding vector for each item in the batch,
+        or the error the model failed with.
      */
-    async fn aembed_batch(
-        self, text_list: Vec<String>, **kwargs: Any
-    ) -> Vec<Vec<f64>>;
+    async fn aembed_batch(&self, text_list: Vec<String>) -> Result<Vec<Vec<f32>>, String>;
 
     /**
     Generate an embedding vector for the given text.
 
     Args:
         text: The text to generate an embedding for.
-        **kwargs: Additional keyword arguments (e.g., model parameters).
 
     Returns
     -------
-        A list of floats representing the embedding vector.
+        A list of floats representing the embedding vector, or the error the model failed with.
      */
-    async fn aembed(self, text: str, **kwargs: Any) -> Vec<f64>;
+    async fn aembed(&self, text: &str) -> Result<Vec<f32>, String>;
 
     /**
     Generate an embedding vector for the given list of strings.
 
     Args:
         text: The text to generate an embedding for.
-        **kwargs: Additional keyword arguments (e.g., model parameters).
 
     Returns
     -------
-        A collections of list of floats representing the embedding vector for each item in the batch.
+        A collections of list of floats representing the embedding vector for each item in the batch,
+        or the error the model failed with.
     */
-    fn embed_batch(self, text_list: Vec<String>, **kwargs: Any) -> Vec<Vec<f64>>;
+    fn embed_batch(&self, text_list: Vec<String>) -> Result<Vec<Vec<f32>>, String>;
 
     /**
     Generate an embedding vector for the given text.
 
     Args:
         text: The text to generate an embedding for.
-        **kwargs: Additional keyword arguments (e.g., model parameters).
 
     Returns
     -------
-        A list of floats representing the embedding vector.
+        A list of floats representing the embedding vector, or the error the model failed with.
      */
-    fn embed(self, text: str, **kwargs: Any) -> Vec<f64>;
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
 }
 
 /**
@@ -0,0 +1,3 @@
+//! Model providers shipped with GraphRAG.
+
+pub mod onnx;
@@ -0,0 +1,432 @@
+//! A local ONNX sentence-transformer embedding model.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::info;
+use ndarray::{Array2, ArrayViewD};
+use ort::session::Session;
+use ort::value::{DynValue, Tensor};
+use tokenizers::{Encoding, Tokenizer, TruncationParams};
+
+use crate::config::models::language_model_config::LanguageModelConfig;
+use crate::language_model::protocol::base::EmbeddingModel;
+
+/// The maximum number of tokens embedded at once, when the tokenizer does not set one.
+const DEFAULT_MAX_LENGTH: usize = 512;
+
+/// The number of windows run through the model at once, so long inputs do not pad one huge batch.
+const WINDOW_BATCH_SIZE: usize = 32;
+
+/// The output holding the token embeddings, or the window embeddings of models exported with their pooling layer.
+const OUTPUT_NAMES: [&str; 2] = ["last_hidden_state", "sentence_embedding"];
+
+/**
+An embedding model running a sentence-transformer exported to ONNX on the CPU.
+
+`LanguageModelConfig.model` points to the local model directory, holding `model.onnx` (or
+`onnx/model.onnx`) and the `tokenizer.json` of the model, or directly to the `.onnx` file with the
+tokenizer next to it. The onnxruntime library is loaded when the first model is, from the path in
+`ORT_DYLIB_PATH`. The token embeddings of the `last_hidden_state` output are mean pooled over the
+attention mask and normalized, texts longer than the model accepts are embedded as windows of the
+maximum length, pooled together, and windows are run in batches of `WINDOW_BATCH_SIZE`.
+*/
+pub struct OnnxEmbeddingModel {
+    /// The name the model is registered under.
+    pub name: String,
+    encoder: Arc<OnnxEncoder>,
+}
+
+/// The session and tokenizer of a model, shared with blocking tasks.
+struct OnnxEncoder {
+    /// Runs take the session exclusively, onnxruntime already spreads every run over the cores.
+    session: Mutex<Session>,
+    tokenizer: Tokenizer,
+    uses_token_type_ids: bool,
+}
+
+/// A named input of the model.
+type ModelInput = (&'static str, DynValue);
+
+/// A window of a text the model embeds at once.
+struct Window<'a> {
+    /// The position of the text in the batch.
+    text: usize,
+    encoding: &'a Encoding,
+}
+
+impl OnnxEmbeddingModel {
+    /// Load the model and tokenizer from the local path configured as the model.
+    pub fn new(name: &str, config: &LanguageModelConfig) -> Result<Self, String> {
+        let (model_path, tokenizer_path) = resolve_paths(Path::new(&config.model))?;
+        info!("Loading ONNX embedding model {} from {}", name, model_path.display());
+
+        let session = Session::builder()
+            .and_then(|builder| builder.commit_from_file(&model_path))
+            .map_err(|e| format!("Could not load ONNX model {}: {e}", model_path.display()))?;
+        let uses_token_type_ids = session.inputs.iter().any(|input| input.name == "token_type_ids");
+
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| format!("Could not load tokenizer {}: {e}", tokenizer_path.display()))?;
+        // exported tokenizers usually carry the truncation and padding of the model, which are kept
+        if tokenizer.get_truncation().is_none() {
+            tokenizer
+                .with_truncation(Some(TruncationParams {
+                    max_length: DEFAULT_MAX_LENGTH,
+                    ..Default::default()
+                }))
+                .map_err(|e| format!("Could not configure tokenizer truncation: {e}"))?;
+        }
+
+        Ok(OnnxEmbeddingModel {
+            name: name.to_string(),
+            encoder: Arc::new(OnnxEncoder {
+                session: Mutex::new(session),
+                tokenizer,
+                uses_token_type_ids,
+            }),
+        })
+    }
+}
+
+impl OnnxEncoder {
+    /// Embed a batch of texts.
+    fn encode(&self, text_list: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        if text_list.is_empty() {
+            return Ok(Vec::new());
+        }
+        let text_count = text_list.len();
+        let encodings = self
+            .tokenizer
+            .encode_batch(text_list, true)
+            .map_err(|e| format!("Could not tokenize texts: {e}"))?;
+        // truncation keeps the rest of long texts as overflowing windows, embedded along with the first one
+        let windows: Vec<Window> = encodings
+            .iter()
+            .enumerate()
+            .flat_map(|(text, encoding)| {
+                std::iter::once(encoding)
+                    .chain(encoding.get_overflowing())
+                    .map(move |encoding| Window { text, encoding })
+            })
+            .collect();
+        if windows.len() > text_count {
+            info!("Embedding {} texts as {} windows of at most the model length", text_count, windows.len());
+        }
+
+        let mut pooling = MeanPooling::new(text_count);
+        for batch in windows.chunks(WINDOW_BATCH_SIZE) {
+            let (inputs, attention_mask) = self.inputs(batch)?;
+            let mut session = self.session.lock().map_err(|_| "The ONNX session is poisoned".to_string())?;
+            let outputs = session
+                .run(inputs)
+                .map_err(|e| format!("Could not run ONNX model: {e}"))?;
+            let output = OUTPUT_NAMES
+                .iter()
+                .find_map(|name| outputs.get(*name))
+                .ok_or_else(|| {
+                    let names: Vec<&str> = outputs.keys().collect();
+                    format!("The ONNX model has none of the outputs {OUTPUT_NAMES:?}, only {names:?}")
+                })?
+                .try_extract_array::<f32>()
+                .map_err(|e| format!("Could not read ONNX output: {e}"))?;
+            pooling.add(output, &attention_mask, batch.iter().map(|window| window.text))?;
+        }
+        Ok(pooling.finish())
+    }
+
+    /// The model inputs of a batch of windows, padded to the longest one, and their attention mask.
+    fn inputs(&self, windows: &[Window]) -> Result<(Vec<ModelInput>, Array2<i64>), String> {
+        // windows are padded with the padding of the tokenizer if it has one
+        let padding = self.tokenizer.get_padding();
+        let shape = (windows.len(), windows.iter().map(|window| window.encoding.len()).max().unwrap_or(0));
+        let matrix = |values: &dyn Fn(&Encoding) -> &[u32], pad: u32| {
+            let mut matrix = Array2::from_elem(shape, i64::from(pad));
+            for (mut row, window) in matrix.outer_iter_mut().zip(windows) {
+                row.iter_mut()
+                    .zip(values(window.encoding))
+                    .for_each(|(cell, value)| *cell = i64::from(*value));
+            }
+            matrix
+        };
+        let input_ids = matrix(&|encoding| encoding.get_ids(), padding.map_or(0, |padding| padding.pad_id));
+        let attention_mask = matrix(&|encoding| encoding.get_attention_mask(), 0);
+
+        let tensor = |array: Array2<i64>| {
+            Tensor::from_array(array).map_err(|e| format!("Could not build ONNX input: {e}"))
+        };
+        let mut inputs = vec![
+            ("input_ids", tensor(input_ids)?.into_dyn()),
+            ("attention_mask", tensor(attention_mask.clone())?.into_dyn()),
+        ];
+        if self.uses_token_type_ids {
+            let pad_type_id = padding.map_or(0, |padding| padding.pad_type_id);
+            inputs.push(("token_type_ids", tensor(matrix(&|encoding| encoding.get_type_ids(), pad_type_id))?.into_dyn()));
+        }
+        Ok((inputs, attention_mask))
+    }
+}
+
+/// The mean of the token embeddings of every text, over all of its windows.
+struct MeanPooling {
+    dimensions: usize,
+    sums: Vec<Vec<f32>>,
+    /// The number of tokens summed for every text.
+    counts: Vec<f32>,
+}
+
+impl MeanPooling {
+    fn new(text_count: usize) -> Self {
+        MeanPooling {
+            dimensions: 0,
+            sums: vec![Vec::new(); text_count],
+            counts: vec![0.0; text_count],
+        }
+    }
+
+    /**
+    Add the model output of a batch of windows.
+
+    Args:
+        - output: The token embeddings of every window, or one embedding per window for models exported with their pooling layer.
+        - attention_mask: The attention mask of the windows, 0 for padding.
+        - texts: The text of every window.
+    */
+    fn add(
+        &mut self,
+        output: ArrayViewD<f32>,
+        attention_mask: &Array2<i64>,
+        texts: impl Iterator<Item = usize>,
+    ) -> Result<(), String> {
+        self.dimensions = *output.shape().last().unwrap_or(&0);
+        let windows = output.outer_iter().zip(attention_mask.outer_iter()).zip(texts);
+        match output.ndim() {
+            // a pooled window counts as many times as it has tokens, as if its tokens were summed
+            2 => {
+                for ((pooled, mask), text) in windows {
+                    let tokens = mask.iter().filter(|mask| **mask == 1).count() as f32;
+                    self.sum(text).iter_mut().zip(pooled.iter()).for_each(|(sum, value)| *sum += value * tokens);
+                    self.counts[text] += tokens;
+                }
+            }
+            3 => {
+                for ((tokens, mask), text) in windows {
+                    for (token, mask) in tokens.outer_iter().zip(mask.iter()) {
+                        if *mask == 1 {
+                            self.sum(text).iter_mut().zip(token.iter()).for_each(|(sum, value)| *sum += value);
+                            self.counts[text] += 1.0;
+                        }
+                    }
+                }
+            }
+            dimensions => return Err(format!("Unexpected ONNX output with {dimensions} dimensions")),
+        }
+        Ok(())
+    }
+
+    /// The sum of a text, started at zero on its first window.
+    fn sum(&mut self, text: usize) -> &mut Vec<f32> {
+        let sum = &mut self.sums[text];
+        sum.resize(self.dimensions, 0.0);
+        sum
+    }
+
+    /// The normalized mean of every text, zero for texts without tokens.
+    fn finish(self) -> Vec<Vec<f32>> {
+        self.sums
+            .into_iter()
+            .zip(self.counts)
+            .map(|(mut sum, count)| {
+                sum.resize(self.dimensions, 0.0);
+                normalize(sum.into_iter().map(|value| value / count.max(1.0)).collect())
+            })
+            .collect()
+    }
+}
+
+impl EmbeddingModel for OnnxEmbeddingModel {
+    /// Generate an embedding vector for each text, on a blocking thread.
+    async fn aembed_batch(&self, text_list: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        let encoder = self.encoder.clone();
+        tokio::task::spawn_blocking(move || encoder.encode(text_list))
+            .await
+            .map_err(|e| format!("The ONNX embedding task failed: {e}"))?
+    }
+
+    /// Generate an embedding vector for the text, on a blocking thread.
+    async fn aembed(&self, text: &str) -> Result<Vec<f32>, String> {
+        Ok(self.aembed_batch(vec![text.to_string()]).await?.pop().unwrap_or_default())
+    }
+
+    /// Generate an embedding vector for each text.
+    fn embed_batch(&self, text_list: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        self.encoder.encode(text_list)
+    }
+
+    /// Generate an embedding vector for the text.
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        Ok(self.embed_batch(vec![text.to_string()])?.pop().unwrap_or_default())
+    }
+}
+
+/// Find the model and tokenizer files of a local model path.
+fn resolve_paths(path: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let (model_path, directory) = if path.is_dir() {
+        let model_path = [path.join("model.onnx"), path.join("onnx").join("model.onnx")]
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| format!("No model.onnx found in {}", path.display()))?;
+        (model_path, path.to_path_buf())
+    } else if path.is_file() {
+        (path.to_path_buf(), path.parent().unwrap_or(Path::new(".")).to_path_buf())
+    } else {
+        return Err(format!("ONNX model path {} does not exist", path.display()));
+    };
+
+    // exports keep the tokenizer either next to the model or in the model directory
+    let tokenizer_path = [directory.join("tokenizer.json"), model_path.with_file_name("tokenizer.json")]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| format!("No tokenizer.json found for {}", model_path.display()))?;
+    Ok((model_path, tokenizer_path))
+}
+
+/// Scale a vector to unit length.
+fn normalize(vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.into_iter().map(|value| value / norm).collect()
+    } else {
+        vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    /// A fresh directory holding the given files.
+    fn model_directory(name: &str, files: &[&str]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("onnx-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        for file in files {
+            let path = directory.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn resolve_paths_finds_the_model_in_a_directory() {
+        let directory = model_directory("directory", &["model.onnx", "tokenizer.json"]);
+        assert_eq!(
+            resolve_paths(&directory),
+            Ok((directory.join("model.onnx"), directory.join("tokenizer.json"))),
+        );
+    }
+
+    #[test]
+    fn resolve_paths_finds_the_model_in_the_onnx_subdirectory() {
+        let directory = model_directory("export", &["onnx/model.onnx", "tokenizer.json"]);
+        assert_eq!(
+            resolve_paths(&directory),
+            Ok((directory.join("onnx").join("model.onnx"), directory.join("tokenizer.json"))),
+        );
+
+        // the tokenizer may also be kept next to the model
+        let directory = model_directory("nested", &["onnx/model.onnx", "onnx/tokenizer.json"]);
+        assert_eq!(
+            resolve_paths(&directory),
+            Ok((directory.join("onnx").join("model.onnx"), directory.join("onnx").join("tokenizer.json"))),
+        );
+    }
+
+    #[test]
+    fn resolve_paths_accepts_the_model_file() {
+        let directory = model_directory("file", &["encoder.onnx", "tokenizer.json"]);
+        assert_eq!(
+            resolve_paths(&directory.join("encoder.onnx")),
+            Ok((directory.join("encoder.onnx"), directory.join("tokenizer.json"))),
+        );
+    }
+
+    #[test]
+    fn resolve_paths_reports_missing_files() {
+        let directory = model_directory("missing", &["tokenizer.json"]);
+        assert!(resolve_paths(&directory).unwrap_err().contains("No model.onnx"));
+        let directory = model_directory("untokenized", &["model.onnx"]);
+        assert!(resolve_paths(&directory).unwrap_err().contains("No tokenizer.json"));
+        assert!(resolve_paths(&directory.join("absent")).unwrap_err().contains("does not exist"));
+    }
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        assert_eq!(normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        let normalized = normalize(vec![1.0, -2.0, 2.0, 0.5]);
+        assert!((normalized.iter().map(|value| value * value).sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normalize_keeps_zero_vectors() {
+        assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+        assert!(normalize(Vec::new()).is_empty());
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        assert!(actual.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6), "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn token_embeddings_are_mean_pooled_over_the_attention_mask() {
+        let mut pooling = MeanPooling::new(2);
+        // the padded tokens would dominate the mean if they were counted
+        let output = array![
+            [[3.0f32, 0.0], [1.0, 2.0], [100.0, 100.0]],
+            [[0.0, 5.0], [-100.0, 100.0], [100.0, -100.0]],
+        ];
+        let attention_mask = array![[1, 1, 0], [1, 0, 0]];
+        pooling.add(output.into_dyn().view(), &attention_mask, [0, 1].into_iter()).unwrap();
+        let vectors = pooling.finish();
+        // (3, 0) and (1, 2) average to (2, 1)
+        assert_close(&vectors[0], &normalize(vec![2.0, 1.0]));
+        assert_close(&vectors[1], &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn overflowing_windows_are_pooled_into_their_text() {
+        // the first text is split over two windows, run in separate batches
+        let mut pooling = MeanPooling::new(2);
+        let first_batch = array![[[1.0f32, 0.0], [1.0, 0.0]], [[0.0, 0.0], [0.0, 1.0]]];
+        pooling.add(first_batch.into_dyn().view(), &array![[1, 1], [0, 1]], [0, 1].into_iter()).unwrap();
+        let second_batch = array![[[0.0f32, 3.0], [9.0, 9.0]]];
+        pooling.add(second_batch.into_dyn().view(), &array![[1, 0]], [0].into_iter()).unwrap();
+        let vectors = pooling.finish();
+        // every token counts once: (1, 0), (1, 0) and (0, 3) average to (2 / 3, 1)
+        assert_close(&vectors[0], &normalize(vec![2.0 / 3.0, 1.0]));
+        assert_close(&vectors[1], &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn pooled_windows_are_weighted_by_their_tokens() {
+        let mut pooling = MeanPooling::new(1);
+        let output = array![[1.0f32, 0.0], [0.0, 1.0]];
+        pooling.add(output.into_dyn().view(), &array![[1, 1, 1], [1, 0, 0]], [0, 0].into_iter()).unwrap();
+        assert_close(&pooling.finish()[0], &normalize(vec![3.0, 1.0]));
+    }
+
+    #[test]
+    fn texts_without_tokens_and_unexpected_outputs() {
+        let mut pooling = MeanPooling::new(2);
+        let output = array![[[1.0f32, 0.0]]];
+        pooling.add(output.into_dyn().view(), &array![[1]], [0].into_iter()).unwrap();
+        assert_eq!(pooling.finish()[1], vec![0.0, 0.0]);
+
+        let mut pooling = MeanPooling::new(1);
+        let output = array![1.0f32, 0.0];
+        assert!(pooling.add(output.into_dyn().view(), &array![[1]], [0].into_iter()).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 //! The query engine package root.
 
 pub mod context_builder;
+pub mod factory;
+pub mod indexer_adapters;
 pub mod input;
 pub mod llm;
 pub mod structured_search;
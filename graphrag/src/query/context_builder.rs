@@ -3,3 +3,4 @@
 pub mod builders;
 pub mod dynamic_community_selection;
 pub mod entity_extraction;
+pub mod temporal;
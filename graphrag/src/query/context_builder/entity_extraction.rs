@@ -1,5 +1,11 @@
 //! Orchestration Context Builders.
 
+use std::collections::HashSet;
+
+use log::warn;
+use serde_json::Value;
+
+use crate::config::enums::EntityRankType;
 use crate::data_model::entity::Entity;
 use crate::data_model::relationship::Relationship;
 use crate::language_model::protocol::base::EmbeddingModel;
@@ -8,7 +14,8 @@ use crate::query::input::retrieval::entities::{
     get_entity_by_key,
     get_entity_by_name,
 };
-use crate::vector_stores::base::BaseVectorStore;
+use crate::vector_stores::base::{BaseVectorStore, VectorStoreDocument};
+use crate::vector_stores::filtering::VectorStoreFilter;
 
 /// Keys used as ids in the entity embedding vectorstores.
 pub enum EntityVectorStoreKey {
@@ -40,7 +47,14 @@ impl EntityVectorStoreKey {
     }
 }
 
-/// Extract entities that match a given query using semantic similarity of text embeddings of query and entity descriptions.
+/**
+Extract entities that match a given query using semantic similarity of text embeddings of query and entity descriptions.
+
+The filter restricts the entities to those whose attributes match, e.g. entity types,
+`valid_from`/`valid_to` date ranges or tenant ids. It applies to the semantic search, to the
+highest ranked entities used without a query and to the explicitly included entities alike.
+Without a query, entities are ranked by `rank_attribute`.
+*/
 pub fn map_query_to_entities(
     query: str,
     text_embedding_vectorstore: BaseVectorStore,
@@ -51,11 +65,14 @@ pub fn map_query_to_entities(
     exclude_entity_names: Option<Vec<String>>, // = None,
     k: usize, // = 10,
     oversample_scaler: usize,  // = 2,
+    filter: Option<VectorStoreFilter>, // = None,
+    rank_attribute: &EntityRankType, // = &EntityRankType::Degree,
 ) -> Vec<Entity> {
     if include_entity_names.is_none():
         include_entity_names = []
     if exclude_entity_names.is_none():
         exclude_entity_names = []
+    let matches_filter = |entity: &Entity| filter.as_ref().is_none_or(|filter| filter.matches(&entity_document(entity)));
     all_entities = list(all_entities_dict.values())
     matched_entities = []
     if query != "":
@@ -63,8 +80,14 @@ pub fn map_query_to_entities(
         // oversample to account for excluded entities
         search_results = text_embedding_vectorstore.similarity_search_by_text(
             text=query,
-            text_embedder=lambda t: text_embedder.embed(t),
+            text_embedder=|t| {
+                text_embedder.embed(t).unwrap_or_else(|e| {
+                    warn!("Could not embed the query: {e}");
+                    Vec::new()
+                })
+            },
             k=k * oversample_scaler,
+            filter=filter.as_ref(),
         )
         for result in search_results:
             if embedding_vectorstore_key == EntityVectorStoreKey.ID and isinstance(
@@ -77,11 +100,14 @@ pub fn map_query_to_entities(
                     key=embedding_vectorstore_key,
                     value=result.document.id,
                 )
-            if matched:
+            if matched and matches_filter(matched):
                 matched_entities.push(matched)
     else:
-        all_entities.sort(key=lambda x: x.rank if x.rank else 0, reverse=True)
-        matched_entities = all_entities[:k]
+        matched_entities = top_ranked_entities(
+            all_entities.iter().filter(|entity| matches_filter(entity)),
+            k,
+            rank_attribute,
+        ).into_iter().cloned().collect()
 
     // filter out excluded entities
     if exclude_entity_names:
@@ -94,35 +120,143 @@ pub fn map_query_to_entities(
     // add entities in the include_entity list
     included_entities = []
     for entity_name in include_entity_names:
-        included_entities.extend(get_entity_by_name(all_entities, entity_name))
+        included_entities.extend(
+            entity for entity in get_entity_by_name(all_entities, entity_name) if matches_filter(entity)
+        )
     return included_entities + matched_entities
 }
 
+/// The entity as the document of its description embedding, with the attributes filters read.
+pub fn entity_document(entity: &Entity) -> VectorStoreDocument {
+    let mut attributes = entity.attributes.clone().unwrap_or_default();
+    let mut insert = |name: &str, value: Option<&String>| {
+        attributes.insert(name.to_string(), value.map_or(Value::Null, |value| Value::from(value.as_str())));
+    };
+    insert("title", Some(&entity.title));
+    insert("type", entity.r#type.as_ref());
+    insert("description", entity.description.as_ref());
+    insert("valid_from", entity.valid_from.as_ref());
+    insert("valid_to", entity.valid_to.as_ref());
+    VectorStoreDocument {
+        id: entity.id.clone(),
+        text: entity.description.clone(),
+        vector: None,
+        attributes,
+        content_hash: None,
+    }
+}
+
+/// Get the value of the rank attribute of an entity, 0 when it was not computed.
+pub fn entity_rank(entity: &Entity, rank_attribute: &EntityRankType) -> f64 {
+    match rank_attribute {
+        EntityRankType::Degree => entity.rank.map(|rank| rank as f64),
+        EntityRankType::PageRank => entity.pagerank,
+        EntityRankType::Betweenness => entity.betweenness,
+        EntityRankType::Eigenvector => entity.eigenvector,
+        EntityRankType::CoreNumber => entity.core_number.map(|core| core as f64),
+    }
+    .unwrap_or_default()
+}
+
+/// The `k` entities ranked highest by the rank attribute, entities with the same rank keep their order.
+pub fn top_ranked_entities<'a>(
+    entities: impl IntoIterator<Item = &'a Entity>,
+    k: usize,
+    rank_attribute: &EntityRankType,
+) -> Vec<&'a Entity> {
+    let mut entities: Vec<&Entity> = entities.into_iter().collect();
+    entities.sort_by(|a, b| entity_rank(b, rank_attribute).total_cmp(&entity_rank(a, rank_attribute)));
+    entities.truncate(k);
+    entities
+}
+
 /// Retrieve entities that have direct connections with the target entity, sorted by entity rank.
-pub fn find_nearest_neighbors_by_entity_rank(
-    entity_name: str,
-    all_entities: Vec<Entity>,
-    all_relationships: Vec<Relationship>,
-    exclude_entity_names: Vec<String> | None = None,
-    k: int | None = 10,
-) -> Vec<Entity> {
-    let exclude_entity_names = exclude_entity_names.unwrap_or_default();
-    let entity_relationships = [
-        rel
-        for rel in all_relationships
-        if rel.source == entity_name or rel.target == entity_name
-    ];
-    let source_entity_names = {rel.source for rel in entity_relationships}
-    let target_entity_names = {rel.target for rel in entity_relationships}
-    let related_entity_names = (source_entity_names.union(target_entity_names)).difference(
-        set(exclude_entity_names)
-    )
-    let top_relations = [
-        entity for entity in all_entities if entity.title in related_entity_names
-    ]
-    let top_relations.sort(key=lambda x: x.rank if x.rank else 0, reverse=True)
-    if k {
-        return top_relations[:k];
+pub fn find_nearest_neighbors_by_entity_rank<'a>(
+    entity_name: &str,
+    all_entities: &'a [Entity],
+    all_relationships: &[Relationship],
+    exclude_entity_names: Option<Vec<String>>, // = None,
+    k: Option<usize>, // = Some(10),
+    rank_attribute: &EntityRankType, // = EntityRankType::Degree,
+) -> Vec<&'a Entity> {
+    let exclude_entity_names: HashSet<String> = exclude_entity_names.unwrap_or_default().into_iter().collect();
+    let related_entity_names: HashSet<&str> = all_relationships
+        .iter()
+        .filter_map(|rel| {
+            if rel.source == entity_name {
+                Some(rel.target.as_str())
+            } else if rel.target == entity_name {
+                Some(rel.source.as_str())
+            } else {
+                None
+            }
+        })
+        .filter(|name| *name != entity_name && !exclude_entity_names.contains(*name))
+        .collect();
+    let mut top_relations: Vec<&Entity> = all_entities
+        .iter()
+        .filter(|entity| related_entity_names.contains(entity.title.as_str()))
+        .collect();
+    top_relations.sort_by(|a, b| entity_rank(b, rank_attribute).total_cmp(&entity_rank(a, rank_attribute)));
+    if let Some(k) = k {
+        top_relations.truncate(k);
     }
     top_relations
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Entities whose degree and PageRank orders differ: A, C, B by degree and B, C, A by PageRank.
+    fn ranked_entities() -> Vec<Entity> {
+        [("A", 5, 0.1), ("B", 1, 0.5), ("C", 3, 0.3)]
+            .into_iter()
+            .map(|(title, degree, pagerank)| {
+                serde_json::from_value(json!({
+                    "id": title,
+                    "title": title,
+                    "rank": degree,
+                    "pagerank": pagerank,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn titles(entities: Vec<&Entity>) -> Vec<&str> {
+        entities.into_iter().map(|entity| entity.title.as_str()).collect()
+    }
+
+    #[test]
+    fn top_entities_follow_the_rank_attribute() {
+        let entities = ranked_entities();
+        assert_eq!(titles(top_ranked_entities(&entities, 2, &EntityRankType::Degree)), ["A", "C"]);
+        assert_eq!(titles(top_ranked_entities(&entities, 2, &EntityRankType::PageRank)), ["B", "C"]);
+    }
+
+    #[test]
+    fn missing_metrics_rank_last() {
+        let entities = ranked_entities();
+        let ranked = top_ranked_entities(&entities, 3, &EntityRankType::Betweenness);
+        // no entity has a betweenness, they keep their order
+        assert_eq!(titles(ranked), ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn neighbors_follow_the_rank_attribute() {
+        let entities = ranked_entities();
+        let relationships: Vec<Relationship> = ["B", "C"]
+            .into_iter()
+            .map(|target| {
+                serde_json::from_value(json!({"id": target, "source": "A", "target": target})).unwrap()
+            })
+            .collect();
+        let by_degree = find_nearest_neighbors_by_entity_rank("A", &entities, &relationships, None, Some(1), &EntityRankType::Degree);
+        let by_pagerank = find_nearest_neighbors_by_entity_rank("A", &entities, &relationships, None, Some(1), &EntityRankType::PageRank);
+        assert_eq!(titles(by_degree), ["C"]);
+        assert_eq!(titles(by_pagerank), ["B"]);
+    }
+}
@@ -0,0 +1,217 @@
+//! Temporal filtering of the knowledge graph for "as of" queries.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+use crate::data_model::community_report::CommunityReport;
+use crate::data_model::entity::Entity;
+use crate::data_model::relationship::Relationship;
+use crate::data_model::text_unit::TextUnit;
+use crate::index::operations::compute_temporal_bounds::parse_date;
+
+/// Return True if a [valid_from, valid_to] period contains the given date. Missing or unparsable bounds are open.
+pub fn is_valid_as_of(valid_from: Option<&str>, valid_to: Option<&str>, as_of: NaiveDate) -> bool {
+    let starts_before = valid_from
+        .and_then(parse_date)
+        .is_none_or(|valid_from| valid_from <= as_of);
+    let ends_after = valid_to
+        .and_then(parse_date)
+        .is_none_or(|valid_to| as_of <= valid_to);
+    starts_before && ends_after
+}
+
+/// Keep the relationships that hold at the given date. No date keeps all relationships.
+pub fn filter_relationships_as_of(
+    relationships: Vec<Relationship>,
+    as_of: Option<NaiveDate>,
+) -> Vec<Relationship> {
+    let Some(as_of) = as_of else {
+        return relationships;
+    };
+    relationships
+        .into_iter()
+        .filter(|relationship| {
+            is_valid_as_of(
+                relationship.valid_from.as_deref(),
+                relationship.valid_to.as_deref(),
+                as_of,
+            )
+        })
+        .collect()
+}
+
+/// Keep the entities that exist at the given date. No date keeps all entities.
+pub fn filter_entities_as_of(entities: Vec<Entity>, as_of: Option<NaiveDate>) -> Vec<Entity> {
+    let Some(as_of) = as_of else {
+        return entities;
+    };
+    entities
+        .into_iter()
+        .filter(|entity| {
+            is_valid_as_of(entity.valid_from.as_deref(), entity.valid_to.as_deref(), as_of)
+        })
+        .collect()
+}
+
+/// Keep the reports of the communities with at least one entity that exists at the given date. No date keeps all reports.
+pub fn filter_reports_as_of(
+    reports: Vec<CommunityReport>,
+    entities: &[Entity],
+    as_of: Option<NaiveDate>,
+) -> Vec<CommunityReport> {
+    let Some(as_of) = as_of else {
+        return reports;
+    };
+    let communities: HashSet<&str> = entities
+        .iter()
+        .filter(|entity| {
+            is_valid_as_of(entity.valid_from.as_deref(), entity.valid_to.as_deref(), as_of)
+        })
+        .flat_map(|entity| entity.community_ids.iter().flatten())
+        .map(String::as_str)
+        .collect();
+    reports
+        .into_iter()
+        .filter(|report| communities.contains(report.community_id.as_str()))
+        .collect()
+}
+
+/**
+Keep the text units that state something holding at the given date. No date keeps all text units.
+
+A text unit is kept if any of its relationships holds at the date, or if none of its relationships is known.
+*/
+pub fn filter_text_units_as_of(
+    text_units: Vec<TextUnit>,
+    relationships: &[Relationship],
+    as_of: Option<NaiveDate>,
+) -> Vec<TextUnit> {
+    let Some(as_of) = as_of else {
+        return text_units;
+    };
+    let valid: HashSet<&str> = relationships
+        .iter()
+        .filter(|relationship| {
+            is_valid_as_of(
+                relationship.valid_from.as_deref(),
+                relationship.valid_to.as_deref(),
+                as_of,
+            )
+        })
+        .map(|relationship| relationship.id.as_str())
+        .collect();
+    let known: HashSet<&str> = relationships.iter().map(|relationship| relationship.id.as_str()).collect();
+    text_units
+        .into_iter()
+        .filter(|text_unit| {
+            let ids = text_unit.relationship_ids.as_deref().unwrap_or_default();
+            let mut known_ids = ids.iter().filter(|id| known.contains(id.as_str())).peekable();
+            known_ids.peek().is_none() || known_ids.any(|id| valid.contains(id.as_str()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn entity(id: &str, community: &str, valid_from: Option<&str>, valid_to: Option<&str>) -> Entity {
+        serde_json::from_value(json!({
+            "id": id,
+            "title": id,
+            "community_ids": [community],
+            "valid_from": valid_from,
+            "valid_to": valid_to,
+        }))
+        .unwrap()
+    }
+
+    fn relationship(id: &str, valid_from: Option<&str>, valid_to: Option<&str>) -> Relationship {
+        serde_json::from_value(json!({
+            "id": id,
+            "source": "A",
+            "target": "B",
+            "valid_from": valid_from,
+            "valid_to": valid_to,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn bounds_are_inclusive_and_open_when_missing() {
+        let as_of = date("2024-06-01");
+        assert!(is_valid_as_of(Some("2024-06-01"), Some("2024-06-01"), as_of));
+        assert!(is_valid_as_of(None, None, as_of));
+        assert!(is_valid_as_of(Some("2020"), None, as_of));
+        assert!(!is_valid_as_of(Some("2024-06-02"), None, as_of));
+        assert!(!is_valid_as_of(None, Some("2024-05"), as_of));
+        // unparsable bounds are treated as open
+        assert!(is_valid_as_of(Some("someday"), Some("never"), as_of));
+    }
+
+    #[test]
+    fn relationships_and_entities_are_filtered_by_date() {
+        let relationships = vec![
+            relationship("r1", Some("2020-01-01"), Some("2021-12-31")),
+            relationship("r2", Some("2022-01-01"), None),
+        ];
+        let kept = filter_relationships_as_of(relationships, Some(date("2023-03-01")));
+        assert_eq!(kept.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["r2"]);
+
+        let entities = vec![entity("e1", "0", None, Some("2019-01-01")), entity("e2", "1", None, None)];
+        let kept = filter_entities_as_of(entities, Some(date("2023-03-01")));
+        assert_eq!(kept.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["e2"]);
+    }
+
+    #[test]
+    fn no_date_keeps_everything() {
+        let relationships = vec![relationship("r1", Some("2999-01-01"), None)];
+        assert_eq!(filter_relationships_as_of(relationships, None).len(), 1);
+    }
+
+    #[test]
+    fn reports_need_an_entity_valid_at_the_date() {
+        let report = |community: &str| -> CommunityReport {
+            serde_json::from_value(json!({
+                "id": community,
+                "title": community,
+                "community_id": community,
+                "summary": "",
+                "full_content": "",
+            }))
+            .unwrap()
+        };
+        let entities = [
+            entity("e1", "0", Some("2020-01-01"), Some("2020-12-31")),
+            entity("e2", "1", Some("2020-01-01"), None),
+        ];
+        let kept = filter_reports_as_of(vec![report("0"), report("1")], &entities, Some(date("2022-01-01")));
+        assert_eq!(kept.iter().map(|r| r.community_id.as_str()).collect::<Vec<_>>(), ["1"]);
+    }
+
+    #[test]
+    fn text_units_need_a_relationship_valid_at_the_date() {
+        let text_unit = |id: &str, relationship_ids: &[&str]| -> TextUnit {
+            serde_json::from_value(json!({"id": id, "text": "", "relationship_ids": relationship_ids})).unwrap()
+        };
+        let relationships = [
+            relationship("r1", Some("2020-01-01"), Some("2020-12-31")),
+            relationship("r2", Some("2021-01-01"), None),
+        ];
+        let text_units = vec![
+            text_unit("t1", &["r1"]),
+            text_unit("t2", &["r1", "r2"]),
+            text_unit("t3", &[]),
+            text_unit("t4", &["unknown"]),
+        ];
+        let kept = filter_text_units_as_of(text_units, &relationships, Some(date("2022-01-01")));
+        assert_eq!(kept.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["t2", "t3", "t4"]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Query Factory methods to support CLI.
+
+use std::collections::HashMap;
+
+use crate::callbacks::query_callbacks::QueryCallbacks;
+use crate::config::models::graph_rag_config::GraphRagConfig;
+use crate::data_model::community_report::CommunityReport;
+use crate::data_model::covariate::Covariate;
+use crate::data_model::entity::Entity;
+use crate::data_model::relationship::Relationship;
+use crate::data_model::text_unit::TextUnit;
+use crate::language_model::manager::ModelManager;
+use crate::query::context_builder::entity_extraction::EntityVectorStoreKey;
+use crate::query::structured_search::local_search::mixed_context::LocalSearchMixedContext;
+use crate::query::structured_search::local_search::search::LocalSearch;
+use crate::vector_stores::base::BaseVectorStore;
+
+/**
+Create a local search engine based on data + configuration.
+
+Args:
+    - config: The configuration, the local search section selects the models and context proportions.
+    - reports: The community reports.
+    - text_units: The text units.
+    - entities: The entities, with the centrality metrics the configured rank attribute reads.
+    - relationships: The relationships.
+    - covariates: The covariates by type.
+    - response_type: The type of response to generate.
+    - description_embedding_store: The store of the entity description embeddings.
+    - text_unit_embedding_store: The store of the text unit embeddings with a vector per segment, ranking the text units by late interaction.
+    - system_prompt: The system prompt, the default local search prompt if not set.
+    - callbacks: The query callbacks.
+
+Fails if the embedding model could not be created.
+*/
+pub fn get_local_search_engine(
+    config: &GraphRagConfig,
+    reports: Vec<CommunityReport>,
+    text_units: Vec<TextUnit>,
+    entities: Vec<Entity>,
+    relationships: Vec<Relationship>,
+    covariates: HashMap<String, Vec<Covariate>>,
+    response_type: &str,
+    description_embedding_store: BaseVectorStore,
+    text_unit_embedding_store: Option<BaseVectorStore>, // = None,
+    system_prompt: Option<String>, // = None,
+    callbacks: Option<Vec<QueryCallbacks>>, // = None,
+) -> Result<LocalSearch, String> {
+    let model_settings = config.get_language_model_config(&config.local_search.chat_model_id);
+    let chat_model = ModelManager::get_instance().get_or_create_chat_model(
+        "local_search_chat",
+        model_settings.r#type.as_str(),
+        model_settings,
+    );
+
+    let embedding_settings = config.get_language_model_config(&config.local_search.embedding_model_id);
+    let embedding_model = ModelManager::get_instance().get_or_create_embedding_model(
+        "local_search_embedding",
+        embedding_settings.r#type.as_str(),
+        embedding_settings,
+    )?;
+
+    let token_encoder = tiktoken_rs::get_bpe_from_tokenizer(&model_settings.encoding_model).ok();
+    let ls_config = &config.local_search;
+
+    Ok(LocalSearch::new(
+        model=chat_model,
+        system_prompt=system_prompt,
+        context_builder=LocalSearchMixedContext::new(
+            community_reports=reports,
+            text_units=text_units,
+            entities=entities,
+            relationships=relationships,
+            covariates=covariates,
+            entity_text_embeddings=description_embedding_store,
+            // if the vectorstore uses entity title as ids, set this to EntityVectorStoreKey::Title
+            embedding_vectorstore_key=EntityVectorStoreKey::Id,
+            text_unit_embeddings=text_unit_embedding_store,
+            text_embedder=embedding_model,
+            token_encoder=token_encoder,
+            entity_rank_attribute=ls_config.entity_rank_attribute,
+        ),
+        token_encoder=token_encoder,
+        model_params={
+            "max_tokens": ls_config.llm_max_tokens,
+            "temperature": ls_config.temperature,
+            "top_p": ls_config.top_p,
+            "n": ls_config.n,
+        },
+        context_builder_params={
+            "text_unit_prop": ls_config.text_unit_prop,
+            "community_prop": ls_config.community_prop,
+            "conversation_history_max_turns": ls_config.conversation_history_max_turns,
+            "conversation_history_user_turns_only": true,
+            "top_k_mapped_entities": ls_config.top_k_entities,
+            "top_k_relationships": ls_config.top_k_relationships,
+            "include_entity_rank": true,
+            "include_relationship_weight": true,
+            "include_community_rank": false,
+            "return_candidate_context": false,
+            "max_tokens": ls_config.max_tokens,
+        },
+        response_type=response_type,
+        callbacks=callbacks,
+    ))
+}
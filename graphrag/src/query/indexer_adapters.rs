@@ -0,0 +1,116 @@
+/*!
+Indexing-Engine to Query Read Adapters.
+
+The parts of these functions that do type adaptation, renaming, collating, etc. should eventually go away.
+Ideally this is just a straight read-through into the object model.
+*/
+
+use polars::prelude::{col, lit, Column, DataFrame, DataType, JoinArgs, JoinType, LazyFrame, UniqueKeepStrategy};
+
+use crate::data_model::entity::Entity;
+use crate::data_model::schemas::{
+    COMMUNITY_ID,
+    COMMUNITY_LEVEL,
+    DESCRIPTION,
+    ENTITY_IDS,
+    ID,
+    NODE_BETWEENNESS,
+    NODE_CORE_NUMBER,
+    NODE_DEGREE,
+    NODE_EIGENVECTOR,
+    NODE_PAGERANK,
+    SHORT_ID,
+    TEXT_UNIT_IDS,
+    TITLE,
+    TYPE,
+    VALID_FROM,
+    VALID_TO,
+};
+use crate::utils::dataframe::get_string;
+
+/**
+Read in the Entities from the raw indexing outputs.
+
+The rank of every entity is its degree. The centrality metrics are only read when `finalize_graph`
+computed them, so local search can rank neighboring entities by any of them.
+
+Args:
+    - entities: The final entities table.
+    - communities: The final communities table.
+    - community_level: Only communities up to this level are assigned to the entities, all if not set.
+*/
+pub fn read_indexer_entities(
+    entities: LazyFrame,
+    communities: LazyFrame,
+    community_level: Option<usize>,
+) -> Vec<Entity> {
+    let mut community_join = communities
+        .select([col(COMMUNITY_ID), col(COMMUNITY_LEVEL), col(ENTITY_IDS)])
+        .explode([col(ENTITY_IDS)]);
+    if let Some(community_level) = community_level {
+        community_join = community_join.filter(col(COMMUNITY_LEVEL).lt_eq(lit(community_level as i64)));
+    }
+    let entity_communities = community_join
+        .group_by_stable([col(ENTITY_IDS)])
+        .agg([col(COMMUNITY_ID).cast(DataType::String).alias(COMMUNITY_ID)]);
+    let df = entities
+        .join(entity_communities, [col(ID)], [col(ENTITY_IDS)], JoinArgs::new(JoinType::Left))
+        .unique_stable(Some(vec![ID.into()]), UniqueKeepStrategy::First)
+        .collect()
+        .unwrap();
+
+    let short_ids = optional_column(&df, SHORT_ID, DataType::Int64);
+    let degrees = optional_column(&df, NODE_DEGREE, DataType::Int64);
+    let pageranks = optional_column(&df, NODE_PAGERANK, DataType::Float64);
+    let betweenness = optional_column(&df, NODE_BETWEENNESS, DataType::Float64);
+    let eigenvectors = optional_column(&df, NODE_EIGENVECTOR, DataType::Float64);
+    let core_numbers = optional_column(&df, NODE_CORE_NUMBER, DataType::Int64);
+    let valid_from = optional_column(&df, VALID_FROM, DataType::String);
+    let valid_to = optional_column(&df, VALID_TO, DataType::String);
+    let integer = |column: &Option<Column>, row: usize| column.as_ref()?.i64().ok()?.get(row);
+    let float = |column: &Option<Column>, row: usize| column.as_ref()?.f64().ok()?.get(row);
+    let string = |column: &Option<Column>, row: usize| {
+        column.as_ref()?.str().ok()?.get(row).map(str::to_string)
+    };
+
+    (0..df.height())
+        .map(|row| Entity {
+            id: get_string(&df, ID, row),
+            short_id: integer(&short_ids, row).map(|short_id| short_id.to_string()),
+            title: get_string(&df, TITLE, row),
+            r#type: Some(get_string(&df, TYPE, row)),
+            description: Some(get_string(&df, DESCRIPTION, row)),
+            description_embedding: None,
+            name_embedding: None,
+            community_ids: Some(strings(&df, COMMUNITY_ID, row)),
+            text_unit_ids: Some(strings(&df, TEXT_UNIT_IDS, row)),
+            rank: Some(integer(&degrees, row).unwrap_or_default() as usize),
+            pagerank: float(&pageranks, row),
+            betweenness: float(&betweenness, row),
+            eigenvector: float(&eigenvectors, row),
+            core_number: integer(&core_numbers, row).map(|core_number| core_number as usize),
+            valid_from: string(&valid_from, row),
+            valid_to: string(&valid_to, row),
+            attributes: None,
+        })
+        .collect()
+}
+
+/// Get a column cast to the given type, None if the table does not have it.
+fn optional_column(df: &DataFrame, column: &str, dtype: DataType) -> Option<Column> {
+    df.column(column).ok()?.cast(&dtype).ok()
+}
+
+/// Get a list of strings cell, empty if it is null.
+fn strings(df: &DataFrame, column: &str, row: usize) -> Vec<String> {
+    df.column(column)
+        .ok()
+        .and_then(|column| column.list().ok()?.get_as_series(row))
+        .map(|values| {
+            values
+                .str()
+                .map(|values| values.into_iter().flatten().map(str::to_string).collect())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
@@ -2,10 +2,12 @@
 
 use std::collections::HashMap;
 
+use chrono::NaiveDate;
 use log;
 use polars::prelude::LazyFrame;
 use tiktoken_rs;
 
+use crate::config::enums::EntityRankType;
 use crate::data_model::community_report::CommunityReport;
 use crate::data_model::covariate::Covariate;
 use crate::data_model::entity::Entity;
@@ -17,6 +19,7 @@ use crate::query::context_builder::community_context::build_community_context;
 use crate::query::context_builder::conversation_history::ConversationHistory;
 use crate::query::context_builder::entity_extraction::{
     EntityVectorStoreKey,
+    find_nearest_neighbors_by_entity_rank,
     map_query_to_entities,
 };
 use crate::query::context_builder::local_context::{
@@ -25,6 +28,10 @@ use crate::query::context_builder::local_context::{
     build_relationship_context,
     get_candidate_context,
 };
+use crate::query::context_builder::temporal::{
+    filter_entities_as_of,
+    filter_relationships_as_of,
+};
 use crate::query::context_builder::source_context::{
     build_text_unit_context,
     count_relationships,
@@ -36,6 +43,7 @@ use crate::query::input::retrieval::text_units::get_candidate_text_units;
 use crate::query::llm::text_utils::num_tokens;
 use crate::query::structured_search::base::LocalContextBuilder;
 use crate::vector_stores::base::BaseVectorStore;
+use crate::vector_stores::filtering::VectorStoreFilter;
 
 /// Build data context for local search prompt combining community reports and entity/relationship/covariate tables.
 pub struct LocalSearchMixedContext(LocalContextBuilder) {
@@ -53,6 +61,8 @@ impl LocalSearchMixedContext {
         covariates: dict[str, Vec<Covariate>] | None = None,
         token_encoder: Option<tiktoken_rs::Encoding>,
         embedding_vectorstore_key: str = EntityVectorStoreKey.ID,
+        text_unit_embeddings: Option<BaseVectorStore> = None,
+        entity_rank_attribute: EntityRankType = EntityRankType::Degree,
     ) {
         if community_reports.is_none():
             community_reports = []
@@ -75,6 +85,8 @@ impl LocalSearchMixedContext {
         self.text_embedder = text_embedder
         self.token_encoder = token_encoder
         self.embedding_vectorstore_key = embedding_vectorstore_key
+        self.text_unit_embeddings = text_unit_embeddings
+        self.entity_rank_attribute = entity_rank_attribute
     }
 
     /// Filter entity text embeddings by entity keys.
@@ -110,6 +122,8 @@ impl LocalSearchMixedContext {
         min_community_rank: int = 0,
         community_context_name: str = "Reports",
         column_delimiter: str = "|",
+        as_of: Option<NaiveDate> = None,
+        entity_filter: Option<VectorStoreFilter> = None,
         **kwargs: HashMap<String, Box<dyn Any>>,
     ) -> ContextBuilderResult {
         if include_entity_names.is_none() {
@@ -134,18 +148,47 @@ impl LocalSearchMixedContext {
             let query = format!("{query}\n{pre_user_questions}")
         }
 
-        let selected_entities = map_query_to_entities(
+        // restrict the graph to the entities and relationships that hold at the requested date
+        let entities = filter_entities_as_of(list(self.entities.values()), as_of);
+        let relationships = filter_relationships_as_of(list(self.relationships.values()), as_of);
+        let all_entities_dict = {entity.id: entity for entity in entities};
+
+        let mut selected_entities = map_query_to_entities(
             query=query,
             text_embedding_vectorstore=self.entity_text_embeddings,
             text_embedder=self.text_embedder,
-            all_entities_dict=self.entities,
+            all_entities_dict=all_entities_dict,
             embedding_vectorstore_key=self.embedding_vectorstore_key,
             include_entity_names=include_entity_names,
             exclude_entity_names=exclude_entity_names,
             k=top_k_mapped_entities,
             oversample_scaler=2,
+            filter=entity_filter,
+            rank_attribute=&self.entity_rank_attribute,
         );
 
+        // fill the remaining slots with the best ranked neighbors of the mapped entities
+        let mut selected_titles: Vec<String> = selected_entities.iter().map(|entity| entity.title.clone()).collect();
+        selected_titles.extend(exclude_entity_names.iter().cloned());
+        for entity in selected_entities.clone() {
+            let remaining = top_k_mapped_entities.saturating_sub(selected_entities.len());
+            if remaining == 0 {
+                break;
+            }
+            let neighbors = find_nearest_neighbors_by_entity_rank(
+                &entity.title,
+                &entities,
+                &relationships,
+                Some(selected_titles.clone()),
+                Some(remaining),
+                &self.entity_rank_attribute,
+            );
+            for neighbor in neighbors {
+                selected_titles.push(neighbor.title.clone());
+                selected_entities.push(neighbor.clone());
+            }
+        }
+
         // build context
         let mut final_context = Vec::<String>::new();
         let mut final_context_data = HashMap::<String, LazyFrame>::new();
@@ -201,6 +244,8 @@ impl LocalSearchMixedContext {
             relationship_ranking_attribute=relationship_ranking_attribute,
             return_candidate_context=return_candidate_context,
             column_delimiter=column_delimiter,
+            entities=entities,
+            relationships=relationships,
         );
         if local_context.strip() != "" {
             final_context.push(str(local_context));
@@ -209,9 +254,11 @@ impl LocalSearchMixedContext {
 
         let text_unit_tokens = max(int(max_tokens * text_unit_prop), 0);
         let (text_unit_context, text_unit_context_data) = self._build_text_unit_context(
+            query=query,
             selected_entities=selected_entities,
             max_tokens=text_unit_tokens,
             return_candidate_context=return_candidate_context,
+            relationships=relationships,
         );
 
         if text_unit_context.strip() != "" {
@@ -322,14 +369,22 @@ impl LocalSearchMixedContext {
         (str(context_text), context_data)
     }
 
-    /// Rank matching text units and add them to the context window until it hits the max_tokens limit.
+    /**
+    Rank matching text units and add them to the context window until it hits the max_tokens limit.
+
+    Text units are ranked by the order of the entity they were selected for and their number of
+    relationships, or by their late-interaction similarity to the query when text unit embeddings
+    are given.
+    */
     fn _build_text_unit_context(
         self,
+        query: &str,
         selected_entities: Vec<Entity>,
         max_tokens: int = 8000,
         return_candidate_context: bool = False,
         column_delimiter: str = "|",
         context_name: str = "Sources",
+        relationships: Vec<Relationship> | None = None,
     ) -> (String, HashMap<String, LazyFrame>) {
         if not selected_entities or not self.text_units {
             return ("", {context_name.lower(): LazyFrame()})
@@ -338,7 +393,7 @@ impl LocalSearchMixedContext {
         let mut text_unit_ids_set = HashSet::new()
 
         let mut unit_info_list = Vec::new();
-        let relationship_values = list(self.relationships.values());
+        let relationship_values = relationships.unwrap_or_else(|| list(self.relationships.values()));
 
         for (index, entity) in selected_entities.iter().enumerate() {
             // get matching relationships
@@ -363,6 +418,30 @@ impl LocalSearchMixedContext {
         // sort by entity_order and the number of relationships desc
         unit_info_list.sort(key=lambda x: (x[1], -x[2]));
 
+        // rank by the best matching segments of every candidate, keeping that order for ties
+        if let Some(text_unit_embeddings) = &self.text_unit_embeddings {
+            let candidate_ids = VectorStoreFilter::Ids(unit_info_list.iter().map(|unit| unit.0.id.clone()).collect());
+            let scores: HashMap<String, f64> = text_unit_embeddings
+                .similarity_search_by_text(
+                    query,
+                    |t| {
+                        self.text_embedder.embed(t).unwrap_or_else(|e| {
+                            log::warn!("Could not embed the query: {e}");
+                            Vec::new()
+                        })
+                    },
+                    unit_info_list.len(),
+                    Some(&candidate_ids),
+                )
+                .into_iter()
+                .map(|result| (result.document.id, result.score))
+                .collect();
+            unit_info_list.sort_by(|a, b| {
+                let score = |unit: &TextUnit| scores.get(&unit.id).copied().unwrap_or(f64::NEG_INFINITY);
+                score(&b.0).total_cmp(&score(&a.0))
+            });
+        }
+
         let selected_text_units = [unit[0] for unit in unit_info_list]
 
         let (context_text, context_data) = build_text_unit_context(
@@ -413,7 +492,13 @@ impl LocalSearchMixedContext {
         relationship_ranking_attribute: str = "rank",
         return_candidate_context: bool = False,
         column_delimiter: str = "|",
+        entities: Vec<Entity> | None = None,
+        relationships: Vec<Relationship> | None = None,
     ) -> (String, HashMap<String, LazyFrame>) {
+        // the entities and relationships default to the whole graph, callers pass them filtered as of a date
+        let entities = entities.unwrap_or_else(|| list(self.entities.values()));
+        let relationships = relationships.unwrap_or_else(|| list(self.relationships.values()));
+
         // build entity context
         let (entity_context, entity_context_data) = build_entity_context(
             selected_entities=selected_entities,
@@ -443,7 +528,7 @@ impl LocalSearchMixedContext {
                 relationship_context_data,
             ) = build_relationship_context(
                 selected_entities=added_entities,
-                relationships=list(self.relationships.values()),
+                relationships=relationships,
                 token_encoder=self.token_encoder,
                 max_tokens=max_tokens,
                 column_delimiter=column_delimiter,
@@ -491,8 +576,8 @@ impl LocalSearchMixedContext {
             // and add a tag to indicate which records were included in the context window
             let candidate_context_data = get_candidate_context(
                 selected_entities=selected_entities,
-                entities=list(self.entities.values()),
-                relationships=list(self.relationships.values()),
+                entities=entities,
+                relationships=relationships,
                 covariates=self.covariates,
                 include_entity_rank=include_entity_rank,
                 entity_rank_description=rank_description,
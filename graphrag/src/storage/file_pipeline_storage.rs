@@ -179,6 +179,11 @@ impl<T> PipelineStorage<T> for FilePipelineStorage {
 
         get_timestamp_formatted_with_local_tz(creation_time_utc)
     }
+
+    /// Get the path of the file of a key.
+    fn local_path(&self, key: &str) -> Option<PathBuf> {
+        Some(join_path(&self._root_dir, key))
+    }
 }
 
 /// Join a path and a file. Independent of the OS.
@@ -2,6 +2,7 @@
 
 use std::any::Any;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use crate::logger::base::ProgressLogger;
 
@@ -79,4 +80,9 @@ pub trait PipelineStorage<T> {
         - output - The creation date for the given key.
     */
     async fn get_creation_date(&self, key: String) -> String;
+
+    /// The path of the file holding the value of the given key, for storages kept on the local file system.
+    fn local_path(&self, _key: &str) -> Option<PathBuf> {
+        None
+    }
 }
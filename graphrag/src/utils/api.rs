@@ -6,7 +6,10 @@ use crate::cache::factory::CacheFactory;
 use crate::cache::pipeline_cache::PipelineCache;
 use crate::config::embeddings::create_collection_name;
 use crate::config::models::cache_config::CacheConfig;
+use crate::config::models::hybrid_search_config::HybridSearchConfig;
+use crate::config::models::multi_vector_config::MultiVectorConfig;
 use crate::config::models::output_config::OutputConfig;
+use crate::config::models::vector_store_config::VectorStoreConfig;
 use crate::data_model::types::TextEmbedder;
 use crate::storage::factory::StorageFactory;
 use crate::storage::pipeline_storage::PipelineStorage;
@@ -16,6 +19,9 @@ use crate::vector_stores::base::{
     VectorStoreSearchResult,
 };
 use crate::vector_stores::factory::VectorStoreFactory;
+use crate::vector_stores::filtering::VectorStoreFilter;
+use crate::vector_stores::hybrid::HybridVectorStore;
+use crate::vector_stores::late_interaction::LateInteractionVectorStore;
 
 /// Multi Vector Store wrapper implementation.
 pub struct MultiVectorStore {
@@ -39,23 +45,29 @@ impl BaseVectorStore for MultiVectorStore {
     /// Load documents into the vector store.
     fn load_documents(
         self, documents: Vec<VectorStoreDocument>, overwrite: bool = True
-    ) {
+    ) -> Result<(), String> {
         msg = "load_documents method not implemented"
         raise NotImplementedError(msg)
     }
 
     /// Connect to vector storage.
-    fn connect(self, **kwargs: Any) -> Any {
+    fn connect(self, **kwargs: Any) -> Result<(), String> {
         msg = "connect method not implemented"
         raise NotImplementedError(msg)
     }
 
     /// Build a query filter to filter documents by id.
-    fn filter_by_id(self, include_ids: Vec<str> | Vec<int>) -> Any {
+    fn filter_by_id(self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
         msg = "filter_by_id method not implemented"
         raise NotImplementedError(msg)
     }
 
+    /// Delete documents by id.
+    fn delete_documents(self, ids: Vec<String>) -> Result<(), String> {
+        msg = "delete_documents method not implemented"
+        raise NotImplementedError(msg)
+    }
+
     /// Search for a document by id.
     fn search_by_id(self, id: str) -> VectorStoreDocument {
         search_index_id = id.split("-")[0]
@@ -74,14 +86,14 @@ impl BaseVectorStore for MultiVectorStore {
 
     /// Perform a vector-based similarity search.
     fn similarity_search_by_vector(
-        self, query_embedding: Vec<float>, k: int = 10, **kwargs: Any
+        self, query_embedding: Vec<float>, k: int = 10, filter: Option<&VectorStoreFilter> = None
     ) -> Vec<VectorStoreSearchResult> {
         let mut all_results = [];
         for (index_name, embedding_store) in zip(
             self.index_names, self.embedding_stores
         ){
             results = embedding_store.similarity_search_by_vector(
-                query_embedding=query_embedding, k=k
+                query_embedding=query_embedding, k=k, filter=filter
             )
             let mut mod_results = [];
             for r in results {
@@ -95,41 +107,52 @@ impl BaseVectorStore for MultiVectorStore {
 
     /// Perform a text-based similarity search.
     fn similarity_search_by_text(
-        self, text: str, text_embedder: TextEmbedder, k: int = 10, **kwargs: Any
+        self, text: str, text_embedder: TextEmbedder, k: int = 10, filter: Option<&VectorStoreFilter> = None
     ) -> Vec<VectorStoreSearchResult> {
         query_embedding = text_embedder(text);
         if query_embedding:
             return self.similarity_search_by_vector(
-                query_embedding=query_embedding, k=k
+                query_embedding=query_embedding, k=k, filter=filter
             )
         return []
     }
 }
 
-pub fn get_embedding_store(
+pub async fn get_embedding_store(
     config_args: dict[str, dict],
     embedding_name: str,
-) -> BaseVectorStore:{
-    /// Get the embedding description store.
+    storage: &impl PipelineStorage<Vec<u8>>,
+    hybrid_search: Option<&HybridSearchConfig>,
+    multi_vector: Option<&MultiVectorConfig>,
+) -> Result<BaseVectorStore, String>:{
+    /// Get the embedding description store, reading in-memory stores from the index output storage.
+    /// Embeddings with a BM25 index are searched with hybrid retrieval when hybrid search is given,
+    /// and embeddings with a vector per segment are searched with late interaction when multi-vector is given.
+    /// Fails if a configured vector store type is not registered.
     num_indexes = len(config_args)
     embedding_stores = []
     index_names = []
     for index, store in config_args.items():
-        vector_store_type = store["type"]
         collection_name = create_collection_name(
             store.get("container_name", "default"), embedding_name
         )
-        embedding_store = VectorStoreFactory().create_vector_store(
-            vector_store_type=vector_store_type,
-            kwargs={**store, "collection_name": collection_name},
-        )
-        embedding_store.connect(**store)
+        embedding_store = VectorStoreFactory::create_vector_store(
+            &VectorStoreConfig(**store), &collection_name
+        )?
+        if let Some(hybrid_search) = hybrid_search.filter(|hybrid| hybrid.enabled && hybrid.embeddings.contains(embedding_name)) {
+            embedding_store = HybridVectorStore::new(embedding_store, collection_name, hybrid_search)
+        }
+        if let Some(multi_vector) = multi_vector.filter(|multi| multi.enabled && multi.embeddings.contains(embedding_name)) {
+            embedding_store = LateInteractionVectorStore::new(embedding_store, multi_vector)
+        }
+        embedding_store.connect()?
+        embedding_store.load(storage).await
         # If there is only a single index, return the embedding store directly
         if num_indexes == 1:
-            return embedding_store
+            return Ok(embedding_store)
         embedding_stores.push(embedding_store)
         index_names.push(index)
-    return MultiVectorStore(embedding_stores, index_names)
+    return Ok(MultiVectorStore(embedding_stores, index_names))
 }
 
 pub fn reformat_context_data(context_data: dict) -> dict {
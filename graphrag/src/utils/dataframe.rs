@@ -0,0 +1,24 @@
+//! Row accessors for collected data frames.
+
+use polars::prelude::DataFrame;
+
+/// Get an integer cell, 0 if it is null.
+pub fn get_i64(df: &DataFrame, column: &str, row: usize) -> i64 {
+    df.column(column).unwrap().i64().unwrap().get(row).unwrap_or_default()
+}
+
+/// Get a float cell, 0 if it is null.
+pub fn get_f64(df: &DataFrame, column: &str, row: usize) -> f64 {
+    df.column(column).unwrap().f64().unwrap().get(row).unwrap_or_default()
+}
+
+/// Get a string cell, empty if it is null.
+pub fn get_string(df: &DataFrame, column: &str, row: usize) -> String {
+    df.column(column)
+        .unwrap()
+        .str()
+        .unwrap()
+        .get(row)
+        .unwrap_or_default()
+        .to_string()
+}
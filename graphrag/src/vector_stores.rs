@@ -1,5 +1,13 @@
 //! A package containing vector store implementations.
 
 pub mod base;
+pub mod bm25;
 pub mod factory;
+pub mod filtering;
+pub mod hnsw;
+pub mod hybrid;
 pub mod lancedb;
+pub mod late_interaction;
+pub mod pgvector;
+pub mod qdrant;
+pub mod quantization;
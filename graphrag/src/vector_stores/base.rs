@@ -1,28 +1,48 @@
 //! Base classes for vector stores.
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
 use crate::data_model::types::TextEmbedder;
+use crate::storage::pipeline_storage::PipelineStorage;
+use crate::vector_stores::filtering::VectorStoreFilter;
+
+/// The vector size of collections created before any vector is known.
+pub const DEFAULT_VECTOR_SIZE: usize = 1536;
 
-const DEFAULT_VECTOR_SIZE: usize = 1536;
+/// The attribute holding the id of the text a segment document was embedded from.
+pub const PARENT_ID_ATTRIBUTE: &str = "parent_id";
 
 /// A document that is stored in vector storage.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VectorStoreDocument {
     /// unique id for the document
-    pub id: str | int,
+    pub id: String,
 
     pub text: Option<String>,
-    pub vector: Option<Vec<f64>>,
+    pub vector: Option<Vec<f32>>,
 
     /// store any additional metadata, e.g. title, date ranges, etc
-    pub attributes: HashMap<String, Any>, // = field(default_factory=dict),
+    pub attributes: HashMap<String, serde_json::Value>,
+
+    /// hash of what the vector was computed from, so unchanged documents are not embedded again
+    #[serde(default)]
+    pub content_hash: Option<String>,
 }
 
 /// A vector storage search result.
+#[derive(Debug, Clone)]
 pub struct VectorStoreSearchResult {
     /// Document that was found.
-    document: VectorStoreDocument,
+    pub document: VectorStoreDocument,
 
     /// Similarity score between -1 and 1. Higher is more similar.
-    score: f64,
+    pub score: f64,
 }
 
 /// The base class for vector storage data-access classes.
@@ -45,38 +65,373 @@ pub trait BaseVectorStore {
     fn connect(
         &mut self,
         // **kwargs: Any
-    );
+    ) -> Result<(), String>;
 
-    /// Load documents into the vector-store.
+    /// Load documents into the vector-store, failing if they could not all be stored.
     fn load_documents(
-        &self,
+        &mut self,
         documents: Vec<VectorStoreDocument>,
         overwrite: bool, // = True
-    );
+    ) -> Result<(), String>;
 
-    /// Perform ANN search by vector.
+    /// Perform ANN search by vector, only returning documents matching the filter.
+    ///
+    /// A search that fails is logged and finds nothing, so a query degrades instead of aborting.
     fn similarity_search_by_vector(
         &self,
-        query_embedding: Vec<f64>,
+        query_embedding: Vec<f32>,
         k: usize, // = 10,
-        // **kwargs: Any
+        filter: Option<&VectorStoreFilter>, // = None,
     ) -> Vec<VectorStoreSearchResult>;
 
-    /// Perform ANN search by text.
+    /// Perform ANN search by text, only returning documents matching the filter.
     fn similarity_search_by_text(
         &self,
         text: &str,
         text_embedder: TextEmbedder,
         k: usize, // = 10,
-        // **kwargs: Any
+        filter: Option<&VectorStoreFilter>, // = None,
     ) -> Vec<VectorStoreSearchResult>;
 
-    /// Build a query filter to filter documents by id.
+    /// Build a query filter to filter documents by id, applied to every following search.
     fn filter_by_id(
+        &mut self,
+        include_ids: Vec<String>
+    ) -> Option<VectorStoreFilter>;
+
+    /**
+    Perform a max-sim search with several query vectors, only returning texts matching the filter.
+
+    Segment documents are aggregated back to the text they were embedded from: every query vector
+    scores a text with its best matching segment, and the score of the text is the mean over the
+    query vectors.
+
+    Args:
+        - query_embeddings: The query vectors, such as one per query sentence.
+        - k: The number of texts to return.
+        - filter: The filter documents must match.
+        - oversample: How many times `k` segments are retrieved for every query vector.
+    */
+    fn similarity_search_by_vectors(
         &self,
-        include_ids: Vec<String> | Vec<int>
-    ) -> Any;
+        query_embeddings: Vec<Vec<f32>>,
+        k: usize, // = 10,
+        filter: Option<&VectorStoreFilter>, // = None,
+        oversample: usize, // = 4,
+    ) -> Vec<VectorStoreSearchResult> {
+        let rankings = query_embeddings
+            .into_iter()
+            .map(|query_embedding| self.similarity_search_by_vector(query_embedding, k * oversample.max(1), filter))
+            .collect();
+        max_sim(rankings, k)
+    }
 
     /// Search for a document by id.
     fn search_by_id(&self, id: &str) -> VectorStoreDocument;
+
+    /// Get the stored documents with the given ids in one batch, leaving out ids that are not stored.
+    fn get_by_ids(&self, ids: &[String]) -> Result<Vec<VectorStoreDocument>, String> {
+        Ok(ids
+            .iter()
+            .map(|id| self.search_by_id(id))
+            .filter(|document| document.text.is_some() || document.vector.is_some())
+            .collect())
+    }
+
+    /// Delete documents by id, ignoring ids that are not stored.
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String>;
+
+    /// The content hash of every stored document that has one, keyed by id.
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        Ok(HashMap::new())
+    }
+
+    /// Read the collection from pipeline storage, for stores that are held in memory.
+    fn load<'a>(&'a mut self, _storage: &'a dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// Write the collection to pipeline storage, for stores that are held in memory.
+    fn save<'a>(&'a self, _storage: &'a mut dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// A boxed store, such as one created by the `VectorStoreFactory`, is a store itself.
+impl<S: BaseVectorStore + ?Sized> BaseVectorStore for Box<S> {
+    fn connect(&mut self) -> Result<(), String> {
+        (**self).connect()
+    }
+
+    fn load_documents(&mut self, documents: Vec<VectorStoreDocument>, overwrite: bool) -> Result<(), String> {
+        (**self).load_documents(documents, overwrite)
+    }
+
+    fn similarity_search_by_vector(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        (**self).similarity_search_by_vector(query_embedding, k, filter)
+    }
+
+    fn similarity_search_by_text(
+        &self,
+        text: &str,
+        text_embedder: TextEmbedder,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        (**self).similarity_search_by_text(text, text_embedder, k, filter)
+    }
+
+    fn filter_by_id(&mut self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
+        (**self).filter_by_id(include_ids)
+    }
+
+    fn similarity_search_by_vectors(
+        &self,
+        query_embeddings: Vec<Vec<f32>>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+        oversample: usize,
+    ) -> Vec<VectorStoreSearchResult> {
+        (**self).similarity_search_by_vectors(query_embeddings, k, filter, oversample)
+    }
+
+    fn search_by_id(&self, id: &str) -> VectorStoreDocument {
+        (**self).search_by_id(id)
+    }
+
+    fn get_by_ids(&self, ids: &[String]) -> Result<Vec<VectorStoreDocument>, String> {
+        (**self).get_by_ids(ids)
+    }
+
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String> {
+        (**self).delete_documents(ids)
+    }
+
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        (**self).content_hashes()
+    }
+
+    fn load<'a>(&'a mut self, storage: &'a dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        (**self).load(storage)
+    }
+
+    fn save<'a>(&'a self, storage: &'a mut dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        (**self).save(storage)
+    }
+}
+
+/**
+The byte storage the collections of stores held in memory are persisted through.
+
+Every `PipelineStorage<Vec<u8>>` is one. Unlike the pipeline storage it can be used as a trait object,
+which keeps `BaseVectorStore` usable as the `dyn BaseVectorStore` the `VectorStoreFactory` creates.
+*/
+pub trait CollectionStorage {
+    /// Get the bytes stored under a key, None if there are none.
+    fn get_bytes<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, Option<Vec<u8>>>;
+
+    /// Store bytes under a key, replacing any bytes stored under it.
+    fn set_bytes<'a>(&'a mut self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, ()>;
+
+    /// The path of the file holding the bytes of a key, None unless they are stored on the local file system.
+    fn local_path(&self, key: &str) -> Option<PathBuf>;
+}
+
+impl<S: PipelineStorage<Vec<u8>>> CollectionStorage for S {
+    fn get_bytes<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            if self.has(key).await {
+                Some(self.get(key.to_string(), Some(true), None).await)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn set_bytes<'a>(&'a mut self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, ()> {
+        Box::pin(self.set(key, value, None))
+    }
+
+    fn local_path(&self, key: &str) -> Option<PathBuf> {
+        PipelineStorage::local_path(self, key)
+    }
+}
+
+/// The id of the text a document was embedded from, its own id unless it is a segment of a text.
+pub fn parent_id(document: &VectorStoreDocument) -> &str {
+    document
+        .attributes
+        .get(PARENT_ID_ATTRIBUTE)
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or(&document.id)
+}
+
+/**
+Aggregate the segment rankings of several query vectors into a ranking of texts with max-sim.
+
+Every text is returned as its best matching segment under the id of the text, best first.
+*/
+pub fn max_sim(rankings: Vec<Vec<VectorStoreSearchResult>>, k: usize) -> Vec<VectorStoreSearchResult> {
+    let query_count = rankings.len().max(1) as f64;
+    // the summed score and best segment of every text
+    let mut texts: HashMap<String, (f64, VectorStoreSearchResult)> = HashMap::new();
+    for ranking in rankings {
+        let mut best: HashMap<String, VectorStoreSearchResult> = HashMap::new();
+        for result in ranking {
+            let parent = parent_id(&result.document).to_string();
+            if best.get(&parent).is_none_or(|current| result.score > current.score) {
+                best.insert(parent, result);
+            }
+        }
+        for (parent, result) in best {
+            match texts.get_mut(&parent) {
+                Some((total, segment)) => {
+                    *total += result.score;
+                    if result.score > segment.score {
+                        *segment = result;
+                    }
+                }
+                None => {
+                    texts.insert(parent, (result.score, result));
+                }
+            }
+        }
+    }
+
+    let mut results: Vec<VectorStoreSearchResult> = texts
+        .into_iter()
+        .map(|(parent, (total, mut segment))| {
+            segment.document.id = parent;
+            segment.document.attributes.remove(PARENT_ID_ATTRIBUTE);
+            VectorStoreSearchResult {
+                document: segment.document,
+                score: total / query_count,
+            }
+        })
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.document.id.cmp(&b.document.id)));
+    results.truncate(k);
+    results
+}
+
+/**
+The runtime driving the async client of a remote store from the synchronous store methods.
+
+It has its own worker thread, so background connection tasks keep running between requests, and it
+can be used from inside another runtime: a multi-threaded runtime hands its worker over while
+blocking, and a current-thread runtime, which cannot, waits for the request on a scoped thread.
+*/
+pub(crate) struct ClientRuntime(Option<Runtime>);
+
+impl ClientRuntime {
+    pub fn new() -> Result<Self, String> {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map(|runtime| ClientRuntime(Some(runtime)))
+            .map_err(|e| format!("Could not start the vector store client runtime: {e}"))
+    }
+
+    /// Run a future to completion on the runtime.
+    pub fn block_on<F: Future + Send>(&self, future: F) -> F::Output
+    where
+        F::Output: Send,
+    {
+        let runtime = self.0.as_ref().expect("the runtime is only taken when dropped");
+        match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+            Err(_) => runtime.block_on(future),
+            // a runtime cannot be blocked on from the thread of another one, so a fresh thread does it
+            Ok(RuntimeFlavor::CurrentThread) => std::thread::scope(|scope| {
+                scope
+                    .spawn(|| runtime.block_on(future))
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            }),
+            Ok(_) => tokio::task::block_in_place(|| runtime.block_on(future)),
+        }
+    }
+
+    /// Spawn a background task on the runtime.
+    pub fn spawn<F: Future<Output = ()> + Send + 'static>(&self, future: F) {
+        if let Some(runtime) = &self.0 {
+            runtime.spawn(future);
+        }
+    }
+}
+
+impl Drop for ClientRuntime {
+    // a runtime cannot be dropped from async code, which is where stores usually are
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A search result for a segment of a text, or for a whole text without a parent.
+    fn result(id: &str, parent: Option<&str>, score: f64) -> VectorStoreSearchResult {
+        let mut document = VectorStoreDocument {
+            id: id.to_string(),
+            text: Some(format!("text of {id}")),
+            ..Default::default()
+        };
+        if let Some(parent) = parent {
+            document.attributes.insert(PARENT_ID_ATTRIBUTE.into(), parent.into());
+        }
+        VectorStoreSearchResult { document, score }
+    }
+
+    fn scores(results: &[VectorStoreSearchResult]) -> Vec<(String, f64)> {
+        results.iter().map(|result| (result.document.id.clone(), result.score)).collect()
+    }
+
+    #[test]
+    fn max_sim_sums_the_best_segment_of_every_query() {
+        let rankings = vec![
+            vec![result("a-0", Some("a"), 0.9), result("a-1", Some("a"), 0.5), result("b-0", Some("b"), 0.7)],
+            vec![result("b-1", Some("b"), 0.8), result("a-1", Some("a"), 0.3)],
+        ];
+        // a: (0.9 + 0.3) / 2, b: (0.7 + 0.8) / 2
+        let results = max_sim(rankings, 10);
+        assert_eq!(scores(&results), vec![("b".to_string(), 0.75), ("a".to_string(), 0.6)]);
+        // every text is returned as its best segment, without the parent attribute
+        assert_eq!(results[0].document.text.as_deref(), Some("text of b-1"));
+        assert_eq!(results[1].document.text.as_deref(), Some("text of a-0"));
+        assert!(results.iter().all(|result| !result.document.attributes.contains_key(PARENT_ID_ATTRIBUTE)));
+    }
+
+    #[test]
+    fn max_sim_keeps_documents_without_a_parent_and_truncates() {
+        let rankings = vec![vec![result("x", None, 0.4), result("y", None, 0.4), result("z-0", Some("z"), 0.2)]];
+        // ties are ordered by id
+        assert_eq!(scores(&max_sim(rankings.clone(), 2)), vec![("x".to_string(), 0.4), ("y".to_string(), 0.4)]);
+        assert_eq!(max_sim(rankings, 5).len(), 3);
+        assert!(max_sim(Vec::new(), 5).is_empty());
+    }
+
+    #[test]
+    fn parent_id_falls_back_to_the_document_id() {
+        assert_eq!(parent_id(&result("a-3", Some("a"), 0.0).document), "a");
+        assert_eq!(parent_id(&result("a", None, 0.0).document), "a");
+    }
+
+    #[test]
+    fn client_runtime_blocks_inside_either_runtime_flavor() {
+        let client = ClientRuntime::new().unwrap();
+        assert_eq!(client.block_on(async { 1 }), 1);
+        for mut builder in [Builder::new_current_thread(), Builder::new_multi_thread()] {
+            let outer = builder.enable_all().build().unwrap();
+            assert_eq!(outer.block_on(async { client.block_on(async { 2 }) }), 2);
+        }
+    }
 }
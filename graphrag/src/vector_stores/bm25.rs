@@ -0,0 +1,292 @@
+//! A sparse BM25 index for exact term retrieval.
+
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::vector_stores::base::CollectionStorage;
+
+/**
+An inverted BM25 index over document texts.
+
+Dense embeddings blur exact identifiers such as product codes and acronyms, so the index keeps the
+exact tokens and scores documents with Okapi BM25.
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bm25Index {
+    /// The term frequency saturation.
+    pub k1: f64,
+    /// The document length normalization.
+    pub b: f64,
+    ids: Vec<String>,
+    lengths: Vec<usize>,
+    total_length: usize,
+    /// The (document, term frequency) of every document containing a term.
+    postings: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl Bm25Index {
+    /// Create an empty index.
+    pub fn new(k1: f64, b: f64) -> Self {
+        Bm25Index {
+            k1,
+            b,
+            ..Default::default()
+        }
+    }
+
+    /// The number of indexed documents.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// The ids of the indexed documents.
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Whether the index has no documents.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Remove every document.
+    pub fn clear(&mut self) {
+        *self = Bm25Index::new(self.k1, self.b);
+    }
+
+    /// Add a document to the index.
+    pub fn add(&mut self, id: &str, text: &str) {
+        let document = self.ids.len();
+        let tokens = tokenize(text);
+        let mut frequencies: HashMap<String, usize> = HashMap::new();
+        for token in &tokens {
+            *frequencies.entry(token.clone()).or_default() += 1;
+        }
+        for (term, frequency) in frequencies {
+            self.postings.entry(term).or_default().push((document, frequency));
+        }
+        self.ids.push(id.to_string());
+        self.lengths.push(tokens.len());
+        self.total_length += tokens.len();
+    }
+
+    /// Remove documents by id, ignoring ids that are not indexed.
+    pub fn remove(&mut self, ids: &[String]) {
+        let removed: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        // the remaining documents keep their order
+        let mut remap = vec![None; self.ids.len()];
+        let mut kept = 0;
+        for (document, id) in self.ids.iter().enumerate() {
+            if !removed.contains(id.as_str()) {
+                remap[document] = Some(kept);
+                kept += 1;
+            }
+        }
+        if kept == self.ids.len() {
+            return;
+        }
+
+        for postings in self.postings.values_mut() {
+            postings.retain_mut(|(document, _)| match remap[*document] {
+                Some(position) => {
+                    *document = position;
+                    true
+                }
+                None => false,
+            });
+        }
+        self.postings.retain(|_, postings| !postings.is_empty());
+        let mut document = 0..;
+        self.ids.retain(|_| remap[document.next().unwrap()].is_some());
+        let mut document = 0..;
+        self.lengths.retain(|_| remap[document.next().unwrap()].is_some());
+        self.total_length = self.lengths.iter().sum();
+    }
+
+    /// Rank every document sharing a term with the query, best first.
+    pub fn search(&self, query: &str) -> Vec<(String, f64)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let document_count = self.len() as f64;
+        let average_length = (self.total_length as f64 / document_count).max(1.0);
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+        for term in terms {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            let document_frequency = postings.len() as f64;
+            let idf = (1.0 + (document_count - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+            for (document, frequency) in postings {
+                let frequency = *frequency as f64;
+                let length = self.lengths[*document] as f64 / average_length;
+                *scores.entry(*document).or_default() +=
+                    idf * frequency * (self.k1 + 1.0) / (frequency + self.k1 * (1.0 - self.b + self.b * length));
+            }
+        }
+
+        let mut ranking: Vec<(String, f64)> = scores
+            .into_iter()
+            .map(|(document, score)| (self.ids[document].clone(), score))
+            .collect();
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranking
+    }
+
+    /// Read the index of a collection from pipeline storage, returning whether it was found.
+    pub async fn load(&mut self, storage: &dyn CollectionStorage, collection_name: &str) -> bool {
+        let key = format!("{collection_name}.bm25.json");
+        let Some(bytes) = storage.get_bytes(&key).await else {
+            warn!("No BM25 index found at {key}");
+            return false;
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(index) => {
+                *self = index;
+                true
+            }
+            Err(e) => {
+                warn!("Could not read BM25 index {key}: {e}");
+                false
+            }
+        }
+    }
+
+    /// Write the index of a collection to pipeline storage.
+    pub async fn save(&self, storage: &mut dyn CollectionStorage, collection_name: &str) {
+        storage
+            .set_bytes(&format!("{collection_name}.bm25.json"), serde_json::to_vec(self).unwrap())
+            .await;
+    }
+}
+
+/**
+Split a text into lowercase search terms.
+
+Identifiers joined by `-`, `_`, `.` or `/` are kept whole, so `ABC-123` matches exactly, and their
+parts are added too so a query for `abc` still finds it.
+*/
+pub fn tokenize(text: &str) -> Vec<String> {
+    let is_joiner = |character: char| matches!(character, '-' | '_' | '.' | '/');
+    let mut tokens = Vec::new();
+    for word in text.split(|character: char| !character.is_alphanumeric() && !is_joiner(character)) {
+        let word = word.trim_matches(is_joiner).to_lowercase();
+        if word.is_empty() {
+            continue;
+        }
+        if word.contains(is_joiner) {
+            tokens.extend(
+                word.split(is_joiner)
+                    .filter(|part| !part.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        tokens.push(word);
+    }
+    tokens
+}
+
+/**
+Fuse several rankings with reciprocal rank fusion.
+
+Every item scores `1 / (k + rank)` in every ranking it appears in, with ranks starting at 1, so
+items ranked well by several retrievers come first regardless of how each retriever scales scores.
+
+Returns the fused (item, score) ranking, best first.
+*/
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>], k: f64) -> Vec<(String, f64)> {
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for ranking in rankings {
+        for (rank, item) in ranking.iter().enumerate() {
+            *scores.entry(item.as_str()).or_default() += 1.0 / (k + rank as f64 + 1.0);
+        }
+    }
+    let mut fused: Vec<(String, f64)> = scores
+        .into_iter()
+        .map(|(item, score)| (item.to_string(), score))
+        .collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(documents: &[(&str, &str)]) -> Bm25Index {
+        let mut index = Bm25Index::new(1.2, 0.75);
+        for (id, text) in documents {
+            index.add(id, text);
+        }
+        index
+    }
+
+    #[test]
+    fn tokenize_keeps_identifiers_and_their_parts() {
+        assert_eq!(tokenize("Order ABC-123, now!"), vec!["order", "abc", "123", "abc-123", "now"]);
+        assert_eq!(tokenize("...__"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn scores_follow_okapi_bm25() {
+        let index = index(&[("a", "apple banana"), ("b", "apple apple cherry cherry"), ("c", "cherry")]);
+        let average_length = 7.0 / 3.0;
+        let idf = |document_frequency: f64| (1.0 + (3.0 - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+        let term = |frequency: f64, length: f64, document_frequency: f64| {
+            idf(document_frequency) * frequency * 2.2 / (frequency + 1.2 * (0.25 + 0.75 * length / average_length))
+        };
+
+        let ranking = index.search("apple");
+        assert_eq!(ranking.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+        assert!((ranking[0].1 - term(2.0, 4.0, 2.0)).abs() < 1e-12);
+        assert!((ranking[1].1 - term(1.0, 2.0, 2.0)).abs() < 1e-12);
+
+        // repeated query terms count once
+        let ranking = index.search("banana banana cherry");
+        let scores: HashMap<String, f64> = ranking.into_iter().collect();
+        assert!((scores["a"] - term(1.0, 2.0, 1.0)).abs() < 1e-12);
+        assert!((scores["c"] - term(1.0, 1.0, 2.0)).abs() < 1e-12);
+        assert!((scores["b"] - term(2.0, 4.0, 2.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rare_terms_outweigh_common_ones() {
+        let index = index(&[("a", "graph rag"), ("b", "graph"), ("c", "graph")]);
+        assert_eq!(index.search("graph rag")[0].0, "a");
+        assert!(index.search("missing").is_empty());
+    }
+
+    #[test]
+    fn removed_documents_are_not_found() {
+        let mut index = index(&[("a", "alpha beta"), ("b", "beta gamma"), ("c", "gamma delta")]);
+        index.remove(&["b".to_string(), "unknown".to_string()]);
+        assert_eq!(index.ids(), ["a", "c"]);
+        assert_eq!(index.search("gamma"), vec![("c".to_string(), index.search("delta")[0].1)]);
+        assert!(index.search("beta").iter().all(|(id, _)| id == "a"));
+    }
+
+    #[test]
+    fn fusion_sums_reciprocal_ranks() {
+        let dense = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let sparse = vec!["c".to_string(), "d".to_string()];
+        let fused = reciprocal_rank_fusion(&[dense, sparse], 60.0);
+        let scores: HashMap<&str, f64> = fused.iter().map(|(id, score)| (id.as_str(), *score)).collect();
+        assert!((scores["c"] - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-12);
+        assert!((scores["a"] - 1.0 / 61.0).abs() < 1e-12);
+        assert!((scores["d"] - 1.0 / 62.0).abs() < 1e-12);
+        assert_eq!(fused.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["c", "a", "b", "d"]);
+    }
+
+    #[test]
+    fn fusion_breaks_ties_by_id() {
+        let fused = reciprocal_rank_fusion(&[vec!["b".to_string()], vec!["a".to_string()]], 60.0);
+        assert_eq!(fused[0].0, "a");
+        assert_eq!(fused[0].1, fused[1].1);
+    }
+}
@@ -1,51 +1,224 @@
 //! A package containing a factory and supported vector store types.
 
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::config::models::vector_store_config::VectorStoreConfig;
 // use crate::vector_stores::azure_ai_search::AzureAISearchVectorStore;
-// use crate::vector_stores::base::BaseVectorStore;
+use crate::vector_stores::base::BaseVectorStore;
 // use crate::vector_stores::cosmosdb::CosmosDBVectorStore;
+use crate::vector_stores::hnsw::HnswVectorStore;
 use crate::vector_stores::lancedb::LanceDBVectorStore;
+use crate::vector_stores::pgvector::PgVectorStore;
+use crate::vector_stores::qdrant::QdrantVectorStore;
 
-/// The supported vector store types.
+/// The built-in vector store types.
 pub enum VectorStoreType {
+    /// The embedded HNSW index, persisted in the pipeline storage.
+    Hnsw,
     LanceDB,
     AzureAISearch,
     CosmosDB,
+    Qdrant,
+    PgVector,
 }
 
 impl VectorStoreType {
     pub fn as_str(&self) -> &str {
         match self {
+            VectorStoreType::Hnsw => "hnsw",
             VectorStoreType::LanceDB => "lancedb",
             VectorStoreType::AzureAISearch => "azure_ai_search",
             VectorStoreType::CosmosDB => "cosmosdb",
+            VectorStoreType::Qdrant => "qdrant",
+            VectorStoreType::PgVector => "pgvector",
         }
     }
 }
 
+/// A vector store constructor, taking the store configuration and the collection name.
+pub type VectorStoreCreator =
+    Arc<dyn Fn(&VectorStoreConfig, &str) -> Box<dyn BaseVectorStore + Send + Sync> + Send + Sync>;
+
+/// The registered vector store constructors, keyed by type.
+static VECTOR_STORE_TYPES: LazyLock<RwLock<HashMap<String, VectorStoreCreator>>> =
+    LazyLock::new(|| RwLock::new(builtin_vector_store_types()));
+
 /**
 A factory for vector stores.
 
-Includes a method for users to register a custom vector store implementation.
+Includes a method for users to register a custom vector store implementation, which can then be
+selected with its type in the `vector_store` settings.
 */
-pub struct VectorStoreFactory {
-    vector_store_types: ClassVar[dict[str, type]] = {}
-}
+pub struct VectorStoreFactory;
 
 impl VectorStoreFactory {
-    /// Register a custom vector store implementation.
-    @classmethod
-    pub fn register(cls, vector_store_type: str, vector_store: type) {
-        cls.vector_store_types[vector_store_type] = vector_store
+    /// Register a custom vector store implementation, replacing any store registered with the same type.
+    pub fn register(
+        vector_store_type: &str,
+        creator: impl Fn(&VectorStoreConfig, &str) -> Box<dyn BaseVectorStore + Send + Sync> + Send + Sync + 'static,
+    ) {
+        VECTOR_STORE_TYPES
+            .write()
+            .unwrap()
+            .insert(vector_store_type.to_string(), Arc::new(creator));
     }
 
-    /// Create or get a vector store from the provided type.
+    /// Create a vector store for a collection from the type in its configuration.
     pub fn create_vector_store(
-        vector_store_type: VectorStoreType | str,
-        kwargs: dict,
-    ) -> BaseVectorStore {
-        match vector_store_type:
-            VectorStoreType::LanceDB => LanceDBVectorStore(**kwargs),
-            VectorStoreType::AzureAISearch => AzureAISearchVectorStore(**kwargs),
-            VectorStoreType::CosmosDB => CosmosDBVectorStore(**kwargs),
+        config: &VectorStoreConfig,
+        collection_name: &str,
+    ) -> Result<Box<dyn BaseVectorStore + Send + Sync>, String> {
+        // the lock is released before calling the constructor, which may register stores itself
+        let creator = VECTOR_STORE_TYPES.read().unwrap().get(&config.r#type).cloned();
+        match creator {
+            Some(creator) => Ok(creator(config, collection_name)),
+            None => Err(format!(
+                "Vector store type '{}' is not registered, expected one of: {}.",
+                config.r#type,
+                Self::get_vector_store_types().join(", ")
+            )),
+        }
+    }
+
+    /// Get the registered vector store types.
+    pub fn get_vector_store_types() -> Vec<String> {
+        let mut types: Vec<String> = VECTOR_STORE_TYPES.read().unwrap().keys().cloned().collect();
+        types.sort();
+        types
+    }
+
+    /// Check if the given vector store type is registered.
+    pub fn is_supported_vector_store(vector_store_type: &str) -> bool {
+        VECTOR_STORE_TYPES.read().unwrap().contains_key(vector_store_type)
+    }
+}
+
+/// The constructors of the built-in vector stores.
+fn builtin_vector_store_types() -> HashMap<String, VectorStoreCreator> {
+    let mut types: HashMap<String, VectorStoreCreator> = HashMap::new();
+    types.insert(
+        VectorStoreType::Hnsw.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(HnswVectorStore::new(
+                collection_name,
+                config.distance,
+                config.hnsw_m,
+                config.hnsw_ef_construction,
+                config.hnsw_ef_search,
+                config.quantization,
+                config.pq_subvector_size,
+                config.rerank_factor,
+            ))
+        }),
+    );
+    types.insert(
+        VectorStoreType::LanceDB.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(LanceDBVectorStore(collection_name=collection_name, **config.model_dump()))
+        }),
+    );
+    types.insert(
+        VectorStoreType::AzureAISearch.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(AzureAISearchVectorStore(collection_name=collection_name, **config.model_dump()))
+        }),
+    );
+    types.insert(
+        VectorStoreType::CosmosDB.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(CosmosDBVectorStore(collection_name=collection_name, **config.model_dump()))
+        }),
+    );
+    types.insert(
+        VectorStoreType::Qdrant.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(QdrantVectorStore::new(
+                collection_name,
+                config.url.as_deref().unwrap_or_default(),
+                config.api_key.clone(),
+                config.distance,
+                config.quantization,
+                config.pq_subvector_size,
+                config.rerank_factor,
+            ))
+        }),
+    );
+    types.insert(
+        VectorStoreType::PgVector.as_str().to_string(),
+        Arc::new(|config, collection_name| {
+            Box::new(PgVectorStore::new(
+                collection_name,
+                config.url.as_deref().unwrap_or_default(),
+                config.database_name.clone(),
+                config.api_key.clone(),
+                config.distance,
+            ))
+        }),
+    );
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::enums::VectorQuantizationType;
+    use crate::vector_stores::base::VectorStoreDocument;
+
+    /// The collections the custom store was created for.
+    static CREATED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn registered_stores_are_created_by_type() {
+        VectorStoreFactory::register("custom_in_memory", |config, collection_name| {
+            CREATED.lock().unwrap().push(collection_name.to_string());
+            Box::new(HnswVectorStore::new(
+                collection_name,
+                config.distance,
+                4,
+                16,
+                16,
+                VectorQuantizationType::None,
+                8,
+                0,
+            ))
+        });
+        assert!(VectorStoreFactory::is_supported_vector_store("custom_in_memory"));
+        assert!(VectorStoreFactory::get_vector_store_types().contains(&"custom_in_memory".to_string()));
+
+        let config = VectorStoreConfig {
+            r#type: "custom_in_memory".into(),
+            ..Default::default()
+        };
+        let Ok(mut store) = VectorStoreFactory::create_vector_store(&config, "default-entity-description") else {
+            panic!("the registered store was not created");
+        };
+        assert_eq!(*CREATED.lock().unwrap(), ["default-entity-description"]);
+
+        store.load_documents(
+            vec![VectorStoreDocument {
+                id: "a".into(),
+                vector: Some(vec![1.0, 0.0]),
+                ..Default::default()
+            }],
+            true,
+        )
+        .unwrap();
+        assert_eq!(store.search_by_id("a").vector, Some(vec![1.0, 0.0]));
+    }
+
+    #[test]
+    fn unknown_types_are_an_error() {
+        let config = VectorStoreConfig {
+            r#type: "missing".into(),
+            ..Default::default()
+        };
+        let Err(error) = VectorStoreFactory::create_vector_store(&config, "collection") else {
+            panic!("an unknown type created a store");
+        };
+        assert!(error.contains("'missing' is not registered"), "{error}");
+        assert!(error.contains("hnsw"), "{error}");
     }
 }
@@ -0,0 +1,398 @@
+//! Typed filter expressions over vector store documents.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::vector_stores::base::VectorStoreDocument;
+
+/**
+A filter over the documents of a vector store, pushed down into each store implementation.
+
+Fields are document attributes. Numbers compare numerically and strings lexicographically, so ISO
+dates such as `valid_from` can be compared with `Range`. A missing or null attribute is outside any
+range, unless the range includes missing attributes, like the open-ended `valid_to` of a relationship
+that still holds.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum VectorStoreFilter {
+    /// Keep documents whose id is in the set.
+    Ids(HashSet<String>),
+    /// Keep documents whose attribute equals the value.
+    Eq(String, Value),
+    /// Keep documents whose attribute is any of the values.
+    In(String, Vec<Value>),
+    /// Keep documents whose attribute is within the inclusive bounds, unbounded when not set.
+    Range {
+        field: String,
+        min: Option<Value>,
+        max: Option<Value>,
+        /// Also keep documents whose attribute is missing or null.
+        include_missing: bool,
+    },
+    /// Keep documents matching all filters.
+    And(Vec<VectorStoreFilter>),
+    /// Keep documents matching any filter.
+    Or(Vec<VectorStoreFilter>),
+}
+
+impl VectorStoreFilter {
+    /// Combine two optional filters, keeping documents matching both.
+    pub fn and(left: Option<VectorStoreFilter>, right: Option<VectorStoreFilter>) -> Option<VectorStoreFilter> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(VectorStoreFilter::And(vec![left, right])),
+            (left, right) => left.or(right),
+        }
+    }
+
+    /// Whether a document matches the filter.
+    pub fn matches(&self, document: &VectorStoreDocument) -> bool {
+        match self {
+            VectorStoreFilter::Ids(ids) => ids.contains(&document.id),
+            VectorStoreFilter::Eq(field, value) => document
+                .attributes
+                .get(field)
+                .is_some_and(|attribute| compare(attribute, value) == Some(Ordering::Equal)),
+            VectorStoreFilter::In(field, values) => document.attributes.get(field).is_some_and(|attribute| {
+                values
+                    .iter()
+                    .any(|value| compare(attribute, value) == Some(Ordering::Equal))
+            }),
+            VectorStoreFilter::Range {
+                field,
+                min,
+                max,
+                include_missing,
+            } => match document.attributes.get(field) {
+                None | Some(Value::Null) => *include_missing,
+                Some(attribute) => {
+                    min.as_ref()
+                        .is_none_or(|min| compare(attribute, min).is_some_and(Ordering::is_ge))
+                        && max
+                            .as_ref()
+                            .is_none_or(|max| compare(attribute, max).is_some_and(Ordering::is_le))
+                }
+            },
+            VectorStoreFilter::And(filters) => filters.iter().all(|filter| filter.matches(document)),
+            VectorStoreFilter::Or(filters) => filters.iter().any(|filter| filter.matches(document)),
+        }
+    }
+
+    /// The ids a document must have to match the filter, if the filter restricts ids at all.
+    pub fn allowed_ids(&self) -> Option<HashSet<&str>> {
+        match self {
+            VectorStoreFilter::Ids(ids) => Some(ids.iter().map(String::as_str).collect()),
+            VectorStoreFilter::And(filters) => filters
+                .iter()
+                .filter_map(VectorStoreFilter::allowed_ids)
+                .reduce(|left, right| left.intersection(&right).copied().collect()),
+            VectorStoreFilter::Or(filters) => filters
+                .iter()
+                .map(VectorStoreFilter::allowed_ids)
+                .reduce(|left, right| Some(left?.union(&right?).copied().collect()))
+                .flatten(),
+            _ => None,
+        }
+    }
+
+    /// The attribute names the filter reads.
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            VectorStoreFilter::Ids(_) => Vec::new(),
+            VectorStoreFilter::Eq(field, _) | VectorStoreFilter::In(field, _) | VectorStoreFilter::Range { field, .. } => {
+                vec![field.as_str()]
+            }
+            VectorStoreFilter::And(filters) | VectorStoreFilter::Or(filters) => {
+                filters.iter().flat_map(VectorStoreFilter::fields).collect()
+            }
+        }
+    }
+
+    /**
+    Translate the filter into a SQL predicate, for stores that filter with SQL.
+
+    Values that are not comparable, such as null or arrays, match nothing, like in `matches`.
+
+    Args:
+        - dialect: How the store reads attributes and writes values.
+    */
+    pub fn to_sql(&self, dialect: &mut impl SqlDialect) -> Result<String, String> {
+        match self {
+            VectorStoreFilter::Ids(ids) if ids.is_empty() => Ok("FALSE".to_string()),
+            VectorStoreFilter::Ids(ids) => {
+                let mut ids: Vec<String> = ids.iter().cloned().collect();
+                ids.sort();
+                Ok(dialect.ids(ids))
+            }
+            VectorStoreFilter::Eq(name, value) => match json_type(value) {
+                Some(kind) => {
+                    let field = dialect.field(name)?;
+                    let comparison = format!("{field} = {}", dialect.value(value));
+                    Ok(guarded(dialect.has_type(&field, &[kind]), comparison))
+                }
+                None => Ok("FALSE".to_string()),
+            },
+            VectorStoreFilter::In(name, values) => {
+                let values: Vec<&Value> = values.iter().filter(|value| json_type(value).is_some()).collect();
+                if values.is_empty() {
+                    return Ok("FALSE".to_string());
+                }
+                let field = dialect.field(name)?;
+                let mut kinds: Vec<&str> = values.iter().filter_map(|value| json_type(value)).collect();
+                kinds.sort();
+                kinds.dedup();
+                let values: Vec<String> = values.into_iter().map(|value| dialect.value(value)).collect();
+                let comparison = format!("{field} IN ({})", values.join(", "));
+                Ok(guarded(dialect.has_type(&field, &kinds), comparison))
+            }
+            VectorStoreFilter::Range {
+                field: name,
+                min,
+                max,
+                include_missing,
+            } => {
+                let field = dialect.field(name)?;
+                let missing = dialect.is_missing(&field);
+                let mut bounds = Vec::new();
+                for (bound, operator) in [(min, ">="), (max, "<=")] {
+                    let Some(bound) = bound else { continue };
+                    match json_type(bound) {
+                        Some(kind) => {
+                            let comparison = format!("{field} {operator} {}", dialect.value(bound));
+                            bounds.push(guarded(dialect.has_type(&field, &[kind]), comparison));
+                        }
+                        None => bounds.push("FALSE".to_string()),
+                    }
+                }
+                Ok(match (bounds.is_empty(), include_missing) {
+                    (true, true) => "TRUE".to_string(),
+                    (true, false) => format!("NOT ({missing})"),
+                    (false, true) => format!("({missing} OR ({}))", bounds.join(" AND ")),
+                    (false, false) => format!("({})", bounds.join(" AND ")),
+                })
+            }
+            VectorStoreFilter::And(filters) if filters.is_empty() => Ok("TRUE".to_string()),
+            VectorStoreFilter::Or(filters) if filters.is_empty() => Ok("FALSE".to_string()),
+            VectorStoreFilter::And(filters) | VectorStoreFilter::Or(filters) => {
+                let separator = if matches!(self, VectorStoreFilter::And(_)) { " AND " } else { " OR " };
+                let predicates = filters
+                    .iter()
+                    .map(|filter| Ok(format!("({})", filter.to_sql(dialect)?)))
+                    .collect::<Result<Vec<String>, String>>()?;
+                Ok(predicates.join(separator))
+            }
+        }
+    }
+}
+
+/// How a SQL store reads document attributes and writes values, for `VectorStoreFilter::to_sql`.
+pub trait SqlDialect {
+    /// The SQL expression reading an attribute, an error if the name cannot be used.
+    fn field(&mut self, name: &str) -> Result<String, String>;
+
+    /// The SQL expression of a value, spliced as a literal or bound as a parameter.
+    fn value(&mut self, value: &Value) -> String;
+
+    /// The SQL predicate keeping the documents with any of the ids, which are sorted.
+    fn ids(&mut self, ids: Vec<String>) -> String;
+
+    /// The SQL predicate keeping the documents whose attribute is missing or null.
+    fn is_missing(&self, field: &str) -> String {
+        format!("{field} IS NULL")
+    }
+
+    /// The SQL predicate keeping attributes of any of the JSON types, if comparisons could otherwise mix types.
+    fn has_type(&self, _field: &str, _kinds: &[&str]) -> Option<String> {
+        None
+    }
+}
+
+/// A SQL dialect splicing values as literals and attribute names into their expressions, after validating them.
+pub struct SqlLiterals<F: Fn(&str) -> String>(pub F);
+
+impl<F: Fn(&str) -> String> SqlDialect for SqlLiterals<F> {
+    fn field(&mut self, name: &str) -> Result<String, String> {
+        if !is_valid_field(name) {
+            return Err(format!("Invalid filter field {name:?}, only letters, digits and underscores are allowed"));
+        }
+        Ok((self.0)(name))
+    }
+
+    fn value(&mut self, value: &Value) -> String {
+        sql_literal(value)
+    }
+
+    fn ids(&mut self, ids: Vec<String>) -> String {
+        let ids: Vec<String> = ids.into_iter().map(|id| sql_literal(&Value::String(id))).collect();
+        format!("id IN ({})", ids.join(", "))
+    }
+}
+
+/// Whether an attribute name can be spliced into a query: letters, digits and underscores only.
+pub fn is_valid_field(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+/// Compare two attribute values, if they are comparable.
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+/// Format a value as a SQL literal.
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("'{}'", text.replace('\'', "''")),
+        Value::Null => "NULL".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Guard a comparison with a predicate, if there is one.
+fn guarded(guard: Option<String>, comparison: String) -> String {
+    match guard {
+        Some(guard) => format!("({guard} AND {comparison})"),
+        None => comparison,
+    }
+}
+
+/// The JSON type of a value that filters can compare, None for null, arrays and objects.
+pub fn json_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Number(_) => Some("number"),
+        Value::String(_) => Some("string"),
+        Value::Bool(_) => Some("boolean"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document(id: &str, attributes: Value) -> VectorStoreDocument {
+        VectorStoreDocument {
+            id: id.to_string(),
+            attributes: serde_json::from_value(attributes).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn range(field: &str, min: Option<Value>, max: Option<Value>) -> VectorStoreFilter {
+        VectorStoreFilter::Range {
+            field: field.to_string(),
+            min,
+            max,
+            include_missing: false,
+        }
+    }
+
+    fn open_ended(field: &str, min: Option<Value>, max: Option<Value>) -> VectorStoreFilter {
+        VectorStoreFilter::Range {
+            field: field.to_string(),
+            min,
+            max,
+            include_missing: true,
+        }
+    }
+
+    fn json_extract(name: &str) -> String {
+        format!("json_extract(attributes, '$.{name}')")
+    }
+
+    #[test]
+    fn ids_match_by_id() {
+        let filter = VectorStoreFilter::Ids(HashSet::from(["a".to_string(), "b".to_string()]));
+        assert!(filter.matches(&document("a", json!({}))));
+        assert!(!filter.matches(&document("c", json!({}))));
+        assert_eq!(filter.allowed_ids(), Some(HashSet::from(["a", "b"])));
+    }
+
+    #[test]
+    fn eq_and_in_compare_numbers_numerically() {
+        let entity = document("a", json!({"type": "PERSON", "year": 2020}));
+        assert!(VectorStoreFilter::Eq("year".into(), json!(2020.0)).matches(&entity));
+        assert!(!VectorStoreFilter::Eq("type".into(), json!("ORG")).matches(&entity));
+        assert!(VectorStoreFilter::In("type".into(), vec![json!("ORG"), json!("PERSON")]).matches(&entity));
+        assert!(!VectorStoreFilter::In("missing".into(), vec![json!("ORG")]).matches(&entity));
+    }
+
+    #[test]
+    fn range_compares_iso_dates_inclusively() {
+        let entity = document("a", json!({"valid_from": "2020-01-01"}));
+        assert!(range("valid_from", Some(json!("2020-01-01")), Some(json!("2020-12-31"))).matches(&entity));
+        assert!(!range("valid_from", Some(json!("2020-01-02")), None).matches(&entity));
+        assert!(!range("valid_from", None, Some(json!("2019-12-31"))).matches(&entity));
+        // values of different types are not comparable
+        assert!(!range("valid_from", Some(json!(2019)), None).matches(&entity));
+    }
+
+    #[test]
+    fn range_excludes_null_and_missing_by_default() {
+        let null = document("a", json!({"valid_to": null}));
+        let missing = document("b", json!({}));
+        let after = range("valid_to", Some(json!("2024-06-01")), None);
+        assert!(!after.matches(&null));
+        assert!(!after.matches(&missing));
+        assert!(!range("valid_to", None, None).matches(&missing));
+        assert!(range("valid_to", None, None).matches(&document("c", json!({"valid_to": "2024-05-31"}))));
+    }
+
+    #[test]
+    fn range_can_include_null_and_missing() {
+        let as_of = open_ended("valid_to", Some(json!("2024-06-01")), None);
+        assert!(as_of.matches(&document("a", json!({"valid_to": null}))));
+        assert!(as_of.matches(&document("b", json!({}))));
+        assert!(!as_of.matches(&document("c", json!({"valid_to": "2024-05-31"}))));
+    }
+
+    #[test]
+    fn and_or_combine_filters() {
+        let entity = document("a", json!({"type": "PERSON"}));
+        let person = VectorStoreFilter::Eq("type".into(), json!("PERSON"));
+        let org = VectorStoreFilter::Eq("type".into(), json!("ORG"));
+        assert!(!VectorStoreFilter::And(vec![person.clone(), org.clone()]).matches(&entity));
+        assert!(VectorStoreFilter::Or(vec![person.clone(), org]).matches(&entity));
+        assert!(VectorStoreFilter::and(None, Some(person)).is_some_and(|filter| filter.matches(&entity)));
+    }
+
+    #[test]
+    fn sql_keeps_null_attributes_only_in_ranges_including_them() {
+        let mut dialect = SqlLiterals(json_extract);
+        assert_eq!(
+            open_ended("valid_to", Some(json!("2024-06-01")), None).to_sql(&mut dialect).unwrap(),
+            "(json_extract(attributes, '$.valid_to') IS NULL OR (json_extract(attributes, '$.valid_to') >= '2024-06-01'))"
+        );
+        assert_eq!(
+            range("valid_to", Some(json!("2024-06-01")), None).to_sql(&mut dialect).unwrap(),
+            "(json_extract(attributes, '$.valid_to') >= '2024-06-01')"
+        );
+        assert_eq!(
+            range("valid_to", None, None).to_sql(&mut dialect).unwrap(),
+            "NOT (json_extract(attributes, '$.valid_to') IS NULL)"
+        );
+    }
+
+    #[test]
+    fn sql_rejects_fields_that_are_not_identifiers() {
+        let filter = VectorStoreFilter::And(vec![VectorStoreFilter::Eq("a') OR 1=1 --".into(), json!(1))]);
+        assert!(filter.to_sql(&mut SqlLiterals(json_extract)).is_err());
+        let ids = VectorStoreFilter::Ids(HashSet::from(["b".to_string(), "it's".to_string()]));
+        assert_eq!(ids.to_sql(&mut SqlLiterals(json_extract)).unwrap(), "id IN ('b', 'it''s')");
+    }
+
+    #[test]
+    fn sql_of_empty_lists_matches_nothing() {
+        let mut dialect = SqlLiterals(json_extract);
+        assert_eq!(VectorStoreFilter::In("type".into(), vec![]).to_sql(&mut dialect).unwrap(), "FALSE");
+        assert_eq!(VectorStoreFilter::Ids(HashSet::new()).to_sql(&mut dialect).unwrap(), "FALSE");
+        assert_eq!(VectorStoreFilter::In("type".into(), vec![json!(null)]).to_sql(&mut dialect).unwrap(), "FALSE");
+    }
+}
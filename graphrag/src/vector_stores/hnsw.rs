@@ -0,0 +1,1178 @@
+//! The embedded HNSW vector storage implementation package.
+
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use futures::future::LocalBoxFuture;
+use log::{info, warn};
+use memmap2::{Mmap, MmapMut};
+use serde::{Deserialize, Serialize};
+
+use crate::config::enums::{VectorDistanceType, VectorQuantizationType};
+use crate::data_model::types::TextEmbedder;
+use crate::index::utils::weighted_graph::SplitMix64;
+use crate::vector_stores::base::{
+    BaseVectorStore,
+    CollectionStorage,
+    VectorStoreDocument,
+    VectorStoreSearchResult,
+};
+use crate::vector_stores::filtering::VectorStoreFilter;
+use crate::vector_stores::quantization::{QueryTable, Quantizer, dot};
+
+/// How many times the search width filtered searches look at, and id allow-lists are scanned up to.
+const FILTER_SCAN_FACTOR: usize = 8;
+
+/**
+Vector storage kept in memory as a Hierarchical Navigable Small World graph.
+
+The collection is persisted through the pipeline storage, next to the index tables, so search works
+without any external service. Quantized collections are searched with their codes, and the best
+candidates are re-ranked against the full vectors. Once a quantized collection is loaded its full
+vectors stay on disk, memory-mapped in place, so only the codes take up memory.
+*/
+pub struct HnswVectorStore {
+    /// The name of the collection, used as the storage key.
+    pub collection_name: String,
+    distance: VectorDistanceType,
+    m: usize,
+    ef_construction: usize,
+    ef_search: usize,
+    quantization: VectorQuantizationType,
+    pq_subvector_size: usize,
+    rerank_factor: usize,
+    documents: Vec<VectorStoreDocument>,
+    positions: HashMap<String, usize>,
+    graph: HnswGraph,
+    quantizer: Option<Quantizer>,
+    /// The codes of every node, concatenated.
+    codes: Vec<u8>,
+    /// The squared norm of the reconstruction of every code.
+    code_norms: Vec<f64>,
+    /// The full vectors of the loaded nodes quantized collections only keep codes of in memory.
+    mapped_vectors: Option<MappedVectors>,
+    /// The vectors decoded while linking nodes, so every node is decoded once rather than once per distance.
+    decoded_vectors: Vec<OnceLock<Vec<f32>>>,
+    query_filter: Option<VectorStoreFilter>,
+}
+
+/// Full vectors stored one after another as little-endian floats, memory-mapped.
+struct MappedVectors {
+    map: Mmap,
+    dimensions: usize,
+    /// The row of every node, None for the nodes inserted since the collection was loaded.
+    rows: Vec<Option<usize>>,
+}
+
+/// The layered neighbor graph of an HNSW index.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct HnswGraph {
+    /// The node every search starts from, on the top layer.
+    entry_point: Option<usize>,
+    /// The neighbors of every node on every layer it is part of, layer 0 first.
+    neighbors: Vec<Vec<Vec<usize>>>,
+}
+
+/// The persisted header of a collection, followed by its documents one after another.
+#[derive(Serialize, Deserialize)]
+struct StoredCollection<'a> {
+    distance: Cow<'a, str>,
+    m: usize,
+    document_count: usize,
+    /// The dimensions of the full vectors stored next to the collection, None if only codes are kept.
+    vector_dimensions: Option<usize>,
+    graph: Cow<'a, HnswGraph>,
+    quantizer: Option<Cow<'a, Quantizer>>,
+    codes: Cow<'a, [u8]>,
+}
+
+/// The persisted form of a document, with its attributes as JSON since the binary format is not self-describing.
+///
+/// Vectors are stored apart from the documents, so they can be memory-mapped instead of read.
+#[derive(Serialize, Deserialize)]
+struct StoredDocument<'a> {
+    id: Cow<'a, str>,
+    text: Option<Cow<'a, str>>,
+    attributes: String,
+    content_hash: Option<Cow<'a, str>>,
+}
+
+/// A query vector, compared with the codes of the nodes when a quantization table is given.
+struct Query<'a> {
+    vector: &'a [f32],
+    squared_norm: f64,
+    table: Option<QueryTable>,
+}
+
+/// A node and its distance to the query, ordered by distance.
+#[derive(PartialEq)]
+struct Candidate(f64, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+impl HnswVectorStore {
+    /**
+    Create an empty collection.
+
+    Args:
+        - collection_name: The name of the collection.
+        - distance: The distance used to compare vectors.
+        - m: The maximum number of neighbors of every node, twice as many on the bottom layer.
+        - ef_construction: The number of candidates considered while inserting a node.
+        - ef_search: The number of candidates considered while searching.
+        - quantization: The quantization of the vectors searched once the collection is saved.
+        - pq_subvector_size: The number of dimensions encoded by every byte of product quantization codes.
+        - rerank_factor: The number of quantized candidates re-ranked for every result, 0 drops the full vectors.
+    */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        collection_name: &str,
+        distance: VectorDistanceType,
+        m: usize,
+        ef_construction: usize,
+        ef_search: usize,
+        quantization: VectorQuantizationType,
+        pq_subvector_size: usize,
+        rerank_factor: usize,
+    ) -> Self {
+        HnswVectorStore {
+            collection_name: collection_name.to_string(),
+            distance,
+            m: m.max(2),
+            ef_construction: ef_construction.max(1),
+            ef_search: ef_search.max(1),
+            quantization,
+            pq_subvector_size: pq_subvector_size.max(1),
+            rerank_factor,
+            documents: Vec::new(),
+            positions: HashMap::new(),
+            graph: HnswGraph::default(),
+            quantizer: None,
+            codes: Vec::new(),
+            code_norms: Vec::new(),
+            mapped_vectors: None,
+            decoded_vectors: Vec::new(),
+            query_filter: None,
+        }
+    }
+
+    /// The storage key of the collection.
+    fn storage_key(&self) -> String {
+        format!("{}.hnsw.bin", self.collection_name)
+    }
+
+    /// The storage key of the full vectors of the collection.
+    fn vectors_key(&self) -> String {
+        format!("{}.hnsw.vectors", self.collection_name)
+    }
+
+    /// The distance between two vectors, lower is more similar.
+    fn distance(&self, a: &[f32], b: &[f32]) -> f64 {
+        self.distance_of(dot(a, b), dot(a, a), dot(b, b))
+    }
+
+    /// The distance between two vectors from their dot product and squared norms.
+    fn distance_of(&self, dot: f64, a_squared_norm: f64, b_squared_norm: f64) -> f64 {
+        match self.distance {
+            VectorDistanceType::Cosine => {
+                let norms = a_squared_norm.sqrt() * b_squared_norm.sqrt();
+                if norms > 0.0 { 1.0 - dot / norms } else { 1.0 }
+            }
+            VectorDistanceType::Dot => -dot,
+            VectorDistanceType::L2 => (a_squared_norm - 2.0 * dot + b_squared_norm).max(0.0).sqrt(),
+        }
+    }
+
+    /// The distance between a query and a node.
+    fn query_distance(&self, query: &Query, node: usize) -> f64 {
+        match &query.table {
+            Some(table) => self.distance_of(table.dot(self.code(node)), query.squared_norm, self.code_norms[node]),
+            None => self.distance(query.vector, &self.vector(node)),
+        }
+    }
+
+    /// Prepare a query vector, compared with codes if the collection is quantized.
+    fn query<'a>(&self, vector: &'a [f32], quantized: bool) -> Query<'a> {
+        Query {
+            vector,
+            squared_norm: dot(vector, vector),
+            table: self.quantizer.as_ref().filter(|_| quantized).map(|quantizer| quantizer.table(vector)),
+        }
+    }
+
+    /// The similarity score of a distance, higher is more similar.
+    fn score(&self, distance: f64) -> f64 {
+        match self.distance {
+            VectorDistanceType::Cosine => 1.0 - distance,
+            VectorDistanceType::Dot => -distance,
+            VectorDistanceType::L2 => 1.0 / (1.0 + distance),
+        }
+    }
+
+    /// The full vector of a node, held in memory or memory-mapped.
+    fn full_vector(&self, node: usize) -> Option<Cow<'_, [f32]>> {
+        match &self.documents[node].vector {
+            Some(vector) => Some(Cow::Borrowed(vector)),
+            None => self.mapped_vectors.as_ref()?.vector(node).map(Cow::Owned),
+        }
+    }
+
+    /// The vector of a node, reconstructed from its code when only the code is kept.
+    fn vector(&self, node: usize) -> Cow<'_, [f32]> {
+        if let Some(vector) = &self.documents[node].vector {
+            return Cow::Borrowed(vector);
+        }
+        match self.decoded_vectors.get(node) {
+            Some(decoded) => Cow::Borrowed(decoded.get_or_init(|| self.decode_vector(node))),
+            None => Cow::Owned(self.decode_vector(node)),
+        }
+    }
+
+    /// The vector of a node kept out of memory, read from the mapped vectors or reconstructed from its code.
+    fn decode_vector(&self, node: usize) -> Vec<f32> {
+        match (self.mapped_vectors.as_ref().and_then(|mapped| mapped.vector(node)), &self.quantizer) {
+            (Some(vector), _) => vector,
+            (None, Some(quantizer)) => quantizer.decode(self.code(node)),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Run `link` with the vectors kept out of memory decoded on first use, dropping them afterwards.
+    fn with_decoded_vectors(&mut self, link: impl FnOnce(&mut Self)) {
+        if self.documents.iter().any(|document| document.vector.is_none()) {
+            self.decoded_vectors = (0..self.documents.len()).map(|_| OnceLock::new()).collect();
+        }
+        link(self);
+        self.decoded_vectors = Vec::new();
+    }
+
+    /// The code of a node, the collection must be quantized.
+    fn code(&self, node: usize) -> &[u8] {
+        let code_size = self.quantizer.as_ref().map_or(0, Quantizer::code_size);
+        &self.codes[node * code_size..(node + 1) * code_size]
+    }
+
+    /// The document of a node, with its vector.
+    fn document(&self, node: usize) -> VectorStoreDocument {
+        let mut document = self.documents[node].clone();
+        if document.vector.is_none() {
+            document.vector = Some(self.vector(node).into_owned());
+        }
+        document
+    }
+
+    /// The top layer of a node, drawn from an exponentially decaying distribution.
+    fn random_layer(&self, node: usize) -> usize {
+        let sample = (SplitMix64::new(node as u64).next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        (-(1.0 - sample).ln() / (self.m as f64).ln()).floor() as usize
+    }
+
+    /// The maximum number of neighbors of a node on a layer.
+    fn max_neighbors(&self, layer: usize) -> usize {
+        if layer == 0 { self.m * 2 } else { self.m }
+    }
+
+    /// Insert an indexed document into the graph.
+    fn insert(&mut self, node: usize) {
+        let top_layer = self.random_layer(node);
+        self.graph.neighbors.push(vec![Vec::new(); top_layer + 1]);
+        let Some(entry_point) = self.graph.entry_point else {
+            self.graph.entry_point = Some(node);
+            return;
+        };
+
+        let vector = self.vector(node).into_owned();
+        let query = self.query(&vector, false);
+        let entry_layer = self.graph.neighbors[entry_point].len() - 1;
+        let mut entry_points = vec![entry_point];
+        for layer in (top_layer + 1..=entry_layer).rev() {
+            entry_points = vec![self.search_layer(&query, &entry_points, 1, layer)[0].1];
+        }
+
+        for layer in (0..=top_layer.min(entry_layer)).rev() {
+            let candidates = self.search_layer(&query, &entry_points, self.ef_construction, layer);
+            let neighbors: Vec<usize> = candidates.iter().take(self.m).map(|candidate| candidate.1).collect();
+            for neighbor in &neighbors {
+                self.graph.neighbors[*neighbor][layer].push(node);
+                self.prune(*neighbor, layer);
+            }
+            self.graph.neighbors[node][layer] = neighbors;
+            entry_points = candidates.into_iter().map(|candidate| candidate.1).collect();
+        }
+
+        if top_layer > entry_layer {
+            self.graph.entry_point = Some(node);
+        }
+    }
+
+    /// Keep only the closest neighbors of a node on a layer.
+    fn prune(&mut self, node: usize, layer: usize) {
+        let max_neighbors = self.max_neighbors(layer);
+        if self.graph.neighbors[node][layer].len() <= max_neighbors {
+            return;
+        }
+        let vector = self.vector(node);
+        let mut neighbors: Vec<Candidate> = self.graph.neighbors[node][layer]
+            .iter()
+            .map(|neighbor| Candidate(self.distance(&vector, &self.vector(*neighbor)), *neighbor))
+            .collect();
+        neighbors.sort();
+        neighbors.truncate(max_neighbors);
+        self.graph.neighbors[node][layer] = neighbors.into_iter().map(|candidate| candidate.1).collect();
+    }
+
+    /// Find the `ef` nodes closest to the query on a layer, closest first.
+    fn search_layer(&self, query: &Query, entry_points: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for node in entry_points {
+            let distance = self.query_distance(query, *node);
+            candidates.push(Reverse(Candidate(distance, *node)));
+            results.push(Candidate(distance, *node));
+        }
+
+        while let Some(Reverse(Candidate(distance, node))) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|furthest| distance > furthest.0) {
+                break;
+            }
+            for neighbor in &self.graph.neighbors[node][layer] {
+                if !visited.insert(*neighbor) {
+                    continue;
+                }
+                let distance = self.query_distance(query, *neighbor);
+                if results.len() < ef || results.peek().is_some_and(|furthest| distance < furthest.0) {
+                    candidates.push(Reverse(Candidate(distance, *neighbor)));
+                    results.push(Candidate(distance, *neighbor));
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Find the `k` nodes closest to the query through the graph, closest first.
+    fn search_graph(&self, query: &Query, k: usize, ef: usize) -> Vec<Candidate> {
+        let Some(entry_point) = self.graph.entry_point else {
+            return Vec::new();
+        };
+        let mut entry_points = vec![entry_point];
+        for layer in (1..self.graph.neighbors[entry_point].len()).rev() {
+            entry_points = vec![self.search_layer(query, &entry_points, 1, layer)[0].1];
+        }
+        let mut results = self.search_layer(query, &entry_points, ef.max(k), 0);
+        results.truncate(k);
+        results
+    }
+
+    /// Find the `k` nodes closest to the query by comparing it with every given node, closest first.
+    fn search_exhaustive(&self, query: &Query, nodes: impl Iterator<Item = usize>, k: usize) -> Vec<Candidate> {
+        let mut results: Vec<Candidate> = nodes
+            .map(|node| Candidate(self.query_distance(query, node), node))
+            .collect();
+        results.sort();
+        results.truncate(k);
+        results
+    }
+
+    /// Find the `k` nodes closest to the query among the documents matching the filter, closest first.
+    fn search_filtered(&self, query: &Query, k: usize, filter: &VectorStoreFilter) -> Vec<Candidate> {
+        let matching = |node: &usize| filter.matches(&self.documents[*node]);
+        let limit = self.ef_search.max(k) * FILTER_SCAN_FACTOR;
+
+        // small id allow-lists are cheaper to scan than to search through the graph
+        if let Some(ids) = filter.allowed_ids().filter(|ids| ids.len() <= limit) {
+            let nodes = ids.into_iter().filter_map(|id| self.positions.get(id).copied());
+            return self.search_exhaustive(query, nodes.filter(matching), k);
+        }
+
+        let mut results = self.search_graph(query, limit, limit);
+        results.retain(|candidate| matching(&candidate.1));
+        if results.len() < k {
+            // the filter is too selective for the graph neighborhood of the query
+            results = self.search_exhaustive(query, (0..self.documents.len()).filter(matching), k);
+        }
+        results.truncate(k);
+        results
+    }
+
+    /// Rebuild the graph from the indexed documents.
+    fn rebuild(&mut self) {
+        self.graph = HnswGraph::default();
+        self.with_decoded_vectors(|store| {
+            for node in 0..store.documents.len() {
+                store.insert(node);
+            }
+        });
+    }
+
+    /**
+    Relink the nodes that lost neighbors to deleted nodes, so their neighborhoods stay connected.
+
+    Every such node picks its closest neighbors among its remaining ones and those of the deleted
+    nodes it was linked to.
+
+    Args:
+        - deleted: The nodes about to be deleted, still part of the graph.
+    */
+    fn repair(&mut self, deleted: &HashSet<usize>) {
+        for node in (0..self.graph.neighbors.len()).filter(|node| !deleted.contains(node)) {
+            for layer in 0..self.graph.neighbors[node].len() {
+                let neighbors = &self.graph.neighbors[node][layer];
+                if !neighbors.iter().any(|neighbor| deleted.contains(neighbor)) {
+                    continue;
+                }
+                let mut candidates: Vec<usize> =
+                    neighbors.iter().copied().filter(|neighbor| !deleted.contains(neighbor)).collect();
+                for removed in neighbors.iter().filter(|neighbor| deleted.contains(neighbor)) {
+                    for candidate in &self.graph.neighbors[*removed][layer] {
+                        if *candidate != node && !deleted.contains(candidate) && !candidates.contains(candidate) {
+                            candidates.push(*candidate);
+                        }
+                    }
+                }
+                self.graph.neighbors[node][layer] = candidates;
+                self.prune(node, layer);
+            }
+        }
+    }
+
+    /// Learn the configured quantizer from the vectors of the collection, returning it with their codes.
+    fn fit_quantizer(&self) -> bincode::Result<(Option<Quantizer>, Vec<u8>)> {
+        let vectors: Vec<Vec<f32>> = (0..self.documents.len()).map(|node| self.vector(node).into_owned()).collect();
+        let slices: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        let quantizer = Quantizer::fit(self.quantization, &slices, self.pq_subvector_size).map_err(|e| {
+            Box::new(bincode::ErrorKind::Custom(format!("Vector store collection {}: {e}", self.collection_name)))
+        })?;
+        let codes = match &quantizer {
+            Some(quantizer) => slices.iter().flat_map(|vector| quantizer.encode(vector)).collect(),
+            None => Vec::new(),
+        };
+        Ok((quantizer, codes))
+    }
+
+    /// Quantize the vectors of the collection with the configured quantization.
+    fn quantize(&mut self) -> bincode::Result<()> {
+        (self.quantizer, self.codes) = self.fit_quantizer()?;
+        self.index_codes();
+        Ok(())
+    }
+
+    /// Append the code of a new node, when the collection is quantized.
+    fn push_code(&mut self, node: usize) {
+        let Some(quantizer) = &self.quantizer else {
+            return;
+        };
+        let code = quantizer.encode(&self.vector(node));
+        let reconstruction = quantizer.decode(&code);
+        self.code_norms.push(dot(&reconstruction, &reconstruction));
+        self.codes.extend(code);
+    }
+
+    /// Drop the codes, restoring the vectors only kept as codes or memory-mapped.
+    fn dequantize(&mut self) {
+        for node in 0..self.documents.len() {
+            if self.documents[node].vector.is_none() {
+                self.documents[node].vector = Some(self.vector(node).into_owned());
+            }
+        }
+        self.mapped_vectors = None;
+        self.quantizer = None;
+        self.codes.clear();
+        self.code_norms.clear();
+    }
+
+    /// Compute the squared norms of the reconstructions of the codes.
+    fn index_codes(&mut self) {
+        self.code_norms = match &self.quantizer {
+            Some(quantizer) => self
+                .codes
+                .chunks(quantizer.code_size())
+                .map(|code| {
+                    let vector = quantizer.decode(code);
+                    dot(&vector, &vector)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// Whether the quantizer matches the configured quantization.
+    fn quantizer_matches(&self) -> bool {
+        match &self.quantizer {
+            None => self.quantization == VectorQuantizationType::None,
+            Some(Quantizer::Product { subvector_size, .. }) => {
+                self.quantization == VectorQuantizationType::Product && *subvector_size == self.pq_subvector_size
+            }
+            Some(quantizer) => quantizer.kind() == self.quantization,
+        }
+    }
+
+    /// Write the collection in its binary form, borrowing the documents instead of copying them,
+    /// and the full vectors of its nodes, when it keeps them, to `vector_writer`.
+    fn write(&self, mut writer: impl Write, mut vector_writer: impl Write) -> bincode::Result<()> {
+        // collections are quantized once they are complete, so the quantizer learns from every vector
+        let fitted = if self.quantizer_matches() { None } else { Some(self.fit_quantizer()?) };
+        let (quantizer, codes) = match &fitted {
+            Some((quantizer, codes)) => (quantizer.as_ref(), codes.as_slice()),
+            None => (self.quantizer.as_ref(), self.codes.as_slice()),
+        };
+        // collections loaded without re-ranking only keep their codes
+        let vector_dimensions = (0..self.documents.len())
+            .all(|node| self.full_vector(node).is_some())
+            .then(|| self.documents.first().map_or(0, |_| self.vector(0).len()));
+        let header = StoredCollection {
+            distance: Cow::Borrowed(self.distance.as_str()),
+            m: self.m,
+            document_count: self.documents.len(),
+            vector_dimensions,
+            graph: Cow::Borrowed(&self.graph),
+            quantizer: quantizer.map(Cow::Borrowed),
+            codes: Cow::Borrowed(codes),
+        };
+        bincode::serialize_into(&mut writer, &header)?;
+        for document in &self.documents {
+            let stored = StoredDocument {
+                id: Cow::Borrowed(&document.id),
+                text: document.text.as_deref().map(Cow::Borrowed),
+                attributes: serde_json::to_string(&document.attributes).unwrap(),
+                content_hash: document.content_hash.as_deref().map(Cow::Borrowed),
+            };
+            bincode::serialize_into(&mut writer, &stored)?;
+        }
+
+        let Some(dimensions) = vector_dimensions else {
+            return Ok(());
+        };
+        for node in 0..self.documents.len() {
+            let vector = self.vector(node);
+            if vector.len() != dimensions {
+                return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                    "Document {} has {} dimensions, expected {dimensions}",
+                    self.documents[node].id,
+                    vector.len(),
+                ))));
+            }
+            for value in vector.iter() {
+                vector_writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /**
+    Write the collection, replacing the file of its full vectors with a new one rather than writing it in place.
+
+    Loaded collections keep the previous file memory-mapped, and the mapping must never change under them.
+
+    Args:
+        - writer: The writer of the collection.
+        - path: The file of the full vectors.
+    */
+    fn write_replacing(&self, writer: impl Write, path: &Path) -> bincode::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let replacement = path.with_extension(format!("vectors.{}.tmp", std::process::id()));
+        let written = File::create(&replacement)
+            .map_err(bincode::Error::from)
+            .and_then(|file| {
+                let mut vector_writer = BufWriter::new(file);
+                self.write(writer, &mut vector_writer)?;
+                Ok(vector_writer.flush()?)
+            })
+            .and_then(|_| Ok(fs::rename(&replacement, path)?));
+        if written.is_err() {
+            let _ = fs::remove_file(&replacement);
+        }
+        written
+    }
+
+    /**
+    Replace the collection with one written by `write`, adapting it to the configuration of the store.
+
+    Quantized collections re-ranking their results keep the full vectors memory-mapped, other
+    collections read them into memory or drop them.
+
+    Args:
+        - reader: The collection.
+        - vectors: The full vectors written next to the collection, memory-mapped.
+    */
+    fn read(&mut self, mut reader: impl Read, vectors: Mmap) -> bincode::Result<()> {
+        let header: StoredCollection = bincode::deserialize_from(&mut reader)?;
+        let mut documents = Vec::with_capacity(header.document_count);
+        for _ in 0..header.document_count {
+            let stored: StoredDocument = bincode::deserialize_from(&mut reader)?;
+            documents.push(VectorStoreDocument {
+                id: stored.id.into_owned(),
+                text: stored.text.map(Cow::into_owned),
+                vector: None,
+                attributes: serde_json::from_str(&stored.attributes).map_err(|e| Box::new(bincode::ErrorKind::Custom(e.to_string())))?,
+                content_hash: stored.content_hash.map(Cow::into_owned),
+            });
+        }
+        let mapped_vectors = match header.vector_dimensions {
+            Some(dimensions) if vectors.len() != header.document_count * dimensions * size_of::<f32>() => {
+                return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                    "Expected {} vectors of {dimensions} dimensions, found {} bytes",
+                    header.document_count,
+                    vectors.len(),
+                ))));
+            }
+            Some(dimensions) => Some(MappedVectors {
+                map: vectors,
+                dimensions,
+                rows: (0..header.document_count).map(Some).collect(),
+            }),
+            None => None,
+        };
+
+        self.positions = documents
+            .iter()
+            .enumerate()
+            .map(|(position, document)| (document.id.clone(), position))
+            .collect();
+        self.documents = documents;
+        self.mapped_vectors = mapped_vectors;
+        self.graph = header.graph.into_owned();
+        self.quantizer = header.quantizer.map(Cow::into_owned);
+        self.codes = header.codes.into_owned();
+        self.index_codes();
+        // the graph only holds for the distance and connectivity it was built with
+        if header.distance != self.distance.as_str() || header.m != self.m {
+            info!("Rebuilding vector store collection {} for distance {:?}", self.collection_name, self.distance);
+            self.rebuild();
+        }
+        if !self.quantizer_matches() {
+            info!("Quantizing vector store collection {} with {:?}", self.collection_name, self.quantization);
+            self.dequantize();
+            self.quantize()?;
+        }
+
+        if self.quantizer.is_none() {
+            // collections without codes are searched with the full vectors
+            for node in 0..self.documents.len() {
+                if self.documents[node].vector.is_none() {
+                    self.documents[node].vector = Some(self.vector(node).into_owned());
+                }
+            }
+            self.mapped_vectors = None;
+        } else if self.rerank_factor == 0 {
+            // without re-ranking only the codes are searched, so the full vectors are not worth keeping
+            for document in self.documents.iter_mut() {
+                document.vector = None;
+            }
+            self.mapped_vectors = None;
+        }
+        Ok(())
+    }
+}
+
+impl MappedVectors {
+    /// The full vector of a node, None if it was inserted since the collection was loaded.
+    fn vector(&self, node: usize) -> Option<Vec<f32>> {
+        let row = (*self.rows.get(node)?)?;
+        let row_size = self.dimensions * size_of::<f32>();
+        let bytes = &self.map[row * row_size..(row + 1) * row_size];
+        Some(
+            bytes
+                .chunks_exact(size_of::<f32>())
+                .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+                .collect(),
+        )
+    }
+}
+
+/// Memory-map stored vectors in place, the file is only ever replaced by `write_replacing`, never written.
+fn map_file(path: &Path) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: saving a collection renames a new file over the old one, so the mapped file never changes
+    unsafe { Mmap::map(&file) }
+}
+
+/// Copy vectors read from storage into an anonymous memory map, for storage off the local file system.
+fn map_bytes(vectors: &[u8]) -> io::Result<Mmap> {
+    let mut map = MmapMut::map_anon(vectors.len())?;
+    map.copy_from_slice(vectors);
+    map.make_read_only()
+}
+
+impl BaseVectorStore for HnswVectorStore {
+    /// Connect to the vector storage, the collection is held in memory so there is nothing to connect to.
+    fn connect(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Load documents into vector storage, replacing documents with the same id and skipping documents without a vector.
+    fn load_documents(&mut self, documents: Vec<VectorStoreDocument>, overwrite: bool) -> Result<(), String> {
+        if overwrite {
+            self.documents.clear();
+            self.positions.clear();
+            self.graph = HnswGraph::default();
+            self.quantizer = None;
+            self.codes.clear();
+            self.code_norms.clear();
+            self.mapped_vectors = None;
+        }
+
+        let (mut documents, missing): (Vec<VectorStoreDocument>, Vec<VectorStoreDocument>) =
+            documents.into_iter().partition(|document| document.vector.is_some());
+        if !missing.is_empty() {
+            warn!(
+                "Skipping {} documents without a vector in vector store collection {}, such as {}",
+                missing.len(),
+                self.collection_name,
+                missing[0].id,
+            );
+        }
+
+        // the last document with an id wins
+        let mut seen = HashSet::new();
+        documents.reverse();
+        documents.retain(|document| seen.insert(document.id.clone()));
+        documents.reverse();
+
+        // replaced documents are inserted again, so they are linked to their new neighbors
+        let replaced = documents
+            .iter()
+            .filter(|document| self.positions.contains_key(&document.id))
+            .map(|document| document.id.clone())
+            .collect();
+        self.delete_documents(replaced)?;
+
+        let first_node = self.documents.len();
+        for document in documents {
+            let node = self.documents.len();
+            self.positions.insert(document.id.clone(), node);
+            self.documents.push(document);
+            self.push_code(node);
+        }
+        self.with_decoded_vectors(|store| {
+            for node in first_node..store.documents.len() {
+                store.insert(node);
+            }
+        });
+        Ok(())
+    }
+
+    /// Delete documents by id, unlinking them from the graph and relinking their neighbors.
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String> {
+        let deleted: HashSet<usize> = ids.iter().filter_map(|id| self.positions.get(id).copied()).collect();
+        if deleted.is_empty() {
+            return Ok(());
+        }
+        self.with_decoded_vectors(|store| store.repair(&deleted));
+
+        let node_count = self.documents.len();
+        // the remaining nodes keep their order
+        let mut remap = vec![None; node_count];
+        for (position, node) in (0..node_count).filter(|node| !deleted.contains(node)).enumerate() {
+            remap[node] = Some(position);
+        }
+        let kept = |node: &usize| remap[*node].is_some();
+
+        let code_size = self.quantizer.as_ref().map_or(0, Quantizer::code_size);
+        if code_size > 0 {
+            self.codes = (0..node_count)
+                .filter(kept)
+                .flat_map(|node| self.codes[node * code_size..(node + 1) * code_size].to_vec())
+                .collect();
+            self.code_norms = (0..node_count).filter(kept).map(|node| self.code_norms[node]).collect();
+        }
+        if let Some(mapped_vectors) = &mut self.mapped_vectors {
+            let rows = std::mem::take(&mut mapped_vectors.rows);
+            mapped_vectors.rows = (0..node_count).filter(kept).map(|node| rows.get(node).copied().flatten()).collect();
+        }
+        self.documents = std::mem::take(&mut self.documents)
+            .into_iter()
+            .enumerate()
+            .filter(|(node, _)| kept(node))
+            .map(|(_, document)| document)
+            .collect();
+        self.positions = self
+            .documents
+            .iter()
+            .enumerate()
+            .map(|(position, document)| (document.id.clone(), position))
+            .collect();
+
+        let neighbors = std::mem::take(&mut self.graph.neighbors);
+        self.graph.neighbors = neighbors
+            .into_iter()
+            .enumerate()
+            .filter(|(node, _)| kept(node))
+            .map(|(_, layers)| {
+                layers
+                    .into_iter()
+                    .map(|layer| layer.into_iter().filter_map(|neighbor| remap[neighbor]).collect())
+                    .collect()
+            })
+            .collect();
+        self.graph.entry_point = match self.graph.entry_point.and_then(|node| remap[node]) {
+            Some(entry_point) => Some(entry_point),
+            // any node on the highest remaining layer can take over
+            None => (0..self.graph.neighbors.len()).max_by_key(|node| self.graph.neighbors[*node].len()),
+        };
+        Ok(())
+    }
+
+    /// The content hash of every stored document that has one, keyed by id.
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        Ok(self
+            .documents
+            .iter()
+            .filter_map(|document| Some((document.id.clone(), document.content_hash.clone()?)))
+            .collect())
+    }
+
+    /// Build a query filter to filter documents by id, applied to every following search.
+    fn filter_by_id(&mut self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
+        self.query_filter = if include_ids.is_empty() {
+            None
+        } else {
+            Some(VectorStoreFilter::Ids(include_ids.into_iter().collect()))
+        };
+        self.query_filter.clone()
+    }
+
+    /// Perform a vector-based similarity search, keeping only documents matching the filter.
+    fn similarity_search_by_vector(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let query = self.query(&query_embedding, true);
+        // quantized distances are approximate, so more candidates are compared with the full vectors
+        let rerank = query.table.is_some() && self.rerank_factor > 0;
+        let depth = if rerank { k * self.rerank_factor } else { k };
+
+        let filter = VectorStoreFilter::and(self.query_filter.clone(), filter.cloned());
+        let mut results = match filter {
+            None => self.search_graph(&query, depth, self.ef_search),
+            Some(filter) => self.search_filtered(&query, depth, &filter),
+        };
+        if rerank {
+            for candidate in results.iter_mut() {
+                candidate.0 = self.distance(&query_embedding, &self.vector(candidate.1));
+            }
+            results.sort();
+            results.truncate(k);
+        }
+
+        results
+            .into_iter()
+            .map(|Candidate(distance, node)| VectorStoreSearchResult {
+                document: self.document(node),
+                score: self.score(distance),
+            })
+            .collect()
+    }
+
+    /// Perform a similarity search using a given input text.
+    fn similarity_search_by_text(
+        &self,
+        text: &str,
+        text_embedder: TextEmbedder,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let query_embedding = text_embedder(text);
+        if query_embedding.is_empty() {
+            return Vec::new();
+        }
+        self.similarity_search_by_vector(query_embedding, k, filter)
+    }
+
+    /// Search for a document by id.
+    fn search_by_id(&self, id: &str) -> VectorStoreDocument {
+        match self.positions.get(id) {
+            Some(position) => self.document(*position),
+            None => VectorStoreDocument {
+                id: id.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Get the stored documents with the given ids, leaving out ids that are not stored.
+    fn get_by_ids(&self, ids: &[String]) -> Result<Vec<VectorStoreDocument>, String> {
+        Ok(ids
+            .iter()
+            .filter_map(|id| self.positions.get(id))
+            .map(|position| self.document(*position))
+            .collect())
+    }
+
+    /// Read the collection from pipeline storage, keeping it empty if it was never saved.
+    fn load<'a>(&'a mut self, storage: &'a dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            let key = self.storage_key();
+            let Some(bytes) = storage.get_bytes(&key).await else {
+                warn!("No vector store collection found at {key}");
+                return;
+            };
+            // local vectors are mapped in place, without reading them into memory
+            let vectors_key = self.vectors_key();
+            let vectors = match storage.local_path(&vectors_key).filter(|path| path.exists()) {
+                Some(path) => map_file(&path),
+                None => map_bytes(&storage.get_bytes(&vectors_key).await.unwrap_or_default()),
+            };
+            let result = vectors.map_err(bincode::Error::from).and_then(|vectors| self.read(bytes.as_slice(), vectors));
+            if let Err(e) = result {
+                warn!("Could not read vector store collection {key}: {e}");
+                *self = HnswVectorStore::new(
+                    &self.collection_name,
+                    self.distance,
+                    self.m,
+                    self.ef_construction,
+                    self.ef_search,
+                    self.quantization,
+                    self.pq_subvector_size,
+                    self.rerank_factor,
+                );
+            }
+        })
+    }
+
+    /// Write the collection and its full vectors to pipeline storage.
+    fn save<'a>(&'a self, storage: &'a mut dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            let key = self.storage_key();
+            let vectors_key = self.vectors_key();
+            let mut bytes = Vec::new();
+            let mut vectors = Vec::new();
+            let written = match storage.local_path(&vectors_key) {
+                Some(path) => self.write_replacing(&mut bytes, &path),
+                None => self.write(&mut bytes, &mut vectors),
+            };
+            if let Err(e) = written {
+                warn!("Could not write vector store collection {key}: {e}");
+                return;
+            }
+            storage.set_bytes(&key, bytes).await;
+            if storage.local_path(&vectors_key).is_none() {
+                storage.set_bytes(&vectors_key, vectors).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo random vectors with components in [-1, 1).
+    fn random_documents(count: usize, dimensions: usize, seed: u64) -> Vec<VectorStoreDocument> {
+        let mut rng = SplitMix64::new(seed);
+        (0..count)
+            .map(|index| VectorStoreDocument {
+                id: format!("doc-{index}"),
+                text: Some(format!("text {index}")),
+                vector: Some(
+                    (0..dimensions)
+                        .map(|_| (rng.next_u64() >> 40) as f32 / (1u64 << 23) as f32 - 1.0)
+                        .collect(),
+                ),
+                attributes: HashMap::from([("index".to_string(), serde_json::json!(index))]),
+                content_hash: Some(format!("hash-{index}")),
+            })
+            .collect()
+    }
+
+    fn store(quantization: VectorQuantizationType, rerank_factor: usize) -> HnswVectorStore {
+        HnswVectorStore::new("test", VectorDistanceType::Cosine, 16, 100, 64, quantization, 4, rerank_factor)
+    }
+
+    fn ids(results: &[VectorStoreSearchResult]) -> Vec<String> {
+        results.iter().map(|result| result.document.id.clone()).collect()
+    }
+
+    /// Write a collection and read it back into a store.
+    fn round_trip(saved: &HnswVectorStore, loaded: &mut HnswVectorStore) {
+        let mut bytes = Vec::new();
+        let mut vectors = Vec::new();
+        saved.write(&mut bytes, &mut vectors).unwrap();
+        loaded.read(bytes.as_slice(), map_bytes(&vectors).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn recall_matches_brute_force() {
+        let documents = random_documents(1000, 32, 1);
+        let mut hnsw = store(VectorQuantizationType::None, 0);
+        hnsw.load_documents(documents.clone(), true).unwrap();
+
+        let k = 10;
+        let mut found = 0;
+        for query in random_documents(50, 32, 2) {
+            let vector = query.vector.unwrap();
+            let mut exact: Vec<Candidate> = documents
+                .iter()
+                .enumerate()
+                .map(|(node, document)| Candidate(hnsw.distance(&vector, document.vector.as_ref().unwrap()), node))
+                .collect();
+            exact.sort();
+            let exact: HashSet<String> = exact[..k].iter().map(|candidate| documents[candidate.1].id.clone()).collect();
+            let results = hnsw.similarity_search_by_vector(vector, k, None);
+            found += results.iter().filter(|result| exact.contains(&result.document.id)).count();
+        }
+        let recall = found as f64 / (50 * k) as f64;
+        assert!(recall >= 0.95, "recall {recall}");
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        for quantization in [VectorQuantizationType::None, VectorQuantizationType::Int8] {
+            let mut saved = store(quantization, 4);
+            saved.load_documents(random_documents(300, 16, 3), true).unwrap();
+            let mut loaded = store(quantization, 4);
+            round_trip(&saved, &mut loaded);
+            assert_eq!(loaded.content_hashes().unwrap(), saved.content_hashes().unwrap());
+            assert_eq!(loaded.search_by_id("doc-7").attributes["index"], serde_json::json!(7));
+            assert_eq!(loaded.search_by_id("doc-7").text.as_deref(), Some("text 7"));
+            for query in random_documents(10, 16, 4) {
+                let vector = query.vector.unwrap();
+                assert_eq!(
+                    ids(&loaded.similarity_search_by_vector(vector.clone(), 5, None)),
+                    ids(&saved.similarity_search_by_vector(vector, 5, None)),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn loaded_quantized_collections_keep_full_vectors_mapped() {
+        let documents = random_documents(300, 16, 6);
+        let mut saved = store(VectorQuantizationType::Product, 4);
+        saved.load_documents(documents.clone(), true).unwrap();
+        let mut loaded = store(VectorQuantizationType::Product, 4);
+        round_trip(&saved, &mut loaded);
+
+        assert!(loaded.documents.iter().all(|document| document.vector.is_none()));
+        assert_eq!(loaded.search_by_id("doc-7").vector, documents[7].vector);
+        // deleting nodes moves the remaining ones, which must keep their own vectors
+        loaded.delete_documents(vec!["doc-0".to_string()]).unwrap();
+        loaded.load_documents(random_documents(1, 16, 7), false).unwrap();
+        assert_eq!(loaded.search_by_id("doc-7").vector, documents[7].vector);
+        assert_eq!(loaded.search_by_id("doc-299").vector, documents[299].vector);
+
+        // collections re-ranking nothing drop the full vectors, and only store codes from then on
+        let mut codes_only = store(VectorQuantizationType::Product, 0);
+        round_trip(&saved, &mut codes_only);
+        assert!(codes_only.mapped_vectors.is_none());
+        let mut reloaded = store(VectorQuantizationType::Product, 4);
+        round_trip(&codes_only, &mut reloaded);
+        assert!(reloaded.mapped_vectors.is_none());
+        assert_eq!(reloaded.search_by_id("doc-7").vector.map(|vector| vector.len()), Some(16));
+    }
+
+    /// Collection storage in a local directory, writing files in place like the file pipeline storage.
+    struct DirectoryStorage(std::path::PathBuf);
+
+    impl CollectionStorage for DirectoryStorage {
+        fn get_bytes<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, Option<Vec<u8>>> {
+            Box::pin(async move { fs::read(self.0.join(key)).ok() })
+        }
+
+        fn set_bytes<'a>(&'a mut self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, ()> {
+            Box::pin(async move { fs::write(self.0.join(key), value).unwrap() })
+        }
+
+        fn local_path(&self, key: &str) -> Option<std::path::PathBuf> {
+            Some(self.0.join(key))
+        }
+    }
+
+    #[test]
+    fn saving_a_loaded_collection_keeps_its_mapped_vectors() {
+        let directory = std::env::temp_dir().join(format!("hnsw-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let mut storage = DirectoryStorage(directory.clone());
+        let documents = random_documents(100, 8, 8);
+        let mut saved = store(VectorQuantizationType::Int8, 4);
+        saved.load_documents(documents.clone(), true).unwrap();
+        futures::executor::block_on(saved.save(&mut storage));
+
+        let mut loaded = store(VectorQuantizationType::Int8, 4);
+        futures::executor::block_on(loaded.load(&storage));
+        assert!(loaded.mapped_vectors.is_some());
+        loaded.delete_documents(vec!["doc-0".to_string()]).unwrap();
+        futures::executor::block_on(loaded.save(&mut storage));
+        // the vectors written by the second save replace the mapped file instead of overwriting it
+        assert_eq!(loaded.search_by_id("doc-7").vector, documents[7].vector);
+        assert_eq!(loaded.search_by_id("doc-99").vector, documents[99].vector);
+
+        let mut reloaded = store(VectorQuantizationType::Int8, 4);
+        futures::executor::block_on(reloaded.load(&storage));
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(reloaded.documents.len(), 99);
+        assert_eq!(reloaded.search_by_id("doc-99").vector, documents[99].vector);
+    }
+
+    #[test]
+    fn deleting_the_neighbors_of_a_node_relinks_it() {
+        let documents = random_documents(200, 8, 9);
+        let mut hnsw = store(VectorQuantizationType::None, 0);
+        hnsw.load_documents(documents.clone(), true).unwrap();
+        let neighbors: Vec<String> = hnsw.graph.neighbors[0][0]
+            .iter()
+            .map(|neighbor| documents[*neighbor].id.clone())
+            .collect();
+        hnsw.delete_documents(neighbors.clone()).unwrap();
+
+        let node = hnsw.positions["doc-0"];
+        assert!(!hnsw.graph.neighbors[node][0].is_empty());
+        // every node stays reachable from the entry point on the bottom layer
+        let mut reached = HashSet::from([hnsw.graph.entry_point.unwrap()]);
+        let mut pending: Vec<usize> = reached.iter().copied().collect();
+        while let Some(node) = pending.pop() {
+            for neighbor in &hnsw.graph.neighbors[node][0] {
+                if reached.insert(*neighbor) {
+                    pending.push(*neighbor);
+                }
+            }
+        }
+        assert_eq!(reached.len(), documents.len() - neighbors.len());
+    }
+
+    #[test]
+    fn documents_without_a_vector_are_skipped() {
+        let mut documents = random_documents(3, 4, 10);
+        documents[1].vector = None;
+        let mut hnsw = store(VectorQuantizationType::None, 0);
+        hnsw.load_documents(documents, true).unwrap();
+        assert_eq!(hnsw.get_by_ids(&["doc-0".into(), "doc-1".into(), "doc-2".into()]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn truncated_collection_is_an_error() {
+        let mut saved = store(VectorQuantizationType::None, 0);
+        saved.load_documents(random_documents(20, 8, 5), true).unwrap();
+        let mut bytes = Vec::new();
+        let mut vectors = Vec::new();
+        saved.write(&mut bytes, &mut vectors).unwrap();
+
+        let mut loaded = store(VectorQuantizationType::None, 0);
+        assert!(loaded.read(&bytes[..bytes.len() / 2], map_bytes(&vectors).unwrap()).is_err());
+        assert!(loaded.read(bytes.as_slice(), map_bytes(&vectors[..vectors.len() / 2]).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Hybrid BM25 and vector retrieval over any vector store.
+
+use std::collections::{HashMap, HashSet};
+
+use futures::future::LocalBoxFuture;
+use log::warn;
+
+use crate::config::models::hybrid_search_config::HybridSearchConfig;
+use crate::data_model::types::TextEmbedder;
+use crate::vector_stores::base::{
+    BaseVectorStore,
+    CollectionStorage,
+    VectorStoreDocument,
+    VectorStoreSearchResult,
+};
+use crate::vector_stores::bm25::{Bm25Index, reciprocal_rank_fusion};
+use crate::vector_stores::filtering::VectorStoreFilter;
+
+/// How many times the fused depth of BM25 hits are looked up in the vector store when they were not found by it.
+const SPARSE_LOOKUP_FACTOR: usize = 4;
+
+/**
+A vector store that also keeps a BM25 index of the document texts.
+
+Text searches retrieve from both indexes and fuse the two rankings with reciprocal rank fusion, so
+the score of a text search result is its fused score rather than a similarity.
+*/
+pub struct HybridVectorStore<S: BaseVectorStore> {
+    dense: S,
+    sparse: Bm25Index,
+    collection_name: String,
+    rrf_k: f64,
+    oversample: usize,
+    query_filter: Option<VectorStoreFilter>,
+}
+
+impl<S: BaseVectorStore> HybridVectorStore<S> {
+    /// Add a BM25 index to a vector store collection.
+    pub fn new(dense: S, collection_name: &str, config: &HybridSearchConfig) -> Self {
+        HybridVectorStore {
+            dense,
+            sparse: Bm25Index::new(config.k1, config.b),
+            collection_name: collection_name.to_string(),
+            rrf_k: config.rrf_k,
+            oversample: config.oversample.max(1),
+            query_filter: None,
+        }
+    }
+}
+
+impl<S: BaseVectorStore> BaseVectorStore for HybridVectorStore<S> {
+    /// Connect to the vector storage.
+    fn connect(&mut self) -> Result<(), String> {
+        self.dense.connect()
+    }
+
+    /// Load documents into both indexes.
+    fn load_documents(&mut self, documents: Vec<VectorStoreDocument>, overwrite: bool) -> Result<(), String> {
+        if overwrite {
+            self.sparse.clear();
+        } else {
+            let replaced: Vec<String> = documents.iter().map(|document| document.id.clone()).collect();
+            self.sparse.remove(&replaced);
+        }
+        for document in &documents {
+            if let Some(text) = &document.text {
+                self.sparse.add(&document.id, text);
+            }
+        }
+        self.dense.load_documents(documents, overwrite)
+    }
+
+    /// Perform a vector-based similarity search, there is no text to match terms against.
+    fn similarity_search_by_vector(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        self.dense.similarity_search_by_vector(query_embedding, k, filter)
+    }
+
+    /// Perform a hybrid search, fusing the BM25 and vector rankings of the text.
+    fn similarity_search_by_text(
+        &self,
+        text: &str,
+        text_embedder: TextEmbedder,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let depth = k * self.oversample;
+        let mut documents: HashMap<String, VectorStoreDocument> = HashMap::new();
+        let mut dense_ranking = Vec::new();
+        for result in self.dense.similarity_search_by_text(text, text_embedder, depth, filter) {
+            dense_ranking.push(result.document.id.clone());
+            documents.insert(result.document.id.clone(), result.document);
+        }
+
+        // the BM25 index only knows ids, so the best hits the vector search missed are fetched in one
+        // batch, and filters are checked against the stored documents
+        let filter = VectorStoreFilter::and(self.query_filter.clone(), filter.cloned());
+        let hits: Vec<String> = self
+            .sparse
+            .search(text)
+            .into_iter()
+            .take(depth * SPARSE_LOOKUP_FACTOR)
+            .map(|(id, _)| id)
+            .collect();
+        let missing: Vec<String> = hits.iter().filter(|id| !documents.contains_key(*id)).cloned().collect();
+        let missing = self.dense.get_by_ids(&missing).unwrap_or_else(|e| {
+            warn!("Could not fetch the documents of the BM25 hits: {e}");
+            Vec::new()
+        });
+        for document in missing {
+            if filter.as_ref().is_none_or(|filter| filter.matches(&document)) {
+                documents.insert(document.id.clone(), document);
+            }
+        }
+        let sparse_ranking: Vec<String> = hits
+            .into_iter()
+            .filter(|id| documents.contains_key(id))
+            .take(depth)
+            .collect();
+
+        reciprocal_rank_fusion(&[dense_ranking, sparse_ranking], self.rrf_k)
+            .into_iter()
+            .take(k)
+            .filter_map(|(id, score)| {
+                Some(VectorStoreSearchResult {
+                    document: documents.remove(&id)?,
+                    score,
+                })
+            })
+            .collect()
+    }
+
+    /// Build a query filter to filter documents by id, applied to every following search.
+    fn filter_by_id(&mut self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
+        self.query_filter = self.dense.filter_by_id(include_ids);
+        self.query_filter.clone()
+    }
+
+    /// Search for a document by id.
+    fn search_by_id(&self, id: &str) -> VectorStoreDocument {
+        self.dense.search_by_id(id)
+    }
+
+    /// Delete documents by id from both indexes.
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String> {
+        self.sparse.remove(&ids);
+        self.dense.delete_documents(ids)
+    }
+
+    /// The content hashes of the documents held by both indexes, so documents missing from either are loaded again.
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        let sparse: HashSet<&str> = self.sparse.ids().iter().map(String::as_str).collect();
+        let mut hashes = self.dense.content_hashes()?;
+        hashes.retain(|id, _| sparse.contains(id.as_str()));
+        Ok(hashes)
+    }
+
+    /// Get the stored documents with the given ids.
+    fn get_by_ids(&self, ids: &[String]) -> Result<Vec<VectorStoreDocument>, String> {
+        self.dense.get_by_ids(ids)
+    }
+
+    /// Read both indexes from pipeline storage.
+    fn load<'a>(&'a mut self, storage: &'a dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.dense.load(storage).await;
+            self.sparse.load(storage, &self.collection_name).await;
+        })
+    }
+
+    /// Write both indexes to pipeline storage.
+    fn save<'a>(&'a self, storage: &'a mut dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.dense.save(storage).await;
+            self.sparse.save(storage, &self.collection_name).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::enums::{VectorDistanceType, VectorQuantizationType};
+    use crate::vector_stores::hnsw::HnswVectorStore;
+
+    /// Embeds every text into the same vector, so only the BM25 ranking tells texts apart.
+    fn constant_embedder(_text: &str) -> Vec<f32> {
+        vec![1.0, 0.0]
+    }
+
+    fn document(id: &str, text: &str, vector: [f32; 2], kind: &str) -> VectorStoreDocument {
+        VectorStoreDocument {
+            id: id.to_string(),
+            text: Some(text.to_string()),
+            vector: Some(vector.to_vec()),
+            attributes: HashMap::from([("type".to_string(), json!(kind))]),
+            content_hash: Some(format!("hash-{id}")),
+        }
+    }
+
+    fn hybrid() -> HybridVectorStore<HnswVectorStore> {
+        let dense = HnswVectorStore::new("test", VectorDistanceType::Cosine, 4, 16, 16, VectorQuantizationType::None, 1, 0);
+        let config = HybridSearchConfig {
+            oversample: 1,
+            ..Default::default()
+        };
+        let mut store = HybridVectorStore::new(dense, "test", &config);
+        store.load_documents(
+            vec![
+                document("a", "the quarterly report", [1.0, 0.0], "REPORT"),
+                document("b", "part number XK-42 recall", [0.0, 1.0], "PART"),
+                document("c", "an unrelated note", [0.9, 0.1], "NOTE"),
+            ],
+            true,
+        )
+        .unwrap();
+        store
+    }
+
+    #[test]
+    fn exact_terms_rank_documents_the_vectors_miss() {
+        let store = hybrid();
+        let results = store.similarity_search_by_text("xk-42", constant_embedder, 2, None);
+        let ids: Vec<&str> = results.iter().map(|result| result.document.id.as_str()).collect();
+        assert!(ids.contains(&"b"), "{ids:?}");
+        assert_eq!(results.iter().find(|result| result.document.id == "b").unwrap().document.text.as_deref(), Some("part number XK-42 recall"));
+    }
+
+    #[test]
+    fn sparse_hits_are_filtered_on_their_documents() {
+        let store = hybrid();
+        let filter = VectorStoreFilter::Eq("type".into(), json!("REPORT"));
+        let results = store.similarity_search_by_text("xk-42 report", constant_embedder, 3, Some(&filter));
+        assert!(results.iter().all(|result| result.document.id == "a"));
+    }
+
+    #[test]
+    fn content_hashes_cover_documents_in_both_indexes() {
+        let mut store = hybrid();
+        assert_eq!(store.content_hashes().unwrap().len(), 3);
+        store.sparse.remove(&["c".to_string()]);
+        let hashes = store.content_hashes().unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert!(!hashes.contains_key("c"));
+        // an index that was never saved leaves every document to be loaded again
+        store.sparse.clear();
+        assert!(store.content_hashes().unwrap().is_empty());
+    }
+}
@@ -2,12 +2,17 @@
 
 //::pyarrow as pa
 
+use std::collections::HashMap;
+
+use log::warn;
+
 use crate::data_model::types::TextEmbedder;
 use crate::vector_stores::base::{
     BaseVectorStore,
     VectorStoreDocument,
     VectorStoreSearchResult,
 };
+use crate::vector_stores::filtering::{SqlLiterals, VectorStoreFilter};
 //::lancedb
 
 /// LanceDB vector storage implementation.
@@ -15,7 +20,7 @@ pub struct LanceDBVectorStore;
 
 impl BaseVectorStore for LanceDBVectorStore {
     /// Connect to the vector storage.
-    fn connect(&mut self, **kwargs: Any) -> Any {
+    fn connect(&mut self, **kwargs: Any) -> Result<(), String> {
         self.db_connection = lancedb.connect(kwargs["db_uri"]);
         if (
             self.collection_name
@@ -25,11 +30,12 @@ impl BaseVectorStore for LanceDBVectorStore {
                 self.collection_name
             )
         }
+        Ok(())
     }
 
     fn load_documents(
         self, documents: Vec<VectorStoreDocument>, overwrite: bool = True
-    ) {
+    ) -> Result<(), String> {
         /// Load documents into vector storage.
         data = [
             {
@@ -37,6 +43,7 @@ impl BaseVectorStore for LanceDBVectorStore {
                 "text": document.text,
                 "vector": document.vector,
                 "attributes": json.dumps(document.attributes),
+                "content_hash": document.content_hash,
             }
             for document in documents
             if document.vector is not None
@@ -48,8 +55,9 @@ impl BaseVectorStore for LanceDBVectorStore {
         schema = pa.schema([
             pa.field("id", pa.string()),
             pa.field("text", pa.string()),
-            pa.field("vector", pa.list_(pa.float64())),
+            pa.field("vector", pa.list_(pa.float32())),
             pa.field("attributes", pa.string()),
+            pa.field("content_hash", pa.string()),
         ])
         // NOTE: If modifying the next section of code, ensure that the schema remains the same.
         //       The pyarrow format of the 'vector' field may change if the order of operations is changed
@@ -70,32 +78,37 @@ impl BaseVectorStore for LanceDBVectorStore {
             )
             if data:
                 self.document_collection.add(data)
+        Ok(())
     }
 
-    fn filter_by_id(self, include_ids: Vec<String> | Vec<int>) -> Any:
+    fn filter_by_id(self, include_ids: Vec<String>) -> Option<VectorStoreFilter>:
         /// Build a query filter to filter documents by id.
         if len(include_ids) == 0:
             self.query_filter = None
         else:
-            if isinstance(include_ids[0], str):
-                id_filter = ", ".join([format!("'{id}'") for id in include_ids])
-                self.query_filter = format!("id in ({id_filter})")
-            else:
-                self.query_filter = (
-                    format!("id in ({', '.join([str(id) for id in include_ids])})")
-                )
+            self.query_filter = VectorStoreFilter::Ids(include_ids.into_iter().collect())
         return self.query_filter
 
     fn similarity_search_by_vector(
-        self, query_embedding: Vec<float>, k: int = 10, **kwargs: Any
+        self, query_embedding: Vec<float>, k: int = 10, filter: Option<&VectorStoreFilter> = None
     ) -> Vec<VectorStoreSearchResult>:
         /// Perform a vector-based similarity search.
-        if self.query_filter:
+        // attributes are stored as a JSON string, so attribute filters read from it
+        filter = VectorStoreFilter::and(self.query_filter, filter)
+        if filter:
+            // field names are validated as identifiers before they are spliced into the JSON path
+            predicate = match filter.to_sql(&mut SqlLiterals(|name| format!("json_extract(attributes, '$.{name}')"))) {
+                Ok(predicate) => predicate,
+                Err(e) => {
+                    warn!("Ignoring vector store search with an invalid filter: {e}");
+                    return [];
+                }
+            };
             docs = (
                 self.document_collection.search(
                     query=query_embedding, vector_column_name="vector"
                 )
-                .where(self.query_filter, prefilter=True)
+                .where(predicate, prefilter=True)
                 .limit(k)
                 .to_list()
             )
@@ -121,19 +134,19 @@ impl BaseVectorStore for LanceDBVectorStore {
         ]
 
     fn similarity_search_by_text(
-        self, text: str, text_embedder: TextEmbedder, k: int = 10, **kwargs: Any
+        self, text: str, text_embedder: TextEmbedder, k: int = 10, filter: Option<&VectorStoreFilter> = None
     ) -> Vec<VectorStoreSearchResult>:
         /// Perform a similarity search using a given input text.
         query_embedding = text_embedder(text)
         if query_embedding:
-            return self.similarity_search_by_vector(query_embedding, k)
+            return self.similarity_search_by_vector(query_embedding, k, filter)
         return []
 
     fn search_by_id(self, id: str) -> VectorStoreDocument:
         /// Search for a document by id.
         doc = (
             self.document_collection.search()
-            .where(format!("id == '{id}'"), prefilter=True)
+            .where(format!("id == '{}'", id.replace('\'', "''")), prefilter=True)
             .to_list()
         )
         if doc:
@@ -144,4 +157,22 @@ impl BaseVectorStore for LanceDBVectorStore {
                 attributes=json.loads(doc[0]["attributes"]),
             )
         return VectorStoreDocument(id=id, text=None, vector=None)
+
+    fn delete_documents(self, ids: Vec<String>) -> Result<(), String>:
+        /// Delete documents by id.
+        if self.document_collection is None or len(ids) == 0:
+            return Ok(())
+        quoted = ", ".join(format!("'{}'", id.replace("'", "''")) for id in ids)
+        self.document_collection.delete(format!("id IN ({quoted})"))
+        Ok(())
+
+    fn content_hashes(self) -> Result<HashMap<String, String>, String>:
+        /// The content hash of every stored document that has one, keyed by id.
+        if self.document_collection is None:
+            return Ok({})
+        // tables written before content hashes were stored have no content_hash column
+        if "content_hash" not in self.document_collection.schema.names:
+            return Ok({})
+        rows = self.document_collection.to_arrow().select(["id", "content_hash"]).to_pylist()
+        return Ok({row["id"]: row["content_hash"] for row in rows if row["content_hash"] is not None})
 }
@@ -0,0 +1,155 @@
+//! Multi-vector retrieval over any vector store, scoring texts by their best matching segments.
+
+use std::collections::HashMap;
+
+use futures::future::LocalBoxFuture;
+use serde_json::Value;
+
+use crate::config::models::multi_vector_config::MultiVectorConfig;
+use crate::data_model::types::TextEmbedder;
+use crate::index::text_splitting::segments::split_sentences;
+use crate::vector_stores::base::{
+    BaseVectorStore,
+    CollectionStorage,
+    PARENT_ID_ATTRIBUTE,
+    VectorStoreDocument,
+    VectorStoreSearchResult,
+    max_sim,
+};
+use crate::vector_stores::filtering::VectorStoreFilter;
+
+/// The id of the document holding a segment of a text.
+pub fn segment_id(parent_id: &str, segment: usize) -> String {
+    format!("{parent_id}#{segment}")
+}
+
+/**
+A vector store holding a vector per segment of every text.
+
+Every segment is stored as its own document, with the id of its text in the `parent_id` attribute.
+Searches retrieve segments for every query sentence and aggregate them back to the texts with
+max-sim, so results, ids and filters refer to the texts rather than the segments.
+*/
+pub struct LateInteractionVectorStore<S: BaseVectorStore> {
+    segments: S,
+    oversample: usize,
+    query_filter: Option<VectorStoreFilter>,
+}
+
+impl<S: BaseVectorStore> LateInteractionVectorStore<S> {
+    /// Search a vector store holding segment documents with late interaction.
+    pub fn new(segments: S, config: &MultiVectorConfig) -> Self {
+        LateInteractionVectorStore {
+            segments,
+            oversample: config.oversample.max(1),
+            query_filter: None,
+        }
+    }
+
+    /// Combine the query filter with a filter, restricting the texts of the segments rather than their ids.
+    fn segment_filter(&self, filter: Option<&VectorStoreFilter>) -> Option<VectorStoreFilter> {
+        VectorStoreFilter::and(self.query_filter.clone(), filter.cloned()).map(|filter| to_segment_filter(&filter))
+    }
+}
+
+impl<S: BaseVectorStore> BaseVectorStore for LateInteractionVectorStore<S> {
+    /// Connect to the vector storage.
+    fn connect(&mut self) -> Result<(), String> {
+        self.segments.connect()
+    }
+
+    /// Load segment documents into the vector store.
+    fn load_documents(&mut self, documents: Vec<VectorStoreDocument>, overwrite: bool) -> Result<(), String> {
+        self.segments.load_documents(documents, overwrite)
+    }
+
+    /// Perform a max-sim search with a single query vector, scoring every text with its best segment.
+    fn similarity_search_by_vector(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let filter = self.segment_filter(filter);
+        self.segments
+            .similarity_search_by_vectors(vec![query_embedding], k, filter.as_ref(), self.oversample)
+    }
+
+    /// Perform a late-interaction search, matching every sentence of the text with the segments of the texts.
+    fn similarity_search_by_text(
+        &self,
+        text: &str,
+        text_embedder: TextEmbedder,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let filter = self.segment_filter(filter);
+        let mut sentences = split_sentences(text);
+        if sentences.is_empty() {
+            sentences.push(text.to_string());
+        }
+        // segments are retrieved through the text search of the store, so hybrid stores still match terms
+        let rankings = sentences
+            .iter()
+            .map(|sentence| {
+                self.segments
+                    .similarity_search_by_text(sentence, text_embedder, k * self.oversample, filter.as_ref())
+            })
+            .collect();
+        max_sim(rankings, k)
+    }
+
+    /// Build a query filter to filter texts by id, applied to every following search.
+    fn filter_by_id(&mut self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
+        self.query_filter = if include_ids.is_empty() {
+            None
+        } else {
+            Some(VectorStoreFilter::Ids(include_ids.into_iter().collect()))
+        };
+        self.query_filter.clone()
+    }
+
+    /// Search for a text by id, returning its first segment, or a segment document by its own id.
+    fn search_by_id(&self, id: &str) -> VectorStoreDocument {
+        let mut document = self.segments.search_by_id(&segment_id(id, 0));
+        if document.vector.is_none() && document.text.is_none() {
+            return self.segments.search_by_id(id);
+        }
+        document.id = id.to_string();
+        document.attributes.remove(PARENT_ID_ATTRIBUTE);
+        document
+    }
+
+    /// Delete segment documents by id.
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String> {
+        self.segments.delete_documents(ids)
+    }
+
+    /// The content hashes of the segment documents.
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        self.segments.content_hashes()
+    }
+
+    /// Read the segments from pipeline storage.
+    fn load<'a>(&'a mut self, storage: &'a dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        self.segments.load(storage)
+    }
+
+    /// Write the segments to pipeline storage.
+    fn save<'a>(&'a self, storage: &'a mut dyn CollectionStorage) -> LocalBoxFuture<'a, ()> {
+        self.segments.save(storage)
+    }
+}
+
+/// Translate a filter on text ids into a filter on the `parent_id` attribute of their segments.
+fn to_segment_filter(filter: &VectorStoreFilter) -> VectorStoreFilter {
+    match filter {
+        VectorStoreFilter::Ids(ids) => VectorStoreFilter::In(
+            PARENT_ID_ATTRIBUTE.to_string(),
+            ids.iter().map(|id| Value::from(id.as_str())).collect(),
+        ),
+        VectorStoreFilter::And(filters) => VectorStoreFilter::And(filters.iter().map(to_segment_filter).collect()),
+        VectorStoreFilter::Or(filters) => VectorStoreFilter::Or(filters.iter().map(to_segment_filter).collect()),
+        condition => condition.clone(),
+    }
+}
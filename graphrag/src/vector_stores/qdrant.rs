@@ -0,0 +1,708 @@
+//! The Qdrant vector storage implementation package.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use log::warn;
+use qdrant_client::qdrant::quantization_config::Quantization;
+use qdrant_client::qdrant::vector_output::Vector;
+use qdrant_client::qdrant::{
+    CompressionRatio,
+    Condition,
+    CreateCollectionBuilder,
+    CreateFieldIndexCollectionBuilder,
+    DatetimeRange,
+    DeletePointsBuilder,
+    Distance,
+    FieldType,
+    Filter,
+    GetPointsBuilder,
+    PointId,
+    PointStruct,
+    PointsIdsList,
+    ProductQuantizationBuilder,
+    QuantizationSearchParamsBuilder,
+    QueryPointsBuilder,
+    Range,
+    ScalarQuantizationBuilder,
+    ScrollPointsBuilder,
+    SearchParamsBuilder,
+    UpsertPointsBuilder,
+    VectorParamsBuilder,
+    VectorsOutput,
+};
+use qdrant_client::{Payload, Qdrant, QdrantError};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::config::enums::{VectorDistanceType, VectorQuantizationType};
+use crate::data_model::types::TextEmbedder;
+use crate::vector_stores::base::{
+    BaseVectorStore,
+    ClientRuntime,
+    DEFAULT_VECTOR_SIZE,
+    VectorStoreDocument,
+    VectorStoreSearchResult,
+};
+use crate::vector_stores::filtering::VectorStoreFilter;
+
+/// The number of points sent in every upsert request.
+const UPSERT_BATCH_SIZE: usize = 256;
+
+/// The payload field holding the original id of a document.
+const ID_FIELD: &str = "id";
+
+/**
+Qdrant vector storage implementation.
+
+Qdrant point ids must be integers or UUIDs, so every document is stored under the UUIDv5 of its id
+and keeps the original id in its payload, where it is indexed for filtering.
+*/
+pub struct QdrantVectorStore {
+    /// The name of the collection.
+    pub collection_name: String,
+    url: String,
+    api_key: Option<String>,
+    distance: VectorDistanceType,
+    quantization: VectorQuantizationType,
+    pq_subvector_size: usize,
+    rerank_factor: usize,
+    client: Option<Qdrant>,
+    runtime: Option<ClientRuntime>,
+    query_filter: Option<VectorStoreFilter>,
+}
+
+impl QdrantVectorStore {
+    /**
+    Create a store for a collection, connected with `connect`.
+
+    Args:
+        - collection_name: The name of the collection.
+        - url: The URL of the Qdrant server, using its gRPC port.
+        - api_key: The API key of the server, if it requires one.
+        - distance: The distance used to compare vectors.
+        - quantization: The quantization of the vectors searched on the server.
+        - pq_subvector_size: The number of dimensions encoded by every byte when quantization == pq.
+        - rerank_factor: How many quantized candidates are rescored against the full vectors for every result.
+    */
+    pub fn new(
+        collection_name: &str,
+        url: &str,
+        api_key: Option<String>,
+        distance: VectorDistanceType,
+        quantization: VectorQuantizationType,
+        pq_subvector_size: usize,
+        rerank_factor: usize,
+    ) -> Self {
+        QdrantVectorStore {
+            collection_name: collection_name.to_string(),
+            url: url.to_string(),
+            api_key,
+            distance,
+            quantization,
+            pq_subvector_size,
+            rerank_factor,
+            client: None,
+            runtime: None,
+            query_filter: None,
+        }
+    }
+
+    /// Run a client request, failing if the store is not connected or the server returns an error.
+    fn request<'a, T: Send, F: Future<Output = Result<T, QdrantError>> + Send>(
+        &'a self,
+        action: &str,
+        request: impl FnOnce(&'a Qdrant) -> F,
+    ) -> Result<T, String> {
+        let (Some(client), Some(runtime)) = (&self.client, &self.runtime) else {
+            return Err(format!("Could not {action} in Qdrant collection {}: not connected", self.collection_name));
+        };
+        runtime
+            .block_on(request(client))
+            .map_err(|e| format!("Could not {action} in Qdrant collection {}: {e}", self.collection_name))
+    }
+
+    /// Whether the collection exists.
+    fn collection_exists(&self) -> Result<bool, String> {
+        self.request("check the collection", |client| client.collection_exists(&self.collection_name))
+    }
+
+    /// Create the collection, replacing it if `overwrite` is set, and index the original ids.
+    fn create_collection(&self, vector_size: usize, overwrite: bool) -> Result<(), String> {
+        let exists = self.collection_exists()?;
+        if exists && !overwrite {
+            return Ok(());
+        }
+        if exists {
+            self.request("delete the collection", |client| client.delete_collection(&self.collection_name))?;
+        }
+        let mut collection = CreateCollectionBuilder::new(&self.collection_name)
+            .vectors_config(VectorParamsBuilder::new(vector_size as u64, qdrant_distance(self.distance)));
+        if let Some(quantization) = qdrant_quantization(self.quantization, self.pq_subvector_size) {
+            collection = collection.quantization_config(quantization);
+        }
+        self.request("create the collection", |client| client.create_collection(collection))?;
+        self.request("index the ids", |client| {
+            client.create_field_index(
+                CreateFieldIndexCollectionBuilder::new(&self.collection_name, ID_FIELD, FieldType::Keyword).wait(true),
+            )
+        })?;
+        Ok(())
+    }
+}
+
+impl BaseVectorStore for QdrantVectorStore {
+    /// Connect to the vector storage.
+    fn connect(&mut self) -> Result<(), String> {
+        let runtime = ClientRuntime::new()?;
+        // the gRPC channel spawns its connection tasks on the runtime it is built in
+        let client = runtime
+            .block_on(async {
+                Qdrant::from_url(&self.url)
+                    .api_key(self.api_key.clone())
+                    .skip_compatibility_check()
+                    .build()
+            })
+            .map_err(|e| format!("Could not connect to Qdrant at {}: {e}", self.url))?;
+        self.client = Some(client);
+        self.runtime = Some(runtime);
+        Ok(())
+    }
+
+    /// Load documents into vector storage, upserting them in batches.
+    fn load_documents(&mut self, documents: Vec<VectorStoreDocument>, overwrite: bool) -> Result<(), String> {
+        let vector_size = documents
+            .iter()
+            .find_map(|document| document.vector.as_ref().map(Vec::len))
+            .unwrap_or(DEFAULT_VECTOR_SIZE);
+        let points: Vec<PointStruct> = documents
+            .into_iter()
+            .filter_map(|mut document| {
+                let vector = document.vector.take()?;
+                Some(PointStruct::new(point_id(&document.id), vector, to_payload(document)))
+            })
+            .collect();
+        // the collection is created even when empty, so searching it finds nothing rather than failing
+        self.create_collection(vector_size, overwrite)?;
+
+        for batch in points.chunks(UPSERT_BATCH_SIZE) {
+            self.request("upsert the documents", |client| {
+                client.upsert_points(UpsertPointsBuilder::new(&self.collection_name, batch.to_vec()).wait(true))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Build a query filter to filter documents by id.
+    fn filter_by_id(&mut self, include_ids: Vec<String>) -> Option<VectorStoreFilter> {
+        self.query_filter = if include_ids.is_empty() {
+            None
+        } else {
+            Some(VectorStoreFilter::Ids(include_ids.into_iter().collect()))
+        };
+        self.query_filter.clone()
+    }
+
+    /// Perform a vector-based similarity search, filtering on the server.
+    fn similarity_search_by_vector(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let mut query = QueryPointsBuilder::new(&self.collection_name)
+            .query(query_embedding)
+            .limit(k as u64)
+            .with_payload(true)
+            .with_vectors(true);
+        if let Some(filter) = VectorStoreFilter::and(self.query_filter.clone(), filter.cloned()) {
+            match to_qdrant_filter(&filter) {
+                Ok(filter) => query = query.filter(filter),
+                Err(e) => {
+                    warn!("Could not search Qdrant collection {}: {e}", self.collection_name);
+                    return Vec::new();
+                }
+            }
+        }
+        if self.quantization != VectorQuantizationType::None {
+            query = query.params(
+                SearchParamsBuilder::default().quantization(
+                    QuantizationSearchParamsBuilder::default()
+                        .rescore(self.rerank_factor > 0)
+                        .oversampling(self.rerank_factor.max(1) as f64),
+                ),
+            );
+        }
+
+        match self.request("search", |client| client.query(query)) {
+            Ok(response) => response
+                .result
+                .into_iter()
+                .map(|point| VectorStoreSearchResult {
+                    document: to_document(point.payload, point.vectors),
+                    score: score(self.distance, point.score as f64),
+                })
+                .collect(),
+            Err(e) => {
+                warn!("{e}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Perform a similarity search using a given input text.
+    fn similarity_search_by_text(
+        &self,
+        text: &str,
+        text_embedder: TextEmbedder,
+        k: usize,
+        filter: Option<&VectorStoreFilter>,
+    ) -> Vec<VectorStoreSearchResult> {
+        let query_embedding = text_embedder(text);
+        if query_embedding.is_empty() {
+            return Vec::new();
+        }
+        self.similarity_search_by_vector(query_embedding, k, filter)
+    }
+
+    /// Search for a document by id.
+    fn search_by_id(&self, id: &str) -> VectorStoreDocument {
+        let documents = self.get_by_ids(&[id.to_string()]).unwrap_or_else(|e| {
+            warn!("{e}");
+            Vec::new()
+        });
+        documents
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| VectorStoreDocument {
+                id: id.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Get the stored documents with the given ids in one request.
+    fn get_by_ids(&self, ids: &[String]) -> Result<Vec<VectorStoreDocument>, String> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let point_ids: Vec<PointId> = ids.iter().map(|id| point_id(id)).collect();
+        let response = self.request("get the documents", |client| {
+            client.get_points(
+                GetPointsBuilder::new(&self.collection_name, point_ids)
+                    .with_payload(true)
+                    .with_vectors(true),
+            )
+        })?;
+        Ok(response
+            .result
+            .into_iter()
+            .map(|point| to_document(point.payload, point.vectors))
+            .collect())
+    }
+
+    /// Delete documents by id.
+    fn delete_documents(&mut self, ids: Vec<String>) -> Result<(), String> {
+        if ids.is_empty() || !self.collection_exists()? {
+            return Ok(());
+        }
+        let points = PointsIdsList {
+            ids: ids.iter().map(|id| point_id(id)).collect(),
+        };
+        self.request("delete the documents", |client| {
+            client.delete_points(DeletePointsBuilder::new(&self.collection_name).points(points).wait(true))
+        })?;
+        Ok(())
+    }
+
+    /// The content hash of every stored document that has one, keyed by id.
+    fn content_hashes(&self) -> Result<HashMap<String, String>, String> {
+        let mut hashes = HashMap::new();
+        if !self.collection_exists()? {
+            return Ok(hashes);
+        }
+        let mut offset: Option<PointId> = None;
+        loop {
+            let mut scroll = ScrollPointsBuilder::new(&self.collection_name)
+                .limit(UPSERT_BATCH_SIZE as u32)
+                .with_payload(true)
+                .with_vectors(false);
+            if let Some(offset) = offset.take() {
+                scroll = scroll.offset(offset);
+            }
+            let response = self.request("list the content hashes", |client| client.scroll(scroll))?;
+            for point in response.result {
+                let document = to_document(point.payload, None);
+                if let Some(content_hash) = document.content_hash {
+                    hashes.insert(document.id, content_hash);
+                }
+            }
+            match response.next_page_offset {
+                Some(next) => offset = Some(next),
+                None => break,
+            }
+        }
+        Ok(hashes)
+    }
+}
+
+/// The Qdrant point id of a document, the UUIDv5 of its id.
+pub fn point_id(id: &str) -> PointId {
+    PointId::from(Uuid::new_v5(&Uuid::NAMESPACE_OID, id.as_bytes()).to_string())
+}
+
+/// The payload of a document, keeping its original id.
+fn to_payload(document: VectorStoreDocument) -> Payload {
+    let mut payload = Map::new();
+    payload.insert(ID_FIELD.into(), Value::String(document.id));
+    payload.insert("text".into(), document.text.map_or(Value::Null, Value::String));
+    payload.insert("attributes".into(), Value::Object(document.attributes.into_iter().collect()));
+    payload.insert("content_hash".into(), document.content_hash.map_or(Value::Null, Value::String));
+    Payload::from(payload)
+}
+
+/// Build a document from the payload and vector of a Qdrant point.
+fn to_document(
+    payload: HashMap<String, qdrant_client::qdrant::Value>,
+    vectors: Option<VectorsOutput>,
+) -> VectorStoreDocument {
+    let mut payload: Map<String, Value> = payload
+        .into_iter()
+        .map(|(key, value)| (key, Value::from(value)))
+        .collect();
+    let mut string = |key: &str| match payload.remove(key) {
+        Some(Value::String(value)) => Some(value),
+        _ => None,
+    };
+    let id = string(ID_FIELD).unwrap_or_default();
+    let text = string("text");
+    let content_hash = string("content_hash");
+    let attributes = match payload.remove("attributes") {
+        Some(Value::Object(attributes)) => attributes.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    let vector = vectors.and_then(|vectors| match vectors.get_vector()? {
+        Vector::Dense(vector) => Some(vector.data),
+        _ => None,
+    });
+    VectorStoreDocument {
+        id,
+        text,
+        vector,
+        attributes,
+        content_hash,
+    }
+}
+
+/// The Qdrant distance of a distance type.
+fn qdrant_distance(distance: VectorDistanceType) -> Distance {
+    match distance {
+        VectorDistanceType::Cosine => Distance::Cosine,
+        VectorDistanceType::Dot => Distance::Dot,
+        VectorDistanceType::L2 => Distance::Euclid,
+    }
+}
+
+/**
+The Qdrant quantization of a collection, if any.
+
+Qdrant compresses product quantization codes by a ratio of the vector size rather than a number of
+dimensions per byte, so the ratio closest to `subvector_size` float32 dimensions per byte is used.
+*/
+pub fn qdrant_quantization(quantization: VectorQuantizationType, subvector_size: usize) -> Option<Quantization> {
+    match quantization {
+        VectorQuantizationType::None => None,
+        VectorQuantizationType::Int8 => Some(ScalarQuantizationBuilder::default().always_ram(true).build().into()),
+        VectorQuantizationType::Product => {
+            let ratio = [
+                (4, CompressionRatio::X4),
+                (8, CompressionRatio::X8),
+                (16, CompressionRatio::X16),
+                (32, CompressionRatio::X32),
+                (64, CompressionRatio::X64),
+            ]
+            .into_iter()
+            .min_by_key(|(ratio, _): &(usize, CompressionRatio)| ratio.abs_diff(subvector_size * 4))
+            .map_or(CompressionRatio::X16, |(_, ratio)| ratio);
+            Some(ProductQuantizationBuilder::new(ratio.into()).always_ram(true).build().into())
+        }
+    }
+}
+
+/// The similarity score of a Qdrant score, which is a distance for `Euclid`.
+fn score(distance: VectorDistanceType, score: f64) -> f64 {
+    match distance {
+        VectorDistanceType::L2 => 1.0 / (1.0 + score),
+        _ => score,
+    }
+}
+
+/**
+Translate a filter into a Qdrant filter.
+
+Attributes live under `attributes` in the payload. Ranges over strings use `datetime_range`, so
+ISO dates compare as dates, and like in memory a missing or null attribute is only within ranges
+that include missing attributes.
+*/
+pub fn to_qdrant_filter(filter: &VectorStoreFilter) -> Result<Filter, String> {
+    match filter {
+        VectorStoreFilter::And(filters) => Ok(Filter::must(conditions(filters)?)),
+        VectorStoreFilter::Or(filters) if filters.is_empty() => Ok(Filter::must([Condition::has_id(Vec::<PointId>::new())])),
+        VectorStoreFilter::Or(filters) => Ok(Filter::should(conditions(filters)?)),
+        condition => Ok(Filter::must([to_qdrant_condition(condition)?])),
+    }
+}
+
+/// Translate several filters into Qdrant conditions.
+fn conditions(filters: &[VectorStoreFilter]) -> Result<Vec<Condition>, String> {
+    filters.iter().map(to_qdrant_condition).collect()
+}
+
+/// Translate a single filter condition into a Qdrant condition.
+fn to_qdrant_condition(filter: &VectorStoreFilter) -> Result<Condition, String> {
+    let key = |field: &str| format!("attributes.{field}");
+    match filter {
+        VectorStoreFilter::Ids(ids) => {
+            let mut ids: Vec<String> = ids.iter().cloned().collect();
+            ids.sort();
+            Ok(Condition::matches(ID_FIELD, ids))
+        }
+        VectorStoreFilter::Eq(field, value) => match_values(&key(field), std::slice::from_ref(value)),
+        VectorStoreFilter::In(field, values) => match_values(&key(field), values),
+        VectorStoreFilter::Range {
+            field,
+            min,
+            max,
+            include_missing,
+        } => {
+            let range = if min.iter().chain(max.iter()).any(Value::is_string) {
+                let timestamp = |value: &Option<Value>| {
+                    value
+                        .as_ref()
+                        .map(|value| {
+                            value
+                                .as_str()
+                                .and_then(|value| value.parse().ok())
+                                .ok_or_else(|| format!("Range bound {value} of {field:?} is not an ISO date"))
+                        })
+                        .transpose()
+                };
+                Condition::datetime_range(
+                    key(field),
+                    DatetimeRange {
+                        gte: timestamp(min)?,
+                        lte: timestamp(max)?,
+                        ..Default::default()
+                    },
+                )
+            } else {
+                let number = |value: &Option<Value>| {
+                    value
+                        .as_ref()
+                        .map(|value| value.as_f64().ok_or_else(|| format!("Range bound {value} of {field:?} is not a number")))
+                        .transpose()
+                };
+                Condition::range(
+                    key(field),
+                    Range {
+                        gte: number(min)?,
+                        lte: number(max)?,
+                        ..Default::default()
+                    },
+                )
+            };
+            if *include_missing {
+                Ok(Filter::should([range, Condition::is_empty(key(field))]).into())
+            } else {
+                Ok(range)
+            }
+        }
+        nested => Ok(to_qdrant_filter(nested)?.into()),
+    }
+}
+
+/**
+Match an attribute against any of several values.
+
+Qdrant matches keywords, integers and booleans exactly; other numbers are matched with a closed range.
+*/
+fn match_values(key: &str, values: &[Value]) -> Result<Condition, String> {
+    let conditions: Vec<Condition> = values
+        .iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(Condition::matches(key, value.clone())),
+            Value::Bool(value) => Ok(Condition::matches(key, *value)),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => Ok(Condition::matches(key, integer)),
+                None => Ok(Condition::range(
+                    key,
+                    Range {
+                        gte: number.as_f64(),
+                        lte: number.as_f64(),
+                        ..Default::default()
+                    },
+                )),
+            },
+            other => Err(format!("Cannot match {key:?} against {other}")),
+        })
+        .collect::<Result<_, _>>()?;
+    match <[Condition; 1]>::try_from(conditions) {
+        Ok([condition]) => Ok(condition),
+        Err(conditions) => Ok(Filter::should(conditions).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qdrant_client::qdrant::point_id::PointIdOptions;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn point_ids_are_stable_uuids() {
+        let id = point_id("entity-1");
+        assert_eq!(id, point_id("entity-1"));
+        assert_ne!(id, point_id("entity-2"));
+        match id.point_id_options {
+            Some(PointIdOptions::Uuid(uuid)) => assert_eq!(Uuid::parse_str(&uuid).unwrap().get_version_num(), 5),
+            other => panic!("expected a UUID point id, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn documents_round_trip_through_the_payload() {
+        let document = VectorStoreDocument {
+            id: "entity-1".into(),
+            text: Some("text".into()),
+            vector: None,
+            attributes: HashMap::from([("title".into(), json!("A"))]),
+            content_hash: Some("hash".into()),
+        };
+        let payload: HashMap<String, qdrant_client::qdrant::Value> = to_payload(document.clone()).into();
+        let stored = to_document(payload, None);
+        assert_eq!(stored.id, document.id);
+        assert_eq!(stored.text, document.text);
+        assert_eq!(stored.attributes, document.attributes);
+        assert_eq!(stored.content_hash, document.content_hash);
+    }
+
+    #[test]
+    fn ranges_keep_missing_attributes_when_asked() {
+        for include_missing in [true, false] {
+            let filter = VectorStoreFilter::Range {
+                field: "valid_to".into(),
+                min: Some(json!("2024-01-01")),
+                max: None,
+                include_missing,
+            };
+            let Filter { must, .. } = to_qdrant_filter(&filter).unwrap();
+            let debug = format!("{must:?}");
+            assert!(debug.contains("DatetimeRange"), "{debug}");
+            assert_eq!(debug.contains("IsEmpty"), include_missing, "{debug}");
+        }
+    }
+
+    #[test]
+    fn ranges_reject_bounds_that_are_not_dates() {
+        let filter = VectorStoreFilter::Range {
+            field: "valid_to".into(),
+            min: Some(json!("yesterday")),
+            max: None,
+            include_missing: false,
+        };
+        assert!(to_qdrant_filter(&filter).is_err());
+    }
+
+    #[test]
+    fn product_quantization_uses_the_closest_ratio() {
+        let Some(Quantization::Product(product)) = qdrant_quantization(VectorQuantizationType::Product, 8) else {
+            panic!("expected product quantization");
+        };
+        assert_eq!(product.compression, CompressionRatio::X32 as i32);
+        assert!(qdrant_quantization(VectorQuantizationType::None, 8).is_none());
+    }
+
+    #[test]
+    fn requests_fail_until_connected() {
+        let mut store = QdrantVectorStore::new(
+            "test",
+            "http://localhost:6334",
+            None,
+            VectorDistanceType::Cosine,
+            VectorQuantizationType::None,
+            8,
+            0,
+        );
+        assert!(store.content_hashes().unwrap_err().contains("not connected"));
+        assert!(store.load_documents(Vec::new(), true).is_err());
+        assert!(store.similarity_search_by_vector(vec![1.0], 1, None).is_empty());
+    }
+
+    /// A store connected to the server at `GRAPHRAG_QDRANT_URL`, with a collection of its own.
+    fn connected_store(name: &str, quantization: VectorQuantizationType) -> QdrantVectorStore {
+        let url = std::env::var("GRAPHRAG_QDRANT_URL").unwrap_or_else(|_| "http://localhost:6334".into());
+        let mut store = QdrantVectorStore::new(
+            &format!("graphrag_test_{name}_{}", std::process::id()),
+            &url,
+            None,
+            VectorDistanceType::Cosine,
+            quantization,
+            8,
+            2,
+        );
+        store.connect().unwrap();
+        store
+    }
+
+    fn document(id: &str, vector: Vec<f32>, kind: &str) -> VectorStoreDocument {
+        VectorStoreDocument {
+            id: id.to_string(),
+            text: Some(format!("text of {id}")),
+            vector: Some(vector),
+            attributes: HashMap::from([("type".to_string(), json!(kind))]),
+            content_hash: Some(format!("hash-{id}")),
+        }
+    }
+
+    fn delete_collection(store: &QdrantVectorStore) {
+        store
+            .request("delete the collection", |client| client.delete_collection(&store.collection_name))
+            .unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a Qdrant server, at GRAPHRAG_QDRANT_URL"]
+    fn documents_are_stored_searched_and_deleted_on_the_server() {
+        for quantization in [VectorQuantizationType::None, VectorQuantizationType::Int8] {
+            let mut store = connected_store("round_trip", quantization);
+            store
+                .load_documents(
+                    vec![
+                        document("a", vec![1.0, 0.0], "PERSON"),
+                        document("b", vec![0.0, 1.0], "GEO"),
+                        document("c", vec![0.9, 0.1], "PERSON"),
+                    ],
+                    true,
+                )
+                .unwrap();
+            assert_eq!(store.content_hashes().unwrap().len(), 3);
+
+            let results = store.similarity_search_by_vector(vec![1.0, 0.0], 2, None);
+            let ids: Vec<&str> = results.iter().map(|result| result.document.id.as_str()).collect();
+            assert_eq!(ids, ["a", "c"]);
+            assert_eq!(results[0].document.attributes["type"], json!("PERSON"));
+
+            let filter = VectorStoreFilter::Eq("type".into(), json!("GEO"));
+            let results = store.similarity_search_by_vector(vec![1.0, 0.0], 2, Some(&filter));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].document.id, "b");
+
+            store.delete_documents(vec!["a".into()]).unwrap();
+            let hashes = store.content_hashes().unwrap();
+            assert_eq!(hashes.len(), 2);
+            assert!(!hashes.contains_key("a"));
+            assert_eq!(store.get_by_ids(&["b".into(), "a".into()]).unwrap().len(), 1);
+            delete_collection(&store);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Compact byte encodings of embedding vectors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::enums::VectorQuantizationType;
+use crate::index::utils::weighted_graph::SplitMix64;
+
+/// The number of centroids learned for every group of dimensions, so a code fits a byte.
+const CENTROIDS: usize = 256;
+
+/// The number of k-means iterations used to learn the centroids.
+const KMEANS_ITERATIONS: usize = 10;
+
+/// The number of training vectors sampled for every centroid.
+const SAMPLES_PER_CENTROID: usize = 16;
+
+/**
+A quantizer encoding vectors as bytes.
+
+Distances are asymmetric: queries keep their full vectors and are compared with the reconstruction
+of every code through a `QueryTable`, built once per query.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Quantizer {
+    /// One byte per dimension, `value = offset + scale * code`.
+    Int8 {
+        offsets: Vec<f32>,
+        scales: Vec<f32>,
+    },
+    /// One byte per group of `subvector_size` dimensions, the last group holding the remainder.
+    Product {
+        subvector_size: usize,
+        dimensions: usize,
+        /// The centroids of every group, concatenated.
+        centroids: Vec<Vec<f32>>,
+    },
+}
+
+/// The dot products between a query and the reconstruction of every code byte.
+pub enum QueryTable {
+    Int8 {
+        offset: f64,
+        weights: Vec<f64>,
+    },
+    Product {
+        dots: Vec<Vec<f64>>,
+    },
+}
+
+impl Quantizer {
+    /**
+    Learn a quantizer from the vectors to encode, if the quantization type has one and there are vectors.
+
+    Args:
+        - kind: The type of quantization.
+        - vectors: The vectors to learn from, all with the same number of dimensions.
+        - subvector_size: The number of dimensions encoded by every byte of product quantization codes.
+
+    Fails if the vectors have no dimensions or differ in their number of dimensions.
+    */
+    pub fn fit(
+        kind: VectorQuantizationType,
+        vectors: &[&[f32]],
+        subvector_size: usize,
+    ) -> Result<Option<Quantizer>, String> {
+        let Some(dimensions) = vectors.first().map(|vector| vector.len()) else {
+            return Ok(None);
+        };
+        if dimensions == 0 {
+            return Err("Cannot quantize vectors without dimensions".to_string());
+        }
+        if let Some(vector) = vectors.iter().find(|vector| vector.len() != dimensions) {
+            return Err(format!(
+                "Cannot quantize vectors of different dimensions, {} and {dimensions}",
+                vector.len()
+            ));
+        }
+        Ok(match kind {
+            VectorQuantizationType::None => None,
+            VectorQuantizationType::Int8 => {
+                let mut minimums = vec![f32::INFINITY; dimensions];
+                let mut maximums = vec![f32::NEG_INFINITY; dimensions];
+                for vector in vectors {
+                    for (dimension, value) in vector.iter().enumerate() {
+                        minimums[dimension] = minimums[dimension].min(*value);
+                        maximums[dimension] = maximums[dimension].max(*value);
+                    }
+                }
+                let scales = minimums
+                    .iter()
+                    .zip(&maximums)
+                    .map(|(minimum, maximum)| (maximum - minimum) / (CENTROIDS - 1) as f32)
+                    .collect();
+                Some(Quantizer::Int8 {
+                    offsets: minimums,
+                    scales,
+                })
+            }
+            VectorQuantizationType::Product => {
+                let subvector_size = subvector_size.clamp(1, dimensions);
+                let mut sample: Vec<&[f32]> = vectors.to_vec();
+                SplitMix64::new(dimensions as u64).shuffle(&mut sample);
+                sample.truncate(CENTROIDS * SAMPLES_PER_CENTROID);
+
+                let centroids = (0..dimensions.div_ceil(subvector_size))
+                    .map(|group| {
+                        let range = group * subvector_size..((group + 1) * subvector_size).min(dimensions);
+                        let subvectors: Vec<&[f32]> = sample.iter().map(|vector| &vector[range.clone()]).collect();
+                        kmeans(&subvectors, range.len())
+                    })
+                    .collect();
+                Some(Quantizer::Product {
+                    subvector_size,
+                    dimensions,
+                    centroids,
+                })
+            }
+        })
+    }
+
+    /// The type of the quantizer.
+    pub fn kind(&self) -> VectorQuantizationType {
+        match self {
+            Quantizer::Int8 { .. } => VectorQuantizationType::Int8,
+            Quantizer::Product { .. } => VectorQuantizationType::Product,
+        }
+    }
+
+    /// The number of bytes of every code.
+    pub fn code_size(&self) -> usize {
+        match self {
+            Quantizer::Int8 { offsets, .. } => offsets.len(),
+            Quantizer::Product { centroids, .. } => centroids.len(),
+        }
+    }
+
+    /// Encode a vector.
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            Quantizer::Int8 { offsets, scales } => vector
+                .iter()
+                .zip(offsets.iter().zip(scales))
+                .map(|(value, (offset, scale))| {
+                    if *scale > 0.0 {
+                        ((value - offset) / scale).round().clamp(0.0, (CENTROIDS - 1) as f32) as u8
+                    } else {
+                        0
+                    }
+                })
+                .collect(),
+            Quantizer::Product {
+                subvector_size,
+                centroids,
+                ..
+            } => vector
+                .chunks(*subvector_size)
+                .zip(centroids)
+                .map(|(subvector, centroids)| nearest(subvector, centroids) as u8)
+                .collect(),
+        }
+    }
+
+    /// Reconstruct the vector of a code.
+    pub fn decode(&self, code: &[u8]) -> Vec<f32> {
+        match self {
+            Quantizer::Int8 { offsets, scales } => code
+                .iter()
+                .zip(offsets.iter().zip(scales))
+                .map(|(code, (offset, scale))| offset + scale * f32::from(*code))
+                .collect(),
+            Quantizer::Product {
+                subvector_size,
+                dimensions,
+                centroids,
+            } => {
+                let mut vector = Vec::with_capacity(*dimensions);
+                for (group, code) in code.iter().enumerate() {
+                    let size = (*dimensions - group * subvector_size).min(*subvector_size);
+                    let start = usize::from(*code) * size;
+                    vector.extend_from_slice(&centroids[group][start..start + size]);
+                }
+                vector
+            }
+        }
+    }
+
+    /// Build the table comparing a query with codes.
+    pub fn table(&self, query: &[f32]) -> QueryTable {
+        match self {
+            Quantizer::Int8 { offsets, scales } => QueryTable::Int8 {
+                offset: query.iter().zip(offsets).map(|(q, offset)| f64::from(*q) * f64::from(*offset)).sum(),
+                weights: query.iter().zip(scales).map(|(q, scale)| f64::from(*q) * f64::from(*scale)).collect(),
+            },
+            Quantizer::Product {
+                subvector_size,
+                centroids,
+                ..
+            } => QueryTable::Product {
+                dots: query
+                    .chunks(*subvector_size)
+                    .zip(centroids)
+                    .map(|(subquery, centroids)| {
+                        centroids
+                            .chunks(subquery.len())
+                            .map(|centroid| dot(subquery, centroid))
+                            .collect()
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl QueryTable {
+    /// The dot product between the query and the reconstruction of a code.
+    pub fn dot(&self, code: &[u8]) -> f64 {
+        match self {
+            QueryTable::Int8 { offset, weights } => {
+                offset + code.iter().zip(weights).map(|(code, weight)| f64::from(*code) * weight).sum::<f64>()
+            }
+            QueryTable::Product { dots } => code
+                .iter()
+                .zip(dots)
+                .map(|(code, dots)| dots[usize::from(*code)])
+                .sum(),
+        }
+    }
+}
+
+/// The dot product of two vectors, accumulated in double precision.
+pub fn dot(a: &[f32], b: &[f32]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| f64::from(*a) * f64::from(*b)).sum()
+}
+
+/// The squared euclidean distance between two vectors.
+fn squared_distance(a: &[f32], b: &[f32]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let difference = f64::from(*a) - f64::from(*b);
+            difference * difference
+        })
+        .sum()
+}
+
+/// The position of the centroid closest to a vector, among concatenated centroids.
+fn nearest(vector: &[f32], centroids: &[f32]) -> usize {
+    centroids
+        .chunks(vector.len())
+        .map(|centroid| squared_distance(vector, centroid))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(position, _)| position)
+}
+
+/// Learn up to `CENTROIDS` centroids of the vectors with k-means, returned concatenated.
+fn kmeans(vectors: &[&[f32]], size: usize) -> Vec<f32> {
+    let count = vectors.len().min(CENTROIDS);
+    // the vectors are already shuffled, so the first ones are a random initialization
+    let mut centroids: Vec<f32> = vectors[..count].iter().flat_map(|vector| vector.iter().copied()).collect();
+    let mut assignments = vec![usize::MAX; vectors.len()];
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (vector, assignment) in vectors.iter().zip(assignments.iter_mut()) {
+            let centroid = nearest(vector, &centroids);
+            changed |= centroid != *assignment;
+            *assignment = centroid;
+        }
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![0.0f64; count * size];
+        let mut members = vec![0usize; count];
+        for (vector, assignment) in vectors.iter().zip(&assignments) {
+            members[*assignment] += 1;
+            for (sum, value) in sums[assignment * size..(assignment + 1) * size].iter_mut().zip(vector.iter()) {
+                *sum += f64::from(*value);
+            }
+        }
+        // empty clusters keep their previous centroid
+        for (centroid, members) in members.iter().enumerate().filter(|(_, members)| **members > 0) {
+            for dimension in centroid * size..(centroid + 1) * size {
+                centroids[dimension] = (sums[dimension] / *members as f64) as f32;
+            }
+        }
+    }
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo random vectors with components in [-1, 1).
+    fn random_vectors(count: usize, dimensions: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = SplitMix64::new(seed);
+        (0..count)
+            .map(|_| {
+                (0..dimensions)
+                    .map(|_| (rng.next_u64() >> 40) as f32 / (1u64 << 23) as f32 - 1.0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn fit(kind: VectorQuantizationType, vectors: &[Vec<f32>], subvector_size: usize) -> Quantizer {
+        let slices: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        Quantizer::fit(kind, &slices, subvector_size).unwrap().unwrap()
+    }
+
+    #[test]
+    fn int8_error_is_at_most_half_a_step() {
+        let vectors = random_vectors(500, 24, 1);
+        let quantizer = fit(VectorQuantizationType::Int8, &vectors, 1);
+        let Quantizer::Int8 { scales, .. } = &quantizer else {
+            panic!("expected an int8 quantizer");
+        };
+        assert_eq!(quantizer.code_size(), 24);
+        for vector in &vectors {
+            let code = quantizer.encode(vector);
+            assert_eq!(code.len(), 24);
+            for ((value, decoded), scale) in vector.iter().zip(quantizer.decode(&code)).zip(scales) {
+                assert!((value - decoded).abs() <= scale / 2.0 + 1e-6, "{value} decoded as {decoded}");
+            }
+        }
+    }
+
+    #[test]
+    fn int8_clamps_values_outside_the_learned_range() {
+        let quantizer = fit(VectorQuantizationType::Int8, &[vec![0.0, -1.0], vec![1.0, 1.0]], 1);
+        assert_eq!(quantizer.encode(&[2.0, -3.0]), vec![255, 0]);
+        assert_eq!(quantizer.decode(&[255, 0]), vec![1.0, -1.0]);
+    }
+
+    #[test]
+    fn product_reconstructs_training_vectors_with_fewer_than_centroids() {
+        // every training vector is a centroid of its own, the last group holding the remaining 2 dimensions
+        let vectors = random_vectors(200, 10, 2);
+        let quantizer = fit(VectorQuantizationType::Product, &vectors, 4);
+        assert_eq!(quantizer.code_size(), 3);
+        for vector in &vectors {
+            let decoded = quantizer.decode(&quantizer.encode(vector));
+            assert_eq!(decoded.len(), 10);
+            assert!(squared_distance(vector, &decoded) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn product_error_is_bounded_by_the_nearest_centroid() {
+        let vectors = random_vectors(2000, 16, 3);
+        let quantizer = fit(VectorQuantizationType::Product, &vectors, 4);
+        let Quantizer::Product { centroids, .. } = &quantizer else {
+            panic!("expected a product quantizer");
+        };
+        let mean: Vec<f32> = (0..16)
+            .map(|dimension| vectors.iter().map(|vector| vector[dimension]).sum::<f32>() / vectors.len() as f32)
+            .collect();
+
+        let mut error = 0.0;
+        let mut variance = 0.0;
+        for vector in random_vectors(200, 16, 4) {
+            let code = quantizer.encode(&vector);
+            let decoded = quantizer.decode(&code);
+            // every group is encoded as its closest centroid
+            for ((subvector, subdecoded), group_centroids) in vector.chunks(4).zip(decoded.chunks(4)).zip(centroids) {
+                let group_error = squared_distance(subvector, subdecoded);
+                assert!(group_centroids.chunks(4).all(|centroid| group_error <= squared_distance(subvector, centroid)));
+            }
+            error += squared_distance(&vector, &decoded);
+            variance += squared_distance(&vector, &mean);
+        }
+        // 256 centroids per group leave a fraction of the error of encoding everything as the mean
+        assert!(error < variance * 0.25, "error {error}, variance {variance}");
+    }
+
+    #[test]
+    fn query_tables_match_the_decoded_vectors() {
+        let vectors = random_vectors(300, 12, 5);
+        let query = &random_vectors(1, 12, 6)[0];
+        for (kind, subvector_size) in [(VectorQuantizationType::Int8, 1), (VectorQuantizationType::Product, 5)] {
+            let quantizer = fit(kind, &vectors, subvector_size);
+            let table = quantizer.table(query);
+            for vector in &vectors {
+                let code = quantizer.encode(vector);
+                // decoding rounds to single precision, the tables do not
+                assert!((table.dot(&code) - dot(query, &quantizer.decode(&code))).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn vectors_of_different_dimensions_are_an_error() {
+        let (short, long) = ([1.0, 0.0], [1.0, 0.0, 0.5]);
+        for kind in [VectorQuantizationType::Int8, VectorQuantizationType::Product] {
+            let error = Quantizer::fit(kind, &[&short, &long], 2).unwrap_err();
+            assert!(error.contains("3 and 2"), "{error}");
+            assert!(Quantizer::fit(kind, &[&[]], 2).is_err());
+            assert!(Quantizer::fit(kind, &[], 2).unwrap().is_none());
+        }
+    }
+}